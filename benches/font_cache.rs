@@ -0,0 +1,94 @@
+//! Reading a document of many pages set in one font, its fonts loaded once for the document,
+//! against the same document with a copy of the font for each page, loaded for every page,
+//! as they would be without the font cache.
+//!
+//! `cargo bench --bench font_cache`
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use lopdf::{dictionary, Dictionary, Document, Object, Stream};
+use rapid_pdf::{ExtractOptions, Extractor};
+use std::time::Duration;
+
+const PAGES: usize = 200;
+/// Glyphs of the font, each in its `/ToUnicode` map and `/W` array; CJK fonts have many more.
+const GLYPHS: usize = 3000;
+
+/// A Type0 font of `GLYPHS` glyphs with a `/ToUnicode` CMap, its parsing most of the work of
+/// loading it.
+fn font(doc: &mut Document) -> Object {
+    let mut cmap = String::from("/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n1 begincodespacerange <0000> <FFFF> endcodespacerange\n");
+    for block in (1..=GLYPHS).collect::<Vec<_>>().chunks(100) {
+        cmap += &format!("{} beginbfchar\n", block.len());
+        for &glyph in block {
+            cmap += &format!("<{:04X}> <{:04X}>\n", glyph, 0x4E00 + glyph);
+        }
+        cmap += "endbfchar\n";
+    }
+    cmap += "endcmap CMapName currentdict /CMap defineresource pop end end";
+    let to_unicode = doc.add_object(Stream::new(Dictionary::new(), cmap.into_bytes()));
+    let widths: Vec<Object> = (0..GLYPHS).map(|glyph| Object::Integer(500 + (glyph % 500) as i64)).collect();
+    let descendant = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "CIDFontType2",
+        "BaseFont" => "Bench",
+        "CIDSystemInfo" => dictionary! { "Registry" => Object::string_literal("Adobe"), "Ordering" => Object::string_literal("Identity"), "Supplement" => 0 },
+        "W" => vec![Object::Integer(1), Object::Array(widths)],
+    });
+    let font = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type0",
+        "BaseFont" => "Bench",
+        "Encoding" => "Identity-H",
+        "DescendantFonts" => vec![Object::Reference(descendant)],
+        "ToUnicode" => to_unicode,
+    });
+    Object::Reference(font)
+}
+
+/// `PAGES` pages of a few lines each, in one font shared by all of them, or with
+/// `font_per_page` in a copy of it for each.
+fn document(font_per_page: bool) -> Vec<u8> {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let shared = font(&mut doc);
+    let line: String = (1..=40).map(|glyph| format!("{:04X}", glyph * 7)).collect();
+    let content: String = (0..20).map(|row| format!("BT /F1 10 Tf 72 {} Td <{}> Tj ET\n", 720 - 14 * row, line)).collect();
+    let mut kids = Vec::new();
+    for _ in 0..PAGES {
+        let font = if font_per_page { font(&mut doc) } else { shared.clone() };
+        let contents = doc.add_object(Stream::new(Dictionary::new(), content.clone().into_bytes()));
+        let page = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+            "Contents" => contents,
+        });
+        kids.push(Object::Reference(page));
+    }
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => PAGES as i64 }));
+    let root = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", root);
+    let mut data = Vec::new();
+    doc.save_to(&mut data).expect("a document built in memory saves");
+    data
+}
+
+fn read(data: &[u8], options: &ExtractOptions) -> usize {
+    let extractor = Extractor::from_bytes(data, options).expect("the document opens");
+    extractor.page_items().map(|page| page.expect("the page reads").items.len()).sum()
+}
+
+fn font_cache(c: &mut Criterion) {
+    let options = ExtractOptions::builder().threads(Some(1)).build().expect("the options are valid");
+    let (shared, per_page) = (document(false), document(true));
+    assert_eq!(read(&shared, &options), read(&per_page, &options));
+    let mut group = c.benchmark_group("font_cache");
+    group.sample_size(10).measurement_time(Duration::from_secs(15));
+    group.bench_function("one font for all pages", |b| b.iter(|| read(&shared, &options)));
+    group.bench_function("a font for each page", |b| b.iter(|| read(&per_page, &options)));
+    group.finish();
+}
+
+criterion_group!(benches, font_cache);
+criterion_main!(benches);
@@ -0,0 +1,153 @@
+//! Compiles the AFM files of the 14 standard fonts in `data/afm` into static width tables
+//! (`$OUT_DIR/standard_fonts.rs`), included by `src/font/metrics.rs`, and the UCS2 CMaps of
+//! the Adobe CJK character collections in `data/cmap` into CID-to-Unicode tables
+//! (`$OUT_DIR/cid_to_unicode.rs`), included by `src/font/predefined.rs`.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::{env, fs};
+
+#[allow(dead_code)]
+#[path = "src/font/glyph_names.rs"]
+mod glyph_names;
+
+#[allow(dead_code)]
+#[path = "src/font/cmap.rs"]
+mod cmap;
+
+const FONTS: [&str; 14] = [
+    "Courier",
+    "Courier-Bold",
+    "Courier-BoldOblique",
+    "Courier-Oblique",
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-BoldOblique",
+    "Helvetica-Oblique",
+    "Symbol",
+    "Times-Bold",
+    "Times-BoldItalic",
+    "Times-Italic",
+    "Times-Roman",
+    "ZapfDingbats",
+];
+
+/// The CJK character collections, by the name of their table and their own, whose UCS2 CMap
+/// is `data/cmap/<collection>-UCS2`.
+const COLLECTIONS: [(&str, &str); 4] = [("GB1", "Adobe-GB1"), ("CNS1", "Adobe-CNS1"), ("JAPAN1", "Adobe-Japan1"), ("KOREA1", "Adobe-Korea1")];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=data/afm");
+    println!("cargo:rerun-if-changed=data/cmap");
+    println!("cargo:rerun-if-changed=src/font/glyph_names.rs");
+    println!("cargo:rerun-if-changed=src/font/cmap.rs");
+    standard_fonts();
+    cid_to_unicode();
+}
+
+fn standard_fonts() {
+    let mut out = String::from("pub static STANDARD_FONTS: [StandardFont; 14] = [\n");
+    for name in FONTS {
+        let path = Path::new("data/afm").join(format!("{name}.afm"));
+        let afm = fs::read_to_string(&path).unwrap_or_else(|err| panic!("reading {}: {err}", path.display()));
+        let font = parse_afm(&afm);
+
+        writeln!(out, "    StandardFont {{").unwrap();
+        writeln!(out, "        name: {name:?},").unwrap();
+        writeln!(out, "        builtin: {:?},", font.builtin).unwrap();
+        writeln!(out, "        by_unicode: &{:?},", font.by_unicode).unwrap();
+        writeln!(out, "    }},").unwrap();
+    }
+    out.push_str("];\n");
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("standard_fonts.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Writes a `CidTable` for each collection, read through the ToUnicode parser the fonts use:
+/// the UCS2 CMaps are ToUnicode CMaps whose codes are two-byte CIDs. A collection whose CMap
+/// is not in `data/cmap` gets an empty table, its CIDs left to the fonts' own mappings.
+fn cid_to_unicode() {
+    let mut out = String::new();
+    for (table, collection) in COLLECTIONS {
+        let path = Path::new("data/cmap").join(format!("{collection}-UCS2"));
+        let cids = match fs::read(&path) {
+            Ok(data) => cid_ranges(&cmap::ToUnicodeCMap::parse(&data)),
+            Err(err) => {
+                println!("cargo:warning=reading {}: {err}; CIDs of {collection} fonts without a ToUnicode map will not decode", path.display());
+                CidRanges { ranges: Vec::new(), strings: Vec::new() }
+            }
+        };
+        writeln!(out, "pub static {table}: CidTable = CidTable {{ ranges: &{:?}, strings: &{:?} }};", cids.ranges, cids.strings).unwrap();
+    }
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("cid_to_unicode.rs");
+    fs::write(dest, out).unwrap();
+}
+
+struct CidRanges {
+    /// Ranges of CIDs mapped to consecutive characters, `(first, last, first character)`,
+    /// sorted by CID.
+    ranges: Vec<(u16, u16, u32)>,
+    /// The CIDs mapped to more than one character, sorted.
+    strings: Vec<(u16, String)>,
+}
+
+fn cid_ranges(cmap: &cmap::ToUnicodeCMap) -> CidRanges {
+    let mut ranges: Vec<(u16, u16, u32)> = Vec::new();
+    let mut strings = Vec::new();
+    for cid in 0..=u16::MAX {
+        let Some(text) = cmap.lookup((cid as u32, 2)) else { continue };
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => match ranges.last_mut() {
+                Some((start, last, first)) if *last + 1 == cid && *first + (cid - *start) as u32 == c as u32 => *last = cid,
+                _ => ranges.push((cid, cid, c as u32)),
+            },
+            (Some(_), Some(_)) => strings.push((cid, text)),
+            _ => {}
+        }
+    }
+    CidRanges { ranges, strings }
+}
+
+struct Afm {
+    /// Widths by code in the font's built-in encoding; 0 for unencoded codes.
+    builtin: Vec<u16>,
+    /// Widths by the Unicode character of the glyph name, sorted by character.
+    by_unicode: Vec<(char, u16)>,
+}
+
+/// Reads the `C <code> ; WX <width> ; N <name> ; ...` lines of the character metrics section.
+fn parse_afm(afm: &str) -> Afm {
+    let mut builtin = vec![0u16; 256];
+    let mut by_unicode = Vec::new();
+    for line in afm.lines().filter(|line| line.starts_with("C ")) {
+        let mut code = -1i32;
+        let mut width = None;
+        let mut glyph = None;
+        for field in line.split(';') {
+            let mut parts = field.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("C"), Some(value)) => code = value.parse().unwrap_or(-1),
+                (Some("WX"), Some(value)) => width = value.parse::<f32>().ok().map(|w| w.round() as u16),
+                (Some("N"), Some(value)) => glyph = Some(value),
+                _ => {}
+            }
+        }
+        let Some(width) = width else { continue };
+        if (0..256).contains(&code) {
+            builtin[code as usize] = width;
+        }
+        let unicode = glyph.and_then(|glyph| {
+            let index = glyph_names::GLYPH_NAMES.binary_search_by(|(name, _)| (*name).cmp(glyph)).ok()?;
+            Some(glyph_names::GLYPH_NAMES[index].1)
+        });
+        if let Some(ch) = unicode {
+            by_unicode.push((ch, width));
+        }
+    }
+    by_unicode.sort_by_key(|&(ch, _)| ch);
+    by_unicode.dedup_by_key(|&mut (ch, _)| ch);
+    Afm { builtin, by_unicode }
+}
@@ -0,0 +1,90 @@
+//! ALTO 4 output (<https://www.loc.gov/standards/alto/>), the layout XML that library and
+//! archive systems ingest for digitized documents.
+//!
+//! Each page's paragraphs become `TextBlock`s of `TextLine`s of `String`s, with `SP` for the
+//! gaps between words. Positions are measured from the top-left corner of the crop box, in
+//! the `MeasurementUnit` standing for `--units`: pixels of a 72 dpi image for points,
+//! tenths of millimetres, or 1200ths of an inch.
+
+use crate::report::xml_escape;
+use crate::Units;
+use rapid_pdf::{Origin, PageText, Rect};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A document of `pages`, read from the PDF at `path`. Word boundaries within lines follow
+/// the line grouping's `word_gap`.
+pub fn document(pages: &[PageText], path: &Path, units: Units, origin: Origin, word_gap: f32) -> String {
+    let (unit, per_point) = match units {
+        Units::Pt => ("pixel", 1.0),
+        Units::Mm => ("mm10", units.per_point() * 10.0),
+        Units::In => ("inch1200", units.per_point() * 1200.0),
+    };
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://www.loc.gov/standards/alto/ns-v4# http://www.loc.gov/standards/alto/v4/alto-4-4.xsd\">\n");
+    xml.push_str("  <Description>\n");
+    let _ = writeln!(xml, "    <MeasurementUnit>{}</MeasurementUnit>", unit);
+    xml.push_str("    <sourceImageInformation>\n");
+    let _ = writeln!(xml, "      <fileName>{}</fileName>", xml_escape(&path.display().to_string()));
+    xml.push_str("    </sourceImageInformation>\n");
+    xml.push_str("  </Description>\n");
+    xml.push_str("  <Layout>\n");
+    for (index, page) in pages.iter().enumerate() {
+        write_page(&mut xml, page, index, per_point, origin, word_gap);
+    }
+    xml.push_str("  </Layout>\n");
+    xml.push_str("</alto>\n");
+    xml
+}
+
+fn write_page(xml: &mut String, page: &PageText, index: usize, per_point: f32, origin: Origin, word_gap: f32) {
+    let n = page.page_num;
+    let position = |rect: &Rect| position(&page.top_left(rect, origin), per_point);
+    let (width, height) = (length(page.crop_box.width() * per_point), length(page.crop_box.height() * per_point));
+    let _ = writeln!(xml, "    <Page ID=\"page_{}\" PHYSICAL_IMG_NR=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\">", n, index + 1, width, height);
+    let _ = writeln!(xml, "      <PrintSpace HPOS=\"0\" VPOS=\"0\" WIDTH=\"{}\" HEIGHT=\"{}\">", width, height);
+    let (mut lines, mut strings) = (0, 0);
+    for (block, paragraph) in page.paragraphs.iter().enumerate() {
+        let _ = writeln!(xml, "        <TextBlock ID=\"page_{}_block_{}\" {}>", n, block + 1, position(&paragraph.bbox));
+        for line in &paragraph.lines {
+            lines += 1;
+            let _ = writeln!(xml, "          <TextLine ID=\"page_{}_line_{}\" {}>", n, lines, position(&line.bbox));
+            let words = line.words(origin, word_gap);
+            for (index, (text, bbox)) in words.iter().enumerate() {
+                if index > 0 {
+                    let gap = Rect { x0: words[index - 1].1.x1, y0: bbox.y0, x1: bbox.x0, y1: bbox.y1 };
+                    let top_left = page.top_left(&gap, origin);
+                    let _ = writeln!(xml, "            <SP HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\"/>",
+                        length(top_left.x0 * per_point), length(top_left.y0 * per_point), length(top_left.width().max(0.0) * per_point));
+                }
+                strings += 1;
+                let _ = writeln!(xml, "            <String ID=\"page_{}_string_{}\" CONTENT=\"{}\" {}/>", n, strings, xml_escape(text), position(bbox));
+            }
+            xml.push_str("          </TextLine>\n");
+        }
+        xml.push_str("        </TextBlock>\n");
+    }
+    xml.push_str("      </PrintSpace>\n");
+    xml.push_str("    </Page>\n");
+}
+
+/// The `HPOS`, `VPOS`, `WIDTH` and `HEIGHT` attributes of a top-left box in points.
+fn position(rect: &Rect, per_point: f32) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        length(rect.x0 * per_point),
+        length(rect.y0 * per_point),
+        length(rect.width() * per_point),
+        length(rect.height() * per_point)
+    )
+}
+
+/// A length in whole units; ALTO allows fractions, but no consumer needs them.
+fn length(value: f32) -> i64 {
+    if value.is_finite() {
+        value.round() as i64
+    } else {
+        0
+    }
+}
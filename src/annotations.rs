@@ -0,0 +1,247 @@
+//! Annotations of a page (PDF 32000-1:2008, 12.5): comments, notes and markup that reviewers
+//! leave in the page's `/Annots` rather than in its content, and links.
+
+use crate::destination::Destinations;
+use crate::layout::lines;
+use crate::matrix::Rect;
+use crate::page::{self, Page};
+use crate::{decode_text_string, Date, ExtractOptions, Origin, TextItem};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+
+/// The markup annotations (12.5.6.2), those with text of their own or marking up the page's.
+/// Links, form fields and popups are not among them.
+const MARKUP: [&[u8]; 16] = [
+    b"Text", b"FreeText", b"Line", b"Square", b"Circle", b"Polygon", b"PolyLine", b"Highlight", b"Underline", b"Squiggly",
+    b"StrikeOut", b"Stamp", b"Caret", b"Ink", b"FileAttachment", b"Redact",
+];
+
+/// Those of them that mark up text, over the region of `/QuadPoints`.
+const TEXT_MARKUP: [&[u8]; 5] = [b"Highlight", b"Underline", b"Squiggly", b"StrikeOut", b"Redact"];
+
+/// A markup annotation of a page, with the text of its popup. Boxes are placed like the
+/// page's items: upright, and measured from the top-left corner with `Origin::TopLeft`.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Annotation {
+    /// The subtype, such as `Text` for a sticky note, `FreeText` or `Highlight`.
+    pub kind: String,
+    /// The text of the annotation, or of its popup when it has none of its own.
+    pub contents: Option<String>,
+    /// `/T`, the name of whoever made it.
+    pub author: Option<String>,
+    pub modified: Option<Date>,
+    pub rect: Rect,
+    /// For markup over text, the box of each quadrilateral of `/QuadPoints`.
+    pub quads: Vec<Rect>,
+    /// For markup over text, the text of the page's items at least half inside `quads`.
+    pub quoted: Option<String>,
+}
+
+/// A link of a page, to a web address or to a page of the document, with the text it covers.
+/// Its box is placed like the page's items.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Link {
+    /// The address of a URI action.
+    pub uri: Option<String>,
+    /// The page, counted from 1, of a go-to action or destination.
+    pub page: Option<u32>,
+    pub rect: Rect,
+    /// The text of the page's items at least half inside the link's `/QuadPoints`, or its
+    /// box when it has none, in the order of the items, lines joined by spaces.
+    pub text: String,
+}
+
+/// Reads the markup annotations of a page, in the order of its `/Annots`. `items` are the
+/// page's, placed as the options say, for the text marked up.
+pub(crate) fn read(doc: &Document, page_id: ObjectId, page: &Page, options: &ExtractOptions, items: &[TextItem]) -> Vec<Annotation> {
+    let place = |rect: &Rect| place(page, rect, options.origin);
+    page_annots(doc, page_id)
+        .filter_map(|annot| {
+            let kind = annot.get(b"Subtype").and_then(Object::as_name).ok().filter(|kind| MARKUP.contains(kind))?;
+            let text = |dict: &Dictionary, key: &[u8]| {
+                let value = dict.get_deref(key, doc).and_then(Object::as_str).ok()?;
+                Some(decode_text_string(value)).filter(|text| !text.trim().is_empty())
+            };
+            let popup = annot.get_deref(b"Popup", doc).and_then(Object::as_dict).ok();
+            let contents = text(annot, b"Contents").or_else(|| popup.and_then(|popup| text(popup, b"Contents")));
+            let rect = annot.get(b"Rect").ok().and_then(|rect| page::read_rect(doc, rect)).unwrap_or_default();
+            let quads = match TEXT_MARKUP.contains(&kind) {
+                true => quad_boxes(doc, annot).iter().map(place).collect(),
+                false => Vec::new(),
+            };
+            let quoted = (!quads.is_empty()).then(|| covered_text(&quads, items, options)).filter(|text| !text.is_empty());
+            Some(Annotation {
+                kind: String::from_utf8_lossy(kind).into_owned(),
+                contents,
+                author: text(annot, b"T"),
+                modified: text(annot, b"M").map(|date| Date::from_pdf(&date)),
+                rect: place(&rect),
+                quads,
+                quoted,
+            })
+        })
+        .collect()
+}
+
+/// Reads the links of a page, in the order of its `/Annots`, with the text of `items` they
+/// cover.
+pub(crate) fn links(destinations: &Destinations, page_id: ObjectId, page: &Page, options: &ExtractOptions, items: &[TextItem]) -> Vec<Link> {
+    let doc = destinations.doc;
+    page_annots(doc, page_id)
+        .filter(|annot| annot.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Link"))
+        .map(|annot| {
+            let action = annot.get_deref(b"A", doc).and_then(Object::as_dict).ok();
+            let uri = action
+                .filter(|action| action.get(b"S").and_then(Object::as_name).ok() == Some(b"URI"))
+                .and_then(|action| action.get_deref(b"URI", doc).and_then(Object::as_str).ok())
+                .map(|uri| String::from_utf8_lossy(uri).trim().to_string());
+            let page_num = match annot.get(b"Dest") {
+                Ok(dest) => destinations.page(dest),
+                Err(_) => action.and_then(|action| destinations.action_page(action)),
+            };
+            let rect = annot.get(b"Rect").ok().and_then(|rect| page::read_rect(doc, rect)).unwrap_or_default();
+            // A link over lines of text may give a quadrilateral for each.
+            let mut regions: Vec<Rect> = quad_boxes(doc, annot).iter().map(|quad| place(page, quad, options.origin)).collect();
+            if regions.is_empty() {
+                regions.push(place(page, &rect, options.origin));
+            }
+            let text = covered_text(&regions, items, options);
+            Link { uri, page: page_num, rect: place(page, &rect, options.origin), text }
+        })
+        .collect()
+}
+
+/// The annotation dictionaries of a page's `/Annots`.
+fn page_annots(doc: &Document, page_id: ObjectId) -> impl Iterator<Item = &Dictionary> {
+    let annots = doc.get_dictionary(page_id).and_then(|page| page.get_deref(b"Annots", doc)).and_then(Object::as_array);
+    annots.into_iter().flatten().filter_map(|annot| doc.dereference(annot).ok()?.1.as_dict().ok())
+}
+
+/// A page-space box placed like the page's items.
+pub(crate) fn place(page: &Page, rect: &Rect, origin: Origin) -> Rect {
+    let rect = page.upright(rect);
+    match origin {
+        Origin::BottomLeft => rect,
+        Origin::TopLeft => page.top_left(&rect),
+    }
+}
+
+/// The boxes of the quadrilaterals of `/QuadPoints`, eight numbers each, in page space.
+fn quad_boxes(doc: &Document, annot: &Dictionary) -> Vec<Rect> {
+    let Ok(points) = annot.get_deref(b"QuadPoints", doc).and_then(Object::as_array) else { return Vec::new() };
+    let points: Vec<f32> = points.iter().filter_map(|point| point.as_float().ok()).collect();
+    points
+        .chunks_exact(8)
+        .map(|quad| {
+            let xs = [quad[0], quad[2], quad[4], quad[6]];
+            let ys = [quad[1], quad[3], quad[5], quad[7]];
+            let min = |values: [f32; 4]| values.into_iter().fold(f32::INFINITY, f32::min);
+            let max = |values: [f32; 4]| values.into_iter().fold(f32::NEG_INFINITY, f32::max);
+            Rect { x0: min(xs), y0: min(ys), x1: max(xs), y1: max(ys) }
+        })
+        .collect()
+}
+
+/// The text of the items at least half inside `regions`, in lines joined by spaces.
+fn covered_text(regions: &[Rect], items: &[TextItem], options: &ExtractOptions) -> String {
+    let area = |rect: &Rect| rect.width() * rect.height();
+    let inside: Vec<TextItem> = items
+        .iter()
+        .filter(|item| {
+            let covered: f32 = regions.iter().filter_map(|region| region.intersection(&item.bbox)).map(|part| area(&part)).sum();
+            area(&item.bbox) > 0.0 && covered * 2.0 >= area(&item.bbox)
+        })
+        .cloned()
+        .collect();
+    let lines = lines::group(inside, options.origin, options.line_tolerance, options.word_gap, options.max_skew);
+    lines.iter().map(|line| line.text.trim()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, near, Fixture};
+    use crate::{ExtractOptions, Origin};
+    use lopdf::{dictionary, Object};
+
+    fn numbers(values: &[f32]) -> Vec<Object> {
+        values.iter().map(|&value| value.into()).collect()
+    }
+
+    #[test]
+    fn reads_the_comments_of_a_page() {
+        let mut fixture = Fixture::new();
+        let popup = fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "Popup", "Contents" => Object::string_literal("Why this word?") });
+        let annots = vec![
+            fixture.object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Text",
+                "Rect" => numbers(&[400.0, 700.0, 420.0, 720.0]),
+                "Contents" => Object::string_literal("Check the figures"),
+                "T" => Object::string_literal("Ada"),
+                "M" => Object::string_literal("D:20240131120000Z"),
+            }),
+            // Its text is that of its popup.
+            fixture.object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Highlight",
+                "Rect" => numbers(&[70.0, 690.0, 150.0, 715.0]),
+                "QuadPoints" => numbers(&[70.0, 715.0, 150.0, 715.0, 70.0, 690.0, 150.0, 690.0]),
+                "Popup" => popup,
+            }),
+            fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "Popup", "Rect" => numbers(&[0.0, 0.0, 1.0, 1.0]) }),
+            fixture.object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "FreeText",
+                "Rect" => numbers(&[400.0, 600.0, 500.0, 620.0]),
+                "Contents" => Object::string_literal("Approved"),
+            }),
+        ];
+        let annots: Vec<Object> = annots.into_iter().map(Object::from).collect();
+        fixture.page_with("BT /F1 12 Tf 72 700 Td (Please review) Tj 0 -20 Td (the second line) Tj ET", dictionary! { "Annots" => annots });
+        let options = ExtractOptions::builder().origin(Origin::TopLeft).build().unwrap();
+        let page = testing::pages_with(&fixture.bytes(), &options).remove(0);
+        let annotations = &page.annotations;
+        let kinds: Vec<&str> = annotations.iter().map(|annotation| annotation.kind.as_str()).collect();
+        assert_eq!(kinds, ["Text", "Highlight", "FreeText"]);
+        let contents: Vec<Option<&str>> = annotations.iter().map(|annotation| annotation.contents.as_deref()).collect();
+        assert_eq!(contents, [Some("Check the figures"), Some("Why this word?"), Some("Approved")]);
+        assert_eq!(annotations[0].author.as_deref(), Some("Ada"));
+        assert_eq!(annotations[0].modified.as_ref().unwrap().value, "2024-01-31T12:00:00Z");
+        // Measured from the top of the page, as the items are.
+        assert!(near(annotations[2].rect.y0, 172.0) && near(annotations[2].rect.y1, 192.0), "{:?}", annotations[2].rect);
+        assert_eq!(annotations[1].quoted.as_deref(), Some("Please review"));
+        assert_eq!(annotations[1].quads.len(), 1);
+        assert!(annotations[0].quads.is_empty() && annotations[0].quoted.is_none());
+    }
+
+    #[test]
+    fn reads_the_links_of_a_page_with_the_text_they_cover() {
+        let mut fixture = Fixture::new();
+        let uri = dictionary! { "S" => "URI", "URI" => Object::string_literal(" https://example.com/report ") };
+        let annots = vec![
+            fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "Link", "Rect" => numbers(&[70.0, 695.0, 200.0, 712.0]), "A" => uri }),
+            // Over the end of one line and the start of the next, the second page by its index.
+            fixture.object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "Rect" => numbers(&[70.0, 655.0, 300.0, 692.0]),
+                "QuadPoints" => numbers(&[150.0, 692.0, 300.0, 692.0, 150.0, 677.0, 300.0, 677.0, 70.0, 672.0, 120.0, 672.0, 70.0, 656.0, 120.0, 656.0]),
+                "Dest" => vec![1.into(), "Fit".into()],
+            }),
+            fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "Link", "Rect" => numbers(&[70.0, 615.0, 200.0, 635.0]), "Dest" => "appendix" }),
+            fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "Text", "Rect" => numbers(&[400.0, 700.0, 420.0, 720.0]) }),
+        ];
+        let annots: Vec<Object> = annots.into_iter().map(Object::from).collect();
+        let content = "BT /F1 12 Tf 72 700 Td (Our report) Tj 0 -20 Td (is in) Tj 80 0 Td (section two) Tj -80 -20 Td (of the) Tj ET \
+            BT /F1 12 Tf 72 620 Td (Appendix) Tj ET";
+        fixture.page_with(content, dictionary! { "Annots" => annots }).page("");
+        let catalog = dictionary! { "Dests" => dictionary! { "appendix" => vec![1.into(), "XYZ".into(), 72.into(), 720.into(), 0.into()] } };
+        let page = testing::pages_with(&testing::save(fixture.document(catalog)), &ExtractOptions::default()).remove(0);
+        let links: Vec<(Option<&str>, Option<u32>, &str)> = page.links.iter().map(|link| (link.uri.as_deref(), link.page, link.text.as_str())).collect();
+        assert_eq!(links, [(Some("https://example.com/report"), None, "Our report"), (None, Some(2), "section two of the"), (None, Some(2), "Appendix")]);
+        assert!(near(page.links[0].rect.x0, 70.0) && near(page.links[0].rect.y1, 712.0));
+        assert!(page.annotations.iter().all(|annotation| annotation.kind == "Text"));
+    }
+}
@@ -0,0 +1,218 @@
+//! Embedded files (PDF 32000-1:2008, 7.11.4): files carried inside the document, such as the
+//! XML of an electronic invoice, listed in the `/EmbeddedFiles` name tree or attached to a
+//! page by a FileAttachment annotation.
+
+use crate::destination::name_tree_entries;
+use crate::{decode_text_string, processor};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How many characters of a name are kept for its file name.
+const MAX_FILE_NAME: usize = 200;
+
+/// An embedded file, with its contents.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Attachment {
+    /// The name of the file as the document gives it, `/UF` or `/F` of its file
+    /// specification, which may hold a path.
+    pub name: String,
+    /// A file name for the attachment that is safe to write: the last part of `name`,
+    /// without characters file systems do not allow, and unique among the document's
+    /// attachments regardless of case, `invoice (2).xml` for a second `invoice.xml`.
+    pub file_name: String,
+    /// `/Desc` of the file specification, or the text of the annotation attaching it.
+    pub description: Option<String>,
+    /// The MIME type of the file, from the `/Subtype` of its stream, such as `text/xml`.
+    pub mime_type: Option<String>,
+    /// Bytes of the file: of `data` when it could be read, else as its `/Params` give.
+    pub size: Option<usize>,
+    /// The page, counted from 1, of the annotation attaching it; `None` for the files of the
+    /// `/EmbeddedFiles` name tree.
+    pub page: Option<u32>,
+    /// Why the contents could not be read, leaving `data` empty.
+    pub error: Option<String>,
+    /// The contents of the file, decompressed.
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+/// Reads the files of the `/EmbeddedFiles` name tree, in the order of the tree, then those
+/// of FileAttachment annotations, by page, that are not among them. Streams decompressing to
+/// more than `max_size` bytes are left unread, with a warning.
+pub(crate) fn read(doc: &Document, pages: &HashMap<ObjectId, u32>, max_size: usize) -> Vec<Attachment> {
+    let mut specs: Vec<(&Dictionary, Option<String>, Option<u32>)> = Vec::new();
+    let tree = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"Names", doc))
+        .and_then(Object::as_dict)
+        .and_then(|names| names.get_deref(b"EmbeddedFiles", doc))
+        .and_then(Object::as_dict);
+    for (_, spec) in tree.map(|tree| name_tree_entries(doc, tree)).unwrap_or_default() {
+        if let Ok(spec) = doc.dereference(spec).and_then(|(_, spec)| spec.as_dict()) {
+            specs.push((spec, None, None));
+        }
+    }
+    let mut pages: Vec<(ObjectId, u32)> = pages.iter().map(|(&id, &page_num)| (id, page_num)).collect();
+    pages.sort_by_key(|&(_, page_num)| page_num);
+    for (page_id, page_num) in pages {
+        let annots = doc.get_dictionary(page_id).and_then(|page| page.get_deref(b"Annots", doc)).and_then(Object::as_array);
+        for annot in annots.into_iter().flatten().filter_map(|annot| doc.dereference(annot).ok()?.1.as_dict().ok()) {
+            if annot.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"FileAttachment") {
+                continue;
+            }
+            let Ok(spec) = annot.get_deref(b"FS", doc).and_then(Object::as_dict) else { continue };
+            let contents = annot.get_deref(b"Contents", doc).and_then(Object::as_str).map(decode_text_string).ok();
+            specs.push((spec, contents.filter(|text| !text.trim().is_empty()), Some(page_num)));
+        }
+    }
+
+    // The same file may be both listed and attached to a page.
+    let mut seen = HashSet::new();
+    let mut file_names = HashSet::new();
+    let mut attachments = Vec::new();
+    for (spec, contents, page) in specs {
+        let Some((stream_id, stream)) = embedded_stream(doc, spec) else { continue };
+        if !seen.insert(stream_id) {
+            continue;
+        }
+        let text = |key: &[u8]| spec.get_deref(key, doc).and_then(Object::as_str).map(decode_text_string).ok();
+        let name = text(b"UF").or_else(|| text(b"F")).filter(|name| !name.is_empty()).unwrap_or_default();
+        let file_name = unique(sanitize(&name), &mut file_names);
+        let (data, error) = match processor::stream_data(stream, max_size) {
+            Ok(data) => (data, None),
+            Err(err) if err.too_large => (Vec::new(), Some(format!("larger than {} bytes decompressed", max_size))),
+            Err(err) => (Vec::new(), Some(err.message)),
+        };
+        if let Some(error) = &error {
+            log::warn!("not reading attached file {}: {}", file_name, error);
+        }
+        let params = stream.dict.get_deref(b"Params", doc).and_then(Object::as_dict).ok();
+        let size = match error {
+            None => Some(data.len()),
+            Some(_) => params.and_then(|params| params.get(b"Size").and_then(Object::as_i64).ok()).and_then(|size| usize::try_from(size).ok()),
+        };
+        attachments.push(Attachment {
+            name,
+            file_name,
+            description: text(b"Desc").filter(|text| !text.trim().is_empty()).or(contents),
+            mime_type: stream.dict.get(b"Subtype").and_then(Object::as_name).ok().map(|mime| String::from_utf8_lossy(mime).into_owned()),
+            size,
+            page,
+            error,
+            data,
+        });
+    }
+    attachments
+}
+
+/// The embedded file stream of a file specification: `/UF` of its `/EF`, else `/F`.
+fn embedded_stream<'a>(doc: &'a Document, spec: &'a Dictionary) -> Option<(ObjectId, &'a lopdf::Stream)> {
+    let files = spec.get_deref(b"EF", doc).and_then(Object::as_dict).ok()?;
+    [b"UF".as_slice(), b"F"].into_iter().find_map(|key| {
+        let id = files.get(key).and_then(Object::as_reference).ok()?;
+        Some((id, doc.get_object(id).and_then(Object::as_stream).ok()?))
+    })
+}
+
+/// The last part of a name that may be a path, of either kind of separator, fit to be a file
+/// name anywhere: characters some file systems do not allow are replaced, leading dots, which
+/// would hide the file or lead out of the directory, and trailing dots and spaces are dropped,
+/// and names Windows keeps for devices are prefixed.
+fn sanitize(name: &str) -> String {
+    let last = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = last
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') { '_' } else { c })
+        .take(MAX_FILE_NAME)
+        .collect();
+    let name = name.trim_start_matches(['.', ' ']).trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        return "attachment".to_string();
+    }
+    let stem = name.split('.').next().unwrap_or_default().trim_end().to_ascii_uppercase();
+    let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4 && (stem.starts_with("COM") || stem.starts_with("LPT")) && stem.ends_with(|c: char| c.is_ascii_digit()));
+    if reserved {
+        format!("_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// `name`, or, when a name already taken differs from it at most in case, the first of
+/// `stem (2).ext`, `stem (3).ext` and so on that is not.
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    if taken.insert(name.to_lowercase()) {
+        return name;
+    }
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name.as_str(), ""),
+    };
+    (2..)
+        .map(|count| format!("{} ({}){}", stem, count, extension))
+        .find(|candidate| taken.insert(candidate.to_lowercase()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use lopdf::{dictionary, Stream};
+
+    /// A file specification of `name` embedding `data`.
+    fn spec(fixture: &mut Fixture, name: &str, data: &[u8], mut entries: Dictionary) -> ObjectId {
+        let file = fixture.object(Stream::new(dictionary! { "Type" => "EmbeddedFile", "Subtype" => "text/xml", "Params" => dictionary! { "Size" => data.len() as i64 } }, data.to_vec()));
+        entries.set("Type", "Filespec");
+        entries.set("F", Object::string_literal(name));
+        entries.set("EF", dictionary! { "F" => file });
+        fixture.object(entries)
+    }
+
+    #[test]
+    fn reads_the_names_and_contents_of_embedded_files() {
+        let mut fixture = Fixture::new();
+        let invoice = spec(&mut fixture, "invoice.xml", b"<Invoice/>", dictionary! { "UF" => Object::string_literal("C:\\bills\\invoice.xml"), "Desc" => Object::string_literal("The invoice") });
+        let copy = spec(&mut fixture, "../Invoice.XML", b"<Invoice version=\"2\"/>", Dictionary::new());
+        let large = spec(&mut fixture, "large.xml", &[b'x'; 100], Dictionary::new());
+        let photo = spec(&mut fixture, "photo.xml", b"<Photo/>", Dictionary::new());
+        let names = vec![Object::string_literal("invoice"), invoice.into(), Object::string_literal("large"), large.into(), Object::string_literal("other"), copy.into()];
+        let annot = |fixture: &mut Fixture, spec: ObjectId| {
+            fixture.object(dictionary! { "Type" => "Annot", "Subtype" => "FileAttachment", "FS" => spec, "Contents" => Object::string_literal("Attached") })
+        };
+        // The invoice is attached to the page as well as listed.
+        let annots: Vec<Object> = vec![annot(&mut fixture, photo).into(), annot(&mut fixture, invoice).into()];
+        fixture.page("").page_with("", dictionary! { "Annots" => annots });
+        let doc = fixture.document(dictionary! { "Names" => dictionary! { "EmbeddedFiles" => dictionary! { "Names" => names } } });
+        let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+
+        let attachments = read(&doc, &pages, 64);
+        let names: Vec<(&str, &str, Option<u32>)> = attachments.iter().map(|file| (file.name.as_str(), file.file_name.as_str(), file.page)).collect();
+        assert_eq!(
+            names,
+            [
+                ("C:\\bills\\invoice.xml", "invoice.xml", None),
+                ("large.xml", "large.xml", None),
+                ("../Invoice.XML", "Invoice (2).XML", None),
+                ("photo.xml", "photo.xml", Some(2)),
+            ]
+        );
+        assert_eq!(attachments[0].data, b"<Invoice/>");
+        assert_eq!((attachments[0].size, attachments[0].description.as_deref(), attachments[0].mime_type.as_deref()), (Some(10), Some("The invoice"), Some("text/xml")));
+        assert_eq!(attachments[2].data, b"<Invoice version=\"2\"/>");
+        assert_eq!(attachments[3].description.as_deref(), Some("Attached"));
+        // Too large to read, its size is what its parameters say.
+        assert!(attachments[1].data.is_empty());
+        assert_eq!((attachments[1].size, attachments[1].error.as_deref()), (Some(100), Some("larger than 64 bytes decompressed")));
+    }
+
+    #[test]
+    fn makes_names_safe_to_write() {
+        let mut taken = HashSet::new();
+        let names: Vec<String> = ["../../etc/passwd", "a:b?.txt", "..", "CON.txt", "notes.txt", "Notes.TXT"].into_iter().map(|name| unique(sanitize(name), &mut taken)).collect();
+        assert_eq!(names, ["passwd", "a_b_.txt", "attachment", "_CON.txt", "notes.txt", "Notes (2).TXT"]);
+    }
+}
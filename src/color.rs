@@ -0,0 +1,168 @@
+//! Color spaces and colors (PDF 32000-1:2008, 8.6), followed as far as telling what color
+//! text is filled with, in RGB.
+
+use lopdf::{Document, Object};
+use serde::Serialize;
+
+/// An RGB color, each component from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rgb(pub [f32; 3]);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb([0.0, 0.0, 0.0]);
+
+    /// Whether every component is within `tolerance` of 1.
+    pub fn is_near_white(&self, tolerance: f32) -> bool {
+        self.0.iter().all(|&component| component >= 1.0 - tolerance)
+    }
+
+    /// The color as `#rrggbb`.
+    pub fn hex(&self) -> String {
+        let [r, g, b] = self.0.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8);
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+/// A color space, given by name or by an array naming its family (8.6.3 to 8.6.6).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpace {
+    /// `DeviceGray`, and `CalGray` or one-component `ICCBased` taken as it.
+    Gray,
+    /// `DeviceRGB`, and `CalRGB` or three-component `ICCBased` taken as it.
+    Rgb,
+    /// `DeviceCMYK`, and four-component `ICCBased` taken as it.
+    Cmyk,
+    /// `Lab`, of which only the lightness is kept, as a gray.
+    Lab,
+    /// `Indexed`: colors of the base space picked from a table by index.
+    Indexed { base: Box<ColorSpace>, table: Vec<u8> },
+    /// `Separation` with an exponential tint transform, which blends the alternate space's
+    /// colors `c0` and `c1` by the tint to the power `n` (7.10.3).
+    Exponential { base: Box<ColorSpace>, c0: Vec<f32>, c1: Vec<f32>, n: f32 },
+    /// Other `Separation` and `DeviceN` spaces, whose tints are taken as a gray's darkness.
+    Tints(usize),
+    /// `Pattern` and anything unknown: no one color to tell.
+    Unknown,
+}
+
+impl ColorSpace {
+    /// The color space an operand of `cs` or an entry of `/ColorSpace` resources names.
+    pub fn load(doc: &Document, obj: &Object) -> ColorSpace {
+        ColorSpace::load_nested(doc, obj, 0)
+    }
+
+    fn load_nested(doc: &Document, obj: &Object, depth: usize) -> ColorSpace {
+        // Base and alternate spaces only nest a level or two; more is a reference cycle.
+        if depth > 4 {
+            return ColorSpace::Unknown;
+        }
+        let obj = doc.dereference(obj).map_or(obj, |(_, obj)| obj);
+        let (family, params) = match obj {
+            Object::Name(name) => (name.as_slice(), &[][..]),
+            Object::Array(array) => match array.split_first() {
+                Some((Object::Name(name), params)) => (name.as_slice(), params),
+                _ => return ColorSpace::Unknown,
+            },
+            _ => return ColorSpace::Unknown,
+        };
+        let param = |index: usize| params.get(index).map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+        match family {
+            b"DeviceGray" | b"G" | b"CalGray" => ColorSpace::Gray,
+            b"DeviceRGB" | b"RGB" | b"CalRGB" => ColorSpace::Rgb,
+            b"DeviceCMYK" | b"CMYK" => ColorSpace::Cmyk,
+            b"Lab" => ColorSpace::Lab,
+            b"ICCBased" => match param(0).and_then(|obj| obj.as_stream().ok()).and_then(|stream| stream.dict.get(b"N").and_then(Object::as_i64).ok()) {
+                Some(1) => ColorSpace::Gray,
+                Some(3) => ColorSpace::Rgb,
+                Some(4) => ColorSpace::Cmyk,
+                _ => ColorSpace::Unknown,
+            },
+            b"Indexed" | b"I" => {
+                let base = param(0).map_or(ColorSpace::Unknown, |base| ColorSpace::load_nested(doc, base, depth + 1));
+                let table = match param(2) {
+                    Some(Object::String(bytes, _)) => bytes.clone(),
+                    Some(Object::Stream(stream)) => stream.get_plain_content().unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                ColorSpace::Indexed { base: Box::new(base), table }
+            }
+            b"Separation" => {
+                let base = param(1).map_or(ColorSpace::Unknown, |base| ColorSpace::load_nested(doc, base, depth + 1));
+                exponential(doc, param(2)).map_or(ColorSpace::Tints(1), |(c0, c1, n)| ColorSpace::Exponential { base: Box::new(base), c0, c1, n })
+            }
+            b"DeviceN" => ColorSpace::Tints(param(0).and_then(|obj| obj.as_array().ok()).map_or(1, Vec::len)),
+            _ => ColorSpace::Unknown,
+        }
+    }
+
+    /// The color `cs` starts a space with: black, or the full tint of a colorant (8.6.8).
+    pub fn initial_color(&self) -> Option<Rgb> {
+        match self {
+            ColorSpace::Cmyk => self.to_rgb(&[0.0, 0.0, 0.0, 1.0]),
+            ColorSpace::Lab | ColorSpace::Indexed { .. } => self.to_rgb(&[0.0]),
+            ColorSpace::Exponential { .. } => self.to_rgb(&[1.0]),
+            ColorSpace::Tints(count) => self.to_rgb(&vec![1.0; *count]),
+            ColorSpace::Gray | ColorSpace::Rgb => Some(Rgb::BLACK),
+            ColorSpace::Unknown => None,
+        }
+    }
+
+    /// A color given by its components in this space, as `sc` and `scn` operands.
+    pub fn to_rgb(&self, components: &[f32]) -> Option<Rgb> {
+        let unit = |index: usize| components.get(index).map(|component| component.clamp(0.0, 1.0));
+        match self {
+            ColorSpace::Gray => unit(0).map(|gray| Rgb([gray; 3])),
+            ColorSpace::Rgb => Some(Rgb([unit(0)?, unit(1)?, unit(2)?])),
+            ColorSpace::Cmyk => {
+                let k = unit(3)?;
+                Some(Rgb([unit(0)?, unit(1)?, unit(2)?].map(|ink| (1.0 - ink) * (1.0 - k))))
+            }
+            ColorSpace::Lab => components.first().map(|lightness| Rgb([(lightness / 100.0).clamp(0.0, 1.0); 3])),
+            ColorSpace::Indexed { base, table } => {
+                let count = base.components()?;
+                let index = components.first()?.max(0.0) as usize;
+                let entry = table.get(index * count..(index + 1) * count)?;
+                base.to_rgb(&entry.iter().map(|&byte| byte as f32 / 255.0).collect::<Vec<_>>())
+            }
+            ColorSpace::Exponential { base, c0, c1, n } => {
+                let tint = unit(0)?.powf(*n);
+                base.to_rgb(&c0.iter().zip(c1).map(|(a, b)| a + tint * (b - a)).collect::<Vec<_>>())
+            }
+            ColorSpace::Tints(_) => {
+                let darkness = components.iter().fold(0.0f32, |darkness, tint| darkness.max(tint.clamp(0.0, 1.0)));
+                Some(Rgb([1.0 - darkness; 3]))
+            }
+            ColorSpace::Unknown => None,
+        }
+    }
+
+    /// How many components a color in the space has, where needed to read an index table.
+    fn components(&self) -> Option<usize> {
+        match self {
+            ColorSpace::Gray | ColorSpace::Exponential { .. } | ColorSpace::Indexed { .. } => Some(1),
+            ColorSpace::Rgb | ColorSpace::Lab => Some(3),
+            ColorSpace::Cmyk => Some(4),
+            ColorSpace::Tints(count) => Some(*count),
+            ColorSpace::Unknown => None,
+        }
+    }
+}
+
+/// `/C0`, `/C1` and `/N` of a type 2 function (7.10.3), the usual tint transform of a spot
+/// color.
+fn exponential(doc: &Document, function: Option<&Object>) -> Option<(Vec<f32>, Vec<f32>, f32)> {
+    let dict = match function? {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &stream.dict,
+        _ => return None,
+    };
+    if dict.get(b"FunctionType").and_then(Object::as_i64).ok()? != 2 {
+        return None;
+    }
+    let numbers = |key: &[u8], default: Vec<f32>| match dict.get_deref(key, doc).and_then(Object::as_array) {
+        Ok(array) => array.iter().filter_map(|obj| obj.as_float().ok()).collect(),
+        Err(_) => default,
+    };
+    let n = dict.get_deref(b"N", doc).and_then(Object::as_float).ok()?;
+    Some((numbers(b"C0", vec![0.0]), numbers(b"C1", vec![1.0]), n))
+}
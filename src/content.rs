@@ -0,0 +1,408 @@
+//! Decoding content streams into operations.
+//!
+//! lopdf parses inline images (`BI <dict> ID <data> EI`) itself, but fails the whole stream
+//! on filtered images or colour spaces it does not know, and binary data may contain the
+//! bytes `EI`. So inline images are first found here and rewritten into the ordinary
+//! operation `<<dict>> <hex data> BI`, which any content parser handles.
+//!
+//! lopdf also stops at the first token it cannot parse, such as stray bytes or an operation
+//! cut short at the end, silently dropping the rest of the stream. So operators are counted
+//! here too, and a stream lopdf stops short in is decoded again in parts, leaving out only
+//! the operations it cannot parse.
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// How many bytes after a candidate `EI` must look like content for it to end the image.
+const EI_LOOKAHEAD: usize = 32;
+
+/// Decodes a content stream, skipping what cannot be decoded of it: returns its operations,
+/// the byte offset in `data` of each, where its first token starts, and how many bytes were
+/// skipped.
+pub fn decode(data: &[u8]) -> (Content, Vec<usize>, usize) {
+    let (data, shifts) = match rewrite_inline_images(data) {
+        Some((rewritten, shifts)) => (Cow::Owned(rewritten), shifts),
+        None => (Cow::Borrowed(data), Vec::new()),
+    };
+    let ranges = operation_ranges(&data);
+    let mut operations = Content::decode(&data).map_or_else(|_| Vec::new(), |content| content.operations);
+    let mut offsets = Vec::new();
+    let mut skipped = 0;
+    // What lopdf decoded is good up to where it stopped.
+    let decoded = operations.len().min(ranges.len());
+    offsets.extend(ranges[..decoded].iter().map(|range| range.start));
+    decode_ranges(&data, &ranges[decoded..], &mut operations, &mut offsets, &mut skipped);
+    // Operands left at the end, with no operator, are an operation cut short.
+    let end = ranges.last().map_or(0, |range| range.end);
+    if let Some((start, _)) = next_token(&data, end) {
+        skipped += data[start..].trim_ascii().len();
+    }
+    // lopdf may make more of the operations than were counted, which are given the end.
+    offsets.resize(operations.len(), end);
+    let offsets = offsets
+        .into_iter()
+        .map(|start| next_token(&data, start).map_or(start, |(token_start, _)| token_start))
+        .map(|offset| original_offset(&shifts, offset))
+        .collect();
+    (Content { operations }, offsets, skipped)
+}
+
+/// The offset in content as it was of an offset in it with its inline images rewritten, given
+/// where each rewritten image starts and ends in both; the whole of an image is at its `BI`.
+fn original_offset(shifts: &[Shift], offset: usize) -> usize {
+    match shifts.iter().rev().find(|(rewritten, _)| *rewritten <= offset) {
+        Some(&(rewritten, original)) => original + (offset - rewritten),
+        None => offset,
+    }
+}
+
+/// Byte ranges of the operations of content, each running from the end of the last one to the
+/// end of its operator. Operators only ever end operations, as operands never hold them.
+fn operation_ranges(data: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let (mut start, mut i) = (0, 0);
+    while let Some((token_start, token_end)) = next_token(data, i) {
+        i = token_end;
+        if is_operator(&data[token_start..token_end]) {
+            ranges.push(start..token_end);
+            start = token_end;
+        }
+    }
+    ranges
+}
+
+/// Decodes the operations in `ranges` together, or, when lopdf stops short in them, each half
+/// of them, down to single operations, which are left out when they do not decode, their
+/// bytes counted in `skipped`. A stream with a few bad operations is decoded in a few passes.
+/// The start of the range of each operation decoded is added to `offsets`.
+fn decode_ranges(data: &[u8], ranges: &[Range<usize>], operations: &mut Vec<Operation>, offsets: &mut Vec<usize>, skipped: &mut usize) {
+    let (Some(first), Some(last)) = (ranges.first(), ranges.last()) else { return };
+    let bytes = &data[first.start..last.end];
+    let decoded = Content::decode(bytes).map_or_else(|_| Vec::new(), |content| content.operations);
+    match decoded.len() >= ranges.len() {
+        true => {
+            offsets.extend(ranges.iter().map(|range| range.start));
+            offsets.resize(operations.len() + decoded.len(), last.end);
+            operations.extend(decoded);
+        }
+        false if ranges.len() == 1 => *skipped += bytes.trim_ascii().len(),
+        false => {
+            let (head, tail) = ranges.split_at(ranges.len() / 2);
+            decode_ranges(data, head, operations, offsets, skipped);
+            decode_ranges(data, tail, operations, offsets, skipped);
+        }
+    }
+}
+
+/// Whether a token is an operator: a run of regular characters that is not a number, a
+/// boolean or `null`.
+fn is_operator(token: &[u8]) -> bool {
+    let number = token.strip_prefix(b"+").or_else(|| token.strip_prefix(b"-")).unwrap_or(token);
+    let digits = number.iter().filter(|byte| byte.is_ascii_digit()).count();
+    let is_number = digits > 0 && number.iter().all(|&byte| byte.is_ascii_digit() || byte == b'.') && digits + 1 >= number.len();
+    token.first().is_some_and(|&byte| is_regular(byte)) && !is_number && !matches!(token, b"true" | b"false" | b"null")
+}
+
+/// Offsets in content with its inline images rewritten and in it as it was, of the same place.
+type Shift = (usize, usize);
+
+/// Returns the content with each inline image rewritten, or `None` when it has none, and the
+/// shifts of the start and the end of each image.
+fn rewrite_inline_images(data: &[u8]) -> Option<(Vec<u8>, Vec<Shift>)> {
+    let mut out = Vec::new();
+    let mut shifts = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while let Some((start, end)) = next_token(data, i) {
+        i = end;
+        if &data[start..end] != b"BI" {
+            continue;
+        }
+        let Some(image) = inline_image(data, end) else { continue };
+        out.extend_from_slice(&data[copied..start]);
+        shifts.push((out.len(), start));
+        out.extend_from_slice(b"<<");
+        out.extend_from_slice(image.dict);
+        out.extend_from_slice(b">> <");
+        for byte in image.data {
+            out.extend_from_slice(format!("{:02X}", byte).as_bytes());
+        }
+        out.extend_from_slice(b"> BI\n");
+        shifts.push((out.len(), image.end));
+        copied = image.end;
+        i = image.end;
+    }
+    if copied == 0 {
+        return None;
+    }
+    out.extend_from_slice(&data[copied..]);
+    Some((out, shifts))
+}
+
+struct InlineImage<'a> {
+    /// The key/value pairs between `BI` and `ID`.
+    dict: &'a [u8],
+    data: &'a [u8],
+    /// Offset just past the closing `EI`.
+    end: usize,
+}
+
+/// Parses the inline image whose dictionary starts at `start`, just after `BI`.
+fn inline_image(data: &[u8], start: usize) -> Option<InlineImage<'_>> {
+    let mut i = start;
+    let id_start = loop {
+        let (token_start, token_end) = next_token(data, i)?;
+        if &data[token_start..token_end] == b"ID" {
+            i = token_end;
+            break token_start;
+        }
+        i = token_end;
+    };
+    let dict = &data[start..id_start];
+    // A single white-space byte separates ID from the data.
+    let data_start = if data.get(i).is_some_and(u8::is_ascii_whitespace) { i + 1 } else { i };
+
+    let known = parse_dict(dict).and_then(|dict| data_length(&dict)).and_then(|length| {
+        let data_end = data_start.checked_add(length)?;
+        let ei = skip_whitespace(data, data_end);
+        is_ei(data, ei).then_some((data_end, ei))
+    });
+    let (data_end, ei) = known.or_else(|| find_ei(data, data_start))?;
+    Some(InlineImage { dict, data: &data[data_start..data_end], end: ei + 2 })
+}
+
+/// Parses the inline dictionary by handing it to lopdf as an ordinary operand.
+fn parse_dict(dict: &[u8]) -> Option<Dictionary> {
+    let source = [b"<<", dict, b">> x"].concat();
+    let content = Content::decode(&source).ok()?;
+    match content.operations.into_iter().next()?.operands.into_iter().next()? {
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
+}
+
+/// Length of the image data: `/L` (PDF 2.0) when present, otherwise computed from the image
+/// size for unfiltered images in a colour space we can count components for.
+fn data_length(dict: &Dictionary) -> Option<usize> {
+    let get = |short: &[u8], long: &[u8]| dict.get(short).or_else(|_| dict.get(long)).ok();
+    if let Some(length) = get(b"L", b"Length").and_then(|obj| obj.as_i64().ok()) {
+        return usize::try_from(length).ok();
+    }
+    if get(b"F", b"Filter").is_some() {
+        return None;
+    }
+    let number = |short: &[u8], long: &[u8]| get(short, long).and_then(|obj| obj.as_i64().ok()).map(|n| n.max(0) as usize);
+    let width = number(b"W", b"Width")?;
+    let height = number(b"H", b"Height")?;
+    let image_mask = get(b"IM", b"ImageMask").and_then(|obj| obj.as_bool().ok()).unwrap_or(false);
+    let (components, bits) = if image_mask {
+        (1, 1)
+    } else {
+        let components = match get(b"CS", b"ColorSpace")? {
+            Object::Name(name) => match name.as_slice() {
+                b"G" | b"DeviceGray" | b"CalGray" => 1,
+                b"RGB" | b"DeviceRGB" | b"CalRGB" => 3,
+                b"CMYK" | b"DeviceCMYK" => 4,
+                _ => return None,
+            },
+            Object::Array(array) if matches!(array.first(), Some(Object::Name(name)) if name == b"I" || name == b"Indexed") => 1,
+            _ => return None,
+        };
+        (components, number(b"BPC", b"BitsPerComponent")?)
+    };
+    let stride = (width.checked_mul(components)?.checked_mul(bits)?).div_ceil(8);
+    height.checked_mul(stride)
+}
+
+/// Finds the `EI` ending an image of unknown length: a delimited `EI` followed by bytes that
+/// look like content rather than binary data. Returns the data end and the `EI` offset.
+fn find_ei(data: &[u8], data_start: usize) -> Option<(usize, usize)> {
+    let mut p = data_start;
+    while p + 1 < data.len() {
+        if is_ei(data, p) && (p == data_start || data[p - 1].is_ascii_whitespace()) {
+            let after = &data[p + 2..(p + 2 + EI_LOOKAHEAD).min(data.len())];
+            if after.iter().all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace()) {
+                let data_end = if p > data_start { p - 1 } else { p };
+                return Some((data_end, p));
+            }
+        }
+        p += 1;
+    }
+    None
+}
+
+/// Whether `data[at..]` is the keyword `EI` followed by a delimiter or the end of data.
+fn is_ei(data: &[u8], at: usize) -> bool {
+    data.get(at..at + 2) == Some(b"EI") && data.get(at + 2).is_none_or(|&b| !is_regular(b))
+}
+
+fn skip_whitespace(data: &[u8], mut i: usize) -> usize {
+    while data.get(i).is_some_and(u8::is_ascii_whitespace) {
+        i += 1;
+    }
+    i
+}
+
+/// Returns the next token at or after `i` as a byte range, skipping white space and
+/// comments. Strings, names and delimiters are tokens too, so keywords inside them are
+/// never mistaken for operators.
+fn next_token(data: &[u8], mut i: usize) -> Option<(usize, usize)> {
+    loop {
+        i = skip_whitespace(data, i);
+        if data.get(i) != Some(&b'%') {
+            break;
+        }
+        while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+            i += 1;
+        }
+    }
+    let start = i;
+    match *data.get(i)? {
+        b'(' => {
+            let mut depth = 0;
+            while i < data.len() {
+                match data[i] {
+                    b'\\' => i += 1,
+                    b'(' => depth += 1,
+                    b')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 1;
+        }
+        b'<' | b'>' if data.get(i + 1) == Some(&data[i]) => i += 2,
+        b'<' => {
+            i = data[i..].iter().position(|&b| b == b'>').map_or(data.len(), |p| i + p + 1);
+        }
+        b'/' => {
+            i += 1;
+            while i < data.len() && is_regular(data[i]) {
+                i += 1;
+            }
+        }
+        byte if !is_regular(byte) => i += 1,
+        _ => {
+            while i < data.len() && is_regular(data[i]) {
+                i += 1;
+            }
+        }
+    }
+    Some((start, i.min(data.len())))
+}
+
+fn is_regular(byte: u8) -> bool {
+    !byte.is_ascii_whitespace() && byte != 0 && !b"()<>[]{}/%".contains(&byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ExtractOptions;
+
+    /// The strings shown by the `Tj` operations of `content`.
+    fn shown(content: &Content) -> Vec<String> {
+        content
+            .operations
+            .iter()
+            .filter(|operation| operation.operator == "Tj")
+            .filter_map(|operation| operation.operands.first()?.as_str().ok())
+            .map(|text| String::from_utf8_lossy(text).into_owned())
+            .collect()
+    }
+
+    fn position(data: &[u8], part: &[u8]) -> usize {
+        data.windows(part.len()).position(|window| window == part).unwrap()
+    }
+
+    #[test]
+    fn recovers_the_text_of_content_cut_short() {
+        let content: String = (1..=10).map(|n| format!("BT /F1 12 Tf 72 {} Td (Line {}) Tj ET\n", 740 - 14 * n, n)).collect();
+        let data = content.as_bytes();
+        let tj_ends: Vec<usize> = (1..=10).map(|n| position(data, format!("(Line {}) Tj", n).as_bytes()) + format!("(Line {}) Tj", n).len()).collect();
+        for cut in (1..data.len()).step_by(3) {
+            let (decoded, offsets, skipped) = decode(&data[..cut]);
+            assert_eq!(offsets.len(), decoded.operations.len());
+            // Every line whose `Tj` is whole comes out, and nothing of the line cut.
+            let whole = tj_ends.iter().filter(|&&end| end <= cut).count();
+            let expected: Vec<String> = (1..=whole).map(|n| format!("Line {}", n)).collect();
+            assert_eq!(shown(&decoded), expected, "cut at {}", cut);
+            // Only operands left without their operator are skipped.
+            let line = data[..cut].rsplit(|&byte| byte == b'\n').next().unwrap();
+            assert!(skipped <= line.len(), "cut at {}: skipped {} of {:?}", cut, skipped, String::from_utf8_lossy(line));
+            if line.ends_with(b")") {
+                assert_eq!(skipped, format!("(Line {})", whole + 1).len(), "cut at {}", cut);
+            }
+        }
+
+        // Cut inside the fifth line's string: the page has the first four and says what it
+        // left out.
+        let cut = position(data, b"(Line 5)") + 3;
+        let pages = testing::pages_with(&testing::pdf(&[&content[..cut]]), &ExtractOptions::default());
+        assert_eq!(testing::texts(&pages[0].items), ["Line 1", "Line 2", "Line 3", "Line 4"]);
+        let messages: Vec<&str> = pages[0].errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(messages, ["skipped 3 bytes that are not content operations"]);
+    }
+
+    #[test]
+    fn skips_stray_bytes_after_an_inline_image() {
+        let data = b"BT /F1 12 Tf 72 720 Td (A) Tj ET BI /W 2 /H 1 /CS /G /BPC 8 ID \x00\x01 EI \xff\xfe\x80 BT (B) Tj ET";
+        let (content, offsets, skipped) = decode(data);
+        assert_eq!(shown(&content), ["A", "B"]);
+        assert_eq!(skipped, 3);
+        let image = content.operations.iter().find(|operation| operation.operator == "BI").unwrap();
+        assert_eq!(image.operands[1].as_str().unwrap(), [0, 1]);
+        // Offsets are of the content as given, not as its images were rewritten.
+        let operators: Vec<&str> = content.operations.iter().map(|operation| operation.operator.as_str()).collect();
+        let second = operators.iter().rposition(|&operator| operator == "BT").unwrap();
+        assert_eq!(offsets[second], position(data, b"BT (B)"));
+        assert_eq!(offsets[operators.iter().position(|&operator| operator == "BI").unwrap()], position(data, b"BI"));
+    }
+
+    #[test]
+    fn ends_an_image_of_unknown_length_at_an_ei_followed_by_content() {
+        // The first `EI` runs into a byte of data, the second does not.
+        let data = b"BT (A) Tj ET BI /W 4 /H 1 /F /AHx ID 0a EI\x80 0b0c> EI BT (B) Tj ET";
+        let (content, _, skipped) = decode(data);
+        assert_eq!((shown(&content), skipped), (vec!["A".to_string(), "B".to_string()], 0));
+        let image = content.operations.iter().find(|operation| operation.operator == "BI").unwrap();
+        assert_eq!(image.operands[1].as_str().unwrap(), b"0a EI\x80 0b0c>");
+        assert_eq!(find_ei(b"ID \x01EI\x02 EI Q", 3), Some((7, 8)));
+        assert_eq!(find_ei(b"\x01 EI \x02\x03", 0), None);
+    }
+
+    #[test]
+    fn reads_an_image_of_known_length_holding_ei() {
+        // 4 bytes of 8-bit gray, the last two `EI`, which a search would end the image at.
+        let data = b"BT (A) Tj ET BI /W 4 /H 1 /CS /G /BPC 8 ID a EI EI BT (B) Tj ET";
+        let (content, _, skipped) = decode(data);
+        assert_eq!((shown(&content), skipped), (vec!["A".to_string(), "B".to_string()], 0));
+        let image = content.operations.iter().find(|operation| operation.operator == "BI").unwrap();
+        assert_eq!(image.operands[1].as_str().unwrap(), b"a EI");
+    }
+
+    #[test]
+    fn keeps_operators_missing_operands() {
+        let data = b"BT /F1 12 Tf 72 720 Td Tj 72 Td (After) Tj ET";
+        let (content, offsets, skipped) = decode(data);
+        let operations: Vec<(&str, usize)> = content.operations.iter().map(|operation| (operation.operator.as_str(), operation.operands.len())).collect();
+        assert_eq!(operations, [("BT", 0), ("Tf", 2), ("Td", 2), ("Tj", 0), ("Td", 1), ("Tj", 1), ("ET", 0)]);
+        assert_eq!((offsets[3], skipped), (position(data, b"Tj"), 0));
+        assert_eq!(testing::texts(&testing::items(std::str::from_utf8(data).unwrap())), ["After"]);
+    }
+
+    #[test]
+    fn counts_operations_by_their_operators() {
+        let ranges = operation_ranges(b"1 0 0 1 72 720 cm (a Tj b) Tj /Name true -0.5 +3 Tz % Tj\nET");
+        assert_eq!(ranges, [0..17, 17..29, 29..51, 51..59]);
+        assert!(rewrite_inline_images(b"BT (BI) Tj ET").is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! What `debug_ops` logs: content stream operands laid out as PDF syntax, and
+//! lengths in points.
+
+use crate::matrix::Rect;
+use lopdf::Object;
+
+/// A length in points, as the debug output gives them.
+pub(crate) fn length(points: f32) -> String {
+    format!("{:.2}", points)
+}
+
+pub(crate) fn rect(rect: &Rect) -> String {
+    format!("[{} {} {} {}]", length(rect.x0), length(rect.y0), length(rect.x1), length(rect.y1))
+}
+
+/// Pieces of pending output for `print_with_layout`. Containers are expanded onto an
+/// explicit stack instead of recursing, so deeply nested dictionaries cannot overflow.
+enum LayoutPiece<'a> {
+    Value(&'a Object, usize),
+    Text(String),
+}
+
+pub(crate) fn print_with_layout(obj: &Object) -> String {
+    let mut out = String::new();
+    let mut stack = vec![LayoutPiece::Value(obj, 0)];
+
+    while let Some(piece) = stack.pop() {
+        let (obj, depth) = match piece {
+            LayoutPiece::Text(text) => {
+                out.push_str(&text);
+                continue;
+            }
+            LayoutPiece::Value(obj, depth) => (obj, depth),
+        };
+        let indent = "  ".repeat(depth);
+        let inner = "  ".repeat(depth + 1);
+
+        match obj {
+            Object::Null => out.push_str("null"),
+            Object::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            Object::Integer(i) => out.push_str(&i.to_string()),
+            Object::Real(r) => out.push_str(&format_real(*r)),
+            Object::Name(name) => out.push_str(&format_name(name)),
+            Object::String(bytes, lopdf::StringFormat::Literal) => {
+                out.push('(');
+                out.push_str(&escape_bytes(bytes));
+                out.push(')');
+            }
+            Object::String(bytes, lopdf::StringFormat::Hexadecimal) => {
+                out.push('<');
+                for byte in bytes {
+                    out.push_str(&format!("{:02X}", byte));
+                }
+                out.push_str(&format!("> \"{}\"", escape_bytes(bytes)));
+            }
+            Object::Reference((id, generation)) => out.push_str(&format!("{} {} R", id, generation)),
+            Object::Array(items) if items.is_empty() => out.push_str("[]"),
+            Object::Array(items) => {
+                out.push_str("[\n");
+                stack.push(LayoutPiece::Text(format!("{}]", indent)));
+                for item in items.iter().rev() {
+                    stack.push(LayoutPiece::Text("\n".to_string()));
+                    stack.push(LayoutPiece::Value(item, depth + 1));
+                    stack.push(LayoutPiece::Text(inner.clone()));
+                }
+            }
+            Object::Dictionary(dict) => push_dictionary(&mut out, &mut stack, dict, depth, None),
+            Object::Stream(stream) => {
+                let summary = format!(" stream ({} bytes)", stream.content.len());
+                push_dictionary(&mut out, &mut stack, &stream.dict, depth, Some(summary));
+            }
+        }
+    }
+
+    out
+}
+
+fn push_dictionary<'a>(
+    out: &mut String,
+    stack: &mut Vec<LayoutPiece<'a>>,
+    dict: &'a lopdf::Dictionary,
+    depth: usize,
+    suffix: Option<String>,
+) {
+    if let Some(suffix) = suffix {
+        stack.push(LayoutPiece::Text(suffix));
+    }
+    if dict.is_empty() {
+        out.push_str("<<>>");
+        return;
+    }
+    let indent = "  ".repeat(depth);
+    let inner = "  ".repeat(depth + 1);
+    out.push_str("<<\n");
+    stack.push(LayoutPiece::Text(format!("{}>>", indent)));
+    let entries: Vec<_> = dict.iter().collect();
+    for (key, value) in entries.into_iter().rev() {
+        stack.push(LayoutPiece::Text("\n".to_string()));
+        stack.push(LayoutPiece::Value(value, depth + 1));
+        stack.push(LayoutPiece::Text(format!("{}{} ", inner, format_name(key))));
+    }
+}
+
+/// Formats a real with at most four decimals, dropping trailing zeros.
+fn format_real(value: f32) -> String {
+    let formatted = format!("{:.4}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" { "0".to_string() } else { trimmed.to_string() }
+}
+
+/// Formats a name as `/Name`, using `#xx` escapes for delimiters and non-printable bytes.
+fn format_name(name: &[u8]) -> String {
+    let mut out = String::from("/");
+    for &byte in name {
+        if byte.is_ascii_graphic() && !b"#()<>[]{}/%".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("#{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Decodes string bytes for display: UTF-16BE (with BOM) and valid UTF-8 are shown as
+/// text, and anything non-printable is escaped the way it would be written in a PDF
+/// literal string.
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let decoded = match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+            String::from_utf16_lossy(&units).into()
+        }
+        _ => String::from_utf8_lossy(bytes),
+    };
+    if !decoded.contains('\u{FFFD}') {
+        for ch in decoded.chars() {
+            match ch {
+                '(' | ')' | '\\' => {
+                    out.push('\\');
+                    out.push(ch);
+                }
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                '\u{8}' => out.push_str("\\b"),
+                '\u{c}' => out.push_str("\\f"),
+                c if c.is_control() => {
+                    let mut buf = [0u8; 4];
+                    for byte in c.encode_utf8(&mut buf).bytes() {
+                        out.push_str(&format!("\\{:03o}", byte));
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        return out;
+    }
+    for &byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Dictionary, Stream, StringFormat};
+
+    #[test]
+    fn prints_scalars_as_pdf_syntax() {
+        assert_eq!(print_with_layout(&Object::Null), "null");
+        assert_eq!(print_with_layout(&Object::Boolean(true)), "true");
+        assert_eq!(print_with_layout(&Object::Integer(-42)), "-42");
+        assert_eq!(print_with_layout(&Object::Real(0.5)), "0.5");
+        assert_eq!(print_with_layout(&Object::Real(12.0)), "12");
+        assert_eq!(print_with_layout(&Object::Real(1.0 / 3.0)), "0.3333");
+        assert_eq!(print_with_layout(&Object::Real(-0.00001)), "0");
+        assert_eq!(print_with_layout(&Object::Reference((12, 0))), "12 0 R");
+    }
+
+    #[test]
+    fn escapes_names() {
+        assert_eq!(print_with_layout(&Object::Name(b"Helvetica".to_vec())), "/Helvetica");
+        assert_eq!(print_with_layout(&Object::Name(b"A B/(C)".to_vec())), "/A#20B#2F#28C#29");
+    }
+
+    #[test]
+    fn decodes_literal_strings_and_escapes_the_rest() {
+        let literal = |bytes: &[u8]| print_with_layout(&Object::String(bytes.to_vec(), StringFormat::Literal));
+        assert_eq!(literal(b"Hello (world)\n"), "(Hello \\(world\\)\\n)");
+        assert_eq!(literal("caf\u{e9}".as_bytes()), "(caf\u{e9})");
+        assert_eq!(literal(b"\x01A"), "(\\001A)");
+        assert_eq!(literal(b"\xff\x00"), "(\\377\\000)");
+        assert_eq!(literal(b"\xfe\xff\x00H\x00i"), "(Hi)");
+    }
+
+    #[test]
+    fn shows_hex_strings_with_a_preview() {
+        let hex = Object::String(b"Hi\x00".to_vec(), StringFormat::Hexadecimal);
+        assert_eq!(print_with_layout(&hex), "<486900> \"Hi\\000\"");
+    }
+
+    #[test]
+    fn indents_arrays_and_dictionaries() {
+        assert_eq!(print_with_layout(&Object::Array(Vec::new())), "[]");
+        assert_eq!(print_with_layout(&Object::Dictionary(Dictionary::new())), "<<>>");
+        let object = Object::Dictionary(dictionary! {
+            "Type" => "Font",
+            "Widths" => vec![Object::Integer(250), Object::Real(333.5)],
+            "FontDescriptor" => dictionary! { "Flags" => 32 },
+        });
+        let expected = "<<\n  /Type /Font\n  /Widths [\n    250\n    333.5\n  ]\n  /FontDescriptor <<\n    /Flags 32\n  >>\n>>";
+        assert_eq!(print_with_layout(&object), expected);
+    }
+
+    #[test]
+    fn summarizes_streams() {
+        let stream = Object::Stream(Stream::new(dictionary! { "Length" => 5 }, b"BT ET".to_vec()));
+        assert_eq!(print_with_layout(&stream), "<<\n  /Length 5\n>> stream (5 bytes)");
+    }
+
+    #[test]
+    fn lays_out_deep_nesting_without_recursing() {
+        let mut object = Object::Integer(1);
+        for _ in 0..5000 {
+            object = Object::Array(vec![object]);
+        }
+        let printed = print_with_layout(&object);
+        assert!(printed.starts_with("[\n  [\n"));
+        assert_eq!(printed.matches('[').count(), 5000);
+        // Nested objects drop one another in turn rather than recursively.
+        while let Object::Array(mut items) = object {
+            object = items.pop().unwrap_or(Object::Null);
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Destinations (PDF 32000-1:2008, 12.3.2), the pages that outline entries and links lead to,
+//! and the name trees they may be looked up in.
+
+use crate::decode_text_string;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How deeply the nodes of name trees may nest before the rest of a branch is ignored.
+const MAX_DEPTH: usize = 256;
+
+/// Where a destination leads: a page and how it is shown, from the forms of destination
+/// arrays (12.3.2.2). Coordinates are those of the page's default user space, measured from
+/// the bottom-left corner of its media box; those left unchanged by the destination are
+/// `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Destination {
+    /// The page, counted from 1, or `None` when the destination names none of the document.
+    pub page: Option<u32>,
+    pub fit: Fit,
+    pub left: Option<f32>,
+    pub top: Option<f32>,
+    /// The right and bottom edges of the rectangle of `FitR`.
+    pub right: Option<f32>,
+    pub bottom: Option<f32>,
+    /// The zoom of `XYZ`, 1 for 100 %.
+    pub zoom: Option<f32>,
+}
+
+/// How a destination shows its page, after the operator that names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub enum Fit {
+    /// At `left` and `top`, magnified by `zoom`.
+    #[serde(rename = "xyz")]
+    Xyz,
+    /// The whole page in the window.
+    #[serde(rename = "fit")]
+    Fit,
+    /// The width of the page in the window, `top` at the top.
+    #[serde(rename = "fit-h")]
+    FitH,
+    /// The height of the page in the window, `left` at the left.
+    #[serde(rename = "fit-v")]
+    FitV,
+    /// The rectangle from `left`, `bottom` to `right`, `top` in the window.
+    #[serde(rename = "fit-r")]
+    FitR,
+    /// As `Fit`, `FitH` and `FitV`, for the bounding box of the page's contents.
+    #[serde(rename = "fit-b")]
+    FitB,
+    #[serde(rename = "fit-bh")]
+    FitBH,
+    #[serde(rename = "fit-bv")]
+    FitBV,
+}
+
+/// Resolves destinations of a document, the one way outline entries, links and the named
+/// destinations themselves are read.
+pub struct Destinations<'a> {
+    pub doc: &'a Document,
+    /// Page numbers, counted from 1, by page object, those of all pages.
+    pub pages: &'a HashMap<ObjectId, u32>,
+}
+
+impl<'a> Destinations<'a> {
+    /// The page of the destination of a go-to action; `None` for other actions.
+    pub fn action_page(&self, action: &Dictionary) -> Option<u32> {
+        match action.get(b"S").and_then(Object::as_name).ok()? {
+            b"GoTo" => self.page(action.get(b"D").ok()?),
+            _ => None,
+        }
+    }
+
+    /// The page of a destination, as [`Destinations::resolve`] finds it.
+    pub fn page(&self, dest: &Object) -> Option<u32> {
+        self.resolve(dest)?.page
+    }
+
+    /// A destination: an array starting with the page, or the name of one in the document's
+    /// named destinations, which may in turn be a dictionary with `/D`. `None` when it is
+    /// none of these, or its name is not defined.
+    pub fn resolve(&self, dest: &Object) -> Option<Destination> {
+        self.resolve_within(dest, 0)
+    }
+
+    fn resolve_within(&self, dest: &Object, depth: usize) -> Option<Destination> {
+        if depth > 2 {
+            return None;
+        }
+        match self.doc.dereference(dest).ok()?.1 {
+            Object::Array(dest) => Some(self.explicit(dest)),
+            Object::Dictionary(dest) => self.resolve_within(dest.get(b"D").ok()?, depth + 1),
+            Object::Name(name) | Object::String(name, _) => self.resolve_within(self.named_dest(name)?, depth + 1),
+            _ => None,
+        }
+    }
+
+    /// A destination array: the page and the fit operator with its numbers, any of which may
+    /// be missing or `null`. An unknown operator reads as `Fit`.
+    fn explicit(&self, dest: &[Object]) -> Destination {
+        let page = match dest.first() {
+            Some(Object::Reference(id)) => self.pages.get(id).copied(),
+            // Some producers give the page by its index, as for remote go-to actions.
+            Some(Object::Integer(index)) => u32::try_from(*index).ok().map(|index| index + 1).filter(|page| *page as usize <= self.pages.len()),
+            _ => None,
+        };
+        let number = |index: usize| dest.get(index).and_then(|value| self.doc.dereference(value).ok()).and_then(|(_, value)| value.as_float().ok());
+        let fit = match dest.get(1).and_then(|fit| fit.as_name().ok()) {
+            Some(b"XYZ") => Fit::Xyz,
+            Some(b"FitH") => Fit::FitH,
+            Some(b"FitV") => Fit::FitV,
+            Some(b"FitR") => Fit::FitR,
+            Some(b"FitB") => Fit::FitB,
+            Some(b"FitBH") => Fit::FitBH,
+            Some(b"FitBV") => Fit::FitBV,
+            _ => Fit::Fit,
+        };
+        let mut destination = Destination { page, fit, left: None, top: None, right: None, bottom: None, zoom: None };
+        match fit {
+            Fit::Xyz => {
+                destination.left = number(2);
+                destination.top = number(3);
+                // A zoom of 0 leaves it unchanged, as null does.
+                destination.zoom = number(4).filter(|zoom| *zoom != 0.0);
+            }
+            Fit::FitH | Fit::FitBH => destination.top = number(2),
+            Fit::FitV | Fit::FitBV => destination.left = number(2),
+            Fit::FitR => {
+                destination.left = number(2);
+                destination.bottom = number(3);
+                destination.right = number(4);
+                destination.top = number(5);
+            }
+            Fit::Fit | Fit::FitB => {}
+        }
+        destination
+    }
+
+    /// All named destinations of the document, by name: those of the `/Dests` dictionary of
+    /// the catalog and of the `/Dests` name tree of its `/Names`, the tree's first where a
+    /// name is in both. Entries that are not destinations are left out.
+    pub fn named(&self) -> BTreeMap<String, Destination> {
+        let mut named = BTreeMap::new();
+        for (name, dest) in self.dests_tree().map(|tree| name_tree_entries(self.doc, tree)).unwrap_or_default() {
+            if let Some(destination) = self.resolve_within(dest, 1) {
+                named.entry(decode_text_string(name)).or_insert(destination);
+            }
+        }
+        let dests = self.doc.catalog().and_then(|catalog| catalog.get_deref(b"Dests", self.doc)).and_then(Object::as_dict);
+        for (name, dest) in dests.into_iter().flatten() {
+            if let Some(destination) = self.resolve_within(dest, 1) {
+                named.entry(String::from_utf8_lossy(name).into_owned()).or_insert(destination);
+            }
+        }
+        named
+    }
+
+    /// The `/Dests` name tree of the catalog's `/Names`.
+    fn dests_tree(&self) -> Option<&'a Dictionary> {
+        let names = self.doc.catalog().ok()?.get_deref(b"Names", self.doc).and_then(Object::as_dict).ok()?;
+        names.get_deref(b"Dests", self.doc).and_then(Object::as_dict).ok()
+    }
+
+    /// A named destination: in the `/Dests` dictionary of the catalog, for names, or the
+    /// `/Dests` name tree of its `/Names`, for strings. Either may be used for either.
+    fn named_dest(&self, name: &[u8]) -> Option<&'a Object> {
+        let catalog = self.doc.catalog().ok()?;
+        let dests = catalog.get_deref(b"Dests", self.doc).and_then(Object::as_dict).ok();
+        if let Some(dest) = dests.and_then(|dests| dests.get(name).ok()) {
+            return Some(dest);
+        }
+        let tree = self.dests_tree()?;
+        // Producers get the `/Limits` of the tree's kids wrong, unsorted or stale, so a name
+        // they rule out is looked for in the whole tree.
+        self.name_tree_lookup(tree, name, &mut HashSet::new(), 0)
+            .or_else(|| name_tree_entries(self.doc, tree).into_iter().find(|&(key, _)| key == name).map(|(_, dest)| dest))
+    }
+
+    /// Looks a key up in a name tree (7.9.6), going by the `/Limits` of its kids.
+    fn name_tree_lookup(&self, node: &'a Dictionary, key: &[u8], visited: &mut HashSet<ObjectId>, depth: usize) -> Option<&'a Object> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        if let Ok(names) = node.get_deref(b"Names", self.doc).and_then(Object::as_array) {
+            let found = names.chunks_exact(2).find(|pair| self.doc.dereference(&pair[0]).ok().and_then(|(_, name)| name.as_str().ok()) == Some(key));
+            if let Some(pair) = found {
+                return Some(&pair[1]);
+            }
+        }
+        let kids = node.get_deref(b"Kids", self.doc).and_then(Object::as_array).ok()?;
+        for kid in kids {
+            let Ok(id) = kid.as_reference() else { continue };
+            if !visited.insert(id) {
+                continue;
+            }
+            let Ok(kid) = self.doc.get_dictionary(id) else { continue };
+            let limits = kid.get_deref(b"Limits", self.doc).and_then(Object::as_array).ok();
+            let within = limits.is_none_or(|limits| {
+                let limit = |index: usize| limits.get(index).and_then(|limit| limit.as_str().ok());
+                limit(0).is_none_or(|low| low <= key) && limit(1).is_none_or(|high| key <= high)
+            });
+            if within {
+                if let Some(dest) = self.name_tree_lookup(kid, key, visited, depth + 1) {
+                    return Some(dest);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The entries of a name tree (7.9.6), keys with their values, in the order of the tree.
+pub(crate) fn name_tree_entries<'a>(doc: &'a Document, root: &'a Dictionary) -> Vec<(&'a [u8], &'a Object)> {
+    fn walk<'a>(doc: &'a Document, node: &'a Dictionary, visited: &mut HashSet<ObjectId>, depth: usize, entries: &mut Vec<(&'a [u8], &'a Object)>) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        if let Ok(names) = node.get_deref(b"Names", doc).and_then(Object::as_array) {
+            for pair in names.chunks_exact(2) {
+                if let Some(key) = doc.dereference(&pair[0]).ok().and_then(|(_, key)| key.as_str().ok()) {
+                    entries.push((key, &pair[1]));
+                }
+            }
+        }
+        let kids = node.get_deref(b"Kids", doc).and_then(Object::as_array);
+        for id in kids.into_iter().flatten().filter_map(|kid| kid.as_reference().ok()) {
+            if visited.insert(id) {
+                if let Ok(kid) = doc.get_dictionary(id) {
+                    walk(doc, kid, visited, depth + 1, entries);
+                }
+            }
+        }
+    }
+    let mut entries = Vec::new();
+    walk(doc, root, &mut HashSet::new(), 0, &mut entries);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use lopdf::dictionary;
+
+    fn dest(values: Vec<Object>) -> Object {
+        Object::Array(values)
+    }
+
+    #[test]
+    fn resolves_named_destinations() {
+        let mut fixture = Fixture::new();
+        fixture.page("").page("").page("");
+        let mut doc = fixture.document(Dictionary::new());
+        let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+        let third = pages.iter().find_map(|(&id, &page_num)| (page_num == 3).then_some(id)).unwrap();
+        // A kid whose `/Limits` leave out a name it holds, as some producers write them.
+        let low = doc.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("a"), Object::string_literal("b")],
+            "Names" => vec![
+                Object::string_literal("chapter1"), dest(vec![0.into(), "XYZ".into(), 72.into(), 720.into(), 0.into()]),
+                Object::string_literal("zoomed"), dest(vec![0.into(), "XYZ".into(), Object::Null, 500.into(), 1.5.into()]),
+            ],
+        });
+        let high = doc.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("figure"), Object::string_literal("table")],
+            "Names" => vec![
+                Object::string_literal("figure"), dictionary! { "D" => dest(vec![third.into(), "FitR".into(), 10.into(), 20.into(), 300.into(), 400.into()]) }.into(),
+                Object::string_literal("table"), dest(vec![third.into(), "FitH".into(), 650.into()]),
+            ],
+        });
+        let tree = doc.add_object(dictionary! { "Kids" => vec![low.into(), high.into()] });
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("Names", dictionary! { "Dests" => tree });
+        catalog.set("Dests", dictionary! { "chapter1" => dest(vec![1.into(), "Fit".into()]), "cover" => dest(vec![0.into(), "Fit".into()]), "broken" => 12 });
+        let destinations = Destinations { doc: &doc, pages: &pages };
+
+        let named = destinations.named();
+        assert_eq!(named.keys().collect::<Vec<_>>(), ["chapter1", "cover", "figure", "table", "zoomed"]);
+        // The tree's wins over the dictionary's.
+        assert_eq!((named["chapter1"].page, named["chapter1"].fit, named["chapter1"].top, named["chapter1"].zoom), (Some(1), Fit::Xyz, Some(720.0), None));
+        assert_eq!((named["zoomed"].left, named["zoomed"].zoom), (None, Some(1.5)));
+        let figure = named["figure"];
+        assert_eq!((figure.page, figure.fit, figure.left, figure.bottom, figure.right, figure.top), (Some(3), Fit::FitR, Some(10.0), Some(20.0), Some(300.0), Some(400.0)));
+        assert_eq!((named["table"].page, named["table"].top), (Some(3), Some(650.0)));
+        // Links and outline entries name them by string or by name, either in either place.
+        assert_eq!(destinations.page(&Object::string_literal("zoomed")), Some(1));
+        assert_eq!(destinations.page(&Object::Name(b"cover".to_vec())), Some(1));
+        assert_eq!(destinations.page(&Object::Name(b"table".to_vec())), Some(3));
+        assert_eq!(destinations.page(&Object::Name(b"missing".to_vec())), None);
+        assert_eq!(destinations.page(&dest(vec![7.into(), "Fit".into()])), None);
+    }
+}
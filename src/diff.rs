@@ -0,0 +1,296 @@
+//! `--diff`: the words inserted, deleted and changed between the text of two documents, page
+//! by page, with where they are on the pages of each.
+
+use crate::report::finite;
+use crate::Units;
+use rapid_pdf::{Origin, PageText, Rect};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How many words of the text both documents share are given before and after a change.
+const CONTEXT_WORDS: usize = 5;
+
+/// Hyphens ignored when comparing words, so that a word broken across lines in one document
+/// matches it whole in the other.
+const HYPHENS: [char; 3] = ['-', '\u{AD}', '\u{2010}'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    Insert,
+    Delete,
+    Change,
+}
+
+/// Words of one document replaced by words of the other, on a page of both.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub page: u32,
+    pub kind: ChangeKind,
+    /// The words of the first document left out of the second, and those the second has
+    /// instead, joined by spaces.
+    pub deleted: String,
+    pub inserted: String,
+    /// Words both have just before and after the change.
+    pub before: String,
+    pub after: String,
+    /// The boxes of the words deleted on the page of the first document, one for each line
+    /// they are on, and of those inserted on the page of the second.
+    pub deleted_boxes: Vec<Rect>,
+    pub inserted_boxes: Vec<Rect>,
+}
+
+impl Change {
+    /// The change with its boxes in `units` rather than points.
+    pub fn scaled(self, units: Units) -> Change {
+        let scale = units.per_point();
+        let scaled = |boxes: Vec<Rect>| {
+            boxes
+                .into_iter()
+                .map(|rect| Rect { x0: finite(rect.x0 * scale), y0: finite(rect.y0 * scale), x1: finite(rect.x1 * scale), y1: finite(rect.y1 * scale) })
+                .collect()
+        };
+        Change { deleted_boxes: scaled(self.deleted_boxes), inserted_boxes: scaled(self.inserted_boxes), ..self }
+    }
+
+    /// The change as unified diff: a head with the page and boxes, the words before, those
+    /// deleted and those inserted, and the words after, a line each. `rect` writes a box.
+    pub fn unified(&self, rect: impl Fn(&Rect) -> String) -> String {
+        let boxes = |boxes: &[Rect]| boxes.iter().map(&rect).collect::<Vec<_>>().join(" ");
+        let mut head = format!("@@ page {}", self.page);
+        if !self.deleted_boxes.is_empty() {
+            head += &format!(" -{}", boxes(&self.deleted_boxes));
+        }
+        if !self.inserted_boxes.is_empty() {
+            head += &format!(" +{}", boxes(&self.inserted_boxes));
+        }
+        let mut lines = vec![head + " @@"];
+        let one_line = |text: &str| text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect::<String>();
+        if !self.before.is_empty() {
+            lines.push(format!(" {}", one_line(&self.before)));
+        }
+        if !self.deleted.is_empty() {
+            lines.push(format!("-{}", one_line(&self.deleted)));
+        }
+        if !self.inserted.is_empty() {
+            lines.push(format!("+{}", one_line(&self.inserted)));
+        }
+        if !self.after.is_empty() {
+            lines.push(format!(" {}", one_line(&self.after)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// The result of `--diff --format json`.
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    /// The files compared, as given, the first of them the one changed from.
+    pub a: String,
+    pub b: String,
+    /// The unit of the boxes.
+    pub units: Units,
+    pub origin: Origin,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub changes: usize,
+    pub differences: Vec<Change>,
+}
+
+impl DiffReport {
+    pub fn new(a: String, b: String, units: Units, origin: Origin, differences: Vec<Change>) -> DiffReport {
+        let count = |kind: ChangeKind| differences.iter().filter(|change| change.kind == kind).count();
+        let (insertions, deletions, changes) = (count(ChangeKind::Insert), count(ChangeKind::Delete), count(ChangeKind::Change));
+        DiffReport { a, b, units, origin, insertions, deletions, changes, differences }
+    }
+}
+
+/// A word of a page as compared.
+struct Word {
+    text: String,
+    /// The text compared: without hyphens.
+    key: String,
+    /// The boxes of the word, with the number of the line each is on: two for a word
+    /// hyphenated across lines.
+    boxes: Vec<(usize, Rect)>,
+}
+
+/// The differences between the pages of two documents, those of the same number compared
+/// with each other, in page order. A page only one of them has is all inserted or deleted.
+pub fn compare(a: &[PageText], b: &[PageText], origin: Origin, word_gap: f32) -> Vec<Change> {
+    let mut pages: BTreeMap<u32, (Option<&PageText>, Option<&PageText>)> = BTreeMap::new();
+    for page in a {
+        pages.entry(page.page_num).or_default().0 = Some(page);
+    }
+    for page in b {
+        pages.entry(page.page_num).or_default().1 = Some(page);
+    }
+    let mut changes = Vec::new();
+    for (page_num, (a, b)) in pages {
+        let a = a.map(|page| words(page, origin, word_gap)).unwrap_or_default();
+        let b = b.map(|page| words(page, origin, word_gap)).unwrap_or_default();
+        changes.extend(page_changes(page_num, &a, &b));
+    }
+    changes
+}
+
+/// The words of a page in reading order, normalized so that differences in white space and
+/// in hyphenation are not told: a word ending a line with a hyphen is joined to the word
+/// starting the next.
+fn words(page: &PageText, origin: Origin, word_gap: f32) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let lines = page.paragraphs.iter().flat_map(|paragraph| &paragraph.lines);
+    for (line_index, line) in lines.enumerate() {
+        for (index, (text, bbox)) in line.words(origin, word_gap).into_iter().enumerate() {
+            let broken = index == 0 && words.last().is_some_and(|last| ends_broken(last, line_index));
+            match words.last_mut() {
+                Some(last) if broken => {
+                    last.text.pop();
+                    last.text.push_str(&text);
+                    last.key = key(&last.text);
+                    last.boxes.push((line_index, bbox));
+                }
+                _ => words.push(Word { key: key(&text), text, boxes: vec![(line_index, bbox)] }),
+            }
+        }
+    }
+    words
+}
+
+/// Whether a word ends the line before `line` with a hyphen after a letter, breaking it.
+fn ends_broken(word: &Word, line: usize) -> bool {
+    let mut chars = word.text.chars().rev();
+    let broken = chars.next().is_some_and(|c| HYPHENS.contains(&c)) && chars.next().is_some_and(char::is_alphabetic);
+    broken && word.boxes.last().is_some_and(|&(last, _)| last + 1 == line)
+}
+
+/// The text of a word as compared: without hyphens, unless it is nothing but hyphens.
+fn key(text: &str) -> String {
+    let key: String = text.chars().filter(|c| !HYPHENS.contains(c)).collect();
+    if key.is_empty() {
+        text.to_string()
+    } else {
+        key
+    }
+}
+
+/// An edit turning one sequence of words into another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// The words at these indexes of the first and the second are the same.
+    Same(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// The changes from the words of a page of the first document to those of the second, each
+/// a run of edits between words both have.
+fn page_changes(page: u32, a: &[Word], b: &[Word]) -> Vec<Change> {
+    let a_keys: Vec<&str> = a.iter().map(|word| word.key.as_str()).collect();
+    let b_keys: Vec<&str> = b.iter().map(|word| word.key.as_str()).collect();
+    let mut edits = Vec::new();
+    diff(&a_keys, 0, &b_keys, 0, &mut edits);
+    let mut changes = Vec::new();
+    let mut start = 0;
+    while start < edits.len() {
+        if let Edit::Same(..) = edits[start] {
+            start += 1;
+            continue;
+        }
+        let end = edits[start..].iter().position(|edit| matches!(edit, Edit::Same(..))).map_or(edits.len(), |offset| start + offset);
+        let deleted: Vec<&Word> = edits[start..end].iter().filter_map(|&edit| if let Edit::Delete(i) = edit { Some(&a[i]) } else { None }).collect();
+        let inserted: Vec<&Word> = edits[start..end].iter().filter_map(|&edit| if let Edit::Insert(j) = edit { Some(&b[j]) } else { None }).collect();
+        let same = |edit: &Edit| if let Edit::Same(i, _) = *edit { Some(a[i].text.as_str()) } else { None };
+        let mut before: Vec<&str> = edits[..start].iter().rev().map_while(same).take(CONTEXT_WORDS).collect();
+        before.reverse();
+        let after: Vec<&str> = edits[end..].iter().map_while(same).take(CONTEXT_WORDS).collect();
+        let kind = match (deleted.is_empty(), inserted.is_empty()) {
+            (true, _) => ChangeKind::Insert,
+            (_, true) => ChangeKind::Delete,
+            _ => ChangeKind::Change,
+        };
+        changes.push(Change {
+            page,
+            kind,
+            deleted: joined(&deleted),
+            inserted: joined(&inserted),
+            before: before.join(" "),
+            after: after.join(" "),
+            deleted_boxes: line_boxes(&deleted),
+            inserted_boxes: line_boxes(&inserted),
+        });
+        start = end;
+    }
+    changes
+}
+
+fn joined(words: &[&Word]) -> String {
+    words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+/// The boxes of words, those of each line joined into one.
+fn line_boxes(words: &[&Word]) -> Vec<Rect> {
+    let mut boxes: Vec<(usize, Rect)> = Vec::new();
+    for &(line, rect) in words.iter().flat_map(|word| &word.boxes) {
+        match boxes.last_mut() {
+            Some((last, bbox)) if *last == line => *bbox = bbox.union(&rect),
+            _ => boxes.push((line, rect)),
+        }
+    }
+    boxes.into_iter().map(|(_, rect)| rect).collect()
+}
+
+/// Adds to `edits` the fewest that turn `a` into `b`, by a longest common subsequence, their
+/// indexes offset by `a_start` and `b_start`. Words the two start and end with are matched
+/// first, then what is left is split in two, Hirschberg's way, so that memory stays linear in
+/// the number of words however different the pages are.
+fn diff<'a>(a: &[&'a str], a_start: usize, b: &[&'a str], b_start: usize, edits: &mut Vec<Edit>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    edits.extend((0..prefix).map(|i| Edit::Same(a_start + i, b_start + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a_start, b_start) = (a_start + prefix, b_start + prefix);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_middle, b_middle) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    match (a_middle.len(), b_middle.len()) {
+        (0, _) => edits.extend((0..b_middle.len()).map(|j| Edit::Insert(b_start + j))),
+        (_, 0) => edits.extend((0..a_middle.len()).map(|i| Edit::Delete(a_start + i))),
+        (1, _) => match b_middle.iter().position(|word| *word == a_middle[0]) {
+            Some(found) => {
+                edits.extend((0..found).map(|j| Edit::Insert(b_start + j)));
+                edits.push(Edit::Same(a_start, b_start + found));
+                edits.extend((found + 1..b_middle.len()).map(|j| Edit::Insert(b_start + j)));
+            }
+            None => {
+                edits.push(Edit::Delete(a_start));
+                edits.extend((0..b_middle.len()).map(|j| Edit::Insert(b_start + j)));
+            }
+        },
+        (length, _) => {
+            let half = length / 2;
+            let forward = lcs_lengths(&a_middle[..half], b_middle);
+            let reversed = |words: &[&'a str]| words.iter().rev().copied().collect::<Vec<_>>();
+            let backward = lcs_lengths(&reversed(&a_middle[half..]), &reversed(b_middle));
+            // Where in `b` the first half of `a` is best matched up to.
+            let split = (0..=b_middle.len()).max_by_key(|&j| forward[j] + backward[b_middle.len() - j]).unwrap_or(0);
+            diff(&a_middle[..half], a_start, &b_middle[..split], b_start, edits);
+            diff(&a_middle[half..], a_start + half, &b_middle[split..], b_start + split, edits);
+        }
+    }
+    let (a_end, b_end) = (a_start + a_middle.len(), b_start + b_middle.len());
+    edits.extend((0..suffix).map(|i| Edit::Same(a_end + i, b_end + i)));
+}
+
+/// The length of the longest common subsequence of `a` and each start of `b`, by the number
+/// of words of `b` in it: a row of the dynamic programming table.
+fn lcs_lengths(a: &[&str], b: &[&str]) -> Vec<usize> {
+    let mut row = vec![0; b.len() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y { diagonal + 1 } else { above.max(row[j]) };
+            diagonal = above;
+        }
+    }
+    row
+}
@@ -0,0 +1,278 @@
+//! What can go wrong reading a document, told apart so callers can act on it.
+
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// Why a document, or a page of it, could not be read.
+///
+/// ```
+/// use rapid_pdf::{Error, ExtractOptions};
+///
+/// let options = ExtractOptions::default();
+/// let missing = rapid_pdf::extract_path("missing.pdf", &options).unwrap_err();
+/// assert!(matches!(missing, Error::Io(_)));
+/// assert_eq!(missing.code(), "file-not-found");
+/// let garbage = rapid_pdf::extract_bytes(b"%PDF-1.7 garbage", &options).unwrap_err();
+/// assert!(matches!(garbage, Error::Pdf(_)));
+/// assert_eq!(garbage.code(), "malformed-pdf");
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not a PDF, or too broken to find its pages in.
+    Pdf(lopdf::Error),
+    /// The document is encrypted and does not open without a password, which was not given.
+    PasswordRequired,
+    /// The password given is neither the user nor the owner password.
+    WrongPassword,
+    /// The document's encryption is malformed, so it cannot be decrypted.
+    Decryption(lopdf::Error),
+    /// The document uses something not supported, named here, such as a kind of encryption.
+    Unsupported(String),
+    /// A page asked for is not in the document.
+    PageOutOfRange { page: u32, page_count: usize },
+    /// A page asked for by its label is not labeled so in the document.
+    UnknownPageLabel(String),
+    /// Options that make no sense, alone or together.
+    InvalidOptions(String),
+    /// The content of a page could not be read in full, with `ExtractOptions::strict`.
+    Content { page: u32, message: String },
+    /// Reading a page went over one of the limits of `ExtractOptions`, with `strict`.
+    Limit { page: u32, limit: Limit },
+    /// An image XObject could not be written as a file, its data being corrupt.
+    Image { object: lopdf::ObjectId, message: String },
+    /// A `TextSink` stopped the extraction.
+    Sink(Box<dyn std::error::Error + Send + Sync>),
+    /// An `OcrProvider` could not read an image.
+    Ocr(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// A name for the kind of error that does not change from one version to the next, for
+    /// telling errors apart without parsing their messages: `file-not-found`, `io`,
+    /// `malformed-pdf`, `password-required`, `wrong-password`, `decryption-failed`,
+    /// `unsupported`, `page-out-of-range`, `unknown-page-label`, `invalid-options`, `content`,
+    /// `limit`, `image`, `sink` or `ocr`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(err) if err.kind() == io::ErrorKind::NotFound => "file-not-found",
+            Error::Io(_) => "io",
+            Error::Pdf(_) => "malformed-pdf",
+            Error::PasswordRequired => "password-required",
+            Error::WrongPassword => "wrong-password",
+            Error::Decryption(_) => "decryption-failed",
+            Error::Unsupported(_) => "unsupported",
+            Error::PageOutOfRange { .. } => "page-out-of-range",
+            Error::UnknownPageLabel(_) => "unknown-page-label",
+            Error::InvalidOptions(_) => "invalid-options",
+            Error::Content { .. } => "content",
+            Error::Limit { .. } => "limit",
+            Error::Image { .. } => "image",
+            Error::Sink(_) => "sink",
+            Error::Ocr(_) => "ocr",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Pdf(err) => write!(f, "not a readable PDF: {}", err),
+            Error::PasswordRequired => write!(f, "the document is encrypted; give its user or owner password"),
+            Error::WrongPassword => write!(f, "wrong password"),
+            Error::Decryption(err) => write!(f, "cannot decrypt: {}", err),
+            Error::Unsupported(feature) => write!(f, "unsupported {}", feature),
+            Error::PageOutOfRange { page, page_count } => write!(f, "page {} is out of range, the document has {} pages", page, page_count),
+            Error::UnknownPageLabel(label) => write!(f, "no page is labeled {:?}", label),
+            Error::InvalidOptions(message) => write!(f, "{}", message),
+            Error::Content { page, message } => write!(f, "cannot decode the content of page {}: {}", page, message),
+            Error::Limit { page, limit } => write!(f, "stopped reading page {}: {}", page, limit),
+            Error::Image { object, message } => write!(f, "cannot write image {} {} R: {}", object.0, object.1, message),
+            Error::Sink(err) => write!(f, "{}", err),
+            Error::Ocr(err) => write!(f, "OCR failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Pdf(err) | Error::Decryption(err) => Some(err),
+            Error::Sink(err) | Error::Ocr(err) => Some(err.as_ref()),
+            Error::PasswordRequired
+            | Error::WrongPassword
+            | Error::Unsupported(_)
+            | Error::PageOutOfRange { .. }
+            | Error::UnknownPageLabel(_)
+            | Error::InvalidOptions(_)
+            | Error::Content { .. }
+            | Error::Limit { .. }
+            | Error::Image { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<lopdf::Error> for Error {
+    fn from(err: lopdf::Error) -> Error {
+        match err {
+            lopdf::Error::IO(err) => Error::Io(err),
+            err => Error::Pdf(err),
+        }
+    }
+}
+
+/// Content of a page that could not be read, such as a corrupt stream, or that was not read
+/// for going over a limit. The page's items are those of the rest of its content, or of what
+/// came before the limit; with `ExtractOptions::strict`, it is an `Error::Content` or
+/// `Error::Limit`. It is serialized with its `code`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PageError {
+    pub page: u32,
+    pub message: String,
+    /// The limit the page went over, if that is what stopped it.
+    pub limit: Option<Limit>,
+}
+
+impl PageError {
+    pub(crate) fn limit(page: u32, limit: Limit) -> PageError {
+        PageError { page, message: limit.to_string(), limit: Some(limit) }
+    }
+
+    /// The code of the error the page would be with `ExtractOptions::strict`: `limit` when it
+    /// went over one, otherwise `content`.
+    pub fn code(&self) -> &'static str {
+        if self.limit.is_some() { "limit" } else { "content" }
+    }
+}
+
+impl Serialize for PageError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("PageError", 3)?;
+        state.serialize_field("page", &self.page)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.message)?;
+        state.end()
+    }
+}
+
+impl fmt::Display for PageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.limit {
+            Some(limit) => write!(f, "stopped reading page {}: {}", self.page, limit),
+            None => write!(f, "cannot decode the content of page {}: {}", self.page, self.message),
+        }
+    }
+}
+
+impl From<PageError> for Error {
+    fn from(err: PageError) -> Error {
+        match err.limit {
+            Some(limit) => Error::Limit { page: err.page, limit },
+            None => Error::Content { page: err.page, message: err.message },
+        }
+    }
+}
+
+/// One of the limits on reading a page, from `ExtractOptions`, with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// `max_operations`: operators run on the page, those of its forms included.
+    Operations(usize),
+    /// `max_form_depth`: form XObjects painted inside one another.
+    FormDepth(usize),
+    /// `max_stream_size`: bytes of one decompressed content stream.
+    StreamSize(usize),
+    /// `time_limit`: time spent reading the document.
+    Time(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Operations(max) => write!(f, "more than {} operations", max),
+            Limit::FormDepth(max) => write!(f, "form XObjects nested more than {} deep", max),
+            Limit::StreamSize(max) => write!(f, "a content stream of more than {} bytes", max),
+            Limit::Time(max) => write!(f, "the document took more than {} s to read", max.as_secs_f64()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, Fixture};
+    use crate::{extract_bytes, extract_path, ExtractOptions};
+    use lopdf::{dictionary, Stream};
+    use std::error::Error as _;
+
+    /// A document whose second page has a Flate content stream that is not Flate data.
+    fn corrupt_second_page() -> Vec<u8> {
+        let mut fixture = Fixture::new();
+        let corrupt = fixture.object(Stream::new(dictionary! { "Filter" => "FlateDecode" }, b"not flate data at all".to_vec()));
+        fixture.page("BT /F1 12 Tf 72 720 Td (fine) Tj ET");
+        fixture.page_with("", dictionary! { "Contents" => corrupt });
+        fixture.bytes()
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = extract_path("tests/no-such-file.pdf", &ExtractOptions::default()).unwrap_err();
+        assert!(matches!(&err, Error::Io(io) if io.kind() == io::ErrorKind::NotFound), "{:?}", err);
+        assert_eq!(err.code(), "file-not-found");
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn not_a_pdf() {
+        let err = extract_bytes(b"%PDF-1.7 garbage", &ExtractOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::Pdf(_)), "{:?}", err);
+        assert_eq!(err.code(), "malformed-pdf");
+        assert!(err.to_string().starts_with("not a readable PDF: "));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn truncated_file() {
+        let data = testing::pdf(&["BT /F1 12 Tf 72 720 Td (cut) Tj ET"]);
+        let err = extract_bytes(&data[..data.len() / 3], &ExtractOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::Pdf(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn encrypted_without_password() {
+        let doc = Fixture::new().document(lopdf::Dictionary::new());
+        let data = testing::encrypted(doc, "user", "owner", 128);
+        let err = extract_bytes(&data, &ExtractOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::PasswordRequired), "{:?}", err);
+        assert_eq!(err.code(), "password-required");
+    }
+
+    #[test]
+    fn corrupt_content_stream() {
+        let data = corrupt_second_page();
+        let text = extract_bytes(&data, &ExtractOptions::default()).expect("pages that fail are left out");
+        assert_eq!(text.errors.len(), 1);
+        assert_eq!(text.errors[0].page, 2);
+        assert_eq!(text.errors[0].code(), "content");
+
+        let strict = ExtractOptions::builder().strict(true).build().unwrap();
+        let err = extract_bytes(&data, &strict).unwrap_err();
+        assert!(matches!(&err, Error::Content { page: 2, .. }), "{:?}", err);
+        assert!(err.to_string().starts_with("cannot decode the content of page 2: "));
+    }
+}
@@ -0,0 +1,342 @@
+//! ToUnicode CMap parsing (PDF 32000-1:2008, 9.10.3).
+//!
+//! Only the parts that matter for text extraction are read: `codespacerange` to split
+//! show strings into character codes, and `bfchar`/`bfrange` to map codes to Unicode.
+
+use std::collections::HashMap;
+
+/// A character code as it appears in a show string: its value and its length in bytes.
+pub type Code = (u32, usize);
+
+#[derive(Debug, Clone)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodespaceRange {
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() == self.low.len()
+            && bytes.iter().zip(self.low.iter().zip(&self.high)).all(|(b, (lo, hi))| lo <= b && b <= hi)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum RangeTarget {
+    /// `<lo> <hi> <dst>`: code `lo + n` maps to `dst` with its last UTF-16 unit increased by `n`.
+    Offset(Vec<u16>),
+    /// `<lo> <hi> [<dst0> <dst1> ...]`.
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+struct BfRange {
+    len: usize,
+    low: u32,
+    high: u32,
+    target: RangeTarget,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ToUnicodeCMap {
+    codespaces: Vec<CodespaceRange>,
+    chars: HashMap<Code, String>,
+    ranges: Vec<BfRange>,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// How many CMaps `parse` has read on this thread, for tests to tell that fonts loaded once
+    /// are not read again.
+    pub(crate) static PARSED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Hex(Vec<u8>),
+    ArrayStart,
+    ArrayEnd,
+    Keyword(Vec<u8>),
+    /// Names, literal strings and dictionary delimiters, which the sections we read never need.
+    Other,
+}
+
+impl ToUnicodeCMap {
+    pub fn parse(data: &[u8]) -> ToUnicodeCMap {
+        #[cfg(test)]
+        PARSED.with(|count| count.set(count.get() + 1));
+        let tokens = tokenize(data);
+        let mut cmap = ToUnicodeCMap::default();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let section = match &tokens[i] {
+                Token::Keyword(keyword) => keyword.as_slice(),
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            i += 1;
+            match section {
+                b"begincodespacerange" => {
+                    while let (Some(Token::Hex(low)), Some(Token::Hex(high))) = (tokens.get(i), tokens.get(i + 1)) {
+                        if low.len() == high.len() && !low.is_empty() && low.len() <= 4 {
+                            cmap.codespaces.push(CodespaceRange { low: low.clone(), high: high.clone() });
+                        }
+                        i += 2;
+                    }
+                }
+                b"beginbfchar" => {
+                    while let (Some(Token::Hex(src)), Some(dst)) = (tokens.get(i), tokens.get(i + 1)) {
+                        if let (Some(code), Token::Hex(dst)) = (code_of(src), dst) {
+                            cmap.chars.insert(code, utf16_be(dst));
+                        }
+                        i += 2;
+                    }
+                }
+                b"beginbfrange" => {
+                    while let (Some(Token::Hex(low)), Some(Token::Hex(high))) = (tokens.get(i), tokens.get(i + 1)) {
+                        i += 2;
+                        let target = match tokens.get(i) {
+                            Some(Token::Hex(dst)) => {
+                                i += 1;
+                                Some(RangeTarget::Offset(utf16_units(dst)))
+                            }
+                            Some(Token::ArrayStart) => {
+                                i += 1;
+                                let mut list = Vec::new();
+                                while let Some(Token::Hex(dst)) = tokens.get(i) {
+                                    list.push(utf16_be(dst));
+                                    i += 1;
+                                }
+                                if tokens.get(i) == Some(&Token::ArrayEnd) {
+                                    i += 1;
+                                }
+                                Some(RangeTarget::List(list))
+                            }
+                            _ => None,
+                        };
+                        if let (Some((lo, len)), Some((hi, _)), Some(target)) = (code_of(low), code_of(high), target) {
+                            if lo <= hi {
+                                cmap.ranges.push(BfRange { len, low: lo, high: hi, target });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        cmap
+    }
+
+    /// Looks up the Unicode text for a character code.
+    pub fn lookup(&self, code: Code) -> Option<String> {
+        if let Some(text) = self.chars.get(&code) {
+            return Some(text.clone());
+        }
+        let (value, len) = code;
+        // Later ranges override earlier ones, matching how CMap definitions accumulate.
+        self.ranges.iter().rev().find(|r| r.len == len && r.low <= value && value <= r.high).and_then(|range| {
+            let offset = value - range.low;
+            match &range.target {
+                RangeTarget::Offset(units) => {
+                    let mut units = units.clone();
+                    let last = units.last_mut()?;
+                    *last = last.wrapping_add(offset as u16);
+                    Some(String::from_utf16_lossy(&units))
+                }
+                RangeTarget::List(list) => list.get(offset as usize).cloned(),
+            }
+        })
+    }
+
+    /// Splits a show string into character codes using the codespace ranges, falling back to
+    /// fixed `default_len`-byte codes when the CMap declares none.
+    pub fn codes(&self, bytes: &[u8], default_len: usize) -> Vec<Code> {
+        let mut codes = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let len = (1..=4)
+                .find(|&len| {
+                    i + len <= bytes.len() && self.codespaces.iter().any(|range| range.matches(&bytes[i..i + len]))
+                })
+                .unwrap_or_else(|| {
+                    self.codespaces.iter().map(|range| range.low.len()).min().unwrap_or(default_len)
+                })
+                .min(bytes.len() - i);
+            codes.push((code_value(&bytes[i..i + len]), len));
+            i += len;
+        }
+        codes
+    }
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn code_of(bytes: &[u8]) -> Option<Code> {
+    if bytes.is_empty() || bytes.len() > 4 {
+        return None;
+    }
+    Some((code_value(bytes), bytes.len()))
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    if bytes.len() % 2 == 1 {
+        // Not valid UTF-16BE; some producers write single-byte destinations.
+        return bytes.iter().map(|&b| b as u16).collect();
+    }
+    bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()
+}
+
+/// Decodes a CMap destination string, which is UTF-16BE (surrogate pairs included).
+fn utf16_be(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+fn tokenize(data: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        match byte {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => {
+                tokens.push(Token::Other);
+                i += 2;
+            }
+            b'>' if data.get(i + 1) == Some(&b'>') => {
+                tokens.push(Token::Other);
+                i += 2;
+            }
+            b'<' => {
+                let start = i + 1;
+                let end = data[start..].iter().position(|&b| b == b'>').map_or(data.len(), |p| start + p);
+                let digits: Vec<u8> = data[start..end].iter().copied().filter(u8::is_ascii_hexdigit).collect();
+                let bytes: Vec<u8> = digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let hi = hex_value(pair[0]);
+                        let lo = pair.get(1).map_or(0, |&d| hex_value(d));
+                        (hi << 4) | lo
+                    })
+                    .collect();
+                tokens.push(Token::Hex(bytes));
+                i = end + 1;
+            }
+            b'[' => {
+                tokens.push(Token::ArrayStart);
+                i += 1;
+            }
+            b']' => {
+                tokens.push(Token::ArrayEnd);
+                i += 1;
+            }
+            b'(' => {
+                let mut depth = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Other);
+                i += 1;
+            }
+            b'/' => {
+                i += 1;
+                while i < data.len() && is_regular(data[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Other);
+            }
+            _ if byte.is_ascii_whitespace() || byte == 0 => i += 1,
+            _ => {
+                let start = i;
+                while i < data.len() && is_regular(data[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    // A stray delimiter such as '{' or '>': skip it.
+                    i += 1;
+                    tokens.push(Token::Other);
+                } else {
+                    tokens.push(Token::Keyword(data[start..i].to_vec()));
+                }
+            }
+        }
+    }
+    tokens
+}
+
+fn is_regular(byte: u8) -> bool {
+    !byte.is_ascii_whitespace() && byte != 0 && !b"()<>[]{}/%".contains(&byte)
+}
+
+fn hex_value(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap_or(0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMAP: &str = "/CIDInit /ProcSet findresource begin 12 dict begin begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+2 begincodespacerange <00> <7F> <8000> <FFFF> endcodespacerange
+3 beginbfchar <41> <0058> <8001> <D83DDE00> <8002> <006600660069> endbfchar
+2 beginbfrange <61> <63> <0041> <8010> <8012> [<2013> <00660066> <2014>] endbfrange
+% a later range overrides what came before it
+1 beginbfrange <62> <62> <005A> endbfrange
+endcmap CMapName currentdict /CMap defineresource pop end end";
+
+    #[test]
+    fn splits_codes_by_the_codespace_ranges() {
+        let cmap = ToUnicodeCMap::parse(CMAP.as_bytes());
+        assert_eq!(cmap.codes(b"a\x80\x01b\x80", 2), vec![(0x61, 1), (0x8001, 2), (0x62, 1), (0x80, 1)]);
+        // Without codespace ranges the codes are `default_len` bytes long.
+        let bare = ToUnicodeCMap::parse(b"1 beginbfchar <0041> <0042> endbfchar");
+        assert_eq!(bare.codes(b"\x00\x41\x00", 2), vec![(0x41, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn maps_codes_through_bfchar_and_bfrange() {
+        let cmap = ToUnicodeCMap::parse(CMAP.as_bytes());
+        assert_eq!(cmap.lookup((0x41, 1)).as_deref(), Some("X"));
+        // An offset range adds the code's distance from the range's start to the target.
+        assert_eq!(cmap.lookup((0x61, 1)).as_deref(), Some("A"));
+        assert_eq!(cmap.lookup((0x63, 1)).as_deref(), Some("C"));
+        assert_eq!(cmap.lookup((0x62, 1)).as_deref(), Some("Z"));
+        // An array range takes each code's target from the array in turn.
+        assert_eq!(cmap.lookup((0x8010, 2)).as_deref(), Some("\u{2013}"));
+        assert_eq!(cmap.lookup((0x8011, 2)).as_deref(), Some("ff"));
+        assert_eq!(cmap.lookup((0x8012, 2)).as_deref(), Some("\u{2014}"));
+        // Only codes of the range's own length, and within it, are mapped.
+        assert_eq!(cmap.lookup((0x61, 2)), None);
+        assert_eq!(cmap.lookup((0x64, 1)), None);
+        assert_eq!(cmap.lookup((0x8013, 2)), None);
+    }
+
+    #[test]
+    fn decodes_surrogate_pairs_and_ligatures() {
+        let cmap = ToUnicodeCMap::parse(CMAP.as_bytes());
+        assert_eq!(cmap.lookup((0x8001, 2)).as_deref(), Some("😀"));
+        assert_eq!(cmap.lookup((0x8002, 2)).as_deref(), Some("ffi"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Simple-font encodings: a base encoding plus `/Differences` (PDF 32000-1:2008, 9.6.6).
+
+use super::encoding_tables::{MAC_ROMAN, PDF_DOC, STANDARD, WIN_ANSI};
+use super::glyph_names::GLYPH_NAMES;
+use lopdf::{Dictionary, Document, Object};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct SimpleEncoding {
+    base: &'static [u16; 256],
+    /// Codes remapped by `/Differences`; `None` for glyph names we cannot map to Unicode.
+    differences: BTreeMap<u8, Option<String>>,
+}
+
+impl SimpleEncoding {
+    /// Builds the encoding of a simple font, or `None` when the font is not a simple font or
+    /// is symbolic with no explicit encoding (its built-in encoding is unknown to us).
+    pub fn from_font(doc: &Document, font: &Dictionary) -> Option<SimpleEncoding> {
+        let subtype = font.get(b"Subtype").and_then(Object::as_name).unwrap_or(b"Type1");
+        if !matches!(subtype, b"Type1" | b"MMType1" | b"TrueType" | b"Type3") {
+            return None;
+        }
+
+        let encoding = font.get(b"Encoding").ok().map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+        match encoding {
+            Some(Object::Name(name)) => base_encoding(name).map(SimpleEncoding::with_base),
+            Some(Object::Dictionary(dict)) => {
+                let base = dict.get(b"BaseEncoding").and_then(Object::as_name).ok().and_then(base_encoding);
+                let mut encoding = SimpleEncoding::with_base(base.unwrap_or(&STANDARD));
+                if let Ok(Object::Array(differences)) = dict.get(b"Differences") {
+                    encoding.apply_differences(differences);
+                }
+                Some(encoding)
+            }
+            _ if is_symbolic(doc, font) => None,
+            _ => Some(SimpleEncoding::with_base(&STANDARD)),
+        }
+    }
+
+    fn with_base(base: &'static [u16; 256]) -> SimpleEncoding {
+        SimpleEncoding { base, differences: BTreeMap::new() }
+    }
+
+    /// Applies a `/Differences` array: `[code /name1 /name2 ... code /name ...]`.
+    fn apply_differences(&mut self, differences: &[Object]) {
+        let mut code: i64 = 0;
+        for entry in differences {
+            match entry {
+                Object::Integer(start) => code = *start,
+                Object::Name(name) => {
+                    if (0..256).contains(&code) {
+                        let glyph = std::str::from_utf8(name).ok().and_then(glyph_to_unicode);
+                        self.differences.insert(code as u8, glyph);
+                    }
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn decode_byte(&self, byte: u8) -> Option<String> {
+        if let Some(glyph) = self.differences.get(&byte) {
+            return glyph.clone();
+        }
+        match self.base[byte as usize] {
+            0 => None,
+            unit => char::from_u32(unit as u32).map(String::from),
+        }
+    }
+}
+
+/// Decodes bytes in PDFDocEncoding, that of text strings such as the document information
+/// and outline titles when they have no byte order mark (PDF 32000-1:2008, D.2). The few codes
+/// it leaves undefined are read as Latin-1.
+///
+/// ```
+/// assert_eq!(rapid_pdf::pdf_doc_decode(b"\x8dQuoted\x8e \x84 \x85 \x80 \x83"), "\u{201C}Quoted\u{201D} \u{2014} \u{2013} \u{2022} \u{2026}");
+/// assert_eq!(rapid_pdf::pdf_doc_decode(b"\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f"), "\u{2D8}\u{2C7}\u{2C6}\u{2D9}\u{2DD}\u{2DB}\u{2DA}\u{2DC}");
+/// assert_eq!(rapid_pdf::pdf_doc_decode(b"\x93\x94 \x95\x9b \x96\x9c \xa0 caf\xe9"), "\u{FB01}\u{FB02} \u{141}\u{142} \u{152}\u{153} \u{20AC} caf\u{E9}");
+/// ```
+pub fn pdf_doc_decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match PDF_DOC[byte as usize] {
+            0 => byte as char,
+            unit => char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER),
+        })
+        .collect()
+}
+
+fn base_encoding(name: &[u8]) -> Option<&'static [u16; 256]> {
+    match name {
+        b"StandardEncoding" => Some(&STANDARD),
+        b"WinAnsiEncoding" => Some(&WIN_ANSI),
+        b"MacRomanEncoding" => Some(&MAC_ROMAN),
+        _ => None,
+    }
+}
+
+/// Symbolic fonts (Symbol, ZapfDingbats, or the Symbolic FontDescriptor flag) use their own
+/// built-in encoding rather than StandardEncoding.
+fn is_symbolic(doc: &Document, font: &Dictionary) -> bool {
+    let base_font = font.get(b"BaseFont").and_then(Object::as_name).unwrap_or(b"");
+    let base_font = base_font.splitn(2, |&b| b == b'+').last().unwrap_or(base_font);
+    if base_font.starts_with(b"Symbol") || base_font.starts_with(b"ZapfDingbats") {
+        return true;
+    }
+    let flags = font
+        .get_deref(b"FontDescriptor", doc)
+        .and_then(Object::as_dict)
+        .and_then(|descriptor| descriptor.get(b"Flags"))
+        .and_then(Object::as_i64)
+        .unwrap_or(0);
+    flags & 0b100 != 0 && flags & 0b10_0000 == 0
+}
+
+/// Maps a glyph name to Unicode following the Adobe Glyph List specification: suffixes after
+/// a period are dropped, `_` joins ligature components, and `uniXXXX` / `uXXXX[XX]` forms
+/// are decoded directly.
+pub fn glyph_to_unicode(name: &str) -> Option<String> {
+    let base = name.split('.').next().unwrap_or("");
+    if base.is_empty() {
+        return None;
+    }
+    let mut text = String::new();
+    for component in base.split('_') {
+        text.push_str(&component_to_unicode(component)?);
+    }
+    Some(text)
+}
+
+fn component_to_unicode(component: &str) -> Option<String> {
+    if let Ok(index) = GLYPH_NAMES.binary_search_by(|(name, _)| (*name).cmp(component)) {
+        return Some(GLYPH_NAMES[index].1.to_string());
+    }
+    if let Some(hex) = component.strip_prefix("uni") {
+        if hex.is_empty() || hex.len() % 4 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        return (0..hex.len())
+            .step_by(4)
+            .map(|i| u32::from_str_radix(&hex[i..i + 4], 16).ok().and_then(char::from_u32))
+            .collect();
+    }
+    if let Some(hex) = component.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(String::from);
+        }
+    }
+    None
+}
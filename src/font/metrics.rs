@@ -0,0 +1,267 @@
+//! Glyph widths (PDF 32000-1:2008, 9.2.4 and 9.7.4.3), in thousandths of a text space unit.
+//!
+//! Fonts carry them in `/Widths` (simple fonts) or `/W` (CIDFonts); the 14 standard fonts
+//! may omit them, so their Adobe AFM metrics are compiled in from `data/afm`.
+
+use lopdf::{Dictionary, Document, Object};
+
+/// Width used when a font gives no other answer, as most PDF consumers do.
+const DEFAULT_MISSING_WIDTH: f32 = 500.0;
+
+/// Ascent and descent assumed without a usable font descriptor; their difference is the
+/// font size, so a line box is one em tall.
+pub const DEFAULT_EXTENT: (f32, f32) = (800.0, -200.0);
+
+/// AFM metrics of one of the 14 standard fonts, generated by `build.rs`.
+#[derive(Debug)]
+pub struct StandardFont {
+    name: &'static str,
+    /// Widths by code in the font's built-in encoding; 0 for unencoded codes.
+    builtin: [u16; 256],
+    /// Widths by the Unicode character of each glyph, sorted by character.
+    by_unicode: &'static [(char, u16)],
+}
+
+include!(concat!(env!("OUT_DIR"), "/standard_fonts.rs"));
+
+impl StandardFont {
+    /// Finds the standard font a `/BaseFont` name refers to, including the common aliases
+    /// (`Arial,Bold`, `TimesNewRomanPS-ItalicMT`, `CourierNew`) and subset prefixes.
+    pub fn find(base_font: &[u8]) -> Option<&'static StandardFont> {
+        let name = std::str::from_utf8(base_font).ok()?;
+        let name = name.split_once('+').map_or(name, |(_, rest)| rest);
+        if let Some(font) = STANDARD_FONTS.iter().find(|font| font.name == name) {
+            return Some(font);
+        }
+
+        let bold = name.contains("Bold");
+        let italic = name.contains("Italic") || name.contains("Oblique");
+        let family = if name.starts_with("Helvetica") || name.starts_with("Arial") {
+            "Helvetica"
+        } else if name.starts_with("Times") {
+            "Times"
+        } else if name.starts_with("Courier") {
+            "Courier"
+        } else if name.starts_with("Symbol") {
+            "Symbol"
+        } else if name.starts_with("ZapfDingbats") || name.starts_with("Dingbats") {
+            "ZapfDingbats"
+        } else {
+            return None;
+        };
+        let canonical = match (family, bold, italic) {
+            ("Symbol" | "ZapfDingbats", _, _) => family.to_string(),
+            ("Times", false, false) => "Times-Roman".to_string(),
+            ("Times", true, false) => "Times-Bold".to_string(),
+            ("Times", false, true) => "Times-Italic".to_string(),
+            ("Times", true, true) => "Times-BoldItalic".to_string(),
+            (_, false, false) => family.to_string(),
+            (_, true, false) => format!("{family}-Bold"),
+            (_, false, true) => format!("{family}-Oblique"),
+            (_, true, true) => format!("{family}-BoldOblique"),
+        };
+        STANDARD_FONTS.iter().find(|font| font.name == canonical)
+    }
+
+    fn width(&self, code: u32, unicode: Option<char>) -> Option<f32> {
+        let by_unicode = unicode.and_then(|ch| {
+            let index = self.by_unicode.binary_search_by_key(&ch, |&(c, _)| c).ok()?;
+            Some(self.by_unicode[index].1)
+        });
+        let width = by_unicode.or_else(|| self.builtin.get(code as usize).copied().filter(|&w| w != 0))?;
+        Some(width as f32)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FontMetrics {
+    /// `/FirstChar` and `/Widths` of a simple font.
+    Widths { first_char: u32, widths: Vec<f32>, missing: f32 },
+    /// `/W` and `/DW` of a CIDFont, as sorted, non-overlapping `(first, last, width)` ranges.
+    Cid { ranges: Vec<(u32, u32, f32)>, default: f32 },
+    /// A standard font without `/Widths`.
+    Standard { font: &'static StandardFont, missing: f32 },
+    /// No width information at all.
+    Missing(f32),
+}
+
+impl Default for FontMetrics {
+    fn default() -> FontMetrics {
+        FontMetrics::Missing(DEFAULT_MISSING_WIDTH)
+    }
+}
+
+impl FontMetrics {
+    /// Reads the widths of a font dictionary. `cid_font` is the descendant of a Type0 font.
+    pub fn load(doc: &Document, font: &Dictionary, cid_font: Option<&Dictionary>) -> FontMetrics {
+        if let Some(cid_font) = cid_font {
+            let default = cid_font.get_deref(b"DW", doc).and_then(Object::as_float).unwrap_or(1000.0);
+            let ranges = cid_font
+                .get_deref(b"W", doc)
+                .and_then(Object::as_array)
+                .map(|array| cid_width_ranges(doc, array))
+                .unwrap_or_default();
+            return FontMetrics::Cid { ranges, default };
+        }
+
+        let missing = font
+            .get_deref(b"FontDescriptor", doc)
+            .and_then(Object::as_dict)
+            .and_then(|descriptor| descriptor.get_deref(b"MissingWidth", doc))
+            .and_then(Object::as_float)
+            .unwrap_or(DEFAULT_MISSING_WIDTH);
+        if let Ok(widths) = font.get_deref(b"Widths", doc).and_then(Object::as_array) {
+            let first_char = font.get_deref(b"FirstChar", doc).and_then(Object::as_i64).unwrap_or(0).max(0) as u32;
+            let widths = widths
+                .iter()
+                .map(|w| doc.dereference(w).ok().and_then(|(_, w)| w.as_float().ok()).unwrap_or(missing))
+                .collect();
+            return FontMetrics::Widths { first_char, widths, missing };
+        }
+        match font.get(b"BaseFont").and_then(Object::as_name).ok().and_then(StandardFont::find) {
+            Some(font) => FontMetrics::Standard { font, missing },
+            None => FontMetrics::Missing(missing),
+        }
+    }
+
+    /// Width of a character code in thousandths of a text space unit. Standard fonts look
+    /// glyphs up by the character `unicode` returns, since that is where the font's encoding
+    /// has already been applied.
+    pub fn width(&self, code: u32, unicode: impl FnOnce() -> Option<char>) -> f32 {
+        match self {
+            FontMetrics::Widths { first_char, widths, missing } => code
+                .checked_sub(*first_char)
+                .and_then(|index| widths.get(index as usize))
+                .copied()
+                .unwrap_or(*missing),
+            FontMetrics::Cid { ranges, default } => {
+                let index = ranges.partition_point(|&(first, _, _)| first <= code);
+                match index.checked_sub(1).map(|i| ranges[i]) {
+                    Some((_, last, width)) if code <= last => width,
+                    _ => *default,
+                }
+            }
+            FontMetrics::Standard { font, missing } => font.width(code, unicode()).unwrap_or(*missing),
+            FontMetrics::Missing(missing) => *missing,
+        }
+    }
+}
+
+/// Vertical advances of a CIDFont written in vertical mode, from `/W2` and `/DW2`
+/// (PDF 32000-1:2008, 9.7.4.3): the `w1y` of each code, in thousandths of a text space unit
+/// and negative, since glyphs advance downwards.
+#[derive(Debug, Clone)]
+pub struct VerticalMetrics {
+    /// Sorted, non-overlapping `(first, last, w1y)` ranges.
+    ranges: Vec<(u32, u32, f32)>,
+    default: f32,
+}
+
+impl Default for VerticalMetrics {
+    /// The `/DW2` default of `[880 -1000]`: one em down.
+    fn default() -> VerticalMetrics {
+        VerticalMetrics { ranges: Vec::new(), default: -1000.0 }
+    }
+}
+
+impl VerticalMetrics {
+    pub fn load(doc: &Document, cid_font: &Dictionary) -> VerticalMetrics {
+        let number = |obj: &Object| doc.dereference(obj).ok().and_then(|(_, obj)| obj.as_float().ok());
+        let default = cid_font
+            .get_deref(b"DW2", doc)
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|array| array.get(1).and_then(number))
+            .unwrap_or(VerticalMetrics::default().default);
+        let ranges = cid_font
+            .get_deref(b"W2", doc)
+            .and_then(Object::as_array)
+            .map(|array| cid_vertical_ranges(doc, array))
+            .unwrap_or_default();
+        VerticalMetrics { ranges, default }
+    }
+
+    /// `w1y` of a character code.
+    pub fn advance(&self, code: u32) -> f32 {
+        let index = self.ranges.partition_point(|&(first, _, _)| first <= code);
+        match index.checked_sub(1).map(|i| self.ranges[i]) {
+            Some((_, last, advance)) if code <= last => advance,
+            _ => self.default,
+        }
+    }
+}
+
+/// `/Ascent` and `/Descent` of a font descriptor in thousandths of a text space unit.
+/// Descriptors with a missing or inverted pair get [`DEFAULT_EXTENT`].
+pub fn vertical_extent(doc: &Document, descriptor: Option<&Dictionary>) -> (f32, f32) {
+    let value = |key: &[u8]| descriptor?.get_deref(key, doc).and_then(Object::as_float).ok();
+    match (value(b"Ascent"), value(b"Descent")) {
+        (Some(ascent), Some(descent)) if ascent > descent && ascent > 0.0 => (ascent, descent.min(0.0)),
+        (Some(ascent), None) if ascent > 0.0 => (ascent, DEFAULT_EXTENT.1),
+        _ => DEFAULT_EXTENT,
+    }
+}
+
+/// Parses a `/W` array: `c [w1 w2 ...]` gives consecutive codes from `c`, `c_first c_last w`
+/// gives one width for a whole range.
+fn cid_width_ranges(doc: &Document, array: &[Object]) -> Vec<(u32, u32, f32)> {
+    let number = |obj: &Object| doc.dereference(obj).ok().and_then(|(_, obj)| obj.as_float().ok());
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < array.len() {
+        let Some(first) = number(&array[i]) else { break };
+        let first = first.max(0.0) as u32;
+        let next = array.get(i + 1).map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+        match next {
+            Some(Object::Array(widths)) => {
+                for (offset, width) in widths.iter().enumerate() {
+                    if let Some(width) = number(width) {
+                        let code = first + offset as u32;
+                        ranges.push((code, code, width));
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(width)) = (number(last), array.get(i + 2).and_then(number)) else { break };
+                ranges.push((first, last.max(0.0) as u32, width));
+                i += 3;
+            }
+            None => break,
+        }
+    }
+    ranges.sort_by_key(|&(first, _, _)| first);
+    ranges
+}
+
+/// Parses a `/W2` array: `c [w1y v1x v1y ...]` gives a triple for each code from `c`,
+/// `c_first c_last w1y v1x v1y` one triple for a whole range. Only `w1y` is kept.
+fn cid_vertical_ranges(doc: &Document, array: &[Object]) -> Vec<(u32, u32, f32)> {
+    let number = |obj: &Object| doc.dereference(obj).ok().and_then(|(_, obj)| obj.as_float().ok());
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < array.len() {
+        let Some(first) = number(&array[i]) else { break };
+        let first = first.max(0.0) as u32;
+        let next = array.get(i + 1).map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+        match next {
+            Some(Object::Array(metrics)) => {
+                for (offset, triple) in metrics.chunks_exact(3).enumerate() {
+                    if let Some(advance) = number(&triple[0]) {
+                        let code = first + offset as u32;
+                        ranges.push((code, code, advance));
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(advance)) = (number(last), array.get(i + 2).and_then(number)) else { break };
+                ranges.push((first, last.max(0.0) as u32, advance));
+                i += 5;
+            }
+            None => break,
+        }
+    }
+    ranges.sort_by_key(|&(first, _, _)| first);
+    ranges
+}
@@ -0,0 +1,532 @@
+//! Font resources and the decoding of show-string bytes into Unicode text.
+
+mod cmap;
+mod encoding;
+mod encoding_tables;
+mod glyph_names;
+mod metrics;
+mod predefined;
+mod truetype;
+
+pub use encoding::pdf_doc_decode;
+pub use metrics::DEFAULT_EXTENT;
+
+use cmap::{Code, ToUnicodeCMap};
+use encoding::SimpleEncoding;
+use metrics::{FontMetrics, VerticalMetrics};
+use predefined::{Collection, PredefinedCMap};
+use truetype::TrueTypeCmap;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// `/StemV` from this many thousandths of an em up is a bold weight; regular weights of common
+/// faces stay well below it.
+const BOLD_STEM_V: f32 = 120.0;
+
+/// `/FontWeight` from this up is bold, as in CSS (PDF 32000-1:2008, 9.8.1).
+const BOLD_FONT_WEIGHT: f32 = 600.0;
+
+/// `/Flags` bits of a font descriptor (PDF 32000-1:2008, 9.8.2).
+const FLAG_ITALIC: i64 = 1 << 6;
+const FLAG_FORCE_BOLD: i64 = 1 << 18;
+
+/// Fonts available to a content stream, keyed by resource name (the `F1` in `/F1 12 Tf`).
+pub type FontMap = BTreeMap<Vec<u8>, Arc<Font>>;
+
+/// Fonts already loaded from a document, by object. Pages mostly share their fonts, and a
+/// font's CMaps and widths are parsed only once however many pages and forms use it, on
+/// however many threads.
+#[derive(Debug, Default)]
+pub struct FontCache {
+    fonts: Mutex<HashMap<ObjectId, Arc<Font>>>,
+}
+
+impl FontCache {
+    /// The font of a `/Font` resource entry; fonts given as direct dictionaries, which no
+    /// other resource can refer to, are not kept.
+    fn load(&self, doc: &Document, obj: &Object) -> Option<Arc<Font>> {
+        let Ok(id) = obj.as_reference() else {
+            return obj.as_dict().ok().map(|dict| Arc::new(Font::load(doc, dict)));
+        };
+        if let Some(font) = self.lock().get(&id) {
+            return Some(font.clone());
+        }
+        // Loaded without holding the lock, so other pages' fonts load meanwhile; a thread that
+        // loaded the same font first has its copy kept.
+        let font = Arc::new(Font::load(doc, doc.get_dictionary(id).ok()?));
+        Some(self.lock().entry(id).or_insert(font).clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ObjectId, Arc<Font>>> {
+        // A thread that panicked loading a font leaves the fonts loaded before intact.
+        self.fonts.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// How a font's show strings split into character codes.
+#[derive(Debug, Clone, Default)]
+enum CodeScheme {
+    /// Simple fonts: every byte is a code.
+    #[default]
+    SingleByte,
+    /// Composite fonts with `Identity-H`/`Identity-V`: two bytes per code, the code being the CID.
+    TwoByte,
+    /// Composite fonts with an embedded encoding CMap, split by its codespace ranges.
+    Codespace(ToUnicodeCMap),
+    /// Composite fonts with a predefined CJK CMap such as `UniGB-UCS2-H` or `90ms-RKSJ-H`.
+    Predefined(PredefinedCMap),
+}
+
+/// The parts of a font dictionary needed to turn show strings into text.
+#[derive(Debug, Clone)]
+pub struct Font {
+    /// `/BaseFont`, as written, subset prefix included.
+    base_font: Option<String>,
+    scheme: CodeScheme,
+    to_unicode: Option<ToUnicodeCMap>,
+    /// Byte encoding of a simple font, used for codes the ToUnicode map does not cover.
+    encoding: Option<SimpleEncoding>,
+    /// `cmap` table of an embedded TrueType program, read only when neither of the above exists.
+    truetype: Option<TrueTypeCmap>,
+    /// `/CIDToGIDMap` of a composite TrueType font; `None` means `Identity`.
+    cid_to_gid: Option<Vec<u16>>,
+    /// The Adobe collection of the CIDs an `Identity` encoding shows, decoded through it
+    /// when the ToUnicode map does not.
+    collection: Option<Collection>,
+    metrics: FontMetrics,
+    /// `/Ascent` and `/Descent` in thousandths of a text space unit.
+    extent: (f32, f32),
+    /// Vertical advances of a composite font whose CMap has `/WMode 1`; `None` for fonts
+    /// written horizontally.
+    vertical: Option<VerticalMetrics>,
+    /// Weight and slant guessed from the name and the font descriptor.
+    bold: bool,
+    italic: bool,
+}
+
+impl Default for Font {
+    fn default() -> Font {
+        Font {
+            base_font: None,
+            scheme: CodeScheme::default(),
+            to_unicode: None,
+            encoding: None,
+            truetype: None,
+            cid_to_gid: None,
+            collection: None,
+            metrics: FontMetrics::default(),
+            extent: metrics::DEFAULT_EXTENT,
+            vertical: None,
+            bold: false,
+            italic: false,
+        }
+    }
+}
+
+impl Font {
+    pub fn load(doc: &Document, dict: &Dictionary) -> Font {
+        let to_unicode = dict
+            .get(b"ToUnicode")
+            .and_then(|obj| doc.dereference(obj))
+            .and_then(|(_, obj)| obj.as_stream())
+            .and_then(|stream| stream.get_plain_content())
+            .ok()
+            .map(|data| ToUnicodeCMap::parse(&data));
+        let encoding = SimpleEncoding::from_font(doc, dict);
+        let scheme = code_scheme(doc, dict, to_unicode.as_ref());
+        let composite = !matches!(scheme, CodeScheme::SingleByte);
+        let cid_font = if composite { descendant_font(doc, dict) } else { None };
+        let metrics = FontMetrics::load(doc, dict, cid_font);
+        let descriptor = cid_font.unwrap_or(dict).get_deref(b"FontDescriptor", doc).and_then(Object::as_dict).ok();
+        let extent = metrics::vertical_extent(doc, descriptor);
+        let vertical = cid_font.filter(|_| is_vertical(doc, dict)).map(|cid_font| VerticalMetrics::load(doc, cid_font));
+        let base_font = dict.get(b"BaseFont").and_then(Object::as_name).ok().map(|name| String::from_utf8_lossy(name).into_owned());
+        let (bold, italic) = style(doc, base_font.as_deref().unwrap_or(""), descriptor);
+        let collection = cid_font.filter(|_| matches!(scheme, CodeScheme::TwoByte)).and_then(|cid_font| collection(doc, cid_font));
+        let mut font = Font { base_font, scheme, to_unicode, encoding, collection, metrics, extent, vertical, bold, italic, ..Font::default() };
+        if font.to_unicode.is_none() && (composite || font.encoding.is_none()) {
+            font.load_truetype(doc, dict, cid_font);
+        }
+        font
+    }
+
+    /// Reads the `cmap` table of the font's `/FontFile2`, which for composite fonts hangs off
+    /// the descendant CIDFont's descriptor.
+    fn load_truetype(&mut self, doc: &Document, dict: &Dictionary, cid_font: Option<&Dictionary>) {
+        let composite = !matches!(self.scheme, CodeScheme::SingleByte);
+        let Some(descendant) = cid_font.or((!composite).then_some(dict)) else { return };
+        let program = descendant
+            .get_deref(b"FontDescriptor", doc)
+            .and_then(Object::as_dict)
+            .and_then(|descriptor| descriptor.get_deref(b"FontFile2", doc))
+            .and_then(Object::as_stream)
+            .and_then(|stream| stream.get_plain_content());
+        let Some(cmap) = program.ok().and_then(|data| TrueTypeCmap::parse(&data)) else { return };
+
+        if composite {
+            if let Ok(Object::Stream(map)) = descendant.get_deref(b"CIDToGIDMap", doc) {
+                let data = map.get_plain_content().unwrap_or_default();
+                self.cid_to_gid = Some(data.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect());
+            }
+        } else if cmap.is_symbolic_only() {
+            let name = dict.get(b"BaseFont").and_then(Object::as_name).unwrap_or(b"");
+            log::warn!(
+                "font {} only has a symbolic cmap, using raw character codes as text",
+                String::from_utf8_lossy(name)
+            );
+        }
+        self.truetype = Some(cmap);
+    }
+
+    /// Maps a code to Unicode through the embedded TrueType program: code to glyph id (via
+    /// `/CIDToGIDMap` or the symbolic subtable), then glyph id to Unicode.
+    fn truetype_lookup(&self, (value, _): Code) -> Option<String> {
+        let cmap = self.truetype.as_ref()?;
+        let glyph = match (&self.scheme, &self.cid_to_gid) {
+            (CodeScheme::SingleByte, _) => cmap.simple_code_glyph(value as u8)?,
+            (_, Some(map)) => *map.get(value as usize)?,
+            (_, None) => u16::try_from(value).ok()?,
+        };
+        cmap.glyph_unicode(glyph).map(String::from)
+    }
+
+    /// Splits a show string into character codes; each code is one glyph.
+    pub fn codes(&self, bytes: &[u8]) -> Vec<Code> {
+        match &self.scheme {
+            CodeScheme::SingleByte => bytes.iter().map(|&b| (b as u32, 1)).collect(),
+            CodeScheme::TwoByte => bytes
+                .chunks(2)
+                .map(|pair| (pair.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32), 2))
+                .collect(),
+            CodeScheme::Codespace(cmap) => cmap.codes(bytes, 2),
+            CodeScheme::Predefined(cmap) => {
+                let mut codes = Vec::new();
+                let mut rest = bytes;
+                while !rest.is_empty() {
+                    let len = cmap.code_len(rest);
+                    codes.push((rest[..len].iter().fold(0u32, |acc, &b| (acc << 8) | b as u32), len));
+                    rest = &rest[len..];
+                }
+                codes
+            }
+        }
+    }
+
+    /// Width of a character code in thousandths of a text space unit.
+    pub fn width(&self, (value, _): Code) -> f32 {
+        self.metrics.width(value, || {
+            let encoding = self.encoding.as_ref().filter(|_| matches!(self.scheme, CodeScheme::SingleByte))?;
+            encoding.decode_byte(value as u8)?.chars().next()
+        })
+    }
+
+    pub fn base_font(&self) -> Option<&str> {
+        self.base_font.as_deref()
+    }
+
+    /// Whether the font looks bold, by its name or its descriptor's flags, stem width or weight.
+    pub fn is_bold(&self) -> bool {
+        self.bold
+    }
+
+    /// Whether the font looks italic or oblique, by its name or its descriptor's flags or
+    /// italic angle.
+    pub fn is_italic(&self) -> bool {
+        self.italic
+    }
+
+    /// Whether the font writes vertically, its glyphs advancing down the page.
+    pub fn is_vertical(&self) -> bool {
+        self.vertical.is_some()
+    }
+
+    /// Vertical advance `w1y` of a character code in thousandths of a text space unit,
+    /// negative for the usual downward advance; 0 for horizontal fonts.
+    pub fn vertical_advance(&self, (value, _): Code) -> f32 {
+        self.vertical.as_ref().map_or(0.0, |metrics| metrics.advance(value))
+    }
+
+    /// Ascent and descent in thousandths of a text space unit, for sizing text boxes.
+    pub fn extent(&self) -> (f32, f32) {
+        self.extent
+    }
+
+    /// Decodes show-string bytes, or returns `None` when the font carries no usable mapping
+    /// and the caller should fall back to interpreting the bytes directly. With the text comes
+    /// how many of its characters each code decodes to, or nothing when it is not decoded
+    /// code by code.
+    ///
+    /// Each code is looked up in the ToUnicode map first, then in the simple-font encoding,
+    /// then in the embedded TrueType `cmap`. Composite-font codes with no mapping decode to
+    /// U+FFFD; simple-font codes decode to the raw byte.
+    pub fn decode(&self, bytes: &[u8]) -> Option<(String, Vec<usize>)> {
+        let composite = !matches!(self.scheme, CodeScheme::SingleByte);
+        if self.to_unicode.is_none() && self.encoding.is_none() && self.truetype.is_none() && !composite {
+            return None;
+        }
+        // Some producers show UTF-16BE text, byte order mark first, in a simple font.
+        if self.to_unicode.is_none() && !composite && bytes.starts_with(&[0xFE, 0xFF]) {
+            return Some((crate::decode_text_string(bytes), Vec::new()));
+        }
+        let mut text = String::new();
+        let mut counts = Vec::new();
+        for code in self.codes(bytes) {
+            let mapped = self.to_unicode.as_ref().and_then(|cmap| cmap.lookup(code));
+            let mapped = mapped.or_else(|| match (&self.scheme, &self.encoding, code) {
+                (CodeScheme::Predefined(cmap), _, (value, len)) => cmap.decode(&value.to_be_bytes()[4 - len..]),
+                (CodeScheme::TwoByte, _, (cid, _)) => self.collection.and_then(|collection| collection.decode(cid)),
+                (_, Some(encoding), (byte, 1)) => encoding.decode_byte(byte as u8),
+                _ => None,
+            });
+            let mapped = mapped.or_else(|| self.truetype_lookup(code));
+            match mapped {
+                Some(mapped) => {
+                    text.push_str(&mapped);
+                    counts.push(mapped.chars().count());
+                }
+                None if !composite => {
+                    text.push(code.0 as u8 as char);
+                    counts.push(1);
+                }
+                None => {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    counts.push(1);
+                }
+            }
+        }
+        Some((text, counts))
+    }
+}
+
+/// The CIDFont a Type0 font draws its glyphs from (the single `/DescendantFonts` entry).
+fn descendant_font<'a>(doc: &'a Document, dict: &'a Dictionary) -> Option<&'a Dictionary> {
+    let fonts = dict.get_deref(b"DescendantFonts", doc).and_then(Object::as_array).ok()?;
+    doc.dereference(fonts.first()?).ok()?.1.as_dict().ok()
+}
+
+/// The Adobe collection a CIDFont's `/CIDSystemInfo` names.
+fn collection(doc: &Document, cid_font: &Dictionary) -> Option<Collection> {
+    let info = cid_font.get_deref(b"CIDSystemInfo", doc).and_then(Object::as_dict).ok()?;
+    let string = |key: &[u8]| info.get_deref(key, doc).and_then(Object::as_str).ok();
+    Collection::from_system_info(string(b"Registry")?, string(b"Ordering")?)
+}
+
+fn code_scheme(doc: &Document, dict: &Dictionary, to_unicode: Option<&ToUnicodeCMap>) -> CodeScheme {
+    if dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Type0") {
+        return CodeScheme::SingleByte;
+    }
+    let encoding = dict.get(b"Encoding").ok().map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+    let scheme = match encoding {
+        Some(Object::Name(name)) if name == b"Identity-H" || name == b"Identity-V" => Some(CodeScheme::TwoByte),
+        Some(Object::Name(name)) => PredefinedCMap::from_name(name).map(CodeScheme::Predefined),
+        Some(Object::Stream(stream)) => {
+            let data = stream.get_plain_content().ok();
+            Some(data.map_or(CodeScheme::TwoByte, |data| CodeScheme::Codespace(ToUnicodeCMap::parse(&data))))
+        }
+        _ => None,
+    };
+    // For CMaps we do not know, the ToUnicode codespace is the best guess at code lengths.
+    scheme.unwrap_or_else(|| match to_unicode {
+        Some(cmap) => CodeScheme::Codespace(cmap.clone()),
+        None => CodeScheme::TwoByte,
+    })
+}
+
+/// Whether a composite font's `/Encoding` CMap has writing mode 1 (PDF 32000-1:2008,
+/// 9.7.5.3): `Identity-V` and the other predefined `-V` CMaps, or an embedded CMap whose
+/// dictionary or program sets `/WMode 1`.
+fn is_vertical(doc: &Document, dict: &Dictionary) -> bool {
+    let encoding = dict.get(b"Encoding").ok().map(|obj| doc.dereference(obj).map_or(obj, |(_, obj)| obj));
+    match encoding {
+        Some(Object::Name(name)) => name == b"V" || name.ends_with(b"-V"),
+        Some(Object::Stream(stream)) => match stream.dict.get(b"WMode").and_then(Object::as_i64) {
+            Ok(mode) => mode == 1,
+            Err(_) => stream.get_plain_content().is_ok_and(|data| {
+                let data = String::from_utf8_lossy(&data);
+                data.split_whitespace().collect::<Vec<_>>().windows(2).any(|pair| pair == ["/WMode", "1"])
+            }),
+        },
+        _ => false,
+    }
+}
+
+/// Guesses whether a font is bold and whether it is italic from its `/BaseFont`, such as
+/// `Arial,BoldItalic` or `TimesNewRomanPS-BoldMT`, and its font descriptor.
+fn style(doc: &Document, base_font: &str, descriptor: Option<&Dictionary>) -> (bool, bool) {
+    let name = without_subset_prefix(base_font);
+    let value = |key: &[u8]| descriptor?.get_deref(key, doc).and_then(Object::as_float).ok();
+    let flags = descriptor.and_then(|descriptor| descriptor.get_deref(b"Flags", doc).and_then(Object::as_i64).ok()).unwrap_or(0);
+    let bold = ["Bold", "Black", "Heavy", "Semibold", "Demi"].iter().any(|weight| name.contains(weight))
+        || flags & FLAG_FORCE_BOLD != 0
+        || value(b"FontWeight").is_some_and(|weight| weight >= BOLD_FONT_WEIGHT)
+        || value(b"StemV").is_some_and(|stem| stem >= BOLD_STEM_V);
+    let italic = name.contains("Italic")
+        || name.contains("Oblique")
+        || flags & FLAG_ITALIC != 0
+        || value(b"ItalicAngle").is_some_and(|angle| angle != 0.0);
+    (bold, italic)
+}
+
+/// A `/BaseFont` without the six capital letters and `+` that mark a subset, as in
+/// `ABCDEF+Helvetica` (PDF 32000-1:2008, 9.6.4).
+pub fn without_subset_prefix(base_font: &str) -> &str {
+    match base_font.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => name,
+        _ => base_font,
+    }
+}
+
+/// Loads every font visible to a page, including fonts inherited from its ancestors, which
+/// the page's own resources take precedence over.
+pub fn load_page_fonts(doc: &Document, page_id: ObjectId, cache: &FontCache) -> FontMap {
+    let mut fonts = FontMap::new();
+    if let Ok((direct, inherited)) = doc.get_page_resources(page_id) {
+        let inherited = inherited.into_iter().filter_map(|id| doc.get_dictionary(id).ok());
+        for resources in direct.into_iter().chain(inherited) {
+            collect_fonts(doc, resources, cache, &mut fonts);
+        }
+    }
+    fonts
+}
+
+/// Loads the fonts of a `/Resources` dictionary's `/Font` entry.
+pub fn load_fonts(doc: &Document, resources: &Dictionary, cache: &FontCache) -> FontMap {
+    let mut fonts = FontMap::new();
+    collect_fonts(doc, resources, cache, &mut fonts);
+    fonts
+}
+
+/// Adds the fonts of `/Font` that are not yet in `fonts`.
+fn collect_fonts(doc: &Document, resources: &Dictionary, cache: &FontCache, fonts: &mut FontMap) {
+    let Ok(dict) = resources.get_deref(b"Font", doc).and_then(Object::as_dict) else { return };
+    for (name, obj) in dict {
+        if !fonts.contains_key(name) {
+            if let Some(font) = cache.load(doc, obj) {
+                fonts.insert(name.clone(), font);
+            }
+        }
+    }
+}
+
+/// Returns the font resource name operand of a `Tf` operation.
+pub fn font_name(operand: &Object) -> Option<Vec<u8>> {
+    operand.as_name().ok().map(<[u8]>::to_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, Fixture};
+    use crate::ExtractOptions;
+    use lopdf::{dictionary, Stream};
+
+    const TO_UNICODE: &str = "/CIDInit /ProcSet findresource begin 12 dict begin begincmap
+1 begincodespacerange <0000> <FFFF> endcodespacerange
+2 beginbfchar <0003> <0020> <0850> <4E2D> endbfchar
+1 beginbfrange <0024> <0026> <0041> endbfrange
+endcmap CMapName currentdict /CMap defineresource pop end end";
+
+    /// An Identity-H Type0 font mapping its codes through `TO_UNICODE`, with widths for
+    /// codes 0x24 and 0x25.
+    fn identity_h(doc: &mut Document) -> Dictionary {
+        let to_unicode = doc.add_object(Stream::new(Dictionary::new(), TO_UNICODE.as_bytes().to_vec()));
+        let cid_font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "ABCDEF+NotoSansCJK",
+            "DW" => 1000,
+            "W" => vec![0x24.into(), vec![600.into(), 700.into()].into()],
+        });
+        dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "ABCDEF+NotoSansCJK",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![cid_font.into()],
+            "ToUnicode" => to_unicode,
+        }
+    }
+
+    #[test]
+    fn decodes_two_byte_codes_through_to_unicode() {
+        let mut doc = Document::with_version("1.7");
+        let dict = identity_h(&mut doc);
+        let font = Font::load(&doc, &dict);
+        assert_eq!(font.codes(&[0x00, 0x24, 0x08, 0x50]), [(0x24, 2), (0x850, 2)]);
+        let (text, counts) = font.decode(&[0x00, 0x24, 0x00, 0x25, 0x00, 0x03, 0x00, 0x26, 0x08, 0x50]).unwrap();
+        assert_eq!(text, "AB C中");
+        assert_eq!(counts, [1; 5]);
+        assert_eq!((font.width((0x24, 2)), font.width((0x25, 2)), font.width((0x850, 2))), (600.0, 700.0, 1000.0));
+    }
+
+    #[test]
+    fn decodes_unmapped_codes_to_the_replacement_character() {
+        let mut doc = Document::with_version("1.7");
+        let dict = identity_h(&mut doc);
+        let font = Font::load(&doc, &dict);
+        let (text, counts) = font.decode(&[0x00, 0x24, 0x12, 0x34]).unwrap();
+        assert_eq!(text, "A\u{FFFD}");
+        assert_eq!(counts, [1, 1]);
+    }
+
+    #[test]
+    fn reads_identity_h_without_to_unicode_as_unmapped() {
+        let mut doc = Document::with_version("1.7");
+        let mut dict = identity_h(&mut doc);
+        dict.remove(b"ToUnicode");
+        let font = Font::load(&doc, &dict);
+        assert_eq!(font.decode(&[0x00, 0x24, 0x00, 0x25]).unwrap().0, "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn falls_back_to_the_embedded_truetype_cmap() {
+        let mut doc = Document::with_version("1.7");
+        let mut dict = identity_h(&mut doc);
+        dict.remove(b"ToUnicode");
+        let program = truetype::tests::program(&[(3, 1, truetype::tests::format4(&[(0x41, 0x42, 0x24u16.wrapping_sub(0x41))]))]);
+        let program = doc.add_object(Stream::new(Dictionary::new(), program));
+        let descriptor = doc.add_object(dictionary! { "Type" => "FontDescriptor", "FontFile2" => program });
+        let cid_font = doc.get_object_mut(descendant_id(&dict)).unwrap().as_dict_mut().unwrap();
+        cid_font.set("FontDescriptor", descriptor);
+        let font = Font::load(&doc, &dict);
+        assert_eq!(font.decode(&[0x00, 0x24, 0x00, 0x25, 0x00, 0x26]).unwrap().0, "AB\u{FFFD}");
+    }
+
+    #[test]
+    fn parses_a_font_shared_by_many_pages_once() {
+        // Pages sharing one resource dictionary, its /F1 a Type0 font mapped by `TO_UNICODE`...
+        let type0 = |fixture: &mut Fixture| {
+            let to_unicode = fixture.object(Stream::new(Dictionary::new(), TO_UNICODE.as_bytes().to_vec()));
+            let cid_font = fixture.object(dictionary! { "Type" => "Font", "Subtype" => "CIDFontType2", "BaseFont" => "ABCDEF+NotoSansCJK" });
+            dictionary! { "Type" => "Font", "Subtype" => "Type0", "BaseFont" => "ABCDEF+NotoSansCJK", "Encoding" => "Identity-H", "DescendantFonts" => vec![cid_font.into()], "ToUnicode" => to_unicode }
+        };
+        let content = "BT /F1 12 Tf 72 700 Td <00240025> Tj ET";
+        let mut fixture = Fixture::new();
+        let font = type0(&mut fixture);
+        fixture.font("F1", font);
+        for _ in 0..20 {
+            fixture.page(content);
+        }
+        let options = ExtractOptions::builder().threads(Some(1)).build().unwrap();
+        cmap::PARSED.with(|count| count.set(0));
+        let pages = testing::pages_with(&fixture.bytes(), &options);
+        assert_eq!(pages.iter().map(|page| testing::texts(&page.items)).collect::<Vec<_>>(), vec![vec!["AB"]; 20]);
+        assert_eq!(cmap::PARSED.with(|count| count.get()), 1);
+
+        // ...and pages with a copy of it each, which is parsed for every one.
+        let mut fixture = Fixture::new();
+        for _ in 0..20 {
+            let font = type0(&mut fixture);
+            let font = fixture.object(font);
+            fixture.page_with(content, dictionary! { "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } } });
+        }
+        cmap::PARSED.with(|count| count.set(0));
+        assert_eq!(testing::pages_with(&fixture.bytes(), &options).len(), 20);
+        assert_eq!(cmap::PARSED.with(|count| count.get()), 20);
+    }
+
+    fn descendant_id(dict: &Dictionary) -> ObjectId {
+        dict.get(b"DescendantFonts").and_then(Object::as_array).unwrap()[0].as_reference().unwrap()
+    }
+}
@@ -0,0 +1,282 @@
+//! Predefined CJK CMaps named by a composite font's `/Encoding` (PDF 32000-1:2008, 9.7.5.2).
+//!
+//! The Unicode-based CMaps (`UniGB-UCS2-H`, `UniJIS-UTF16-V`, ...) take Unicode code units
+//! as their input codes, so codes decode directly. The legacy ones (`GBK-EUC-H`,
+//! `90ms-RKSJ-H`, `ETen-B5-H`, `KSCms-UHC-H`, ...) are byte encodings of national character
+//! sets; their codes are decoded with the corresponding `encoding_rs` tables, which map the
+//! same codes to Unicode as going through the Adobe CID collections' UCS2 CMaps would.
+//!
+//! Fonts with an `Identity` encoding show CIDs themselves. Without a ToUnicode map, those of
+//! the Adobe collections named by `/CIDSystemInfo` decode through the collection's UCS2 CMap,
+//! compiled by `build.rs` from `data/cmap`.
+
+use encoding_rs::{Encoding, BIG5, EUC_JP, EUC_KR, GB18030, GBK, SHIFT_JIS};
+
+#[derive(Debug, Clone, Copy)]
+pub enum PredefinedCMap {
+    /// `Uni*-UCS2-*`: two-byte UCS-2 codes.
+    Ucs2,
+    /// `Uni*-UTF16-*`: two-byte codes, four for surrogate pairs.
+    Utf16,
+    /// `Uni*-UTF8-*`.
+    Utf8,
+    /// `Uni*-UTF32-*`: four-byte codes.
+    Utf32,
+    /// Mixed single- and multi-byte legacy encodings.
+    Legacy(Legacy),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Legacy {
+    Gbk,
+    Gb18030,
+    Big5,
+    ShiftJis,
+    EucJp,
+    /// The bare `H`/`V` CMaps: two-byte JIS X 0208 codes in the 0x21-0x7E range.
+    Jis,
+    EucKr,
+}
+
+impl PredefinedCMap {
+    pub fn from_name(name: &[u8]) -> Option<PredefinedCMap> {
+        let name = std::str::from_utf8(name).ok()?;
+        let base = match name {
+            "H" | "V" => return Some(PredefinedCMap::Legacy(Legacy::Jis)),
+            _ => name.strip_suffix("-H").or_else(|| name.strip_suffix("-V"))?,
+        };
+        let cmap = if base.starts_with("Uni") {
+            if base.contains("UCS2") {
+                PredefinedCMap::Ucs2
+            } else if base.contains("UTF16") {
+                PredefinedCMap::Utf16
+            } else if base.contains("UTF8") {
+                PredefinedCMap::Utf8
+            } else if base.contains("UTF32") {
+                PredefinedCMap::Utf32
+            } else {
+                return None;
+            }
+        } else if base == "GBK2K" {
+            PredefinedCMap::Legacy(Legacy::Gb18030)
+        } else if base.starts_with("GB") {
+            PredefinedCMap::Legacy(Legacy::Gbk)
+        } else if base.starts_with("B5") || base.starts_with("ETen") || base.starts_with("HKscs") || base.starts_with("ETHK") {
+            PredefinedCMap::Legacy(Legacy::Big5)
+        } else if base.contains("RKSJ") {
+            PredefinedCMap::Legacy(Legacy::ShiftJis)
+        } else if base == "EUC" {
+            PredefinedCMap::Legacy(Legacy::EucJp)
+        } else if base.starts_with("KSC") {
+            PredefinedCMap::Legacy(Legacy::EucKr)
+        } else {
+            return None;
+        };
+        Some(cmap)
+    }
+
+    /// Length in bytes of the code starting at `bytes[0]`.
+    pub fn code_len(&self, bytes: &[u8]) -> usize {
+        let lead = bytes[0];
+        let len = match self {
+            PredefinedCMap::Ucs2 => 2,
+            PredefinedCMap::Utf16 => match lead {
+                0xD8..=0xDB => 4,
+                _ => 2,
+            },
+            PredefinedCMap::Utf8 => match lead {
+                0xF0..=0xF7 => 4,
+                0xE0..=0xEF => 3,
+                0xC0..=0xDF => 2,
+                _ => 1,
+            },
+            PredefinedCMap::Utf32 => 4,
+            PredefinedCMap::Legacy(legacy) => match legacy {
+                Legacy::Gbk | Legacy::Big5 | Legacy::EucKr => match lead {
+                    0x81..=0xFE => 2,
+                    _ => 1,
+                },
+                Legacy::Gb18030 => match (lead, bytes.get(1)) {
+                    (0x81..=0xFE, Some(0x30..=0x39)) => 4,
+                    (0x81..=0xFE, _) => 2,
+                    _ => 1,
+                },
+                Legacy::ShiftJis => match lead {
+                    0x81..=0x9F | 0xE0..=0xFC => 2,
+                    _ => 1,
+                },
+                Legacy::EucJp => match lead {
+                    0x8F => 3,
+                    0x8E | 0xA1..=0xFE => 2,
+                    _ => 1,
+                },
+                Legacy::Jis => 2,
+            },
+        };
+        len.min(bytes.len())
+    }
+
+    /// Decodes one code's bytes, or `None` if they are not a valid code.
+    pub fn decode(&self, code: &[u8]) -> Option<String> {
+        match self {
+            PredefinedCMap::Ucs2 | PredefinedCMap::Utf16 => {
+                let units: Vec<u16> = code.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+                String::from_utf16(&units).ok()
+            }
+            PredefinedCMap::Utf8 => std::str::from_utf8(code).ok().map(str::to_string),
+            PredefinedCMap::Utf32 => {
+                let value = code.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+                char::from_u32(value).map(String::from)
+            }
+            PredefinedCMap::Legacy(Legacy::Jis) => {
+                let euc: Vec<u8> = code.iter().map(|b| b | 0x80).collect();
+                decode_with(EUC_JP, &euc)
+            }
+            PredefinedCMap::Legacy(legacy) => {
+                let encoding = match legacy {
+                    Legacy::Gbk => GBK,
+                    Legacy::Gb18030 => GB18030,
+                    Legacy::Big5 => BIG5,
+                    Legacy::ShiftJis => SHIFT_JIS,
+                    Legacy::EucJp | Legacy::Jis => EUC_JP,
+                    Legacy::EucKr => EUC_KR,
+                };
+                decode_with(encoding, code)
+            }
+        }
+    }
+}
+
+/// An Adobe character collection of CJK CIDs (Adobe Technical Notes #5078, #5079, #5080 and
+/// #5093), named by the `/Ordering` of a CIDFont's `/CIDSystemInfo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collection {
+    Gb1,
+    Cns1,
+    Japan1,
+    Korea1,
+}
+
+impl Collection {
+    /// The collection of a `/CIDSystemInfo` whose `/Registry` is `Adobe`.
+    pub fn from_system_info(registry: &[u8], ordering: &[u8]) -> Option<Collection> {
+        if registry != b"Adobe" {
+            return None;
+        }
+        match ordering {
+            b"GB1" => Some(Collection::Gb1),
+            b"CNS1" => Some(Collection::Cns1),
+            b"Japan1" => Some(Collection::Japan1),
+            b"Korea1" => Some(Collection::Korea1),
+            _ => None,
+        }
+    }
+
+    /// The text of CID `cid`, or `None` if the collection's UCS2 CMap does not map it.
+    pub fn decode(self, cid: u32) -> Option<String> {
+        let table = match self {
+            Collection::Gb1 => &GB1,
+            Collection::Cns1 => &CNS1,
+            Collection::Japan1 => &JAPAN1,
+            Collection::Korea1 => &KOREA1,
+        };
+        table.decode(u16::try_from(cid).ok()?)
+    }
+}
+
+/// The CIDs of a collection mapped to Unicode.
+pub struct CidTable {
+    /// `(first, last, c)`: CIDs `first` to `last` map to the characters from `c` on, one each;
+    /// sorted by CID.
+    ranges: &'static [(u16, u16, u32)],
+    /// CIDs mapped to more than one character, sorted by CID.
+    strings: &'static [(u16, &'static str)],
+}
+
+impl CidTable {
+    fn decode(&self, cid: u16) -> Option<String> {
+        if let Ok(index) = self.strings.binary_search_by_key(&cid, |&(cid, _)| cid) {
+            return Some(self.strings[index].1.to_string());
+        }
+        let index = self.ranges.partition_point(|&(first, _, _)| first <= cid).checked_sub(1)?;
+        let (first, last, c) = self.ranges[index];
+        (cid <= last).then(|| char::from_u32(c + (cid - first) as u32)).flatten().map(String::from)
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/cid_to_unicode.rs"));
+
+fn decode_with(encoding: &'static Encoding, bytes: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Font;
+    use lopdf::{dictionary, Document, Object};
+
+    /// A Type0 font with the predefined CMap `encoding` and no ToUnicode map.
+    fn font(encoding: &str) -> Font {
+        let mut doc = Document::with_version("1.7");
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType0",
+            "BaseFont" => "STSong-Light",
+            "CIDSystemInfo" => dictionary! { "Registry" => Object::string_literal("Adobe"), "Ordering" => Object::string_literal("GB1"), "Supplement" => 4 },
+        });
+        let dict = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "STSong-Light",
+            "Encoding" => encoding,
+            "DescendantFonts" => vec![Object::Reference(descendant)],
+        };
+        Font::load(&doc, &dict)
+    }
+
+    #[test]
+    fn decodes_ucs2_codes_as_unicode() {
+        let cmap = PredefinedCMap::from_name(b"UniGB-UCS2-H").unwrap();
+        assert!(matches!(cmap, PredefinedCMap::Ucs2));
+        assert_eq!(cmap.code_len(&[0x4E, 0x2D, 0x65, 0x87]), 2);
+        assert_eq!(cmap.decode(&[0x4E, 0x2D]).as_deref(), Some("中"));
+
+        let font = font("UniGB-UCS2-H");
+        let bytes = [0x4E, 0x2D, 0x65, 0x87, 0x00, 0x41];
+        assert_eq!(font.codes(&bytes), [(0x4E2D, 2), (0x6587, 2), (0x41, 2)]);
+        assert_eq!(font.decode(&bytes).unwrap().0, "中文A");
+    }
+
+    #[test]
+    fn splits_shift_jis_codes_into_one_and_two_bytes() {
+        let cmap = PredefinedCMap::from_name(b"90ms-RKSJ-H").unwrap();
+        assert!(matches!(cmap, PredefinedCMap::Legacy(Legacy::ShiftJis)));
+
+        // ASCII and half-width katakana take one byte, hiragana and kanji two.
+        let font = font("90ms-RKSJ-H");
+        let bytes = [0x41, 0xB1, 0x82, 0xA0, 0x93, 0xFA, 0x31];
+        assert_eq!(font.codes(&bytes), [(0x41, 1), (0xB1, 1), (0x82A0, 2), (0x93FA, 2), (0x31, 1)]);
+        assert_eq!(font.decode(&bytes).unwrap().0, "Aｱあ日1");
+        // A lead byte cut off at the end of the string is a code of its own.
+        assert_eq!(font.codes(&[0x41, 0x82]), [(0x41, 1), (0x82, 1)]);
+    }
+
+    #[test]
+    fn names_the_adobe_collections() {
+        assert_eq!(Collection::from_system_info(b"Adobe", b"Japan1"), Some(Collection::Japan1));
+        assert_eq!(Collection::from_system_info(b"Adobe", b"Korea1"), Some(Collection::Korea1));
+        assert_eq!(Collection::from_system_info(b"Adobe", b"Identity"), None);
+        assert_eq!(Collection::from_system_info(b"Other", b"GB1"), None);
+    }
+
+    #[test]
+    fn decodes_cids_through_a_table() {
+        let table = CidTable { ranges: &[(1, 95, 0x20), (96, 96, 0xA5), (633, 634, 0x3000)], strings: &[(97, "ff")] };
+        let decoded: Vec<Option<String>> = [0, 1, 34, 95, 96, 97, 98, 633, 634, 635].iter().map(|&cid| table.decode(cid)).collect();
+        let expected = [None, Some(" "), Some("A"), Some("~"), Some("¥"), Some("ff"), None, Some("\u{3000}"), Some("、"), None];
+        assert_eq!(decoded, expected.map(|text| text.map(String::from)));
+    }
+}
@@ -0,0 +1,301 @@
+//! Reading the `cmap` table of an embedded TrueType font program (`/FontFile2`).
+//!
+//! This is the last resort for fonts without a ToUnicode map or a usable encoding: the
+//! Unicode subtable is inverted to find which character each glyph draws.
+
+use std::collections::HashMap;
+
+/// Upper bound on mappings read from one subtable, so a hostile format 12 group covering
+/// the whole code space cannot exhaust memory.
+const MAX_MAPPINGS: usize = 1 << 20;
+
+#[derive(Debug, Clone, Default)]
+pub struct TrueTypeCmap {
+    /// Inverse of the Unicode subtable: glyph id to the lowest code point drawing it.
+    glyph_to_unicode: HashMap<u16, char>,
+    /// Single-byte codes to glyph ids, from the (3,0) symbolic or (1,0) Macintosh subtable.
+    code_to_glyph: HashMap<u32, u16>,
+}
+
+impl TrueTypeCmap {
+    pub fn parse(font: &[u8]) -> Option<TrueTypeCmap> {
+        let cmap = find_table(font, b"cmap")?;
+        let count = read_u16(cmap, 2)? as usize;
+        let mut unicode: Option<(u8, &[u8])> = None;
+        let mut symbolic: Option<(u8, &[u8])> = None;
+
+        for i in 0..count {
+            let record = 4 + i * 8;
+            let platform = read_u16(cmap, record)?;
+            let encoding = read_u16(cmap, record + 2)?;
+            let offset = read_u32(cmap, record + 4)? as usize;
+            let Some(subtable) = cmap.get(offset..) else { continue };
+            // Rank subtables so the most complete one wins: (3,10) > (0,4+) > (3,1) > (0,*).
+            let unicode_rank = match (platform, encoding) {
+                (3, 10) => Some(4),
+                (0, 4..) => Some(3),
+                (3, 1) => Some(2),
+                (0, _) => Some(1),
+                _ => None,
+            };
+            let symbolic_rank = match (platform, encoding) {
+                (3, 0) => Some(2),
+                (1, 0) => Some(1),
+                _ => None,
+            };
+            if let Some(rank) = unicode_rank {
+                if unicode.is_none_or(|(best, _)| rank > best) {
+                    unicode = Some((rank, subtable));
+                }
+            }
+            if let Some(rank) = symbolic_rank {
+                if symbolic.is_none_or(|(best, _)| rank > best) {
+                    symbolic = Some((rank, subtable));
+                }
+            }
+        }
+
+        let mut result = TrueTypeCmap::default();
+        if let Some((_, subtable)) = unicode {
+            let mut mappings = read_subtable(subtable);
+            // Invert with the lowest code point first so duplicates keep the canonical one.
+            mappings.sort_unstable();
+            for (code, glyph) in mappings {
+                if let Some(ch) = char::from_u32(code) {
+                    result.glyph_to_unicode.entry(glyph).or_insert(ch);
+                }
+            }
+        }
+        if let Some((_, subtable)) = symbolic {
+            result.code_to_glyph = read_subtable(subtable).into_iter().collect();
+        }
+        Some(result)
+    }
+
+    pub fn glyph_unicode(&self, glyph: u16) -> Option<char> {
+        self.glyph_to_unicode.get(&glyph).copied()
+    }
+
+    /// Glyph for a simple-font byte code. Symbolic (3,0) tables usually place codes at
+    /// U+F000 + code, but some use the bare code.
+    pub fn simple_code_glyph(&self, code: u8) -> Option<u16> {
+        let code = code as u32;
+        self.code_to_glyph.get(&(0xF000 | code)).or_else(|| self.code_to_glyph.get(&code)).copied()
+    }
+
+    /// True when the font can only map codes to glyphs, never glyphs to Unicode.
+    pub fn is_symbolic_only(&self) -> bool {
+        self.glyph_to_unicode.is_empty() && !self.code_to_glyph.is_empty()
+    }
+}
+
+fn find_table<'a>(font: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = read_u16(font, 4)? as usize;
+    for i in 0..count {
+        let record = 12 + i * 16;
+        if font.get(record..record + 4)? == tag {
+            let offset = read_u32(font, record + 8)? as usize;
+            let length = read_u32(font, record + 12)? as usize;
+            return font.get(offset..offset.checked_add(length)?.min(font.len()));
+        }
+    }
+    None
+}
+
+/// Reads a cmap subtable into `(code, glyph)` pairs. Formats 0, 4 and 12 are supported.
+fn read_subtable(subtable: &[u8]) -> Vec<(u32, u16)> {
+    match read_u16(subtable, 0) {
+        Some(0) => read_format0(subtable),
+        Some(4) => read_format4(subtable).unwrap_or_default(),
+        Some(12) => read_format12(subtable).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn read_format0(subtable: &[u8]) -> Vec<(u32, u16)> {
+    subtable
+        .get(6..262)
+        .map(|glyphs| glyphs.iter().enumerate().filter(|(_, &g)| g != 0).map(|(c, &g)| (c as u32, g as u16)).collect())
+        .unwrap_or_default()
+}
+
+fn read_format4(subtable: &[u8]) -> Option<Vec<(u32, u16)>> {
+    let seg_count = read_u16(subtable, 6)? as usize / 2;
+    let end_codes = 14;
+    let start_codes = end_codes + seg_count * 2 + 2;
+    let deltas = start_codes + seg_count * 2;
+    let range_offsets = deltas + seg_count * 2;
+    let mut mappings = Vec::new();
+
+    for seg in 0..seg_count {
+        let end = read_u16(subtable, end_codes + seg * 2)?;
+        let start = read_u16(subtable, start_codes + seg * 2)?;
+        let delta = read_u16(subtable, deltas + seg * 2)?;
+        let range_offset_pos = range_offsets + seg * 2;
+        let range_offset = read_u16(subtable, range_offset_pos)? as usize;
+        if start > end {
+            continue;
+        }
+        for code in start..=end {
+            if code == 0xFFFF {
+                break;
+            }
+            let glyph = if range_offset == 0 {
+                code.wrapping_add(delta)
+            } else {
+                let index = range_offset_pos + range_offset + (code - start) as usize * 2;
+                match read_u16(subtable, index) {
+                    Some(0) | None => 0,
+                    Some(glyph) => glyph.wrapping_add(delta),
+                }
+            };
+            if glyph != 0 {
+                mappings.push((code as u32, glyph));
+            }
+        }
+    }
+    Some(mappings)
+}
+
+fn read_format12(subtable: &[u8]) -> Option<Vec<(u32, u16)>> {
+    let groups = read_u32(subtable, 12)? as usize;
+    let mut mappings = Vec::new();
+    for group in 0..groups {
+        let pos = 16 + group * 12;
+        let start = read_u32(subtable, pos)?;
+        let end = read_u32(subtable, pos + 4)?;
+        let start_glyph = read_u32(subtable, pos + 8)?;
+        if start > end {
+            continue;
+        }
+        for (i, code) in (start..=end).enumerate() {
+            if mappings.len() >= MAX_MAPPINGS {
+                return Some(mappings);
+            }
+            if let Ok(glyph) = u16::try_from(start_glyph as u64 + i as u64) {
+                mappings.push((code, glyph));
+            }
+        }
+    }
+    Some(mappings)
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// A font program of nothing but a `cmap` table holding `subtables`, each under its
+    /// platform and encoding id.
+    pub(in crate::font) fn program(subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut cmap = Vec::new();
+        cmap.extend(0u16.to_be_bytes());
+        cmap.extend((subtables.len() as u16).to_be_bytes());
+        let mut offset = 4 + subtables.len() * 8;
+        for (platform, encoding, subtable) in subtables {
+            cmap.extend(platform.to_be_bytes());
+            cmap.extend(encoding.to_be_bytes());
+            cmap.extend((offset as u32).to_be_bytes());
+            offset += subtable.len();
+        }
+        for (_, _, subtable) in subtables {
+            cmap.extend(subtable);
+        }
+        let mut font = Vec::new();
+        font.extend(0x0001_0000u32.to_be_bytes());
+        font.extend(1u16.to_be_bytes());
+        font.extend([0; 6]);
+        font.extend(b"cmap");
+        font.extend(0u32.to_be_bytes());
+        font.extend(28u32.to_be_bytes());
+        font.extend((cmap.len() as u32).to_be_bytes());
+        font.extend(cmap);
+        font
+    }
+
+    /// A format 4 subtable of `(start, end, delta)` segments mapping by delta, and the final
+    /// 0xFFFF segment.
+    pub(in crate::font) fn format4(segments: &[(u16, u16, u16)]) -> Vec<u8> {
+        let segments: Vec<(u16, u16, u16)> = segments.iter().copied().chain([(0xFFFF, 0xFFFF, 1)]).collect();
+        let count = segments.len() as u16;
+        let mut table = Vec::new();
+        for value in [4, 16 + 8 * count, 0, count * 2, 0, 0, 0] {
+            table.extend(value.to_be_bytes());
+        }
+        table.extend(segments.iter().flat_map(|&(_, end, _)| end.to_be_bytes()));
+        table.extend(0u16.to_be_bytes());
+        table.extend(segments.iter().flat_map(|&(start, _, _)| start.to_be_bytes()));
+        table.extend(segments.iter().flat_map(|&(_, _, delta)| delta.to_be_bytes()));
+        table.extend(segments.iter().flat_map(|_| 0u16.to_be_bytes()));
+        table
+    }
+
+    /// A format 12 subtable of `(start, end, first glyph)` groups.
+    fn format12(groups: &[(u32, u32, u32)]) -> Vec<u8> {
+        let mut table = Vec::new();
+        table.extend(12u16.to_be_bytes());
+        table.extend(0u16.to_be_bytes());
+        table.extend((16 + 12 * groups.len() as u32).to_be_bytes());
+        table.extend(0u32.to_be_bytes());
+        table.extend((groups.len() as u32).to_be_bytes());
+        for &(start, end, glyph) in groups {
+            table.extend([start, end, glyph].iter().flat_map(|value| value.to_be_bytes()));
+        }
+        table
+    }
+
+    /// The delta mapping `code` to `glyph` in a format 4 segment.
+    fn delta(code: u16, glyph: u16) -> u16 {
+        glyph.wrapping_sub(code)
+    }
+
+    #[test]
+    fn inverts_a_format_4_subtable() {
+        let cmap = TrueTypeCmap::parse(&program(&[(3, 1, format4(&[(0x41, 0x43, delta(0x41, 10))]))])).unwrap();
+        assert_eq!((cmap.glyph_unicode(10), cmap.glyph_unicode(12), cmap.glyph_unicode(13)), (Some('A'), Some('C'), None));
+        assert!(!cmap.is_symbolic_only());
+    }
+
+    #[test]
+    fn keeps_the_lowest_code_point_of_a_shared_glyph() {
+        // U+00A0 no-break space drawn by the glyph of the space.
+        let segments = [(0x20, 0x20, delta(0x20, 3)), (0xA0, 0xA0, delta(0xA0, 3))];
+        let cmap = TrueTypeCmap::parse(&program(&[(3, 1, format4(&segments))])).unwrap();
+        assert_eq!(cmap.glyph_unicode(3), Some(' '));
+    }
+
+    #[test]
+    fn reads_code_points_beyond_the_bmp_from_format_12() {
+        let cmap = TrueTypeCmap::parse(&program(&[(3, 10, format12(&[(0x1F600, 0x1F602, 20)]))])).unwrap();
+        assert_eq!((cmap.glyph_unicode(20), cmap.glyph_unicode(22)), (Some('\u{1F600}'), Some('\u{1F602}')));
+    }
+
+    #[test]
+    fn prefers_the_full_unicode_subtable() {
+        let bmp = format4(&[(0x41, 0x41, delta(0x41, 5))]);
+        let full = format12(&[(0x61, 0x61, 5)]);
+        let cmap = TrueTypeCmap::parse(&program(&[(3, 1, bmp), (3, 10, full)])).unwrap();
+        assert_eq!(cmap.glyph_unicode(5), Some('a'));
+    }
+
+    #[test]
+    fn maps_symbolic_codes_in_the_private_use_area() {
+        let cmap = TrueTypeCmap::parse(&program(&[(3, 0, format4(&[(0xF041, 0xF042, delta(0xF041, 7))]))])).unwrap();
+        assert_eq!((cmap.simple_code_glyph(0x41), cmap.simple_code_glyph(0x42), cmap.simple_code_glyph(0x43)), (Some(7), Some(8), None));
+        assert!(cmap.is_symbolic_only());
+    }
+
+    #[test]
+    fn rejects_a_program_without_a_cmap() {
+        assert!(TrueTypeCmap::parse(b"not a font").is_none());
+        let truncated = program(&[(3, 1, format4(&[(0x41, 0x43, 1)]))]);
+        assert!(TrueTypeCmap::parse(&truncated[..40]).is_some_and(|cmap| cmap.glyph_unicode(1).is_none()));
+    }
+}
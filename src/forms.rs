@@ -0,0 +1,287 @@
+//! The fields of interactive forms (PDF 32000-1:2008, 12.7), whose values are filled in to
+//! the `/AcroForm` field dictionaries rather than drawn in the content of the pages.
+
+use crate::annotations;
+use crate::matrix::Rect;
+use crate::page::{self, Page};
+use crate::{decode_text_string, Origin};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How deeply fields may nest before the rest of a branch is ignored.
+const MAX_DEPTH: usize = 64;
+
+/// Field flags (`/Ff`) of buttons, told apart by them (12.7.4.2).
+const RADIO: i64 = 1 << 15;
+const PUSH_BUTTON: i64 = 1 << 16;
+
+/// A field of the document's form that holds a value, with the widgets it is shown in.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct FormField {
+    /// The fully qualified name: the partial names of the field and its ancestors, joined by
+    /// periods, such as `address.city`.
+    pub name: String,
+    pub kind: FieldKind,
+    /// What is filled in: the text of text fields, whether a check box is checked, the
+    /// export value of the radio button chosen, the option or options chosen of choice
+    /// fields, and whether a signature field is signed. `None` when nothing is.
+    pub value: Option<FieldValue>,
+    /// The options of choice fields, as displayed.
+    pub options: Vec<String>,
+    pub widgets: Vec<Widget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum FieldKind {
+    Text,
+    Checkbox,
+    Radio,
+    /// A list box or combo box.
+    Choice,
+    Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+#[non_exhaustive]
+pub enum FieldValue {
+    Text(String),
+    Checked(bool),
+    /// The options chosen of a choice field that allows several.
+    Choices(Vec<String>),
+}
+
+/// Where a field is shown: its page, counted from 1, and its box there, placed like the
+/// items of the page.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Widget {
+    pub page: Option<u32>,
+    pub rect: Rect,
+}
+
+/// Reads the fields of the document's form, in the order of the field tree. Push buttons,
+/// which hold no value, are left out. Documents without a form give an empty list.
+pub(crate) fn read(doc: &Document, pages: &HashMap<ObjectId, u32>, origin: Origin) -> Vec<FormField> {
+    let Ok(fields) = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", doc))
+        .and_then(Object::as_dict)
+        .and_then(|form| form.get_deref(b"Fields", doc))
+        .and_then(Object::as_array)
+    else {
+        return Vec::new();
+    };
+    // Widgets do not always name their page with `/P`; the page's `/Annots` always list them.
+    let mut widget_pages = HashMap::new();
+    for &page_id in pages.keys() {
+        let annots = doc.get_dictionary(page_id).and_then(|page| page.get_deref(b"Annots", doc)).and_then(Object::as_array);
+        for annot in annots.into_iter().flatten().filter_map(|annot| annot.as_reference().ok()) {
+            widget_pages.insert(annot, page_id);
+        }
+    }
+    let mut walker = Walker { doc, pages, widget_pages, loaded: HashMap::new(), origin, visited: HashSet::new(), fields: Vec::new() };
+    for field in fields {
+        walker.field(field, &Inherited::default(), 0);
+    }
+    walker.fields
+}
+
+/// What a field takes from its ancestors when it does not have it itself.
+#[derive(Debug, Clone, Default)]
+struct Inherited<'a> {
+    name: String,
+    kind: Option<&'a [u8]>,
+    flags: i64,
+    value: Option<&'a Object>,
+    options: Option<&'a Object>,
+}
+
+struct Walker<'a> {
+    doc: &'a Document,
+    pages: &'a HashMap<ObjectId, u32>,
+    /// The page objects of widgets by their object.
+    widget_pages: HashMap<ObjectId, ObjectId>,
+    /// Pages loaded so far, to place the boxes of their widgets.
+    loaded: HashMap<ObjectId, Page>,
+    origin: Origin,
+    /// Fields already walked, so cycles in the tree are followed once.
+    visited: HashSet<ObjectId>,
+    fields: Vec<FormField>,
+}
+
+impl<'a> Walker<'a> {
+    fn field(&mut self, field: &'a Object, inherited: &Inherited<'a>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let id = field.as_reference().ok();
+        if id.is_some_and(|id| !self.visited.insert(id)) {
+            return;
+        }
+        let Ok((_, Object::Dictionary(dict))) = self.doc.dereference(field) else { return };
+        let partial = dict.get_deref(b"T", self.doc).and_then(Object::as_str).map(decode_text_string).ok();
+        let name = match partial {
+            Some(partial) if !inherited.name.is_empty() => format!("{}.{}", inherited.name, partial),
+            Some(partial) => partial,
+            None => inherited.name.clone(),
+        };
+        let inherited = Inherited {
+            name,
+            kind: dict.get(b"FT").and_then(Object::as_name).ok().or(inherited.kind),
+            flags: dict.get(b"Ff").and_then(Object::as_i64).unwrap_or(inherited.flags),
+            value: dict.get(b"V").ok().or(inherited.value),
+            options: dict.get(b"Opt").ok().or(inherited.options),
+        };
+        let kids: Vec<&'a Object> =
+            dict.get_deref(b"Kids", self.doc).and_then(Object::as_array).map(|kids| kids.iter().collect()).unwrap_or_default();
+        // Kids without a partial name are the field's widgets, not fields of their own.
+        let (fields, widgets): (Vec<&Object>, Vec<&Object>) = kids.into_iter().partition(|kid| {
+            self.doc.dereference(kid).ok().and_then(|(_, kid)| kid.as_dict().ok()).is_some_and(|kid| kid.has(b"T"))
+        });
+        if !fields.is_empty() {
+            for kid in fields {
+                self.field(kid, &inherited, depth + 1);
+            }
+            return;
+        }
+        let mut widget_ids: Vec<(Option<ObjectId>, &Dictionary)> = widgets
+            .into_iter()
+            .filter_map(|widget| Some((widget.as_reference().ok(), self.doc.dereference(widget).ok()?.1.as_dict().ok()?)))
+            .collect();
+        // A field with a single widget may be merged with it.
+        if widget_ids.is_empty() && dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget") {
+            widget_ids.push((id, dict));
+        }
+        let widgets = widget_ids.into_iter().map(|(id, widget)| self.widget(id, widget)).collect();
+        if let Some(field) = self.terminal(inherited, widgets) {
+            self.fields.push(field);
+        }
+    }
+
+    /// The field of a terminal field dictionary, or `None` for push buttons and fields of
+    /// no known type.
+    fn terminal(&self, inherited: Inherited<'a>, widgets: Vec<Widget>) -> Option<FormField> {
+        let doc = self.doc;
+        let value = inherited.value.and_then(|value| doc.dereference(value).ok()).map(|(_, value)| value);
+        let text = |value: &Object| match value {
+            Object::String(bytes, _) => Some(decode_text_string(bytes)),
+            Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+            // Rich text fields may keep their value in a stream.
+            Object::Stream(stream) => stream.get_plain_content().ok().map(|data| decode_text_string(&data)),
+            _ => None,
+        };
+        let (kind, value, options) = match inherited.kind? {
+            b"Tx" => (FieldKind::Text, value.and_then(text).map(FieldValue::Text), Vec::new()),
+            b"Btn" if inherited.flags & PUSH_BUTTON != 0 => return None,
+            b"Btn" if inherited.flags & RADIO != 0 => {
+                let chosen = value.and_then(|value| value.as_name().ok()).filter(|name| *name != b"Off");
+                (FieldKind::Radio, chosen.map(|name| FieldValue::Text(String::from_utf8_lossy(name).into_owned())), Vec::new())
+            }
+            // The on state of a check box is named by the producer, often `Yes`; off is `Off`.
+            b"Btn" => {
+                let checked = value.and_then(|value| value.as_name().ok()).is_some_and(|name| name != b"Off");
+                (FieldKind::Checkbox, Some(FieldValue::Checked(checked)), Vec::new())
+            }
+            b"Ch" => {
+                let options = inherited.options.and_then(|options| doc.dereference(options).ok()).and_then(|(_, options)| options.as_array().ok());
+                let options = options
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|option| match doc.dereference(option).ok()?.1 {
+                        // An export value and the text displayed for it.
+                        Object::Array(pair) => pair.last().and_then(|shown| doc.dereference(shown).ok()).and_then(|(_, shown)| text(shown)),
+                        option => text(option),
+                    })
+                    .collect();
+                let value = match value {
+                    Some(Object::Array(chosen)) => {
+                        Some(FieldValue::Choices(chosen.iter().filter_map(|chosen| doc.dereference(chosen).ok().and_then(|(_, chosen)| text(chosen))).collect()))
+                    }
+                    value => value.and_then(text).map(FieldValue::Text),
+                };
+                (FieldKind::Choice, value, options)
+            }
+            b"Sig" => (FieldKind::Signature, Some(FieldValue::Checked(value.is_some_and(|value| value.as_dict().is_ok()))), Vec::new()),
+            _ => return None,
+        };
+        let value = value.filter(|value| !matches!(value, FieldValue::Text(text) if text.is_empty()));
+        Some(FormField { name: inherited.name, kind, value, options, widgets })
+    }
+
+    fn widget(&mut self, id: Option<ObjectId>, widget: &Dictionary) -> Widget {
+        let page_id = widget.get(b"P").and_then(Object::as_reference).ok().filter(|page| self.pages.contains_key(page));
+        let page_id = page_id.or_else(|| id.and_then(|id| self.widget_pages.get(&id).copied()));
+        let rect = widget.get(b"Rect").ok().and_then(|rect| page::read_rect(self.doc, rect)).unwrap_or_default();
+        let Some(page_id) = page_id else { return Widget { page: None, rect } };
+        let page_num = self.pages[&page_id];
+        let page = self.loaded.entry(page_id).or_insert_with(|| Page::load(self.doc, page_id, page_num));
+        Widget { page: Some(page_num), rect: annotations::place(page, &rect, self.origin) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{near, Fixture};
+    use lopdf::dictionary;
+
+    fn rect(values: [f32; 4]) -> Object {
+        Object::Array(values.iter().map(|&value| value.into()).collect())
+    }
+
+    fn text(value: &str) -> Object {
+        Object::string_literal(value)
+    }
+
+    #[test]
+    fn reads_the_names_kinds_and_values_of_fields() {
+        let mut fixture = Fixture::new();
+        let city = fixture.object(dictionary! { "T" => text("city"), "FT" => "Tx", "V" => text("Paris"), "Subtype" => "Widget", "Rect" => rect([100.0, 700.0, 300.0, 720.0]) });
+        let zip = fixture.object(dictionary! { "T" => text("zip"), "FT" => "Tx", "V" => text("") });
+        let address = fixture.object(dictionary! { "T" => text("address"), "Kids" => vec![city.into(), zip.into()] });
+        let agree = fixture.object(dictionary! { "T" => text("agree"), "FT" => "Btn", "V" => "Yes", "Subtype" => "Widget", "Rect" => rect([100.0, 650.0, 112.0, 662.0]) });
+        // The widgets of a radio button are its kids without names of their own.
+        let red = fixture.object(dictionary! { "Subtype" => "Widget", "Rect" => rect([100.0, 600.0, 112.0, 612.0]) });
+        let blue = fixture.object(dictionary! { "Subtype" => "Widget", "Rect" => rect([120.0, 600.0, 132.0, 612.0]) });
+        let colour = fixture.object(dictionary! { "T" => text("colour"), "FT" => "Btn", "Ff" => RADIO, "V" => "Blue", "Kids" => vec![red.into(), blue.into()] });
+        let send = fixture.object(dictionary! { "T" => text("send"), "FT" => "Btn", "Ff" => PUSH_BUTTON });
+        let languages = fixture.object(dictionary! {
+            "T" => text("languages"),
+            "FT" => "Ch",
+            "Opt" => vec![vec![text("en"), text("English")].into(), text("French")],
+            "V" => vec![text("en"), text("French")],
+        });
+        let signature = fixture.object(dictionary! { "T" => text("signature"), "FT" => "Sig" });
+        let fields: Vec<Object> = [address, agree, colour, send, languages, signature].into_iter().map(Object::from).collect();
+        let annots: Vec<Object> = [city, agree, red, blue].into_iter().map(Object::from).collect();
+        fixture.page_with("", dictionary! { "Annots" => annots });
+        let doc = fixture.document(dictionary! { "AcroForm" => dictionary! { "Fields" => fields } });
+        let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+
+        let fields = read(&doc, &pages, Origin::TopLeft);
+        let values: Vec<(&str, FieldKind, Option<&FieldValue>)> = fields.iter().map(|field| (field.name.as_str(), field.kind, field.value.as_ref())).collect();
+        assert_eq!(
+            values,
+            [
+                ("address.city", FieldKind::Text, Some(&FieldValue::Text("Paris".to_string()))),
+                ("address.zip", FieldKind::Text, None),
+                ("agree", FieldKind::Checkbox, Some(&FieldValue::Checked(true))),
+                ("colour", FieldKind::Radio, Some(&FieldValue::Text("Blue".to_string()))),
+                ("languages", FieldKind::Choice, Some(&FieldValue::Choices(vec!["en".to_string(), "French".to_string()]))),
+                ("signature", FieldKind::Signature, Some(&FieldValue::Checked(false))),
+            ]
+        );
+        assert_eq!(fields[4].options, ["English", "French"]);
+        // Shown on the first page, measured from its top.
+        let widget = &fields[0].widgets[0];
+        assert_eq!(widget.page, Some(1));
+        assert!(near(widget.rect.x0, 100.0) && near(widget.rect.y0, 72.0) && near(widget.rect.y1, 92.0), "{:?}", widget.rect);
+        assert_eq!(fields[3].widgets.len(), 2);
+        assert!(fields[1].widgets.is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+//! Highlight annotations (PDF 32000-1:2008, 12.5.6.10) written over text into a copy of a
+//! document, such as over the matches of a search.
+
+use crate::matrix::Matrix;
+use crate::page::Page;
+use crate::{Error, Origin, Rgb};
+use lopdf::{dictionary, Document, Object, Stream};
+use std::io::Write;
+
+/// The annotation flag that has viewers print it (12.5.3).
+const PRINT: i64 = 4;
+
+/// A highlight to add over text of a page.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Highlight {
+    /// The page, counted from 1.
+    pub page: u32,
+    /// The corners of each region highlighted, as `/QuadPoints` give them: top left, top right,
+    /// bottom left, bottom right, each as x and y, in points, placed like the page's items.
+    pub quads: Vec<[f32; 8]>,
+    pub color: Rgb,
+    /// The text of the annotation, such as the text highlighted.
+    pub contents: Option<String>,
+}
+
+impl Highlight {
+    pub fn new(page: u32, quads: Vec<[f32; 8]>, color: Rgb, contents: Option<String>) -> Highlight {
+        Highlight { page, quads, color, contents }
+    }
+}
+
+/// Writes the PDF `data` to `out` with `highlights` added to the `/Annots` of their pages,
+/// and an appearance for each, so that viewers that do not draw highlights themselves show
+/// them too. Their quadrilaterals are placed as items are with `origin`. The rest of the
+/// document is kept as it is, though written anew. Encrypted documents are not written, as
+/// they would have to be written decrypted.
+pub fn write_highlights(data: &[u8], highlights: &[Highlight], origin: Origin, out: &mut impl Write) -> Result<(), Error> {
+    let mut doc = Document::load_mem(data)?;
+    if doc.is_encrypted() {
+        return Err(Error::Unsupported("highlighting encrypted PDFs".to_string()));
+    }
+    let pages = doc.get_pages();
+    for highlight in highlights.iter().filter(|highlight| !highlight.quads.is_empty()) {
+        let Some(&page_id) = pages.get(&highlight.page) else {
+            return Err(Error::PageOutOfRange { page: highlight.page, page_count: pages.len() });
+        };
+        // Back from the space items are placed in to the page's.
+        let page = Page::load(&doc, page_id, highlight.page);
+        let to_page = match origin {
+            Origin::BottomLeft => page.upright_matrix().inverse(),
+            Origin::TopLeft => page.upright_matrix().multiply(&page.top_left_matrix()).inverse(),
+        };
+        let to_page = to_page.unwrap_or(Matrix::IDENTITY);
+        let points: Vec<f32> = highlight
+            .quads
+            .iter()
+            .flat_map(|quad| quad.chunks_exact(2))
+            .flat_map(|point| <[f32; 2]>::from(to_page.transform_point(point[0], point[1])))
+            .collect();
+        let xs = points.iter().step_by(2);
+        let ys = points.iter().skip(1).step_by(2);
+        let rect = [
+            xs.clone().copied().fold(f32::INFINITY, f32::min),
+            ys.clone().copied().fold(f32::INFINITY, f32::min),
+            xs.copied().fold(f32::NEG_INFINITY, f32::max),
+            ys.copied().fold(f32::NEG_INFINITY, f32::max),
+        ];
+        let [r, g, b] = highlight.color.0;
+        let appearance = doc.add_object(appearance(&points, rect, highlight.color));
+        let mut annotation = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Highlight",
+            "Rect" => rect.map(Object::Real).to_vec(),
+            "QuadPoints" => points.iter().map(|&value| Object::Real(value)).collect::<Vec<_>>(),
+            "C" => vec![Object::Real(r), Object::Real(g), Object::Real(b)],
+            "F" => PRINT,
+            "P" => page_id,
+            "AP" => dictionary! { "N" => appearance },
+        };
+        if let Some(contents) = &highlight.contents {
+            annotation.set("Contents", lopdf::text_string(contents));
+        }
+        let annotation = doc.add_object(annotation);
+        add_annotation(&mut doc, page_id, annotation.into())?;
+    }
+    doc.save_to(out)?;
+    Ok(())
+}
+
+/// Appends an annotation to the `/Annots` of a page, which may be an array of its own or
+/// missing.
+fn add_annotation(doc: &mut Document, page_id: lopdf::ObjectId, annotation: Object) -> Result<(), Error> {
+    let annots = doc.get_dictionary(page_id)?.get(b"Annots").ok().cloned();
+    match annots {
+        Some(Object::Reference(id)) if doc.get_object(id).and_then(Object::as_array).is_ok() => {
+            doc.get_object_mut(id)?.as_array_mut()?.push(annotation);
+        }
+        Some(Object::Array(mut annots)) => {
+            annots.push(annotation);
+            doc.get_dictionary_mut(page_id)?.set("Annots", annots);
+        }
+        // An `/Annots` that is not an array holds no annotations to keep.
+        _ => doc.get_dictionary_mut(page_id)?.set("Annots", vec![annotation]),
+    }
+    Ok(())
+}
+
+/// The normal appearance of a highlight: its quadrilaterals filled with `color`, multiplied
+/// with what is under them so that the text stays readable, as viewers draw highlights.
+fn appearance(points: &[f32], rect: [f32; 4], color: Rgb) -> Stream {
+    let [r, g, b] = color.0;
+    let mut content = format!("/GS0 gs {} {} {} rg\n", r, g, b);
+    for quad in points.chunks_exact(8) {
+        // Around the corners, which are given top left, top right, bottom left, bottom right.
+        content += &format!("{} {} m {} {} l {} {} l {} {} l h f\n", quad[0], quad[1], quad[2], quad[3], quad[6], quad[7], quad[4], quad[5]);
+    }
+    let dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => rect.map(Object::Real).to_vec(),
+        "Resources" => dictionary! {
+            "ExtGState" => dictionary! { "GS0" => dictionary! { "Type" => "ExtGState", "BM" => "Multiply" } },
+        },
+    };
+    Stream::new(dict, content.into_bytes())
+}
@@ -0,0 +1,96 @@
+//! hOCR 1.2 output (<http://kba.github.io/hocr-spec/1.2/>), the XHTML that OCR engines such
+//! as Tesseract write, so born-digital PDFs can feed the same pipelines as scans.
+//!
+//! Pages hold content areas, one per run of paragraphs in the same column, then paragraphs,
+//! lines and words. Boxes are whole points from the top-left corner of the crop box, which is
+//! how hOCR measures pixels of a 72 dpi page image.
+
+use crate::report::xml_escape;
+use rapid_pdf::layout::lines::Line;
+use rapid_pdf::{Origin, PageText, Rect};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A document of `pages`, read from the PDF at `path`. Word boundaries within lines follow
+/// the line grouping's `word_gap`.
+pub fn document(pages: &[PageText], path: &Path, origin: Origin, word_gap: f32) -> String {
+    let mut html = String::new();
+    html.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    html.push_str("<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n");
+    html.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"en\" lang=\"en\">\n<head>\n");
+    let _ = writeln!(html, "<title>{}</title>", xml_escape(&path.display().to_string()));
+    html.push_str("<meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n");
+    let _ = writeln!(html, "<meta name=\"ocr-system\" content=\"rapid_pdf {}\"/>", env!("CARGO_PKG_VERSION"));
+    html.push_str("<meta name=\"ocr-capabilities\" content=\"ocr_page ocr_carea ocr_par ocr_line ocrx_word\"/>\n");
+    html.push_str("</head>\n<body>\n");
+    for (index, page) in pages.iter().enumerate() {
+        write_page(&mut html, page, index, path, origin, word_gap);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn write_page(html: &mut String, page: &PageText, index: usize, path: &Path, origin: Origin, word_gap: f32) {
+    let n = page.page_num;
+    let image = format!("image \"{}\"; ", path.display());
+    let size = Rect { x0: 0.0, y0: 0.0, x1: page.crop_box.width(), y1: page.crop_box.height() };
+    let _ = writeln!(html, "<div class=\"ocr_page\" id=\"page_{}\" title=\"{}{}; ppageno {}\">", n, xml_escape(&image), bbox(&size), index);
+
+    let (mut blocks, mut paragraphs, mut lines, mut words) = (0, 0, 0, 0);
+    let mut rest = &page.paragraphs[..];
+    while let Some(first) = rest.first() {
+        // A content area is a run of paragraphs in one column.
+        let column = first.lines[0].column;
+        let count = rest.iter().take_while(|paragraph| paragraph.lines[0].column == column).count();
+        let (area, after) = rest.split_at(count);
+        rest = after;
+
+        blocks += 1;
+        let area_box = area.iter().skip(1).fold(first.bbox, |bbox, paragraph| bbox.union(&paragraph.bbox));
+        let _ = writeln!(html, "<div class=\"ocr_carea\" id=\"page_{}_block_{}\" title=\"{}\">", n, blocks, bbox(&page.top_left(&area_box, origin)));
+        for paragraph in area {
+            paragraphs += 1;
+            let _ = writeln!(html, "<p class=\"ocr_par\" id=\"page_{}_par_{}\" title=\"{}\">", n, paragraphs, bbox(&page.top_left(&paragraph.bbox, origin)));
+            for line in &paragraph.lines {
+                lines += 1;
+                let title = match baseline(page, line, origin) {
+                    Some(baseline) => format!("{}; {}", bbox(&page.top_left(&line.bbox, origin)), baseline),
+                    None => bbox(&page.top_left(&line.bbox, origin)),
+                };
+                let _ = write!(html, "<span class=\"ocr_line\" id=\"page_{}_line_{}\" title=\"{}\">", n, lines, title);
+                for (text, word_box) in line.words(origin, word_gap) {
+                    words += 1;
+                    let _ = write!(
+                        html,
+                        "<span class=\"ocrx_word\" id=\"page_{}_word_{}\" title=\"{}; x_wconf 100\">{}</span> ",
+                        n,
+                        words,
+                        bbox(&page.top_left(&word_box, origin)),
+                        xml_escape(&text)
+                    );
+                }
+                html.push_str("</span>\n");
+            }
+            html.push_str("</p>\n");
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+}
+
+/// The `bbox` property: whole points, rounded outwards.
+/// The `baseline` of a horizontal line: the slope of its baseline, as `Line::skew` has it,
+/// and how far below the bottom left corner of its box the baseline starts, downwards as hOCR
+/// measures.
+fn baseline(page: &PageText, line: &Line, origin: Origin) -> Option<String> {
+    let first = line.items.first().filter(|_| !line.vertical)?;
+    let start = page.top_left(&Rect { x0: first.x, y0: first.y, x1: first.x, y1: first.y }, origin);
+    let bbox = page.top_left(&line.bbox, origin);
+    let slope = -line.skew.to_radians().tan() + 0.0;
+    let offset = start.y0 + slope * (bbox.x0 - start.x0) - bbox.y1;
+    Some(format!("baseline {:.3} {}", slope, offset.round() as i64))
+}
+
+fn bbox(rect: &Rect) -> String {
+    format!("bbox {} {} {} {}", rect.x0.floor() as i64, rect.y0.floor() as i64, rect.x1.ceil() as i64, rect.y1.ceil() as i64)
+}
@@ -0,0 +1,103 @@
+//! HTML output for previewing an extraction in a browser: each page is a box the size of its
+//! crop box, with every text item placed where it appears on the page.
+//!
+//! Lengths are in CSS points, which are PDF points, so pages keep their size and aspect
+//! ratio. Items are turned upright with their page, so rotated pages show as displayed.
+
+use crate::report::xml_escape;
+use rapid_pdf::{Origin, PageText, Rect, TextItem};
+use std::fmt::Write;
+use std::path::Path;
+
+const STYLE: &str = "body{margin:0;padding:1em 0;background:#888}\
+.page{position:relative;overflow:hidden;margin:0 auto 1em;background:#fff;box-shadow:0 0 4px #444}\
+.page span{position:absolute;white-space:pre}";
+
+/// A preview of `pages`, read from the PDF at `path`.
+pub fn document(pages: &[PageText], path: &Path, origin: Origin) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", xml_escape(&path.display().to_string()));
+    let _ = writeln!(html, "<style>{}</style>", STYLE);
+    html.push_str("</head>\n<body>\n");
+    for page in pages {
+        let _ = writeln!(
+            html,
+            "<div class=\"page\" id=\"page-{}\" style=\"width:{};height:{}\">",
+            page.page_num,
+            points(page.crop_box.width()),
+            points(page.crop_box.height())
+        );
+        for item in page.paragraphs.iter().flat_map(|paragraph| &paragraph.lines).flat_map(|line| &line.items) {
+            write_item(&mut html, item, &page.top_left(&item.bbox, origin));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// A span at `bbox`, the item's box from the top-left corner of the crop box, one line high.
+/// Turned text is centred on its box and turned back into place around its centre.
+fn write_item(html: &mut String, item: &TextItem, bbox: &Rect) {
+    let turned = item.angle.is_finite() && item.angle != 0.0;
+    let mut style = if turned {
+        let (x, y) = ((bbox.x0 + bbox.x1) / 2.0, (bbox.y0 + bbox.y1) / 2.0);
+        // CSS turns clockwise, as seen with y growing downwards.
+        format!("left:{};top:{};transform:translate(-50%,-50%) rotate({}deg)", points(x), points(y), -item.angle)
+    } else {
+        format!("left:{};top:{}", points(bbox.x0), points(bbox.y0))
+    };
+    let _ = write!(style, ";font-size:{}", points(item.font_size));
+    // The line is as thick as an upright item's box across the text; a turned item's box
+    // tells little of it, so the font size stands in.
+    let thickness = match (turned, item.vertical) {
+        (true, _) => item.font_size,
+        (false, true) => bbox.width(),
+        (false, false) => bbox.height(),
+    };
+    let _ = write!(style, ";line-height:{}", points(thickness));
+    if item.vertical {
+        style.push_str(";writing-mode:vertical-rl");
+    }
+    if let Some(font) = &item.font {
+        let _ = write!(style, ";font-family:{}", font_family(font));
+    }
+    if let Some(color) = item.color {
+        let _ = write!(style, ";color:{}", color.hex());
+    }
+    if item.bold || item.synthetic_bold {
+        style.push_str(";font-weight:bold");
+    }
+    if item.italic {
+        style.push_str(";font-style:italic");
+    }
+    let _ = writeln!(html, "<span style=\"{}\">{}</span>", style, xml_escape(&item.text));
+}
+
+/// The CSS font family for a `/BaseFont` without subset prefix: its family name without the
+/// style, such as `Helvetica` for `Helvetica-Bold`, then the generic family it resembles in
+/// case the browser lacks it.
+fn font_family(name: &str) -> String {
+    let family = name.split([',', '-']).next().unwrap_or(name);
+    // Only a plain name is safe inside a quoted CSS string within an attribute.
+    let family: String = family.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '_').collect();
+    let generic = if name.contains("Courier") || name.contains("Mono") {
+        "monospace"
+    } else if (name.contains("Times") || name.contains("Serif") || name.contains("Roman")) && !name.contains("Sans") {
+        "serif"
+    } else {
+        "sans-serif"
+    };
+    if family.is_empty() {
+        generic.to_string()
+    } else {
+        format!("'{}',{}", family, generic)
+    }
+}
+
+/// A CSS length in points; numbers that are not finite, as broken matrices produce, are 0.
+fn points(value: f32) -> String {
+    let value = if value.is_finite() { value } else { 0.0 };
+    format!("{:.2}pt", value)
+}
@@ -0,0 +1,360 @@
+//! Image XObjects (PDF 32000-1:2008, 8.9.5): where pages paint them, and their samples as
+//! image files, JPEG and JPEG 2000 data as it is and other images as PNG.
+
+use crate::matrix::Rect;
+use crate::{processor, Error};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::Serialize;
+use std::io::Write;
+
+/// An image painted on a page, by an image XObject or inline. Its box is placed like the
+/// page's items.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct PlacedImage {
+    /// The resource name of an image XObject, such as `Im1`; `None` for inline images.
+    pub name: Option<String>,
+    /// The image XObject, the same wherever it is painted, which `Extractor::image` reads;
+    /// `None` for inline images.
+    pub object: Option<ObjectId>,
+    /// Where the unit square of the image is painted, by the transformation matrix of `Do`.
+    pub rect: Rect,
+    /// Pixel dimensions of the image, `/Width` and `/Height`.
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An image XObject as a file.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    /// The contents of the file.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// `/DCTDecode` data.
+    Jpeg,
+    /// `/JPXDecode` data.
+    Jpeg2000,
+    /// Samples of the other filters, in gray, RGB or a palette.
+    Png,
+}
+
+impl ImageFormat {
+    /// The extension of files of the format, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Jpeg2000 => "jp2",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// The image dictionary of an object, which is a stream, or a dictionary alone when its data
+/// was dropped reading the document.
+pub(crate) fn image_dict(object: &Object) -> Option<&Dictionary> {
+    let dict = match object {
+        Object::Stream(stream) => &stream.dict,
+        Object::Dictionary(dict) => dict,
+        _ => return None,
+    };
+    (dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")).then_some(dict)
+}
+
+/// The pixel dimensions of an image dictionary, or of an inline image's, whose keys may be
+/// abbreviated.
+pub(crate) fn dimensions(dict: &Dictionary) -> (u32, u32) {
+    let size = |key: &[u8], short: &[u8]| {
+        let size = dict.get(key).or_else(|_| dict.get(short)).and_then(Object::as_i64);
+        size.ok().and_then(|size| u32::try_from(size).ok()).unwrap_or(0)
+    };
+    (size(b"Width", b"W"), size(b"Height", b"H"))
+}
+
+/// The image XObject `id` as a file. Its soft mask, if any, is left out. Samples decompressing
+/// to more than `max_size` bytes are not read.
+pub(crate) fn read(doc: &Document, id: ObjectId, max_size: usize) -> Result<Image, Error> {
+    let object = doc.get_object(id)?;
+    let Some(dict) = image_dict(object) else {
+        return Err(image_error(id, "not an image".to_string()));
+    };
+    let Object::Stream(stream) = object else {
+        return Err(Error::InvalidOptions("image data is only kept with ExtractOptions::images".to_string()));
+    };
+    let (width, height) = dimensions(dict);
+    let filters = stream.filters().unwrap_or_default();
+    // JPEG data is the file itself, once undone any filters applied over it.
+    if let Some(format) = match filters.last() {
+        Some(&b"DCTDecode") => Some(ImageFormat::Jpeg),
+        Some(&b"JPXDecode") => Some(ImageFormat::Jpeg2000),
+        _ => None,
+    } {
+        let mut outer = stream.clone();
+        match &filters[..filters.len() - 1] {
+            [] => {
+                outer.dict.remove(b"Filter");
+            }
+            outer_filters => outer.dict.set("Filter", outer_filters.iter().map(|filter| Object::Name(filter.to_vec())).collect::<Vec<_>>()),
+        }
+        let data = samples(&outer, id, max_size)?;
+        return Ok(Image { width, height, format, data });
+    }
+    if let Some(filter) = filters.iter().find(|filter| matches!(**filter, b"CCITTFaxDecode" | b"JBIG2Decode" | b"RunLengthDecode")) {
+        return Err(Error::Unsupported(format!("image filter /{}", String::from_utf8_lossy(filter))));
+    }
+    let data = samples(stream, id, max_size)?;
+    Ok(Image { width, height, format: ImageFormat::Png, data: png_of(doc, id, dict, width, height, data)? })
+}
+
+fn image_error(object: ObjectId, message: String) -> Error {
+    Error::Image { object, message }
+}
+
+/// The decompressed data of an image stream.
+fn samples(stream: &Stream, id: ObjectId, max_size: usize) -> Result<Vec<u8>, Error> {
+    processor::stream_data(stream, max_size).map_err(|err| match err.too_large {
+        true => image_error(id, format!("larger than {} bytes decompressed", max_size)),
+        false => image_error(id, err.message),
+    })
+}
+
+/// The colors of an image's samples as PNG has them.
+enum Colors {
+    Gray,
+    Rgb,
+    /// Converted to RGB, as PNG has no CMYK.
+    Cmyk,
+    /// An index into a palette of RGB colors.
+    Indexed(Vec<u8>),
+}
+
+/// The samples of an image in a PNG file.
+fn png_of(doc: &Document, id: ObjectId, dict: &Dictionary, width: u32, height: u32, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mask = dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+    let bits = if mask { 1 } else { dict.get(b"BitsPerComponent").and_then(Object::as_i64).unwrap_or(8) };
+    let colors = if mask {
+        Colors::Gray
+    } else {
+        match dict.get_deref(b"ColorSpace", doc) {
+            Ok(space) => colors(doc, space, 0)?,
+            Err(_) => return Err(image_error(id, "no color space".to_string())),
+        }
+    };
+    let components = match colors {
+        Colors::Gray | Colors::Indexed(_) => 1,
+        Colors::Rgb => 3,
+        Colors::Cmyk => 4,
+    };
+    let supported = match colors {
+        Colors::Gray => matches!(bits, 1 | 2 | 4 | 8 | 16),
+        Colors::Indexed(_) => matches!(bits, 1 | 2 | 4 | 8),
+        Colors::Rgb => matches!(bits, 8 | 16),
+        Colors::Cmyk => bits == 8,
+    };
+    if !supported {
+        return Err(Error::Unsupported(format!("images of {} bits per component in their color space", bits)));
+    }
+    let row = (width as usize * components * bits as usize).div_ceil(8);
+    let size = row.checked_mul(height as usize).filter(|size| *size > 0).ok_or_else(|| image_error(id, "no pixels".to_string()))?;
+    if data.len() < size {
+        return Err(image_error(id, format!("{} bytes of samples where {}x{} pixels take {}", data.len(), width, height, size)));
+    }
+    data.truncate(size);
+    // A decode array of [1 0] for every component inverts the samples, as for masks that paint
+    // their 1s; other decode arrays are not followed.
+    let decode = dict.get(b"Decode").and_then(Object::as_array).ok();
+    let inverted = decode.is_some_and(|decode| {
+        !decode.is_empty() && decode.chunks(2).all(|pair| pair.len() == 2 && pair[0].as_float().ok() == Some(1.0) && pair[1].as_float().ok() == Some(0.0))
+    });
+    if inverted && !matches!(colors, Colors::Indexed(_)) {
+        data.iter_mut().for_each(|byte| *byte = !*byte);
+    }
+    let (data, row, color_type, palette) = match colors {
+        Colors::Gray => (data, row, 0, None),
+        Colors::Rgb => (data, row, 2, None),
+        Colors::Cmyk => (data.chunks_exact(4).flat_map(cmyk_to_rgb).collect(), width as usize * 3, 2, None),
+        Colors::Indexed(palette) => (data, row, 3, Some(palette)),
+    };
+    png(width, height, bits as u8, color_type, palette.as_deref(), &data, row).map_err(|err| image_error(id, err.to_string()))
+}
+
+/// The colors of a color space, given by name or by array.
+fn colors(doc: &Document, space: &Object, depth: usize) -> Result<Colors, Error> {
+    let unsupported = |name: &[u8]| Error::Unsupported(format!("images in color space /{}", String::from_utf8_lossy(name)));
+    match space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" | b"CalGray" | b"G" => Ok(Colors::Gray),
+            b"DeviceRGB" | b"CalRGB" | b"RGB" => Ok(Colors::Rgb),
+            b"DeviceCMYK" | b"CMYK" => Ok(Colors::Cmyk),
+            name => Err(unsupported(name)),
+        },
+        Object::Array(array) if depth < 2 => {
+            let family = array.first().and_then(|family| family.as_name().ok()).unwrap_or_default();
+            match family {
+                b"ICCBased" => {
+                    let stream = array.get(1).and_then(|profile| doc.dereference(profile).ok()).and_then(|(_, profile)| profile.as_stream().ok());
+                    match stream.and_then(|stream| stream.dict.get(b"N").and_then(Object::as_i64).ok()) {
+                        Some(1) => Ok(Colors::Gray),
+                        Some(3) => Ok(Colors::Rgb),
+                        Some(4) => Ok(Colors::Cmyk),
+                        _ => Err(unsupported(family)),
+                    }
+                }
+                b"Indexed" | b"I" => {
+                    let base = array.get(1).and_then(|base| doc.dereference(base).ok()).map(|(_, base)| base).ok_or_else(|| unsupported(family))?;
+                    let high = array.get(2).and_then(|high| high.as_i64().ok()).unwrap_or(255).clamp(0, 255) as usize;
+                    let mut lookup = match array.get(3).and_then(|lookup| doc.dereference(lookup).ok()).map(|(_, lookup)| lookup) {
+                        Some(Object::String(bytes, _)) => bytes.clone(),
+                        Some(Object::Stream(stream)) => stream.get_plain_content().unwrap_or_default(),
+                        _ => Vec::new(),
+                    };
+                    let base = colors(doc, base, depth + 1)?;
+                    let components = match base {
+                        Colors::Gray => 1,
+                        Colors::Rgb => 3,
+                        Colors::Cmyk => 4,
+                        Colors::Indexed(_) => return Err(unsupported(family)),
+                    };
+                    // Entries missing from a short lookup table are black.
+                    lookup.resize((high + 1) * components, 0);
+                    let palette = lookup.chunks_exact(components).flat_map(|color| match base {
+                        Colors::Gray => [color[0]; 3],
+                        Colors::Cmyk => cmyk_to_rgb(color),
+                        _ => [color[0], color[1], color[2]],
+                    });
+                    Ok(Colors::Indexed(palette.collect()))
+                }
+                b"CalGray" | b"CalRGB" => colors(doc, &Object::Name(family.to_vec()), depth + 1),
+                family => Err(unsupported(family)),
+            }
+        }
+        _ => Err(Error::Unsupported("images in this color space".to_string())),
+    }
+}
+
+/// A CMYK color as RGB, without a color profile.
+fn cmyk_to_rgb(cmyk: &[u8]) -> [u8; 3] {
+    let k = 255 - cmyk[3] as u16;
+    [0, 1, 2].map(|i| ((255 - cmyk[i] as u16) * k / 255) as u8)
+}
+
+/// A PNG file (ISO/IEC 15948) of rows of `row` bytes, unfiltered.
+fn png(width: u32, height: u32, bits: u8, color_type: u8, palette: Option<&[u8]>, data: &[u8], row: usize) -> std::io::Result<Vec<u8>> {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let mut crc = flate2::Crc::new();
+        crc.update(kind);
+        crc.update(data);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[bits, color_type, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+    if let Some(palette) = palette {
+        chunk(&mut png, b"PLTE", palette);
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    for line in data.chunks_exact(row) {
+        // Each row starts with its filter type, 0 for none.
+        encoder.write_all(&[0])?;
+        encoder.write_all(line)?;
+    }
+    chunk(&mut png, b"IDAT", &encoder.finish()?);
+    chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, near, Fixture};
+    use crate::{ExtractOptions, Extractor};
+    use lopdf::dictionary;
+    use std::io::Read;
+
+    /// The chunks of a PNG file, by kind, the data of `IDAT` decompressed.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while rest.len() >= 12 {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+            let mut data = rest[8..8 + len].to_vec();
+            if &kind == b"IDAT" {
+                let mut samples = Vec::new();
+                flate2::read::ZlibDecoder::new(&data[..]).read_to_end(&mut samples).unwrap();
+                data = samples;
+            }
+            chunks.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    fn image(entries: Dictionary, data: &[u8]) -> Stream {
+        let mut dict = dictionary! { "Type" => "XObject", "Subtype" => "Image" };
+        for (key, value) in entries {
+            dict.set(key, value);
+        }
+        Stream::new(dict, data.to_vec())
+    }
+
+    #[test]
+    fn places_each_painting_of_an_image() {
+        let mut fixture = Fixture::new();
+        fixture.xobject("Im1", image(dictionary! { "Width" => 2, "Height" => 2, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8 }, &[0; 12]));
+        fixture.page(b"q 100 0 0 50 72 600 cm /Im1 Do Q q 20 0 0 20 300 300 cm /Im1 Do Q BI /W 3 /H 1 /CS /G /BPC 8 ID \x01\x02\x03 EI");
+        let page = testing::pages_with(&fixture.bytes(), &ExtractOptions::default()).remove(0);
+        let placed: Vec<(Option<&str>, u32, u32)> = page.images.iter().map(|image| (image.name.as_deref(), image.width, image.height)).collect();
+        assert_eq!(placed, [(Some("Im1"), 2, 2), (Some("Im1"), 2, 2), (None, 3, 1)]);
+        assert_eq!(page.images[0].object, page.images[1].object);
+        assert!(page.images[0].object.is_some() && page.images[2].object.is_none());
+        let rect = page.images[0].rect;
+        assert!(near(rect.x0, 72.0) && near(rect.y0, 600.0) && near(rect.x1, 172.0) && near(rect.y1, 650.0), "{:?}", rect);
+    }
+
+    #[test]
+    fn writes_samples_as_png_and_jpeg_as_it_is() {
+        let mut fixture = Fixture::new();
+        let rgb = fixture.xobject("Im1", image(dictionary! { "Width" => 2, "Height" => 1, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8 }, &[255, 0, 0, 0, 0, 255]));
+        let palette = vec!["Indexed".into(), "DeviceRGB".into(), 1.into(), Object::String(vec![0, 0, 0, 255, 255, 255], lopdf::StringFormat::Hexadecimal)];
+        let indexed = fixture.xobject("Im2", image(dictionary! { "Width" => 8, "Height" => 1, "ColorSpace" => palette, "BitsPerComponent" => 1 }, &[0b1010_0000]));
+        let jpeg = fixture.xobject("Im3", image(dictionary! { "Width" => 1, "Height" => 1, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8, "Filter" => "DCTDecode" }, b"\xFF\xD8 not really \xFF\xD9"));
+        let large = fixture.xobject("Im4", image(dictionary! { "Width" => 64, "Height" => 64, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8 }, &[0; 4096]));
+        fixture.page("");
+        let options = ExtractOptions::builder().images(true).max_stream_size(1024).build().unwrap();
+        let extractor = Extractor::from_bytes(&fixture.bytes(), &options).unwrap();
+
+        let png = extractor.image(rgb).unwrap();
+        assert_eq!((png.format, png.width, png.height), (ImageFormat::Png, 2, 1));
+        let rgb = chunks(&png.data);
+        assert_eq!(rgb.iter().map(|(kind, _)| kind).collect::<Vec<_>>(), [b"IHDR", b"IDAT", b"IEND"]);
+        // 2 by 1 pixels of 8-bit RGB, each row after its filter type.
+        assert_eq!(rgb[0].1, [0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(rgb[1].1, [0, 255, 0, 0, 0, 0, 255]);
+
+        let indexed = chunks(&extractor.image(indexed).unwrap().data);
+        assert_eq!((indexed[0].1[8], indexed[0].1[9]), (1, 3));
+        assert_eq!((indexed[1].0, indexed[1].1.as_slice()), (*b"PLTE", [0, 0, 0, 255, 255, 255].as_slice()));
+        assert_eq!(indexed[2].1, [0, 0b1010_0000]);
+
+        let jpeg = extractor.image(jpeg).unwrap();
+        assert_eq!((jpeg.format, jpeg.data.as_slice()), (ImageFormat::Jpeg, b"\xFF\xD8 not really \xFF\xD9".as_slice()));
+        let error = extractor.image(large).unwrap_err().to_string();
+        assert!(error.contains("larger than 1024 bytes"), "{}", error);
+    }
+}
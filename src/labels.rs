@@ -0,0 +1,130 @@
+//! Page labels (PDF 32000-1:2008, 12.4.2): the numbers pages are shown with, such as i, ii,
+//! iii for the front matter of a book and A-1 for its appendix, rather than their index.
+
+use crate::decode_text_string;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+
+/// How deeply the nodes of the number tree may nest before the rest of a branch is ignored.
+const MAX_DEPTH: usize = 256;
+
+/// Numbers past this are written in digits whatever the style, as roman numerals and letters
+/// for them would run on for pages.
+const MAX_SPELLED: usize = 10_000;
+
+/// The label of each of the document's `page_count` pages, in order, or `None` when it has no
+/// `/PageLabels`. Each page is labeled by the range of the nearest entry at or before its
+/// index; pages before the first entry are numbered from 1.
+pub(crate) fn read(doc: &Document, page_count: usize) -> Option<Vec<String>> {
+    let tree = doc.catalog().ok()?.get_deref(b"PageLabels", doc).and_then(Object::as_dict).ok()?;
+    let mut ranges = Vec::new();
+    number_tree_entries(doc, tree, &mut HashSet::new(), 0, &mut ranges);
+    // Entries should be sorted; a stable sort keeps the first of any given twice first.
+    ranges.sort_by_key(|&(start, _)| start);
+    ranges.dedup_by_key(|&mut (start, _)| start);
+    let labels = (0..page_count)
+        .map(|index| {
+            let Some(&(start, range)) = ranges.iter().rev().find(|&&(start, _)| start <= index) else {
+                return (index + 1).to_string();
+            };
+            let prefix = range.get_deref(b"P", doc).and_then(Object::as_str).map(decode_text_string).unwrap_or_default();
+            let first = range.get(b"St").and_then(Object::as_i64).ok().and_then(|first| usize::try_from(first).ok()).filter(|first| *first >= 1).unwrap_or(1);
+            let number = first.saturating_add(index - start);
+            let number = match range.get(b"S").and_then(Object::as_name).ok() {
+                Some(b"D") => number.to_string(),
+                Some(b"R" | b"r" | b"A" | b"a") if number > MAX_SPELLED => number.to_string(),
+                Some(b"R") => roman(number),
+                Some(b"r") => roman(number).to_lowercase(),
+                Some(b"A") => letters(number),
+                Some(b"a") => letters(number).to_lowercase(),
+                // A range without a style is labeled with its prefix alone.
+                _ => String::new(),
+            };
+            prefix + &number
+        })
+        .collect();
+    Some(labels)
+}
+
+/// The ranges of a number tree (7.9.7) of page labels: the index of each range's first page
+/// with its label dictionary.
+fn number_tree_entries<'a>(doc: &'a Document, node: &'a Dictionary, visited: &mut HashSet<ObjectId>, depth: usize, entries: &mut Vec<(usize, &'a Dictionary)>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    if let Ok(nums) = node.get_deref(b"Nums", doc).and_then(Object::as_array) {
+        for pair in nums.chunks_exact(2) {
+            let start = doc.dereference(&pair[0]).ok().and_then(|(_, start)| start.as_i64().ok()).and_then(|start| usize::try_from(start).ok());
+            let range = doc.dereference(&pair[1]).ok().and_then(|(_, range)| range.as_dict().ok());
+            if let (Some(start), Some(range)) = (start, range) {
+                entries.push((start, range));
+            }
+        }
+    }
+    let kids = node.get_deref(b"Kids", doc).and_then(Object::as_array);
+    for id in kids.into_iter().flatten().filter_map(|kid| kid.as_reference().ok()) {
+        if visited.insert(id) {
+            if let Ok(kid) = doc.get_dictionary(id) {
+                number_tree_entries(doc, kid, visited, depth + 1, entries);
+            }
+        }
+    }
+}
+
+/// A number in upper-case roman numerals; thousands past 3999 are written as more `M`s.
+fn roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] =
+        [(1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"), (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            roman.push_str(numeral);
+            number -= value;
+        }
+    }
+    roman
+}
+
+/// A number in upper-case letters: A to Z for 1 to 26, then AA to ZZ, AAA and so on.
+fn letters(number: usize) -> String {
+    let letter = char::from(b'A' + ((number - 1) % 26) as u8);
+    letter.to_string().repeat((number - 1) / 26 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use lopdf::dictionary;
+
+    fn labels(page_labels: Dictionary, page_count: usize) -> Option<Vec<String>> {
+        let mut fixture = Fixture::new();
+        for _ in 0..page_count {
+            fixture.page("");
+        }
+        read(&fixture.document(dictionary! { "PageLabels" => page_labels }), page_count)
+    }
+
+    #[test]
+    fn labels_pages_by_their_ranges() {
+        // Front matter in roman numerals, the body from 1, an appendix with a prefix and
+        // letters, and a cover labeled by its prefix alone, in a tree of two kids.
+        let mut fixture = Fixture::new();
+        let front = fixture.object(dictionary! { "Nums" => vec![0.into(), dictionary! { "P" => Object::string_literal("Cover") }.into(), 1.into(), dictionary! { "S" => "r" }.into()] });
+        let back = fixture.object(dictionary! {
+            "Nums" => vec![4.into(), dictionary! { "S" => "D" }.into(), 6.into(), dictionary! { "S" => "A", "P" => Object::string_literal("App. "), "St" => 26 }.into()],
+        });
+        for _ in 0..8 {
+            fixture.page("");
+        }
+        let doc = fixture.document(dictionary! { "PageLabels" => dictionary! { "Kids" => vec![front.into(), back.into()] } });
+        assert_eq!(read(&doc, 8).unwrap(), ["Cover", "i", "ii", "iii", "1", "2", "App. Z", "App. AA"]);
+    }
+
+    #[test]
+    fn numbers_the_pages_before_the_first_range() {
+        let page_labels = dictionary! { "Nums" => vec![2.into(), dictionary! { "S" => "R", "St" => 4 }.into()] };
+        assert_eq!(labels(page_labels, 4).unwrap(), ["1", "2", "IV", "V"]);
+        assert!(read(&Fixture::new().document(Dictionary::new()), 0).is_none());
+    }
+}
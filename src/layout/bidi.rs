@@ -0,0 +1,152 @@
+//! Putting right-to-left text into logical order.
+//!
+//! Hebrew and Arabic are drawn in visual order, so a line's text, read left to right as its
+//! glyphs appear, has right-to-left words backwards. This undoes the reordering of the
+//! Unicode Bidirectional Algorithm (UAX #9) for the simple case of one embedding level:
+//! right-to-left runs are reversed while numbers and left-to-right words inside them keep
+//! their order. Arabic presentation forms, which PDFs often map shaped glyphs to, are
+//! replaced by the letters they are shaped from.
+
+use super::lines::Line;
+use unicode_bidi::{bidi_class, BidiClass};
+use unicode_normalization::UnicodeNormalization;
+
+/// Rewrites the text of lines, given in visual order, in logical order.
+pub fn reorder(lines: &mut [Line]) {
+    for line in lines {
+        line.text = logical(&line.text);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Ltr,
+    Rtl,
+    /// Digits, which are written left to right in either direction.
+    Number,
+    Neutral,
+}
+
+fn direction(c: char) -> Direction {
+    match bidi_class(c) {
+        BidiClass::L => Direction::Ltr,
+        BidiClass::R | BidiClass::AL => Direction::Rtl,
+        BidiClass::EN | BidiClass::AN => Direction::Number,
+        _ => Direction::Neutral,
+    }
+}
+
+fn logical(text: &str) -> String {
+    let chars: Vec<char> = text.chars().flat_map(base_letters).collect();
+    let rtl = chars.iter().filter(|&&c| direction(c) == Direction::Rtl).count();
+    if rtl == 0 {
+        return chars.into_iter().collect();
+    }
+    let ltr = chars.iter().filter(|&&c| direction(c) == Direction::Ltr).count();
+    if rtl > ltr {
+        return reverse_rtl(&chars).into_iter().collect();
+    }
+
+    // A left-to-right line: reverse each span from one right-to-left letter to the last one
+    // before the next left-to-right letter, leaving the white space around it in place.
+    let mut result = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if direction(chars[i]) != Direction::Rtl {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let end = chars[i..].iter().position(|&c| direction(c) == Direction::Ltr).map_or(chars.len(), |n| i + n);
+        let last = (i..end).rfind(|&j| direction(chars[j]) == Direction::Rtl).unwrap_or(i);
+        result.extend(reverse_rtl(&chars[i..=last]));
+        i = last + 1;
+    }
+    result.into_iter().collect()
+}
+
+/// Right-to-left text in visual order, reversed, except that runs of left-to-right letters and
+/// numbers, with the separators between them such as in `1,234`, keep their order.
+fn reverse_rtl(chars: &[char]) -> Vec<char> {
+    let mut runs: Vec<Vec<char>> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if direction(chars[i]) == Direction::Rtl || direction(chars[i]) == Direction::Neutral {
+            runs.push(vec![mirrored(chars[i])]);
+            i += 1;
+            continue;
+        }
+        let end = chars[i..].iter().position(|&c| direction(c) == Direction::Rtl).map_or(chars.len(), |n| i + n);
+        let last = (i..end).rfind(|&j| direction(chars[j]) != Direction::Neutral).unwrap_or(i);
+        runs.push(chars[i..=last].to_vec());
+        i = last + 1;
+    }
+    runs.into_iter().rev().flatten().collect()
+}
+
+/// The paired bracket a reversed character is drawn as (UAX #9, rule L4).
+fn mirrored(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// Arabic Presentation Forms-A and -B (U+FB50-U+FDFF, U+FE70-U+FEFF) as the letters they
+/// are compatibility forms of; other characters as they are.
+fn base_letters(c: char) -> Vec<char> {
+    match c {
+        '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFE}' => c.to_string().nfkc().collect(),
+        _ => vec![c],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_left_to_right_text_alone() {
+        assert_eq!(logical("Total: 1,234 (net)"), "Total: 1,234 (net)");
+    }
+
+    #[test]
+    fn reverses_a_right_to_left_word_in_a_left_to_right_line() {
+        // Drawn as `Total: 1,234 ₪ כ"הס`, the Hebrew word right to left.
+        assert_eq!(logical("Total: 1,234 ₪ כ\"הס"), "Total: 1,234 ₪ סה\"כ");
+    }
+
+    #[test]
+    fn keeps_numbers_in_order_in_a_right_to_left_line() {
+        // סה"כ לתשלום: 1,234 ₪ as drawn.
+        assert_eq!(logical("₪ 1,234 :םולשתל כ\"הס"), "סה\"כ לתשלום: 1,234 ₪");
+        assert_eq!(logical("2024-05-17 ךיראת"), "תאריך 2024-05-17");
+    }
+
+    #[test]
+    fn keeps_latin_words_in_order_in_a_right_to_left_line() {
+        assert_eq!(logical("PDF ךמסמה תא חתפ"), "פתח את המסמך PDF");
+    }
+
+    #[test]
+    fn mirrors_brackets() {
+        // Drawn the way they face, the closing bracket first.
+        assert_eq!(logical("(םולש) םוי"), "יום (שלום)");
+    }
+
+    #[test]
+    fn replaces_arabic_presentation_forms() {
+        // مرحبا shaped and drawn right to left.
+        assert_eq!(logical("\u{FE8E}\u{FE92}\u{FEA3}\u{FEAE}\u{FEE3}"), "مرحبا");
+        assert_eq!(base_letters('\u{FEFB}'), ['ل', 'ا']);
+    }
+}
@@ -0,0 +1,202 @@
+//! Finding text columns, so a reading order can finish one column before starting the next.
+//!
+//! Columns are separated by gutters: vertical strips of the page that almost no line crosses.
+//! Lines that do cross one, such as headings over two-column text, split the page into bands
+//! that are arranged one after the other.
+
+use super::lines::Line;
+use std::str::FromStr;
+
+/// The narrowest strip, in font sizes, that counts as a gutter when detecting columns.
+const MIN_GUTTER: f32 = 1.0;
+
+/// How many lines must lie wholly on each side of a detected gutter.
+const MIN_COLUMN_LINES: usize = 2;
+
+/// The largest page extent handled at one-point resolution; wider pages use coarser steps.
+const MAX_BINS: usize = 10_000;
+
+/// How columns are found, as given to `--columns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Columns {
+    /// Keep lines as they are.
+    Off,
+    /// Detect columns from the gutters between lines.
+    Auto,
+    /// Split the page into this many columns at its widest gutters.
+    Count(usize),
+}
+
+impl FromStr for Columns {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Columns, String> {
+        match value {
+            "off" => Ok(Columns::Off),
+            "auto" => Ok(Columns::Auto),
+            _ => match value.parse() {
+                Ok(0) | Err(_) => Err(format!("expected off, auto or a number of columns, got {:?}", value)),
+                Ok(count) => Ok(Columns::Count(count)),
+            },
+        }
+    }
+}
+
+/// Reorders a page's lines, given top to bottom, so each column is read in full before the
+/// next, and numbers them with `Line::column`. Lines crossing a gutter stay in column 0 and
+/// keep their place between the bands above and below them.
+pub fn arrange(lines: &mut Vec<Line>, columns: Columns) {
+    // Vertical lines are columns themselves, already in order right to left.
+    if lines.iter().filter(|line| line.vertical).count() * 2 > lines.len() {
+        return;
+    }
+    let gutters = match columns {
+        Columns::Off | Columns::Count(1) => return,
+        Columns::Auto => find_gutters(lines, None),
+        Columns::Count(count) => find_gutters(lines, Some(count - 1)),
+    };
+    if gutters.is_empty() {
+        return;
+    }
+
+    let mut arranged = Vec::with_capacity(lines.len());
+    let mut band: Vec<Line> = Vec::new();
+    for mut line in lines.drain(..) {
+        if gutters.iter().any(|&(start, end)| line.bbox.x0 < end && line.bbox.x1 > start) {
+            arranged.extend(by_column(std::mem::take(&mut band)));
+            line.column = 0;
+            arranged.push(line);
+        } else {
+            let center = (line.bbox.x0 + line.bbox.x1) / 2.0;
+            line.column = gutters.iter().filter(|&&(start, _)| start < center).count();
+            band.push(line);
+        }
+    }
+    arranged.extend(by_column(band));
+    *lines = arranged;
+}
+
+/// The lines of a band, column by column; sorting is stable, so each column stays in order.
+fn by_column(mut band: Vec<Line>) -> Vec<Line> {
+    band.sort_by_key(|line| line.column);
+    band
+}
+
+/// Finds the x ranges of gutters, left to right. With `count`, returns up to that many of the
+/// widest strips, however narrow; otherwise those wide enough to separate real columns.
+fn find_gutters(lines: &[Line], count: Option<usize>) -> Vec<(f32, f32)> {
+    if lines.len() < 2 * MIN_COLUMN_LINES {
+        return Vec::new();
+    }
+    let left = lines.iter().map(|line| line.bbox.x0).fold(f32::INFINITY, f32::min);
+    let right = lines.iter().map(|line| line.bbox.x1).fold(f32::NEG_INFINITY, f32::max);
+    if right <= left || !right.is_finite() {
+        return Vec::new();
+    }
+    let step = ((right - left) / MAX_BINS as f32).max(1.0);
+    let bins = ((right - left) / step).ceil() as usize;
+
+    // How many lines cover each strip; a few headings may cross a gutter.
+    let mut coverage = vec![0usize; bins];
+    for line in lines {
+        let first = ((line.bbox.x0 - left) / step).floor().max(0.0) as usize;
+        let last = (((line.bbox.x1 - left) / step).ceil() as usize).min(bins);
+        for covered in &mut coverage[first.min(bins)..last] {
+            *covered += 1;
+        }
+    }
+    let allowed = lines.len() / 10;
+
+    let mut candidates = Vec::new();
+    let mut start = None;
+    for (bin, &covered) in coverage.iter().enumerate() {
+        match (covered <= allowed, start) {
+            (true, None) => start = Some(bin),
+            (false, Some(first)) => {
+                // Strips touching the page's text edges are margins, not gutters.
+                if first > 0 {
+                    candidates.push((left + first as f32 * step, left + bin as f32 * step));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let separates = |&(start, end): &(f32, f32)| {
+        let before = lines.iter().filter(|line| line.bbox.x1 <= start).count();
+        let after = lines.iter().filter(|line| line.bbox.x0 >= end).count();
+        before >= MIN_COLUMN_LINES && after >= MIN_COLUMN_LINES
+    };
+    let mut gutters: Vec<(f32, f32)> = match count {
+        None => {
+            let mut sizes: Vec<f32> = lines.iter().flat_map(|line| &line.items).map(|item| item.font_size).collect();
+            sizes.sort_by(f32::total_cmp);
+            let font_size = sizes.get(sizes.len() / 2).copied().unwrap_or(0.0);
+            candidates.into_iter().filter(|&(start, end)| end - start >= MIN_GUTTER * font_size).filter(separates).collect()
+        }
+        Some(count) => {
+            candidates.sort_by(|a, b| (b.1 - b.0).total_cmp(&(a.1 - a.0)));
+            candidates.into_iter().take(count).collect()
+        }
+    };
+    gutters.sort_by(|a, b| a.0.total_cmp(&b.0));
+    gutters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::lines;
+    use crate::{testing, Origin};
+
+    /// The lines of a page with a heading across two columns of three lines each, drawn row
+    /// by row, as some producers draw them, in 10-point Helvetica.
+    fn two_columns() -> Vec<Line> {
+        let rows = [("Left one,", "Right one,"), ("left two,", "right two,"), ("left three.", "right three.")];
+        let shown: Vec<String> = rows.iter().enumerate().map(|(row, (left, right))| {
+            let y = 700 - 14 * row;
+            format!("1 0 0 1 72 {} Tm ({}) Tj 1 0 0 1 320 {} Tm ({}) Tj", y, left, y, right)
+        }).collect();
+        let content = format!("BT /F1 10 Tf 1 0 0 1 72 730 Tm (A heading across the columns of this page) Tj {} ET", shown.join(" "));
+        lines::group(testing::items(&content), Origin::BottomLeft, lines::DEFAULT_TOLERANCE, lines::DEFAULT_WORD_GAP, lines::DEFAULT_MAX_SKEW)
+    }
+
+    fn texts(lines: &[Line]) -> Vec<(&str, usize)> {
+        lines.iter().map(|line| (line.text.as_str(), line.column)).collect()
+    }
+
+    #[test]
+    fn reads_each_column_in_full() {
+        let mut lines = two_columns();
+        arrange(&mut lines, Columns::Auto);
+        assert_eq!(
+            texts(&lines),
+            [
+                ("A heading across the columns of this page", 0),
+                ("Left one,", 0),
+                ("left two,", 0),
+                ("left three.", 0),
+                ("Right one,", 1),
+                ("right two,", 1),
+                ("right three.", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_lines_as_they_are_when_off() {
+        let mut lines = two_columns();
+        arrange(&mut lines, Columns::Off);
+        let order: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(order[1..3], ["Left one,", "Right one,"]);
+    }
+
+    #[test]
+    fn parses_how_columns_are_found() {
+        assert_eq!("auto".parse::<Columns>(), Ok(Columns::Auto));
+        assert_eq!("off".parse::<Columns>(), Ok(Columns::Off));
+        assert_eq!("3".parse::<Columns>(), Ok(Columns::Count(3)));
+        assert!("0".parse::<Columns>().is_err() && "two".parse::<Columns>().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+//! Collapsing text drawn more than once in nearly the same place, as producers do to fake a
+//! bold face or a drop shadow.
+
+use crate::TextItem;
+use std::collections::HashMap;
+
+/// The default distance, as a fraction of the font size, within which copies are collapsed:
+/// about a point at common text sizes.
+pub const DEFAULT_TOLERANCE: f32 = 0.1;
+
+/// Keeps the first of each group of items with the same text whose origins are less than
+/// `tolerance` font sizes apart, widening its box to cover the copies and setting
+/// `synthetic_bold`.
+pub fn collapse(items: Vec<TextItem>, tolerance: f32) -> Vec<TextItem> {
+    let mut kept: Vec<TextItem> = Vec::with_capacity(items.len());
+    let mut by_text: HashMap<String, Vec<usize>> = HashMap::new();
+    for item in items {
+        if item.text.trim().is_empty() {
+            kept.push(item);
+            continue;
+        }
+        let candidates = by_text.entry(item.text.clone()).or_default();
+        let original = candidates.iter().copied().find(|&index| {
+            let other: &TextItem = &kept[index];
+            let distance = tolerance * item.font_size.max(other.font_size);
+            (item.x - other.x).abs() < distance
+                && (item.y - other.y).abs() < distance
+                && (item.font_size - other.font_size).abs() < distance
+        });
+        match original {
+            Some(index) => {
+                let other = &mut kept[index];
+                other.bbox = other.bbox.union(&item.bbox);
+                other.width = other.bbox.width();
+                other.height = other.bbox.height();
+                other.synthetic_bold = true;
+            }
+            None => {
+                candidates.push(kept.len());
+                kept.push(item);
+            }
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+    use crate::ExtractOptions;
+
+    /// The letters of "Invoice" in 12-point Helvetica, each drawn twice, the copy 0.3 points
+    /// to the right, as faux bold is.
+    fn faux_bold() -> Vec<u8> {
+        let mut content = String::from("BT /F1 12 Tf ");
+        let mut x = 72.0;
+        for (letter, width) in [('I', 3.336), ('n', 6.672), ('v', 6.0), ('o', 6.672), ('i', 2.664), ('c', 6.0), ('e', 6.672)] {
+            content += &format!("1 0 0 1 {x} 720 Tm ({letter}) Tj 1 0 0 1 {} 720 Tm ({letter}) Tj ", x + 0.3);
+            x += width;
+        }
+        content += "ET BT /F1 12 Tf 72 700 Td (Total) Tj ET BT /F1 12 Tf 72.3 700 Td (Total) Tj ET BT /F1 12 Tf 72.6 700.3 Td (Total) Tj ET";
+        testing::pdf(&[&content])
+    }
+
+    fn lines(options: &ExtractOptions) -> Vec<String> {
+        let items = testing::items_with(&faux_bold(), options);
+        crate::page_lines(items, options).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn collapses_faux_bold_before_lines_are_grouped() {
+        let items = testing::items_with(&faux_bold(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&items), ["I", "n", "v", "o", "i", "c", "e", "Total"]);
+        assert!(items.iter().all(|item| item.synthetic_bold));
+        // The box covers the copies, 0.6 points further right and 0.3 up for "Total".
+        let total = &items[7];
+        assert!(testing::near(total.bbox.x0, 72.0) && testing::near(total.bbox.width(), total.width));
+        assert!(testing::near(total.bbox.x1 - 72.0, 0.6 + (611.0 + 556.0 + 278.0 + 556.0 + 222.0) * 0.012), "{:?}", total.bbox);
+        assert_eq!(lines(&ExtractOptions::default()), ["Invoice", "Total"]);
+
+        let kept = ExtractOptions::builder().duplicate_tolerance(0.0).build().unwrap();
+        assert_eq!(testing::items_with(&faux_bold(), &kept).len(), 17);
+        assert_eq!(lines(&kept), ["IInnvvooiiccee", "TotalTotalTotal"]);
+    }
+
+    #[test]
+    fn keeps_the_same_text_drawn_apart() {
+        // 2 points apart is more than a tenth of 12 points; so is another size.
+        let items = testing::items("BT /F1 12 Tf 72 720 Td (the) Tj 2 0 Td (the) Tj ET BT /F1 14 Tf 72 720 Td (the) Tj ET BT /F1 12 Tf 200 720 Td (the) Tj ET");
+        assert_eq!(items.len(), 4);
+        assert!(items.iter().all(|item| !item.synthetic_bold));
+    }
+}
@@ -0,0 +1,47 @@
+//! Where the images of a page go among its text, once the text is in reading order.
+
+use crate::{Origin, Rect};
+
+/// The index among `blocks`, boxes of text in reading order, before which an image painted
+/// at `image` is read: that of the first block starting level with or below the image's top
+/// edge and sharing some of its width, as a caption or the text after a figure does, else
+/// of the first starting below it at all, else the end.
+pub fn position(image: &Rect, blocks: &[Rect], origin: Origin) -> usize {
+    let below = |block: &Rect| match origin {
+        Origin::BottomLeft => block.y1 <= image.y1,
+        Origin::TopLeft => block.y0 >= image.y0,
+    };
+    let shares_width = |block: &Rect| block.x0 < image.x1 && image.x0 < block.x1;
+    blocks
+        .iter()
+        .position(|block| below(block) && shares_width(block))
+        .or_else(|| blocks.iter().position(below))
+        .unwrap_or(blocks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Rect {
+        Rect { x0, y0, x1, y1 }
+    }
+
+    #[test]
+    fn reads_an_image_before_the_text_under_it() {
+        // Two columns: a figure at the top of the right one, above its caption.
+        let blocks = [rect(72.0, 600.0, 290.0, 720.0), rect(72.0, 300.0, 290.0, 580.0), rect(320.0, 560.0, 540.0, 575.0), rect(320.0, 300.0, 540.0, 540.0)];
+        let figure = rect(320.0, 590.0, 540.0, 720.0);
+        assert_eq!(position(&figure, &blocks, Origin::BottomLeft), 2);
+        let flipped: Vec<Rect> = blocks.iter().map(|block| rect(block.x0, 792.0 - block.y1, block.x1, 792.0 - block.y0)).collect();
+        assert_eq!(position(&rect(320.0, 72.0, 540.0, 202.0), &flipped, Origin::TopLeft), 2);
+    }
+
+    #[test]
+    fn reads_an_image_below_the_text_after_it() {
+        let figure = rect(72.0, 100.0, 290.0, 300.0);
+        assert_eq!(position(&figure, &[rect(72.0, 600.0, 290.0, 720.0), rect(320.0, 600.0, 540.0, 720.0)], Origin::BottomLeft), 2);
+        // With text below it in another column only, it goes before that text.
+        assert_eq!(position(&figure, &[rect(72.0, 600.0, 290.0, 720.0), rect(320.0, 50.0, 540.0, 90.0)], Origin::BottomLeft), 1);
+    }
+}
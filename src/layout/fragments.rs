@@ -0,0 +1,94 @@
+//! Joining the fragments a word is drawn in, as producers that kern by moving the text
+//! position between show-string operators leave it: "W" "ork" "flow".
+
+use crate::TextItem;
+
+/// The widest gap between fragments, as a fraction of the font size, that is kerning rather
+/// than the space between words; the same as the default of `ExtractOptions::tj_space_threshold`.
+pub const KERNING_GAP: f32 = 0.1;
+
+/// Joins each item, in drawing order, into the one before it when it carries on the same
+/// word: horizontal text on the same baseline, in the same font, size, color and marked
+/// content, starting less than `KERNING_GAP` font sizes from where that one ends, either way.
+/// The text is joined as is and the boxes unioned, and `fragment_ends` records where the text
+/// of each fragment but the last ended.
+pub fn merge(items: Vec<TextItem>) -> Vec<TextItem> {
+    let mut merged: Vec<TextItem> = Vec::with_capacity(items.len());
+    for item in items {
+        match merged.last_mut() {
+            Some(last) if continues(last, &item) => join(last, item),
+            _ => merged.push(item),
+        }
+    }
+    merged
+}
+
+/// Whether `item` is drawn as the next fragment of the word `last` ends with.
+fn continues(last: &TextItem, item: &TextItem) -> bool {
+    let size = last.font_size;
+    let gap = item.bbox.x0 - last.bbox.x1;
+    !last.vertical
+        && !item.vertical
+        && last.angle == 0.0
+        && item.angle == 0.0
+        && size > 0.0
+        && (item.font_size - size).abs() < 0.01
+        && (item.y - last.y).abs() < KERNING_GAP * size
+        && gap.abs() < KERNING_GAP * size
+        && item.font_resource == last.font_resource
+        && item.font == last.font
+        && item.color == last.color
+        && item.render_mode == last.render_mode
+        && item.rise == last.rise
+        && item.artifact == last.artifact
+        && item.content_id == last.content_id
+        && item.region == last.region
+}
+
+/// Adds `item` to the end of `last`, keeping where the characters of both end.
+fn join(last: &mut TextItem, item: TextItem) {
+    let bbox = last.bbox.union(&item.bbox);
+    let width = bbox.width();
+    let known = last.char_ends.len() == last.text.chars().count() && item.char_ends.len() == item.text.chars().count();
+    if known && width > 0.0 {
+        let at = |item: &TextItem, end: f32| (item.bbox.x0 + end * item.bbox.width() - bbox.x0) / width;
+        let mut ends: Vec<f32> = last.char_ends.iter().map(|&end| at(last, end)).collect();
+        ends.extend(item.char_ends.iter().map(|&end| at(&item, end)));
+        last.char_ends = ends;
+    } else {
+        last.char_ends.clear();
+    }
+    last.fragment_ends.push(last.text.chars().count());
+    last.text.push_str(&item.text);
+    last.bbox = bbox;
+    last.width = bbox.width();
+    last.height = bbox.height();
+    last.synthetic_bold |= item.synthetic_bold;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, near};
+
+    #[test]
+    fn joins_the_fragments_of_a_word() {
+        // "W" is 11.328 points wide in 12-point Helvetica, "ork" 16.668; the fragments after
+        // them are kerned half a point in and a point out, and "apart" a word space away.
+        let content = "BT /F1 12 Tf 1 0 0 1 72 700 Tm (W) Tj 1 0 0 1 82.828 700 Tm (ork) Tj 1 0 0 1 100.496 700 Tm (flow) Tj \
+            1 0 0 1 130 700 Tm (apart) Tj /F1 10 Tf 1 0 0 1 157.8 700 Tm (small) Tj ET";
+        let merged = merge(testing::items(content));
+        assert_eq!(testing::texts(&merged), ["Workflow", "apart", "small"]);
+        assert_eq!(merged[0].fragment_ends, [1, 4]);
+        assert!(near(merged[0].bbox.x0, 72.0) && near(merged[0].bbox.x1, merged[0].x + merged[0].width), "{:?}", merged[0].bbox);
+        assert_eq!(merged[0].char_ends.len(), "Workflow".len());
+        assert!(merged[0].char_ends.windows(2).all(|pair| pair[0] < pair[1]) && near(merged[0].char_ends[7], 1.0), "{:?}", merged[0].char_ends);
+        assert!(merged[1].fragment_ends.is_empty());
+    }
+
+    #[test]
+    fn keeps_fragments_on_other_baselines_apart() {
+        let merged = merge(testing::items("BT /F1 12 Tf 72 700 Td (x) Tj 2 Ts (2) Tj 0 Ts 0 -20 Td (Next line) Tj ET"));
+        assert_eq!(testing::texts(&merged), ["x", "2", "Next line"]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Finding page furniture: running headers, footers and page numbers repeated across pages.
+
+use super::lines::Line;
+use crate::matrix::Rect;
+use crate::Origin;
+use std::collections::HashMap;
+
+/// The fraction of the page height at its top and bottom where furniture is looked for.
+const MARGIN: f32 = 0.1;
+
+/// How far, as a fraction of the page height, a repeat may sit from where the others do.
+const POSITION_TOLERANCE: f32 = 0.02;
+
+/// Documents shorter than this have too few pages to tell furniture from body text.
+const MIN_PAGES: usize = 3;
+
+/// Where a candidate line occurs: page index, line index and height from the top of the page.
+type Occurrence = (usize, usize, f32);
+
+/// A page's lines and its crop box, in the same space.
+pub struct PageLines {
+    pub crop_box: Rect,
+    pub lines: Vec<Line>,
+}
+
+/// Sets `Line::repeated` on lines in the top or bottom margin whose text, with digits
+/// ignored so page numbers may differ, recurs at the same height on most pages.
+pub fn mark_repeated(pages: &mut [PageLines], origin: Origin) {
+    if pages.len() < MIN_PAGES {
+        return;
+    }
+    // Candidates by margin and text: each occurrence's page, line and height on the page.
+    let mut candidates: HashMap<(bool, String), Vec<Occurrence>> = HashMap::new();
+    for (page_index, page) in pages.iter().enumerate() {
+        let height = page.crop_box.height();
+        if height <= 0.0 {
+            continue;
+        }
+        for (line_index, line) in page.lines.iter().enumerate() {
+            let center = (line.bbox.y0 + line.bbox.y1) / 2.0;
+            let from_top = match origin {
+                Origin::BottomLeft => (page.crop_box.y1 - center) / height,
+                Origin::TopLeft => (center - page.crop_box.y0) / height,
+            };
+            let top = from_top <= MARGIN;
+            if !top && from_top < 1.0 - MARGIN {
+                continue;
+            }
+            let Some(key) = normalize(&line.text) else { continue };
+            candidates.entry((top, key)).or_default().push((page_index, line_index, from_top));
+        }
+    }
+
+    for occurrences in candidates.values() {
+        let mut heights: Vec<f32> = occurrences.iter().map(|&(_, _, height)| height).collect();
+        heights.sort_by(f32::total_cmp);
+        let median = heights[heights.len() / 2];
+        let consistent: Vec<&Occurrence> =
+            occurrences.iter().filter(|&&(_, _, height)| (height - median).abs() <= POSITION_TOLERANCE).collect();
+        let mut pages_seen: Vec<usize> = consistent.iter().map(|&&(page, _, _)| page).collect();
+        pages_seen.dedup();
+        if pages_seen.len() * 2 <= pages.len() {
+            continue;
+        }
+        for &&(page, line, _) in &consistent {
+            pages[page].lines[line].repeated = true;
+        }
+    }
+}
+
+/// The text compared across pages: lowercased, white space collapsed, digits replaced by
+/// `#`. `None` for lines without any visible text.
+fn normalize(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).flat_map(char::to_lowercase).collect())
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::lines;
+    use crate::{testing, ExtractOptions};
+
+    /// The lines of each page of a document showing `pages`, in 10-point Helvetica.
+    fn pages(pages: &[String]) -> Vec<PageLines> {
+        let contents: Vec<&str> = pages.iter().map(String::as_str).collect();
+        testing::pages_with(&testing::pdf(&contents), &ExtractOptions::default())
+            .into_iter()
+            .map(|page| PageLines {
+                crop_box: page.crop_box,
+                lines: lines::group(page.items, Origin::BottomLeft, lines::DEFAULT_TOLERANCE, lines::DEFAULT_WORD_GAP, lines::DEFAULT_MAX_SKEW),
+            })
+            .collect()
+    }
+
+    /// A page with a running header, a body line and a numbered footer; the header of the
+    /// second page is a little lower.
+    fn page(number: usize, body: &str) -> String {
+        let header = if number == 2 { 764.0 } else { 770.0 };
+        format!(
+            "BT /F1 10 Tf 1 0 0 1 72 {} Tm (Acme Corp Annual Report) Tj 1 0 0 1 72 400 Tm ({}) Tj 1 0 0 1 290 30 Tm (Page {}) Tj ET",
+            header, body, number
+        )
+    }
+
+    fn repeated(pages: &[PageLines]) -> Vec<Vec<&str>> {
+        pages.iter().map(|page| page.lines.iter().filter(|line| line.repeated).map(|line| line.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn marks_a_header_and_footer_repeated_across_pages() {
+        let mut lines = pages(&[page(1, "Revenue grew."), page(2, "Costs fell."), page(3, "Revenue grew.")]);
+        mark_repeated(&mut lines, Origin::BottomLeft);
+        // The page numbers differ in their digits alone; the body text repeats in the middle.
+        let header = "Acme Corp Annual Report";
+        assert_eq!(repeated(&lines), [[header, "Page 1"], [header, "Page 2"], [header, "Page 3"]]);
+    }
+
+    #[test]
+    fn marks_nothing_in_a_short_document_or_text_that_changes() {
+        let mut short = pages(&[page(1, "Revenue grew."), page(2, "Costs fell.")]);
+        mark_repeated(&mut short, Origin::BottomLeft);
+        assert!(repeated(&short).iter().all(Vec::is_empty));
+        let titles: Vec<String> = ["Revenue", "Costs", "Outlook"].iter().map(|title| format!("BT /F1 10 Tf 72 770 Td ({}) Tj ET", title)).collect();
+        let mut changing = pages(&titles);
+        mark_repeated(&mut changing, Origin::BottomLeft);
+        assert!(repeated(&changing).iter().all(Vec::is_empty));
+    }
+}
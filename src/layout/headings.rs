@@ -0,0 +1,145 @@
+//! Finding the headings of a document and their levels, from the type they are set in and
+//! the numbers they are given.
+
+use super::paragraphs::{Paragraph, HEADING_RATIO};
+use crate::{Origin, PageText};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Heading sizes within this fraction of each other are one heading level.
+const SIZE_CLUSTER: f32 = 0.05;
+
+/// Paragraphs with more lines or words than these are body text, whatever their type.
+const MAX_LINES: usize = 3;
+const MAX_WORDS: usize = 25;
+
+/// Section numbers with more parts than this, such as `1.2.3.4.5.6.7`, are something else.
+const MAX_DEPTH: usize = 6;
+
+/// A heading of the document.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Heading {
+    /// From 1 for the largest headings.
+    pub level: usize,
+    pub text: String,
+    pub page: u32,
+    /// The top of the heading on its page, placed like the items.
+    pub y: f32,
+    /// Which of its page's paragraphs it is.
+    #[serde(skip)]
+    pub paragraph: usize,
+}
+
+/// The headings of the pages, in reading order: short paragraphs set larger than the body
+/// text, or single lines all in bold, leaving out running headers. Each size, and bold or
+/// not within a size, is a level, from the largest down, and bold lines of the body size are
+/// the lowest. Headings numbered as sections, such as `3.2.1 Results`, take their level from
+/// the number instead, as long as it follows from the number before, where the numbers of
+/// top sections sit as deep as their type.
+pub fn detect(pages: &[PageText], origin: Origin) -> Vec<Heading> {
+    let body = body_size(pages);
+    // Each heading with its size, or `None` for bold lines of the body size, and weight.
+    let mut candidates: Vec<(Heading, Option<f32>, bool)> = Vec::new();
+    for page in pages {
+        for (index, paragraph) in page.paragraphs.iter().enumerate() {
+            let words = paragraph.text.split_whitespace().count();
+            let furniture = paragraph.lines.iter().all(|line| line.repeated);
+            if paragraph.lines.len() > MAX_LINES || words > MAX_WORDS || furniture || !paragraph.text.chars().any(char::is_alphabetic) {
+                continue;
+            }
+            let size = font_size(paragraph);
+            let bold = paragraph.lines.iter().flat_map(|line| &line.items).all(|item| item.bold || item.synthetic_bold);
+            let sized = (size > body * HEADING_RATIO).then_some(size);
+            if sized.is_none() && !(bold && paragraph.lines.len() == 1) {
+                continue;
+            }
+            let y = match origin {
+                Origin::BottomLeft => paragraph.bbox.y1,
+                Origin::TopLeft => paragraph.bbox.y0,
+            };
+            let heading = Heading { level: 0, text: paragraph.text.clone(), page: page.page_num, y, paragraph: index };
+            candidates.push((heading, sized, bold));
+        }
+    }
+
+    // The styles headings are set in, largest first, bold first within a size.
+    let mut sizes: Vec<f32> = candidates.iter().filter_map(|&(_, size, _)| size).collect();
+    sizes.sort_by(|a, b| b.total_cmp(a));
+    let mut clusters: Vec<f32> = Vec::new();
+    for size in sizes {
+        if clusters.last().is_none_or(|&last| size < last * (1.0 - SIZE_CLUSTER)) {
+            clusters.push(size);
+        }
+    }
+    let cluster = |size: Option<f32>| match size {
+        Some(size) => clusters.iter().position(|&cluster| size >= cluster * (1.0 - SIZE_CLUSTER)).unwrap_or(clusters.len()),
+        None => clusters.len(),
+    };
+    let mut styles: Vec<(usize, bool)> = candidates.iter().map(|&(_, size, bold)| (cluster(size), !bold)).collect();
+    styles.sort();
+    styles.dedup();
+    for (heading, size, bold) in &mut candidates {
+        heading.level = styles.iter().position(|&style| style == (cluster(*size), !*bold)).unwrap_or(0) + 1;
+    }
+
+    // Section numbers that follow from the one before are trusted over the type.
+    let mut previous: Option<Vec<u32>> = None;
+    let mut numbered: Vec<(usize, usize)> = Vec::new();
+    for (index, (heading, _, _)) in candidates.iter().enumerate() {
+        let Some(number) = section_number(&heading.text) else { continue };
+        if previous.as_ref().is_none_or(|previous| follows(previous, &number)) {
+            numbered.push((index, number.len()));
+            previous = Some(number);
+        }
+    }
+    // How much deeper than its number a top section sits, as most of them do.
+    let top = numbered.iter().map(|&(_, depth)| depth).min();
+    let mut offsets: HashMap<usize, usize> = HashMap::new();
+    for &(index, depth) in numbered.iter().filter(|&&(_, depth)| Some(depth) == top) {
+        *offsets.entry(candidates[index].0.level.saturating_sub(depth)).or_default() += 1;
+    }
+    let offset = offsets.into_iter().max_by_key(|&(offset, count)| (count, std::cmp::Reverse(offset))).map_or(0, |(offset, _)| offset);
+    for (index, depth) in numbered {
+        candidates[index].0.level = depth + offset;
+    }
+    candidates.into_iter().map(|(heading, _, _)| heading).collect()
+}
+
+/// The font size most of the document's characters are set in, to the nearest half point.
+pub fn body_size(pages: &[PageText]) -> f32 {
+    let mut counts: HashMap<i32, usize> = HashMap::new();
+    for item in pages.iter().flat_map(|page| &page.paragraphs).flat_map(|paragraph| &paragraph.lines).flat_map(|line| &line.items) {
+        *counts.entry((item.font_size * 2.0).round() as i32).or_default() += item.text.chars().count();
+    }
+    counts.into_iter().max_by_key(|&(size, count)| (count, -size)).map_or(0.0, |(size, _)| size as f32 / 2.0)
+}
+
+fn font_size(paragraph: &Paragraph) -> f32 {
+    paragraph.lines.iter().map(|line| line.font_size()).fold(0.0, f32::max)
+}
+
+/// The parts of the section number a heading starts with, such as 3, 2 and 1 for
+/// `3.2.1 Results` or `3.2.1. Results`, when words follow it.
+fn section_number(text: &str) -> Option<Vec<u32>> {
+    let (number, rest) = text.trim_start().split_once(char::is_whitespace)?;
+    let number = number.strip_suffix('.').unwrap_or(number);
+    if !rest.trim_start().starts_with(char::is_alphabetic) {
+        return None;
+    }
+    let parts: Vec<u32> = number
+        .split('.')
+        .map(|part| Some(part).filter(|part| part.len() <= 3 && part.chars().all(|c| c.is_ascii_digit())).and_then(|part| part.parse().ok()))
+        .collect::<Option<_>>()?;
+    (parts.len() <= MAX_DEPTH).then_some(parts)
+}
+
+/// Whether a section number can come after another: the next at its depth or any above it,
+/// as 3.3 or 4 after 3.2.1, or the first below it, as 3.2.1.1.
+fn follows(previous: &[u32], number: &[u32]) -> bool {
+    let Some((last, parents)) = number.split_last() else { return false };
+    if number.len() == previous.len() + 1 {
+        return parents == previous && *last == 1;
+    }
+    number.len() <= previous.len() && parents == &previous[..parents.len()] && *last == previous[parents.len()] + 1
+}
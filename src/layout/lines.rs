@@ -0,0 +1,390 @@
+//! Grouping text items into lines.
+
+use crate::matrix::Rect;
+use crate::{Origin, TextItem};
+
+/// The default baseline tolerance, as a fraction of the font size.
+pub const DEFAULT_TOLERANCE: f32 = 0.3;
+
+/// The default gap between fragments that separates words, as a fraction of the font size.
+/// Kerning moves glyphs by a few hundredths of an em, word spaces by a quarter or more.
+pub const DEFAULT_WORD_GAP: f32 = 0.25;
+
+/// The default largest angle, in degrees, the baseline of a line may lean by for its items
+/// to be grouped by how far they are from it rather than by their y.
+pub const DEFAULT_MAX_SKEW: f32 = 0.5;
+
+/// How many degrees the baselines of items may differ by for them to share a line.
+const ANGLE_TOLERANCE: f32 = 2.0;
+
+/// Horizontal gaps wider than this many font sizes separate columns or table cells, whose
+/// text is never merged into one line here.
+const COLUMN_GAP: f32 = 2.0;
+
+/// Items sharing a baseline, left to right, or for vertical text a column, top to bottom.
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub text: String,
+    pub bbox: Rect,
+    pub items: Vec<TextItem>,
+    /// Which column of its band the line is in, counted from the left, once columns have
+    /// been arranged; 0 otherwise.
+    pub column: usize,
+    /// Whether the line is a running header or footer, once furniture has been marked.
+    pub repeated: bool,
+    /// Whether the line is vertical text, its items written top to bottom.
+    pub vertical: bool,
+    /// The region of the page its items are in, as `TextItem::region`.
+    pub region: Option<usize>,
+    /// How many degrees the baseline fitted through the origins of the line's items leans,
+    /// counterclockwise on the page as displayed; 0 for lines grouped by y alone.
+    pub skew: f32,
+}
+
+/// Groups consecutive items whose baselines differ by at most `tolerance` times their font
+/// size into lines, keeping the order the lines first appear in. Fragments within a line are
+/// joined with a space where they are more than `word_gap` font sizes apart, and directly
+/// otherwise, so kerned pieces of one word stay one word.
+///
+/// Where the baseline fitted through the items of a line so far leans by no more than
+/// `max_skew` degrees, as in scanned and vectorized pages drawn a little askew, the next item
+/// is measured from that baseline, across it, rather than by its y; lines leaning more are
+/// grouped by y alone.
+///
+/// Vertical items are grouped the same way by the x of their centres, and run down the page,
+/// which is decreasing y unless `origin` measures from the top.
+pub fn group(items: Vec<TextItem>, origin: Origin, tolerance: f32, word_gap: f32, max_skew: f32) -> Vec<Line> {
+    let mut runs: Vec<(Vec<TextItem>, Fit)> = Vec::new();
+    for item in items {
+        match runs.last_mut() {
+            Some((run, fit)) if fit.holds(&run[0], &item, tolerance, origin, max_skew) => {
+                fit.add(&item);
+                run.push(item);
+            }
+            _ => {
+                let mut fit = Fit::new(&item);
+                fit.add(&item);
+                runs.push((vec![item], fit));
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (mut run, fit) in runs {
+        let skew = fit.slope(&run[0], origin, max_skew).map_or(0.0, |slope| {
+            let degrees = slope.atan().to_degrees() + 0.0;
+            if origin == Origin::TopLeft { -degrees } else { degrees }
+        });
+        run.sort_by(|a, b| extent(a, origin).0.partial_cmp(&extent(b, origin).0).unwrap_or(std::cmp::Ordering::Equal));
+        let mut items: Vec<TextItem> = Vec::new();
+        for item in run {
+            let column_break = items.last().is_some_and(|last| {
+                extent(&item, origin).0 - extent(last, origin).1 > COLUMN_GAP * item.font_size.max(last.font_size)
+            });
+            if column_break {
+                lines.push(Line::new(std::mem::take(&mut items), origin, word_gap, skew));
+            }
+            items.push(item);
+        }
+        lines.push(Line::new(items, origin, word_gap, skew));
+    }
+    lines
+}
+
+/// A baseline fitted by least squares through the origins of the items of a line, from the
+/// sums of their coordinates. These are measured from the origin of the line's first item,
+/// for the sums of level lines to stay exactly level rather than lose their low digits to
+/// the size of page coordinates.
+#[derive(Debug, Clone, Copy, Default)]
+struct Fit {
+    from: (f32, f32),
+    count: f32,
+    x: f32,
+    y: f32,
+    xx: f32,
+    xy: f32,
+}
+
+impl Fit {
+    fn new(first: &TextItem) -> Fit {
+        Fit { from: (first.x, first.y), ..Fit::default() }
+    }
+
+    fn add(&mut self, item: &TextItem) {
+        let (x, y) = (item.x - self.from.0, item.y - self.from.1);
+        self.count += 1.0;
+        self.x += x;
+        self.y += y;
+        self.xx += x * x;
+        self.xy += x * y;
+    }
+
+    /// The slope of the baseline, dy over dx as `origin` measures them, when it leans by
+    /// `max_skew` degrees or less, and by more than nothing: fitted through the origins of
+    /// two items or more spread along it, or the baseline of the single item `first`.
+    fn slope(&self, first: &TextItem, origin: Origin, max_skew: f32) -> Option<f32> {
+        if first.vertical {
+            return None;
+        }
+        let spread = self.xx - self.x * self.x / self.count;
+        let slope = if self.count >= 2.0 && spread > f32::EPSILON {
+            (self.xy - self.x * self.y / self.count) / spread
+        } else {
+            let slope = first.angle.to_radians().tan();
+            if origin == Origin::TopLeft { -slope } else { slope }
+        };
+        (slope != 0.0 && slope.abs() <= max_skew.to_radians().tan()).then_some(slope)
+    }
+
+    /// Whether `item` carries on the line begun by `first`: written alike, and within
+    /// `tolerance` font sizes of the baseline, across it, when the baseline leans, or of
+    /// `first` by y, as `same_baseline` has it, when it does not.
+    fn holds(&self, first: &TextItem, item: &TextItem, tolerance: f32, origin: Origin, max_skew: f32) -> bool {
+        let Some(slope) = self.slope(first, origin, max_skew) else { return same_baseline(first, item, tolerance) };
+        if !written_alike(first, item) {
+            return false;
+        }
+        let (x, y) = (self.from.0 + self.x / self.count, self.from.1 + self.y / self.count);
+        let distance = ((item.y - y) - slope * (item.x - x)).abs() / (1.0 + slope * slope).sqrt();
+        distance <= tolerance * first.font_size.max(item.font_size)
+    }
+}
+
+/// Where an item starts and ends in the direction it is written.
+fn extent(item: &TextItem, origin: Origin) -> (f32, f32) {
+    match (item.vertical, origin) {
+        (false, _) => (item.bbox.x0, item.bbox.x1),
+        (true, Origin::BottomLeft) => (-item.bbox.y1, -item.bbox.y0),
+        (true, Origin::TopLeft) => (item.bbox.y0, item.bbox.y1),
+    }
+}
+
+/// Whether a space separates two consecutive items of a line: they are more than `word_gap`
+/// font sizes apart and neither has white space at the gap.
+fn is_word_break(left: &TextItem, right: &TextItem, origin: Origin, word_gap: f32) -> bool {
+    let spaced = left.text.ends_with(char::is_whitespace) || right.text.starts_with(char::is_whitespace);
+    !spaced && extent(right, origin).0 - extent(left, origin).1 > word_gap * left.font_size.max(right.font_size)
+}
+
+/// Whether two items are written the same way, their baselines at compatible angles, and
+/// are close enough across the writing direction to be on one line.
+fn same_baseline(a: &TextItem, b: &TextItem, tolerance: f32) -> bool {
+    let (a_position, b_position) = if a.vertical { (a.x, b.x) } else { (a.y, b.y) };
+    written_alike(a, b) && (a_position - b_position).abs() <= tolerance * a.font_size.max(b.font_size)
+}
+
+/// Whether two items are written the same way, their baselines at compatible angles.
+fn written_alike(a: &TextItem, b: &TextItem) -> bool {
+    let turn = (a.angle - b.angle).rem_euclid(360.0);
+    a.vertical == b.vertical && turn.min(360.0 - turn) <= ANGLE_TOLERANCE
+}
+
+impl Line {
+    /// The largest font size among the line's items.
+    pub fn font_size(&self) -> f32 {
+        self.items.iter().map(|item| item.font_size).fold(0.0, f32::max)
+    }
+
+    /// The words of the line and their boxes: each glyph is given the slice of its item's box
+    /// its advance covers, or, when that is not known, an equal share of it.
+    pub fn words(&self, origin: Origin, word_gap: f32) -> Vec<(String, Rect)> {
+        let mut words = Vec::new();
+        let mut word: Option<(String, Rect)> = None;
+        let mut previous: Option<&TextItem> = None;
+        for item in &self.items {
+            if previous.is_some_and(|previous| is_word_break(previous, item, origin, word_gap)) {
+                words.extend(word.take());
+            }
+            previous = Some(item);
+            let count = item.text.chars().count();
+            for (index, c) in item.text.chars().enumerate() {
+                if c.is_whitespace() {
+                    words.extend(word.take());
+                    continue;
+                }
+                let glyph = glyph_box(item, index, count, origin);
+                match &mut word {
+                    Some((text, bbox)) => {
+                        text.push(c);
+                        *bbox = bbox.union(&glyph);
+                    }
+                    None => word = Some((c.to_string(), glyph)),
+                }
+            }
+        }
+        words.extend(word);
+        words
+    }
+
+    /// The gaps between consecutive items of the line, in the direction it is written, in
+    /// font sizes of the larger of the two: those wider than `word_gap` separate words.
+    pub fn gaps(&self, origin: Origin) -> Vec<f32> {
+        self.items
+            .windows(2)
+            .map(|pair| (extent(&pair[1], origin).0 - extent(&pair[0], origin).1, pair[0].font_size.max(pair[1].font_size)))
+            .filter(|&(_, size)| size > 0.0)
+            .map(|(gap, size)| gap / size)
+            .collect()
+    }
+
+    /// Each character of the line's text with its box, as [`Line::words`] shares them out;
+    /// `None` for the spaces put between items that are words apart.
+    pub fn glyphs(&self, origin: Origin, word_gap: f32) -> Vec<(char, Option<Rect>)> {
+        let mut glyphs = Vec::new();
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 && is_word_break(&self.items[i - 1], item, origin, word_gap) {
+                glyphs.push((' ', None));
+            }
+            let count = item.text.chars().count();
+            glyphs.extend(item.text.chars().enumerate().map(|(index, c)| (c, Some(glyph_box(item, index, count, origin)))));
+        }
+        glyphs
+    }
+
+    fn new(items: Vec<TextItem>, origin: Origin, word_gap: f32, skew: f32) -> Line {
+        let mut text = String::new();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 && is_word_break(&items[i - 1], item, origin, word_gap) {
+                text.push(' ');
+            }
+            text.push_str(&item.text);
+        }
+        let bbox = items.iter().skip(1).fold(items[0].bbox, |bbox, item| bbox.union(&item.bbox));
+        let (vertical, region) = (items[0].vertical, items[0].region);
+        Line { text, bbox, items, column: 0, repeated: false, vertical, region, skew }
+    }
+}
+
+/// The slice of an item's box of the `index`th of its `count` characters, from where its text
+/// starts: as far along it as the glyph's advance, or an equal share when that is not known.
+fn glyph_box(item: &TextItem, index: usize, count: usize, origin: Origin) -> Rect {
+    let (start, end) = match item.char_ends.get(index) {
+        Some(&end) if item.char_ends.len() == count => (index.checked_sub(1).map_or(0.0, |before| item.char_ends[before]), end),
+        _ => (index as f32 / count as f32, (index + 1) as f32 / count as f32),
+    };
+    let Rect { x0, y0, x1, y1 } = item.bbox;
+    match (item.vertical, origin) {
+        (false, _) => Rect { x0: x0 + (x1 - x0) * start, y0, x1: x0 + (x1 - x0) * end, y1 },
+        (true, Origin::BottomLeft) => Rect { x0, y0: y1 - (y1 - y0) * end, x1, y1: y1 - (y1 - y0) * start },
+        (true, Origin::TopLeft) => Rect { x0, y0: y0 + (y1 - y0) * start, x1, y1: y0 + (y1 - y0) * end },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn lines(content: &str, tolerance: f32) -> Vec<Line> {
+        group(testing::items(content), Origin::BottomLeft, tolerance, DEFAULT_WORD_GAP, DEFAULT_MAX_SKEW)
+    }
+
+    fn texts(lines: &[Line]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn groups_a_two_line_page() {
+        // "Ke" ends at 72 + 14.676, where "rned" starts; "text" starts a word space later.
+        let content = "BT /F1 12 Tf 1 0 0 1 72 700 Tm (Ke) Tj 1 0 0 1 86.7 700 Tm (rned) Tj 1 0 0 1 115 700.5 Tm (text) Tj \
+            1 0 0 1 72 686 Tm (The second) Tj ( line) Tj ET";
+        let lines = lines(content, DEFAULT_TOLERANCE);
+        assert_eq!(texts(&lines), ["Kerned text", "The second line"]);
+        assert_eq!(lines[0].items.len(), 3);
+        assert!(lines[0].bbox.x0 == 72.0 && lines[0].bbox.y0 < lines[0].bbox.y1);
+        assert!(lines[1].bbox.y1 < lines[0].bbox.y1);
+    }
+
+    #[test]
+    fn joins_the_kerned_fragments_of_a_tj() {
+        let lines = lines("BT /F1 12 Tf 72 700 Td [(W) 80 (ave) -20 (s) -600 (apart)] TJ ET", DEFAULT_TOLERANCE);
+        assert_eq!(texts(&lines), ["Waves apart"]);
+    }
+
+    #[test]
+    fn spaces_words_placed_without_space_glyphs() {
+        // Words apart by a third of an em in TJ, and by Td gaps of 4.7 to 8 points.
+        let content = "BT /F1 12 Tf 72 720 Td [(The) -300 (quick) -300 (brown) -300 (fox)] TJ \
+            0 -14 Td (jumps) Tj 40 0 Td (over) Tj 30 0 Td (the) Tj 22 0 Td (lazy) Tj 26 0 Td (dog.) Tj ET";
+        assert_eq!(texts(&lines(content, DEFAULT_TOLERANCE)), ["The quick brown fox", "jumps over the lazy dog."]);
+    }
+
+    #[test]
+    fn keeps_tightly_kerned_words_whole() {
+        // Pairs drawn together by up to 0.11 em, as display type is set, and apart by less
+        // than the 0.1 em a TJ moves by to break words.
+        let content = "BT /F1 12 Tf 14 TL 72 720 Td [(A) 110 (V) 110 (A) 40 (T) 80 (A) 90 (R)] TJ T* \
+            [(T) 70 (y) -30 (p) -15 (o) -45 (g) 20 (r) -60 (a) 10 (p) -5 (h) 25 (y)] TJ T* \
+            [(W) 80 (ave) -60 (s) 60 (,) -350 (T) 70 (o) 15 (w) -20 (er)] TJ ET";
+        assert_eq!(texts(&lines(content, DEFAULT_TOLERANCE)), ["AVATAR", "Typography", "Waves, Tower"]);
+    }
+
+    #[test]
+    fn sorts_a_line_left_to_right() {
+        let lines = lines("BT /F1 12 Tf 1 0 0 1 145 700 Tm (right) Tj 1 0 0 1 72 700 Tm (left,) Tj 1 0 0 1 96 700 Tm (middle,) Tj ET", DEFAULT_TOLERANCE);
+        assert_eq!(texts(&lines), ["left, middle, right"]);
+    }
+
+    #[test]
+    fn keeps_columns_apart() {
+        let lines = lines("BT /F1 12 Tf 1 0 0 1 72 700 Tm (First column) Tj 1 0 0 1 320 700 Tm (Second column) Tj ET", DEFAULT_TOLERANCE);
+        assert_eq!(texts(&lines), ["First column", "Second column"]);
+    }
+
+    #[test]
+    fn splits_lines_by_the_tolerance() {
+        // Baselines 3 points apart: within 0.3 of 12 points, not within 0.2.
+        let content = "BT /F1 12 Tf 1 0 0 1 72 700 Tm (Up) Tj 1 0 0 1 92 697 Tm (down) Tj ET";
+        assert_eq!(texts(&lines(content, DEFAULT_TOLERANCE)), ["Up down"]);
+        assert_eq!(texts(&lines(content, 0.2)), ["Up", "down"]);
+    }
+
+    #[test]
+    fn keeps_text_at_other_angles_off_the_line() {
+        let content = "BT /F1 12 Tf 1 0 0 1 72 700 Tm (Level) Tj 0.9994 0.0349 -0.0349 0.9994 106 700 Tm (turned) Tj \
+            0 1 -1 0 150 700 Tm (Up) Tj ET";
+        // 2 degrees is still the same line; a quarter turn is not.
+        assert_eq!(texts(&lines(content, DEFAULT_TOLERANCE)), ["Level turned", "Up"]);
+    }
+
+    #[test]
+    fn groups_a_page_turned_a_little_as_its_upright_twin() {
+        // Lines of 500 points or so, which rise 2.6 points over their length when turned 0.3
+        // degrees: more than a tolerance of 0.2 of 12 points.
+        let line = "(The quick brown) Tj ( fox jumps over) Tj ( the lazy dog,) Tj ( then back again) Tj ( and over once more.) Tj";
+        let text: String = (0..10).map(|row| format!("BT /F1 12 Tf 1 0 0 1 36 {} Tm {} ET ", 700 - 14 * row, line)).collect();
+        let turned = format!("q 0.99998629 0.00523596 -0.00523596 0.99998629 0 0 cm {}Q", text);
+        for tolerance in [DEFAULT_TOLERANCE, 0.2] {
+            let (upright, turned) = (lines(&text, tolerance), lines(&turned, tolerance));
+            assert_eq!(upright.len(), 10);
+            assert_eq!(turned.len(), upright.len());
+            assert_eq!(texts(&turned), texts(&upright));
+            assert!(upright.iter().all(|line| line.skew == 0.0), "{:?}", upright.iter().map(|line| line.skew).collect::<Vec<_>>());
+            assert!(turned.iter().all(|line| (line.skew - 0.3).abs() < 0.01), "{:?}", turned.iter().map(|line| line.skew).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn derives_the_same_words_and_lines_from_characters() {
+        let content = "BT /F1 12 Tf 14 TL 72 720 Td (Hello World) Tj T* [(Ke) 20 (rned,) -300 (spaced)] TJ T* \
+            0.5 Tc 2 Tw (Char and word spacing) Tj T* 0 Tc 0 Tw 80 Tz (Condensed text) Tj T* 100 Tz 3 Ts (Raised) Tj 0 Ts ( and not) Tj ET";
+        let data = testing::pdf(&[content]);
+        let group_items = |granularity| {
+            let options = crate::ExtractOptions::builder().granularity(granularity).build().unwrap();
+            group(testing::items_with(&data, &options), Origin::BottomLeft, DEFAULT_TOLERANCE, DEFAULT_WORD_GAP, DEFAULT_MAX_SKEW)
+        };
+        let (items, chars) = (group_items(crate::Granularity::Items), group_items(crate::Granularity::Chars));
+        assert_eq!(texts(&items), ["Hello World", "Kerned, spaced", "Char and word spacing", "Condensed text", "Raised and not"]);
+        assert_eq!(texts(&chars), texts(&items));
+        assert!(chars.iter().flat_map(|line| &line.items).all(|item| item.text.chars().count() == 1));
+        let near = |a: &Rect, b: &Rect| [a.x0 - b.x0, a.y0 - b.y0, a.x1 - b.x1, a.y1 - b.y1].iter().all(|d| d.abs() < 0.01);
+        for (from_chars, from_items) in chars.iter().zip(&items) {
+            assert!(near(&from_chars.bbox, &from_items.bbox), "{}: {:?} {:?}", from_items.text, from_chars.bbox, from_items.bbox);
+            let (words, expected) = (from_chars.words(Origin::BottomLeft, DEFAULT_WORD_GAP), from_items.words(Origin::BottomLeft, DEFAULT_WORD_GAP));
+            assert_eq!(words.iter().map(|(word, _)| word).collect::<Vec<_>>(), expected.iter().map(|(word, _)| word).collect::<Vec<_>>());
+            for ((word, rect), (_, expected)) in words.iter().zip(&expected) {
+                assert!(near(rect, expected), "{}: {:?} {:?}", word, rect, expected);
+            }
+        }
+    }
+}
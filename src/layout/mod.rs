@@ -0,0 +1,13 @@
+//! Rebuilding the visual structure of a page from its positioned text items.
+
+pub(crate) mod bidi;
+pub mod columns;
+pub mod duplicates;
+pub mod figures;
+pub mod fragments;
+pub(crate) mod furniture;
+pub mod headings;
+pub mod lines;
+pub mod paragraphs;
+pub mod tables;
+pub mod title;
@@ -0,0 +1,273 @@
+//! Joining lines into paragraphs.
+
+use super::lines::Line;
+use crate::matrix::Rect;
+use std::collections::HashSet;
+
+/// How much closer than the page's dominant leading two baselines may be, as a fraction of
+/// it, for the lines to be in one paragraph.
+const LEADING_TOLERANCE: f32 = 0.3;
+
+/// The default distance between baselines, as a multiple of the page's dominant leading, past
+/// which lines are in paragraphs of their own.
+pub const DEFAULT_GAP: f32 = 1.3;
+
+/// How far, in font sizes, a first line may be indented from the rest of its paragraph.
+const MAX_INDENT: f32 = 4.0;
+
+/// How far, in font sizes, the left edges of a paragraph's other lines may differ.
+const MARGIN_TOLERANCE: f32 = 0.5;
+
+/// A line narrower than this fraction of the widest line around it ends its paragraph.
+const SHORT_LINE: f32 = 0.75;
+
+/// A line whose font is this much larger than its neighbour's is a heading of its own.
+pub const HEADING_RATIO: f32 = 1.2;
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+#[derive(Debug, Clone)]
+pub struct Paragraph {
+    /// The lines' text joined with spaces, or without one after a soft hyphen, which is
+    /// dropped; see also [`dehyphenate`]. The lines keep the original text.
+    pub text: String,
+    pub bbox: Rect,
+    pub lines: Vec<Line>,
+    /// The region of the page its lines are in, as `TextItem::region`.
+    pub region: Option<usize>,
+}
+
+/// Joins consecutive lines of a page, given in reading order, into paragraphs. Lines whose
+/// baselines are more than `gap` times the page's dominant leading apart are in different
+/// paragraphs.
+pub fn group(lines: Vec<Line>, gap: f32) -> Vec<Paragraph> {
+    let leading = dominant_leading(&lines);
+    let mut paragraphs: Vec<Vec<Line>> = Vec::new();
+    for line in lines {
+        match paragraphs.last_mut() {
+            Some(paragraph) if continues(paragraph, &line, leading, gap) => paragraph.push(line),
+            _ => paragraphs.push(vec![line]),
+        }
+    }
+    paragraphs.into_iter().map(Paragraph::new).collect()
+}
+
+/// Joins words hyphenated across line ends in the paragraphs of a whole document:
+/// `extrac-` `tion` becomes `extraction` when lowercase letters surround the hyphen, unless
+/// the document also has the hyphenated form within a line, as with `well-known`.
+pub fn dehyphenate(paragraphs: &mut [Paragraph]) {
+    let mut compounds = HashSet::new();
+    for line in paragraphs.iter().flat_map(|paragraph| &paragraph.lines) {
+        for word in line.text.split_whitespace() {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if word.contains('-') {
+                compounds.insert(word.to_lowercase());
+            }
+        }
+    }
+    for paragraph in paragraphs {
+        paragraph.text = join_lines(&paragraph.lines, Some(&compounds));
+    }
+}
+
+/// Joins the text of a paragraph's lines; with `compounds`, removes end-of-line hyphens
+/// that break a word rather than a compound.
+pub fn join_lines(lines: &[Line], compounds: Option<&HashSet<String>>) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line = line.text.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if text.ends_with(SOFT_HYPHEN) {
+            text.pop();
+        } else if let Some(joined) = compounds.and_then(|compounds| broken_word(&text, line, compounds)) {
+            if !joined {
+                text.pop();
+            }
+        } else if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(line);
+    }
+    text.replace(SOFT_HYPHEN, "")
+}
+
+/// Whether `text` ends in a word hyphenated before `next`: `Some(true)` if the hyphen
+/// belongs to a compound and stays, `Some(false)` if it only broke the word.
+fn broken_word(text: &str, next: &str, compounds: &HashSet<String>) -> Option<bool> {
+    let before = text.strip_suffix('-')?;
+    let head = before.rsplit(char::is_whitespace).next()?;
+    let tail = next.split(char::is_whitespace).next()?;
+    if !head.chars().last()?.is_lowercase() || !tail.chars().next()?.is_lowercase() {
+        return None;
+    }
+    let head = head.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let tail = tail.trim_end_matches(|c: char| !c.is_alphanumeric());
+    Some(compounds.contains(&format!("{}-{}", head, tail).to_lowercase()))
+}
+
+impl Paragraph {
+    fn new(lines: Vec<Line>) -> Paragraph {
+        let text = join_lines(&lines, None);
+        let bbox = lines.iter().skip(1).fold(lines[0].bbox, |bbox, line| bbox.union(&line.bbox));
+        let region = lines[0].region;
+        Paragraph { text, bbox, lines, region }
+    }
+}
+
+/// Whether `line` carries on the paragraph made of `lines`.
+fn continues(lines: &[Line], line: &Line, leading: Option<f32>, gap: f32) -> bool {
+    let previous = &lines[lines.len() - 1];
+    let size = previous.font_size().max(line.font_size());
+    if line.column != previous.column || line.vertical != previous.vertical || line.region != previous.region || size <= 0.0 {
+        return false;
+    }
+    let (smaller, larger) = (previous.font_size().min(line.font_size()), size);
+    if larger > smaller * HEADING_RATIO {
+        return false;
+    }
+
+    let distance = (baseline(previous) - baseline(line)).abs();
+    let leading = leading.unwrap_or(size * 1.2);
+    if distance > leading * gap || distance < leading * (1.0 - LEADING_TOLERANCE) {
+        return false;
+    }
+
+    // The first line may be indented; later ones share the paragraph's left edge, or top
+    // edge for vertical text, where an indent may go either way depending on the origin.
+    let indent = start(&lines[0]) - start(line);
+    let indent = if line.vertical { indent.abs() } else { indent };
+    let aligned = match lines.get(1) {
+        Some(second) => (start(second) - start(line)).abs() <= MARGIN_TOLERANCE * size,
+        None => indent >= -MARGIN_TOLERANCE * size && indent <= MAX_INDENT * size,
+    };
+    if !aligned {
+        return false;
+    }
+
+    let widest = lines.iter().chain([line]).map(length).fold(0.0, f32::max);
+    length(previous) >= widest * SHORT_LINE
+}
+
+/// The baseline's y, or for vertical text the x of the line's centre.
+pub fn baseline(line: &Line) -> f32 {
+    match (line.items.first(), line.vertical) {
+        (Some(item), true) => item.x,
+        (Some(item), false) => item.y,
+        (None, _) => line.bbox.y0,
+    }
+}
+
+/// Where the line begins: its left edge, or the top of its first glyph for vertical text.
+fn start(line: &Line) -> f32 {
+    match (line.items.first(), line.vertical) {
+        (Some(item), true) => item.y,
+        _ => line.bbox.x0,
+    }
+}
+
+fn length(line: &Line) -> f32 {
+    if line.vertical {
+        line.bbox.height()
+    } else {
+        line.bbox.width()
+    }
+}
+
+/// The most common distance between consecutive baselines, to the nearest half point,
+/// ignoring gaps too large to be between lines of one paragraph.
+pub fn dominant_leading(lines: &[Line]) -> Option<f32> {
+    let mut counts: Vec<(i32, usize)> = Vec::new();
+    for pair in lines.windows(2) {
+        let distance = (baseline(&pair[0]) - baseline(&pair[1])).abs();
+        let size = pair[0].font_size().max(pair[1].font_size());
+        if distance <= 0.0 || distance > size * 3.0 {
+            continue;
+        }
+        let key = (distance * 2.0).round() as i32;
+        match counts.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+    counts.into_iter().max_by_key(|&(key, count)| (count, -key)).map(|(key, _)| key as f32 / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::lines;
+    use crate::testing;
+    use crate::Origin;
+
+    /// A text object showing `lines` from the top of the page down, each `leading` below the
+    /// one before, in 12-point Helvetica.
+    fn text(lines: &[&str], leading: f32) -> String {
+        let shown: Vec<String> = lines.iter().map(|line| format!("({}) Tj T*", line)).collect();
+        format!("BT /F1 12 Tf {} TL 72 720 Td {} ET", leading, shown.join(" "))
+    }
+
+    fn paragraphs(content: &str, gap: f32) -> Vec<Paragraph> {
+        let lines = lines::group(testing::items(content), Origin::BottomLeft, lines::DEFAULT_TOLERANCE, lines::DEFAULT_WORD_GAP, lines::DEFAULT_MAX_SKEW);
+        group(lines, gap)
+    }
+
+    fn texts(paragraphs: &[Paragraph]) -> Vec<&str> {
+        paragraphs.iter().map(|paragraph| paragraph.text.as_str()).collect()
+    }
+
+    #[test]
+    fn joins_lines_set_at_the_leading() {
+        let content = text(&["The quick brown fox jumps", "over the lazy dog, again", "and again and again."], 14.0);
+        let paragraphs = paragraphs(&content, DEFAULT_GAP);
+        assert_eq!(texts(&paragraphs), ["The quick brown fox jumps over the lazy dog, again and again and again."]);
+        assert_eq!(paragraphs[0].lines.len(), 3);
+    }
+
+    #[test]
+    fn breaks_at_a_blank_line() {
+        let first = text(&["The quick brown fox jumps", "over the lazy dog, again"], 14.0);
+        let second = "BT /F1 12 Tf 14 TL 72 678 Td (A second paragraph starts) Tj T* (here and carries on.) Tj ET";
+        let paragraphs = paragraphs(&format!("{} {}", first, second), DEFAULT_GAP);
+        assert_eq!(texts(&paragraphs), ["The quick brown fox jumps over the lazy dog, again", "A second paragraph starts here and carries on."]);
+    }
+
+    #[test]
+    fn ends_a_paragraph_at_a_short_line() {
+        let content = text(&["The quick brown fox jumps", "over the lazy dog.", "Then a new paragraph goes on", "for a while longer."], 14.0);
+        assert_eq!(texts(&paragraphs(&content, DEFAULT_GAP)), ["The quick brown fox jumps over the lazy dog.", "Then a new paragraph goes on for a while longer."]);
+    }
+
+    #[test]
+    fn keeps_a_larger_heading_apart() {
+        let content = "BT /F1 18 Tf 72 720 Td (A heading) Tj ET BT /F1 12 Tf 14 TL 72 700 Td (The body of the section) Tj T* (follows the heading here) Tj ET";
+        assert_eq!(texts(&paragraphs(content, DEFAULT_GAP)), ["A heading", "The body of the section follows the heading here"]);
+    }
+
+    #[test]
+    fn measures_the_dominant_leading() {
+        let content = text(&["one", "two", "three", "four"], 14.0);
+        let lines = lines::group(testing::items(&content), Origin::BottomLeft, 0.3, 0.25, 0.5);
+        assert_eq!(dominant_leading(&lines), Some(14.0));
+        assert_eq!(dominant_leading(&lines[..1]), None);
+    }
+
+    #[test]
+    fn joins_hyphenated_words_but_not_compounds() {
+        let content = text(&["A well-known extrac-", "tion method is well-", "known to all of us."], 14.0);
+        let mut paragraphs = paragraphs(&content, DEFAULT_GAP);
+        assert_eq!(texts(&paragraphs), ["A well-known extrac- tion method is well- known to all of us."]);
+        dehyphenate(&mut paragraphs);
+        assert_eq!(texts(&paragraphs), ["A well-known extraction method is well-known to all of us."]);
+    }
+
+    #[test]
+    fn drops_soft_hyphens() {
+        let content = text(&["A line ending in a hyph", "enated word, and more."], 14.0);
+        let mut lines = lines::group(testing::items(&content), Origin::BottomLeft, 0.3, 0.25, 0.5);
+        // As ToUnicode maps give them: encodings map the hyphen glyph to U+002D.
+        lines[0].text.push('\u{AD}');
+        assert_eq!(join_lines(&lines, None), "A line ending in a hyphenated word, and more.");
+    }
+}
@@ -0,0 +1,268 @@
+//! Finding tables drawn with ruling lines and reading their cells.
+//!
+//! Only lattice tables are found: those whose rows and columns are separated by drawn rules.
+//! Rules that touch each other form one table; the distinct positions of its rules give the
+//! grid, and where a grid edge has no rule over it the cells on both sides are merged.
+
+use super::lines::{self, Line};
+use crate::matrix::Rect;
+use crate::{Origin, TextItem};
+
+/// How far apart, in points, rule ends and positions may be and still be taken as meeting.
+const SNAP: f32 = 2.0;
+
+/// Rules shorter than this are decoration such as bullets or underlined letters.
+const MIN_RULE_LENGTH: f32 = 4.0;
+
+/// Filled rectangles no thicker than this are drawn rules rather than shading.
+pub const MAX_RULE_THICKNESS: f32 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub bbox: Rect,
+    /// Cell text by row, top to bottom, and column, left to right. A merged cell's text is
+    /// in its top-left cell; the others are empty.
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A horizontal or vertical rule: `position` is its y or x, `start..end` its extent across.
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    position: f32,
+    start: f32,
+    end: f32,
+}
+
+/// Finds the tables formed by `rules`, thin page-space rectangles along drawn lines, and fills
+/// their cells with the text of `items` whose centre lies in them. Cell text is grouped into
+/// lines like the page's own with `tolerance`, `word_gap` and `max_skew`. Tables are given top
+/// to bottom.
+pub fn detect(rules: &[Rect], items: &[TextItem], origin: Origin, tolerance: f32, word_gap: f32, max_skew: f32) -> Vec<Table> {
+    let mut horizontal = Vec::new();
+    let mut vertical = Vec::new();
+    for rule in rules {
+        if rule.width() >= rule.height() && rule.width() >= MIN_RULE_LENGTH && rule.height() <= MAX_RULE_THICKNESS {
+            horizontal.push(Rule { position: (rule.y0 + rule.y1) / 2.0, start: rule.x0, end: rule.x1 });
+        } else if rule.height() >= MIN_RULE_LENGTH && rule.width() <= MAX_RULE_THICKNESS {
+            vertical.push(Rule { position: (rule.x0 + rule.x1) / 2.0, start: rule.y0, end: rule.y1 });
+        }
+    }
+    let horizontal = merge_collinear(horizontal);
+    let vertical = merge_collinear(vertical);
+
+    // Rules that cross or touch end to end belong to the same table.
+    let mut groups = UnionFind::new(horizontal.len() + vertical.len());
+    for (h, hrule) in horizontal.iter().enumerate() {
+        for (v, vrule) in vertical.iter().enumerate() {
+            if meets(hrule, vrule) {
+                groups.union(h, horizontal.len() + v);
+            }
+        }
+    }
+    let group_of: Vec<usize> = (0..horizontal.len() + vertical.len()).map(|i| groups.find(i)).collect();
+    let (h_groups, v_groups) = group_of.split_at(horizontal.len());
+    let mut roots = group_of.clone();
+    roots.sort_unstable();
+    roots.dedup();
+
+    let mut tables = Vec::new();
+    for root in roots {
+        let table_h: Vec<Rule> = horizontal.iter().zip(h_groups).filter(|&(_, &group)| group == root).map(|(rule, _)| *rule).collect();
+        let table_v: Vec<Rule> = vertical.iter().zip(v_groups).filter(|&(_, &group)| group == root).map(|(rule, _)| *rule).collect();
+        if let Some(table) = build_table(&table_h, &table_v, items, origin, tolerance, word_gap, max_skew) {
+            tables.push(table);
+        }
+    }
+    tables.sort_by(|a, b| match origin {
+        Origin::BottomLeft => b.bbox.y1.total_cmp(&a.bbox.y1),
+        Origin::TopLeft => a.bbox.y0.total_cmp(&b.bbox.y0),
+    });
+    tables
+}
+
+fn meets(horizontal: &Rule, vertical: &Rule) -> bool {
+    vertical.position >= horizontal.start - SNAP
+        && vertical.position <= horizontal.end + SNAP
+        && horizontal.position >= vertical.start - SNAP
+        && horizontal.position <= vertical.end + SNAP
+}
+
+/// Joins rules on the same line whose extents overlap or nearly touch, as tables are often
+/// drawn one cell edge at a time.
+fn merge_collinear(mut rules: Vec<Rule>) -> Vec<Rule> {
+    rules.sort_by(|a, b| a.position.total_cmp(&b.position).then(a.start.total_cmp(&b.start)));
+    let mut merged: Vec<Rule> = Vec::new();
+    for rule in rules {
+        let joined = merged.iter_mut().rev().take_while(|last| rule.position - last.position <= SNAP).find(|last| {
+            rule.start <= last.end + SNAP && rule.end >= last.start - SNAP
+        });
+        match joined {
+            Some(last) => {
+                last.start = last.start.min(rule.start);
+                last.end = last.end.max(rule.end);
+            }
+            None => merged.push(rule),
+        }
+    }
+    merged
+}
+
+/// Distinct rule positions, closer ones snapped together, in increasing order.
+fn grid_lines(rules: &[Rule]) -> Vec<f32> {
+    let mut positions: Vec<f32> = rules.iter().map(|rule| rule.position).collect();
+    positions.sort_by(f32::total_cmp);
+    let mut lines: Vec<f32> = Vec::new();
+    for position in positions {
+        if lines.last().is_none_or(|&last| position - last > SNAP) {
+            lines.push(position);
+        }
+    }
+    lines
+}
+
+/// Whether a rule at `position` covers the grid edge from `from` to `to`.
+fn has_edge(rules: &[Rule], position: f32, from: f32, to: f32) -> bool {
+    let middle = (from + to) / 2.0;
+    rules.iter().any(|rule| (rule.position - position).abs() <= SNAP && rule.start - SNAP <= middle && middle <= rule.end + SNAP)
+}
+
+fn build_table(
+    horizontal: &[Rule],
+    vertical: &[Rule],
+    items: &[TextItem],
+    origin: Origin,
+    tolerance: f32,
+    word_gap: f32,
+    max_skew: f32,
+) -> Option<Table> {
+    let xs = grid_lines(vertical);
+    let mut ys = grid_lines(horizontal);
+    if xs.len() < 2 || ys.len() < 2 {
+        return None;
+    }
+    // Rows go down the page, which is decreasing y unless measured from the top.
+    if origin == Origin::BottomLeft {
+        ys.reverse();
+    }
+    let (rows, columns) = (ys.len() - 1, xs.len() - 1);
+
+    // Cells not separated by a rule form one merged cell, named by its first cell.
+    let mut cells = UnionFind::new(rows * columns);
+    for row in 0..rows {
+        for column in 0..columns {
+            let cell = row * columns + column;
+            if column + 1 < columns && !has_edge(vertical, xs[column + 1], ys[row].min(ys[row + 1]), ys[row].max(ys[row + 1])) {
+                cells.union(cell, cell + 1);
+            }
+            if row + 1 < rows && !has_edge(horizontal, ys[row + 1], xs[column], xs[column + 1]) {
+                cells.union(cell, cell + columns);
+            }
+        }
+    }
+
+    let locate = |lines: &[f32], value: f32| lines.windows(2).position(|pair| pair[0].min(pair[1]) <= value && value <= pair[0].max(pair[1]));
+    let mut contents: Vec<Vec<TextItem>> = vec![Vec::new(); rows * columns];
+    for item in items {
+        let (x, y) = ((item.bbox.x0 + item.bbox.x1) / 2.0, (item.bbox.y0 + item.bbox.y1) / 2.0);
+        if let (Some(column), Some(row)) = (locate(&xs, x), locate(&ys, y)) {
+            contents[cells.find(row * columns + column)].push(item.clone());
+        }
+    }
+    let rows = (0..rows)
+        .map(|row| {
+            (0..columns)
+                .map(|column| {
+                    let mut items = std::mem::take(&mut contents[row * columns + column]);
+                    crate::sort_by_position(&mut items, origin);
+                    let lines: Vec<String> = lines::group(items, origin, tolerance, word_gap, max_skew).into_iter().map(|line: Line| line.text).collect();
+                    lines.join(" ").trim().to_string()
+                })
+                .collect()
+        })
+        .collect();
+    let bbox = Rect { x0: xs[0], y0: ys[0].min(ys[ys.len() - 1]), x1: xs[xs.len() - 1], y1: ys[0].max(ys[ys.len() - 1]) };
+    Some(Table { bbox, rows })
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> UnionFind {
+        UnionFind { parent: (0..len).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Joins two sets under the smaller root, so a set's root is always its smallest member.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ExtractOptions;
+
+    /// Strokes of a grid: rows between the `ys`, top to bottom, and columns between the `xs`.
+    fn grid(xs: &[f32], ys: &[f32]) -> String {
+        let (left, right, top, bottom) = (xs[0], xs[xs.len() - 1], ys[0], ys[ys.len() - 1]);
+        let across = ys.iter().map(|y| format!("{} {} m {} {} l", left, y, right, y));
+        let down = xs.iter().map(|x| format!("{} {} m {} {} l", x, top, x, bottom));
+        across.chain(down).collect::<Vec<_>>().join(" ") + " S"
+    }
+
+    /// A text object showing each `(x, y, text)`.
+    fn cells(cells: &[(f32, f32, &str)]) -> String {
+        let shown: Vec<String> = cells.iter().map(|(x, y, text)| format!("1 0 0 1 {} {} Tm ({}) Tj", x, y, text)).collect();
+        format!("BT /F1 10 Tf {} ET", shown.join(" "))
+    }
+
+    fn tables(content: &str) -> Vec<Table> {
+        let page = testing::pages_with(&testing::pdf(&[content]), &ExtractOptions::default()).remove(0);
+        detect(&page.rules, &page.items, Origin::BottomLeft, lines::DEFAULT_TOLERANCE, lines::DEFAULT_WORD_GAP, lines::DEFAULT_MAX_SKEW)
+    }
+
+    #[test]
+    fn reads_the_cells_of_a_grid() {
+        let text = cells(&[
+            (76.0, 686.0, "Fruit"), (176.0, 686.0, "Count"), (276.0, 686.0, "Price"),
+            (76.0, 666.0, "Apples"), (176.0, 666.0, "3"), (276.0, 666.0, "1.20"),
+            (76.0, 646.0, "Pears"), (176.0, 646.0, "12"), (276.0, 646.0, "0.80"),
+        ]);
+        let content = format!("{} {} BT /F1 10 Tf 72 600 Td (Below the table.) Tj ET", grid(&[72.0, 172.0, 272.0, 372.0], &[700.0, 680.0, 660.0, 640.0]), text);
+        let tables = tables(&content);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].rows, [["Fruit", "Count", "Price"], ["Apples", "3", "1.20"], ["Pears", "12", "0.80"]]);
+        let bbox = tables[0].bbox;
+        assert_eq!([bbox.x0, bbox.y0, bbox.x1, bbox.y1], [72.0, 640.0, 372.0, 700.0]);
+    }
+
+    #[test]
+    fn merges_cells_without_a_rule_between_them() {
+        // The rule between the first two columns stops under the header, which spans both,
+        // and the header's text of two lines is one cell's.
+        let content = "72 700 m 372 700 l 72 670 m 372 670 l 72 650 m 372 650 l 72 700 m 72 650 l 372 700 m 372 650 l 272 700 m 272 650 l 172 670 m 172 650 l S";
+        let text = cells(&[(76.0, 688.0, "Fruit and"), (76.0, 676.0, "how many"), (276.0, 686.0, "Price"), (76.0, 656.0, "Apples"), (176.0, 656.0, "3"), (276.0, 656.0, "1.20")]);
+        let tables = tables(&format!("{} {}", content, text));
+        assert_eq!(tables[0].rows, [["Fruit and how many", "", "Price"], ["Apples", "3", "1.20"]]);
+    }
+
+    #[test]
+    fn finds_no_table_in_lone_rules() {
+        let content = "72 700 m 372 700 l 72 600 m 372 600 l S BT /F1 10 Tf 76 650 Td (Between two rules) Tj ET";
+        assert!(tables(content).is_empty());
+    }
+}
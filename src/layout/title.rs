@@ -0,0 +1,211 @@
+//! Guessing the title of a document from the text of its first page, for documents whose
+//! metadata has none, or a wrong one.
+
+use super::lines::Line;
+use crate::matrix::Rect;
+use crate::{Origin, PageText};
+use serde::Serialize;
+
+/// Font sizes closer than this fraction of the larger are taken for the same size.
+const SIZE_TOLERANCE: f32 = 0.05;
+
+/// How far below a title line, in font sizes, the next may start to carry on the title.
+const MAX_LEADING: f32 = 0.8;
+
+/// The part of the page, from its top, the title is looked for in.
+const TOP: f32 = 0.5;
+
+/// Lines with more words than this are more likely a paragraph than a title.
+const MAX_WORDS: usize = 25;
+
+const MONTHS: [&str; 12] = ["january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december"];
+
+/// A title guessed from the text of a page.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct TitleGuess {
+    /// Its lines, joined by spaces.
+    pub title: String,
+    /// How sure the guess is, from 0 to 1: over 0.5 for a title set clearly larger than the
+    /// text around it; callers can fall back to the metadata below some threshold.
+    pub confidence: f32,
+    pub font_size: f32,
+    /// Its box on the page, placed like the items.
+    pub bbox: Rect,
+}
+
+/// Guesses the title of a page, as a document's first page shows it: the line in the top half
+/// of the page set in the largest type, the highest and then the longest when several are,
+/// with the lines of the same size just above and below it. Lines that look like dates, page
+/// numbers or running headers, such as a publisher's name in small capitals, are passed over
+/// unless there is nothing else. `None` for a page without text in its top half.
+pub fn guess(page: &PageText, origin: Origin) -> Option<TitleGuess> {
+    let height = page.crop_box.height();
+    let lines: Vec<&Line> = page
+        .paragraphs
+        .iter()
+        .flat_map(|paragraph| &paragraph.lines)
+        .filter(|line| !line.vertical && line.font_size() > 0.0 && line.text.chars().any(char::is_alphanumeric))
+        .collect();
+    let body = median_size(&lines)?;
+    let top = |line: &Line| page.top_left(&line.bbox, origin);
+    let candidates: Vec<&Line> = lines.iter().copied().filter(|line| (top(line).y0 + top(line).y1) / 2.0 <= height * TOP).collect();
+    let (likely, unlikely): (Vec<&Line>, Vec<&Line>) = candidates.into_iter().partition(|line| !is_furniture(line, body));
+    let fallback = likely.is_empty();
+    let mut pool = if fallback { unlikely } else { likely };
+    pool.sort_by(|a, b| top(a).y0.total_cmp(&top(b).y0));
+
+    let best = (0..pool.len()).max_by(|&a, &b| {
+        let (a, b) = (pool[a], pool[b]);
+        let size = if same_size(a.font_size(), b.font_size()) { std::cmp::Ordering::Equal } else { a.font_size().total_cmp(&b.font_size()) };
+        size.then(top(b).y0.total_cmp(&top(a).y0)).then(a.text.trim().chars().count().cmp(&b.text.trim().chars().count()))
+    })?;
+    let size = pool[best].font_size();
+    // The lines of the same size running on from it, up and down.
+    let follows = |above: &Line, below: &Line| {
+        let same = same_size(above.font_size(), size) && same_size(below.font_size(), size);
+        let (above, below) = (top(above), top(below));
+        same && below.y0 - above.y1 <= MAX_LEADING * size && above.x0 < below.x1 && below.x0 < above.x1
+    };
+    let (mut first, mut last) = (best, best);
+    while first > 0 && follows(pool[first - 1], pool[first]) {
+        first -= 1;
+    }
+    while last + 1 < pool.len() && follows(pool[last], pool[last + 1]) {
+        last += 1;
+    }
+    let title_lines = &pool[first..=last];
+    let title = title_lines.iter().map(|line| line.text.trim()).collect::<Vec<_>>().join(" ");
+    let bbox = title_lines.iter().skip(1).fold(title_lines[0].bbox, |bbox, line| bbox.union(&line.bbox));
+
+    // Set larger than the body text, and than any other line near the top, with a title's
+    // length of words, each worth part of the confidence.
+    let prominence = (size / body - 1.0).clamp(0.0, 1.0);
+    let runner_up = pool.iter().enumerate().filter(|&(index, _)| !(first..=last).contains(&index)).map(|(_, line)| line.font_size()).fold(0.0, f32::max);
+    let distinction = ((size - runner_up) / size * 4.0).clamp(0.0, 1.0);
+    let words = title.split_whitespace().count();
+    let length = if (2..=MAX_WORDS).contains(&words) { 1.0 } else { 0.5 };
+    let confidence = 0.5 * prominence + 0.3 * distinction + 0.2 * length;
+    let confidence = if fallback { confidence / 2.0 } else { confidence };
+    Some(TitleGuess { title, confidence: (confidence * 100.0).round() / 100.0, font_size: size, bbox })
+}
+
+fn same_size(a: f32, b: f32) -> bool {
+    (a - b).abs() <= SIZE_TOLERANCE * a.max(b)
+}
+
+/// The font size of the middle line, by size: that of the body text, unless the page has
+/// little of it, as a cover page, where it is still smaller than a title.
+fn median_size(lines: &[&Line]) -> Option<f32> {
+    let mut sizes: Vec<f32> = lines.iter().map(|line| line.font_size()).collect();
+    sizes.sort_by(f32::total_cmp);
+    sizes.get(sizes.len() / 2).copied()
+}
+
+/// Whether a line looks like a date, a page number or a running header rather than a title.
+fn is_furniture(line: &Line, body: f32) -> bool {
+    let text = line.text.trim();
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let capitals = letters.len() >= 2 && letters.iter().all(|c| c.is_uppercase());
+    line.repeated || is_page_number(text) || is_date(text) || (capitals && line.font_size() <= body * (1.0 + 2.0 * SIZE_TOLERANCE))
+}
+
+/// `3`, `- 3 -`, `Page 3`, `3 of 12` or `iv`.
+fn is_page_number(text: &str) -> bool {
+    let text = text.trim_matches(|c: char| c == '-' || c == '–' || c == '—' || c.is_whitespace()).to_lowercase();
+    let text = text.strip_prefix("page").unwrap_or(&text).trim();
+    let number = |word: &str| !word.is_empty() && word.chars().all(|c| c.is_ascii_digit());
+    let words: Vec<&str> = text.split(|c: char| c.is_whitespace() || c == '/').filter(|word| !word.is_empty()).collect();
+    match words[..] {
+        [word] => number(word) || (word.len() <= 6 && word.chars().all(|c| "ivxlcdm".contains(c))),
+        [a, "of", b] | [a, b] => number(a) && number(b),
+        _ => false,
+    }
+}
+
+/// A short line with a number and a month, such as `3 March 2024`, or written all in
+/// numbers, such as `2024-03-05`.
+fn is_date(text: &str) -> bool {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    if words.len() > 6 || !text.chars().any(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    // Months in full or cut to three letters, as `Mar.`.
+    let month = words.iter().any(|word| {
+        let word = word.trim_matches(|c: char| !c.is_alphabetic());
+        MONTHS.iter().any(|month| word == *month || (word.len() == 3 && month.starts_with(word)))
+    });
+    let numeric = words.iter().any(|word| {
+        let parts: Vec<&str> = word.split(['/', '-', '.']).collect();
+        parts.len() == 3 && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    });
+    month || numeric
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, ExtractOptions};
+
+    /// The title guessed from a page showing `lines`, each at `(size, x, y, text)`, in Helvetica.
+    fn guess_lines(lines: &[(f32, f32, f32, &str)]) -> Option<TitleGuess> {
+        let shown: Vec<String> = lines.iter().map(|(size, x, y, text)| format!("BT /F1 {} Tf {} {} Td ({}) Tj ET", size, x, y, text)).collect();
+        let options = ExtractOptions::default();
+        let pages = crate::layout_pages(testing::pages_with(&testing::pdf(&[&shown.join(" ")]), &options), &options);
+        guess(&pages[0], Origin::BottomLeft)
+    }
+
+    #[test]
+    fn guesses_the_title_of_a_paper() {
+        let guess = guess_lines(&[
+            (8.0, 72.0, 760.0, "PREPRINT UNDER REVIEW"),
+            (17.0, 120.0, 700.0, "Sparse Attention at the Price"),
+            (17.0, 150.0, 680.0, "of Dense Attention"),
+            (12.0, 200.0, 650.0, "Ada Lovelace and Alan Turing"),
+            (10.0, 72.0, 610.0, "Abstract. We show that attention over long documents"),
+            (10.0, 72.0, 598.0, "can be made sparse at no cost to accuracy, and that"),
+            (10.0, 72.0, 586.0, "the models so trained generalise to longer inputs."),
+            (10.0, 72.0, 560.0, "1 Introduction to the problem of long documents"),
+        ])
+        .unwrap();
+        assert_eq!(guess.title, "Sparse Attention at the Price of Dense Attention");
+        assert_eq!(guess.font_size, 17.0);
+        assert!(guess.bbox.y0 < 680.0 && guess.bbox.y1 > 700.0, "{:?}", guess.bbox);
+        // Set 70% larger than the body and well above the authors' line.
+        assert_eq!(guess.confidence, 0.85);
+    }
+
+    #[test]
+    fn passes_over_the_date_and_running_header_of_a_cover() {
+        let guess = guess_lines(&[
+            (14.0, 72.0, 740.0, "ACME HOLDINGS PLC"),
+            (24.0, 72.0, 660.0, "October 2026"),
+            (24.0, 72.0, 560.0, "Annual Report 2025"),
+            (14.0, 72.0, 520.0, "Acme Holdings plc"),
+            (8.0, 72.0, 60.0, "Registered office: 1 High Street, London"),
+            (8.0, 72.0, 48.0, "www.acme.example"),
+        ])
+        .unwrap();
+        assert_eq!(guess.title, "Annual Report 2025");
+        assert_eq!(guess.confidence, 0.86);
+    }
+
+    #[test]
+    fn keeps_lines_of_another_size_or_far_below_out_of_the_title() {
+        let guess = guess_lines(&[
+            (20.0, 72.0, 700.0, "Field Notes"),
+            (14.0, 72.0, 680.0, "on the Birds of the Marsh"),
+            (20.0, 72.0, 620.0, "Spring"),
+            (10.0, 72.0, 580.0, "Notes kept through the spring by the warden of the marsh."),
+        ])
+        .unwrap();
+        assert_eq!(guess.title, "Field Notes");
+    }
+
+    #[test]
+    fn falls_back_to_furniture_with_less_confidence() {
+        let guess = guess_lines(&[(12.0, 72.0, 700.0, "3 March 2024"), (10.0, 72.0, 300.0, "The rest of the page is below the fold.")]).unwrap();
+        assert_eq!(guess.title, "3 March 2024");
+        assert!(guess.confidence < 0.5, "{}", guess.confidence);
+    }
+}
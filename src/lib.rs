@@ -1,261 +1,925 @@
-use lopdf::{Document, Object};
-use std::io::BufWriter;
+//! Extracts the text of PDF documents in reading order, with the position, size, font and
+//! color of every piece of text.
+//!
+//! [`extract_path`] and [`extract_bytes`] read a whole document into paragraphs; an
+//! [`Extractor`] reads it a page at a time, as positioned [`TextItem`]s. A [`PageTree`] tells
+//! only how many pages there are and their sizes, without decoding any page's content.
+//!
+//! What cannot be read is logged as warnings through the [`log`](https://docs.rs/log)
+//! facade, and nothing is written to stdout or stderr: install a logger to see them.
+//!
+//! ```no_run
+//! let options = rapid_pdf::ExtractOptions::default();
+//! let text = rapid_pdf::extract_path("report.pdf", &options)?;
+//! for page in &text.pages {
+//!     for paragraph in &page.paragraphs {
+//!         println!("{}", paragraph.text);
+//!     }
+//! }
+//! # Ok::<(), rapid_pdf::Error>(())
+//! ```
+
+mod annotations;
+mod attachments;
+mod color;
+mod content;
+mod debug;
+mod destination;
+mod error;
+mod font;
+mod forms;
+mod highlight;
+mod images;
+mod labels;
+pub mod layout;
+mod matrix;
+mod metadata;
+mod normalize;
+mod ocr;
+mod operations;
+mod options;
+mod outline;
+mod page;
+mod page_tree;
+mod path;
+mod processor;
+mod progress;
+mod python;
+mod quality;
+mod resources;
+mod security;
+mod sink;
+mod structure;
+#[cfg(test)]
+mod testing;
+mod threads;
+
+pub use annotations::{Annotation, Link};
+pub use attachments::Attachment;
+pub use color::Rgb;
+pub use destination::{Destination, Fit};
+pub use error::{Error, Limit, PageError};
+pub use font::pdf_doc_decode;
+pub use forms::{FieldKind, FieldValue, FormField, Widget};
+pub use highlight::{write_highlights, Highlight};
+pub use images::{Image, ImageFormat, PlacedImage};
+pub use matrix::Rect;
+pub use metadata::{Date, DocumentInfo, Metadata};
+pub use operations::{ContentOperation, Operand, ShownText};
+pub use ocr::{NoOcr, OcrProvider, OcrWord};
+#[cfg(feature = "tesseract")]
+pub use ocr::Tesseract;
+pub use options::{ExtractOptions, ExtractOptionsBuilder, Granularity, Ocr, Order, Origin, Region, RegionMode};
+pub use outline::OutlineItem;
+pub use page::PageSelection;
+pub use page_tree::{PageAttributes, PageTree};
+pub use progress::{OnPage, PageProgress};
+pub use quality::{PageKind, PageQuality, QualitySignals};
+pub use sink::{PageInfo, TextSink};
+
+use lopdf::{Object, ObjectId};
+use page::Page;
+use resources::Resources;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::path::Path;
-use pyo3::prelude::*;
-
-#[pyclass]
-#[derive(Debug, Clone)] 
-struct TextItem {
-    #[pyo3(get)]
-    text: String,
-    #[pyo3(get)]
-    x: f32,
-    #[pyo3(get)]
-    y: f32,
-    #[pyo3(get)]
-    font_size: f32,
-    #[pyo3(get)]
-    page_num: u32,
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use threads::Threads;
+
+/// A run of text shown by one operator, with where and how it is drawn. Lengths are in points.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct TextItem {
+    pub text: String,
+    /// Where the text starts on its baseline, in page space turned upright like `bbox`.
+    pub x: f32,
+    pub y: f32,
+    /// The font size scaled by the text and transformation matrices.
+    pub font_size: f32,
+    /// Size of `bbox`.
+    pub width: f32,
+    pub height: f32,
+    /// Box enclosing the shown glyphs, from the font's descent to its ascent, in page space
+    /// turned upright by `/Rotate` like `x` and `y`, and measured from the top-left corner
+    /// with `Origin::TopLeft`.
+    pub bbox: Rect,
+    /// Resource name of the font the text is shown in, the `F1` of `/F1 12 Tf`.
+    pub font_resource: Option<String>,
+    /// `/BaseFont` of that font, without a subset prefix.
+    pub font: Option<String>,
+    /// Whether the font looks bold or italic, going by its name and font descriptor.
+    pub bold: bool,
+    pub italic: bool,
+    /// The fill color the text is drawn in, as RGB; `None` when it cannot be told.
+    pub color: Option<Rgb>,
+    /// Text rise `Ts` scaled like `font_size`; already included in `y`.
+    pub rise: f32,
+    pub baseline: Baseline,
+    /// Text rendering mode `Tr`: 0 fill, 1 stroke, 2 both, 3 invisible, 4-7 the same plus clipping.
+    pub render_mode: u8,
+    /// Whether the text was written vertically, top to bottom, with a `/WMode 1` font.
+    pub vertical: bool,
+    /// Direction of the baseline, in degrees counterclockwise from the x axis of upright page
+    /// space and in (-180, 180], whatever the origin: 0 for text read left to right on the
+    /// page as displayed, 90 for text running up it.
+    pub angle: f32,
+    /// Whether the text is inside `/Artifact` marked content (PDF 32000-1:2008, 14.8.2.2).
+    pub artifact: bool,
+    /// Whether copies of the text drawn over it, slightly offset, were collapsed into it.
+    pub synthetic_bold: bool,
+    /// Where each character of `text` ends in the direction it is written, as a fraction of
+    /// the item's extent, by the advances of the glyphs; each starts where the one before it
+    /// ends. Empty when not known.
+    #[serde(skip)]
+    pub(crate) char_ends: Vec<f32>,
+    /// Where the text of each fragment joined into the item but the last ended, in characters
+    /// of `text`, with `ExtractOptions::merge_fragments`; empty for items drawn whole.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fragment_ends: Vec<usize>,
+    /// The marked-content sequence the text belongs to, for matching it to the structure tree.
+    #[serde(skip)]
+    pub(crate) content_id: Option<structure::ContentId>,
+    /// Structure type of the element the text belongs to, with `Order::Structure`.
+    pub role: Option<String>,
+    /// Which of `ExtractOptions::regions` the text is in, counted from 1, the first of them
+    /// when it is in several; `None` without regions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<usize>,
+    pub page_num: u32,
 }
 
-#[pyfunction]
-fn replace_text_by_pos(
-    path: String, 
-    output_path: String,
-    page_num: u32, 
-    target_text: &str,
-    replacement: &str,
-    target_x: f32,  
-    target_y: f32,
-    target_font_size: f32,
-) -> PyResult<String>
-{
-    let mut doc = Document::load(path).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-    let pages = doc.get_pages();
-    if let Some(&object_id) = pages.get(&page_num) {
-        let content_data = doc.get_page_content(object_id).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-        let mut content = lopdf::content::Content::decode(&content_data).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-        
-        let mut current_font_size: f32 = 0.0;
-        let mut current_x: f32 = 0.0;
-        let mut current_y: f32 = 0.0;
-        
-        for operation in &mut content.operations {
-            let operator = &operation.operator;
-            let operands = &operation.operands;
-            
-            match operator.as_str() {
-                "BT" => {
-                    current_x = 0.0;
-                    current_y = 0.0;
-                }
-                "Tf" => {
-                    if operands.len() >= 2 {
-                        if let Ok(size) = operands[1].as_f32() {
-                            current_font_size = size;
-                        }
-                    }
-                }
-                "Td" | "TD" => {
-                    if operands.len() >= 2 {
-                        if let (Ok(tx), Ok(ty)) = (operands[0].as_f32(), operands[1].as_f32()) {
-                            current_x += tx;
-                            current_y += ty;
-                        }
-                    }
-                }
-                "Tm" => {
-                    if operands.len() >= 6 {
-                        if let (Ok(e), Ok(f)) = (operands[4].as_f32(), operands[5].as_f32()) {
-                            current_x = e;
-                            current_y = f;
-                        }
-                    }
-                }
-                "Tj" => {
-                    if let Some(Object::String(bytes, _)) = operands.first() {
-                        println!("Checking text at ({}, {}): '{}'", current_x, current_y, String::from_utf8_lossy(bytes));
-                    
-                        if current_x == target_x && current_y == target_y  {
-                            println!("Positions match for Tj at ({}, {})", current_x, current_y);
-                            if String::from_utf8_lossy(bytes).contains(target_text){
-                                let mut original_text = String::from_utf8_lossy(bytes).to_string();
-                                original_text = original_text.replace(target_text, replacement);
-                                operation.operands[0] = Object::String(original_text.as_bytes().to_vec(), lopdf::StringFormat::Literal);
-                                println!("Replaced text at ({}, {})", current_x, current_y);    
-                                break;  // Replace first exact match
-                                }
-                        }
-                        if String::from_utf8_lossy(bytes) == target_text
-                            && (current_x - target_x).abs() < 0.01  // Allow small floating-point tolerance
-                            && (current_y - target_y).abs() < 0.01
-                            // && (current_font_size - target_font_size).abs() < 0.01
-                                {
-                            operation.operands[0] = Object::String(replacement.as_bytes().to_vec(), lopdf::StringFormat::Literal);
-                            println!("Replaced text at ({}, {})", current_x, current_y);    
-                            break;  // Replace first exact match
-                        }
-                    }
-                }
-                _ => {}
-            }
+/// Whether an item sits on its line's baseline or is raised or lowered by `Ts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Baseline {
+    Normal,
+    Super,
+    Sub,
+}
+
+impl Baseline {
+    /// Rises smaller than this fraction of the font size are treated as noise.
+    const THRESHOLD: f32 = 0.1;
+
+    fn from_rise(rise: f32, font_size: f32) -> Baseline {
+        if font_size <= 0.0 || rise.abs() < font_size * Baseline::THRESHOLD {
+            Baseline::Normal
+        } else if rise > 0.0 {
+            Baseline::Super
+        } else {
+            Baseline::Sub
         }
-        
-        let new_content_data = content.encode().map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-        doc.change_page_content(object_id, new_content_data).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-    }    
-    let path = Path::new(&output_path);
-    let mut file = BufWriter::new(File::create(path).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?);
-    doc.save_modern(&mut file).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-    println!("Saved modified PDF to {}", output_path);
-    let result_message = format!("Saved modified PDF to {}", output_path);
-    Ok(result_message)
+    }
+}
+
+/// A page's text items, in the order asked for, with the page's geometry.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PageItems {
+    pub page_num: u32,
+    /// The page's label from the document's `/PageLabels`, such as `iv` or `A-1`.
+    pub label: Option<String>,
+    /// Size of the crop box, turned upright by `/Rotate`.
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation for display: 0, 90, 180 or 270.
+    pub rotation: u16,
+    /// The page's boxes, upright and measured from the origin like the items.
+    pub media_box: Rect,
+    pub crop_box: Rect,
+    pub items: Vec<TextItem>,
+    /// The ruling lines drawn on the page, which tables are found from.
+    pub rules: Vec<Rect>,
+    /// The images painted on the page, in its forms too, placed like the items.
+    pub images: Vec<PlacedImage>,
+    /// What of the page's content could not be read; the items are those of the rest.
+    pub errors: Vec<PageError>,
+    /// Its comments, notes and markup, placed like the items.
+    pub annotations: Vec<Annotation>,
+    /// Its links, placed like the items, with the text they cover.
+    pub links: Vec<Link>,
+    /// Whether reading stopped at this page for `ExtractOptions::max_chars` or `max_items`,
+    /// leaving out the rest of its items, or the pages after it.
+    pub truncated: bool,
+    /// How trustworthy the text of its items looks.
+    pub quality: PageQuality,
+    /// Whether it is a page of text, a scan without text, or blank.
+    pub kind: PageKind,
 }
 
-#[pyfunction]
-fn extract_text_from_pdf(path: String) -> PyResult<Vec<TextItem>> {
-    let doc = Document::load(path).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-    let mut all_items = Vec::new();
+/// A page's paragraphs in reading order, ready to be written out.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PageText {
+    pub page_num: u32,
+    /// The crop box the paragraphs are positioned in, upright and measured from the origin.
+    pub crop_box: Rect,
+    pub paragraphs: Vec<layout::paragraphs::Paragraph>,
+    pub annotations: Vec<Annotation>,
+    pub links: Vec<Link>,
+    /// The images painted on the page, in drawing order, which
+    /// [`layout::figures::position`] places among the paragraphs.
+    pub images: Vec<PlacedImage>,
+}
 
-    for (_page_num, object_id) in doc.get_pages() {
-        if let Ok(content_data) = doc.get_page_content(object_id) {
-            if let Ok(content) = lopdf::content::Content::decode(&content_data) {
-                let mut items = process_content_stream(&content, _page_num);
-                all_items.append(&mut items);
-            }
+impl PageText {
+    /// A box on the page relative to the top-left corner of its crop box, y growing downwards,
+    /// whatever the origin of the coordinates.
+    pub fn top_left(&self, rect: &Rect, origin: Origin) -> Rect {
+        match origin {
+            Origin::TopLeft => *rect,
+            Origin::BottomLeft => Rect {
+                x0: rect.x0 - self.crop_box.x0,
+                y0: self.crop_box.y1 - rect.y1,
+                x1: rect.x1 - self.crop_box.x0,
+                y1: self.crop_box.y1 - rect.y0,
+            },
         }
     }
+}
 
-    all_items.sort_by(|a, b| {
-        b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-           .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-    });
+/// The text of a document, page by page.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DocumentText {
+    /// The PDF version of the header, such as `1.7`.
+    pub version: String,
+    /// The entries of the document information dictionary, such as `Title` and `Author`.
+    pub info: BTreeMap<String, String>,
+    /// The outline, or bookmarks, with the pages its entries lead to.
+    pub outline: Vec<OutlineItem>,
+    /// The named destinations, by name.
+    pub destinations: BTreeMap<String, Destination>,
+    pub pages: Vec<PageText>,
+    /// What of the pages' content could not be read, in page order.
+    pub errors: Vec<PageError>,
+    /// Whether reading stopped short for `ExtractOptions::max_chars` or `max_items`.
+    pub truncated: bool,
+}
 
+/// Reads the text of the PDF at `path`.
+pub fn extract_path(path: impl AsRef<Path>, options: &ExtractOptions) -> Result<DocumentText, Error> {
+    Extractor::open(path, options)?.text()
+}
 
-    Ok(all_items)
+/// Reads the text of a PDF held in memory.
+pub fn extract_bytes(data: &[u8], options: &ExtractOptions) -> Result<DocumentText, Error> {
+    Extractor::from_bytes(data, options)?.text()
 }
 
-#[pymodule]
-fn rapid_pdf(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_class::<TextItem>()?;
-    m.add_function(wrap_pyfunction!(extract_text_from_pdf, m)?)?;
-    m.add_function(wrap_pyfunction!(replace_text_by_pos, m)?)?;
-    Ok(())
+/// Reads the PDF at `path`, giving its items to `sink` as each page is read.
+pub fn extract_with(path: impl AsRef<Path>, options: &ExtractOptions, sink: &mut impl TextSink) -> Result<(), Error> {
+    Extractor::open(path, options)?.visit(sink)
 }
 
-fn process_content_stream(content: &lopdf::content::Content, page_num: u32) -> Vec<TextItem> {
-    let mut extracted_items = Vec::new();
+/// An open PDF, decrypted if need be, whose pages are read only as they are asked for, a few
+/// per thread at a time: stopping after the first page leaves most others undecoded.
+///
+/// ```no_run
+/// use rapid_pdf::{ExtractOptions, Extractor};
+///
+/// let extractor = Extractor::open("report.pdf", &ExtractOptions::default())?;
+/// if let Some(first) = extractor.pages().next().transpose()? {
+///     println!("{} paragraphs on page {}", first.paragraphs.len(), first.page_num);
+/// }
+/// # Ok::<(), rapid_pdf::Error>(())
+/// ```
+pub struct Extractor {
+    doc: lopdf::Document,
+    /// The selected pages' objects by page number.
+    pages: BTreeMap<u32, ObjectId>,
+    /// The numbers of all pages by page object, for the pages that outlines and links lead to.
+    page_numbers: HashMap<ObjectId, u32>,
+    /// The label of each page, in order, when the document gives them.
+    labels: Option<Vec<String>>,
+    options: ExtractOptions,
+    /// The structure tree's reading order, with `Order::Structure`.
+    reading_order: Vec<structure::StructureRef>,
+    /// Fonts loaded so far, shared by the pages read.
+    fonts: font::FontCache,
+    threads: Threads,
+    /// When the document started being read, which the time limit runs from.
+    opened: Instant,
+    /// How many selected pages have been read, for `ExtractOptions::on_page`.
+    pages_done: AtomicUsize,
+}
 
-    let mut current_font_size: f32 = 0.0;
-    let mut current_x: f32 = 0.0;
-    let mut current_y: f32 = 0.0;
+/// How many pages each thread is given at a time: enough to keep threads busy when pages take
+/// unequal time, few enough not to hold many pages' items.
+const PAGES_PER_THREAD: usize = 4;
 
-    for operation in &content.operations {
-        let operator = &operation.operator; // e.g., "Tf", "Tj", "Tm"
-        let operands = &operation.operands; 
+impl Extractor {
+    /// Opens the PDF at `path`, mapping the file into memory rather than reading it, so that
+    /// only the parts of it parsed are ever loaded.
+    pub fn open(path: impl AsRef<Path>, options: &ExtractOptions) -> Result<Extractor, Error> {
+        with_mapped(path.as_ref(), |data| Extractor::from_bytes(data, options))
+    }
 
+    /// Opens a PDF held in memory, copying only the objects needed from it.
+    pub fn from_bytes(data: &[u8], options: &ExtractOptions) -> Result<Extractor, Error> {
+        let opened = Instant::now();
+        let doc = read_document(data, options.password.as_deref(), if options.images || options.ocr_provider.is_some() { None } else { Some(without_image_data) })?;
+        let (pages, labels) = selected_pages(&doc, options)?;
+        let page_numbers = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+        let reading_order = match options.order {
+            Order::Structure => structure::reading_order(&doc),
+            Order::Stream | Order::Layout => Vec::new(),
+        };
+        let threads = Threads::new(options)?;
+        Ok(Extractor { doc, pages, page_numbers, labels, options: options.clone(), reading_order, fonts: font::FontCache::default(), threads, opened, pages_done: AtomicUsize::new(0) })
+    }
 
-        match operator.as_str() {
-            
-            // "BT": Begin Text Object. Resets the text matrix.
-            "BT" => {
-                current_x = 0.0;
-                current_y = 0.0;
+    /// The PDF version of the header, such as `1.7`.
+    pub fn version(&self) -> &str {
+        &self.doc.version
+    }
+
+    /// The entries of the document information dictionary that are text strings.
+    pub fn info(&self) -> BTreeMap<String, String> {
+        let doc = &self.doc;
+        let Ok(info) = doc.trailer.get_deref(b"Info", doc).and_then(Object::as_dict) else { return BTreeMap::new() };
+        info.iter()
+            .filter_map(|(key, value)| {
+                let value = doc.dereference(value).ok()?.1.as_str().ok()?;
+                Some((String::from_utf8_lossy(key).into_owned(), decode_text_string(value)))
+            })
+            .collect()
+    }
+
+    /// The document's metadata from its information dictionary and its XMP packet.
+    pub fn metadata(&self) -> Metadata {
+        metadata::read(&self.doc)
+    }
+
+    /// The files embedded in the document, with their contents, those of its `/EmbeddedFiles`
+    /// and those attached to its pages.
+    pub fn attachments(&self) -> Vec<Attachment> {
+        attachments::read(&self.doc, &self.page_numbers, self.options.max_stream_size)
+    }
+
+    /// The named destinations of the document, by name, such as `section.3.2` of LaTeX
+    /// documents, which links and outline entries of this and other documents may lead to.
+    pub fn destinations(&self) -> BTreeMap<String, Destination> {
+        self.resolver().named()
+    }
+
+    fn resolver(&self) -> destination::Destinations<'_> {
+        destination::Destinations { doc: &self.doc, pages: &self.page_numbers }
+    }
+
+    /// The fields of the document's form with their values, and where they are shown.
+    pub fn form_fields(&self) -> Vec<FormField> {
+        forms::read(&self.doc, &self.page_numbers, self.options.origin)
+    }
+
+    /// The outline, or bookmarks, of the document, with the pages its entries lead to.
+    pub fn outline(&self) -> Vec<OutlineItem> {
+        outline::read(&self.doc, &self.page_numbers)
+    }
+
+    /// The label of a page, counted from 1, from the document's `/PageLabels`, such as `iv`
+    /// for a page of the front matter; `None` for documents without labels.
+    pub fn page_label(&self, page_num: u32) -> Option<&str> {
+        let index = usize::try_from(page_num).ok()?.checked_sub(1)?;
+        self.labels.as_ref()?.get(index).map(String::as_str)
+    }
+
+    /// An image painted on the pages as a file, given its object from `PageItems::images`.
+    /// Needs `ExtractOptions::images`, without which image data is not kept.
+    pub fn image(&self, object: ObjectId) -> Result<Image, Error> {
+        images::read(&self.doc, object, self.options.max_stream_size)
+    }
+
+    /// How many pages the document has, selected or not.
+    pub fn page_count(&self) -> usize {
+        self.page_numbers.len()
+    }
+
+    /// The numbers of the selected pages, in order.
+    pub fn page_numbers(&self) -> Vec<u32> {
+        self.pages.keys().copied().collect()
+    }
+
+    /// Reads the items of a selected page; `None` for pages not selected or not there. Pages
+    /// left out are never loaded, so their content is not even decompressed. Content that
+    /// cannot be read is recorded in the page's `errors`, or with `strict` is the error. Only
+    /// the iterators over pages stop at `max_chars` and `max_items`; a page asked for is
+    /// read whole.
+    pub fn page(&self, page_num: u32) -> Result<Option<PageItems>, Error> {
+        self.page_within(page_num, None)
+    }
+
+    /// Reads a selected page as `page` does, keeping only the items within `budget`, which
+    /// they are taken from.
+    fn page_within(&self, page_num: u32, budget: Option<&mut Budget>) -> Result<Option<PageItems>, Error> {
+        let Some(&object_id) = self.pages.get(&page_num) else { return Ok(None) };
+        let page = self.read_page(page_num, object_id, budget);
+        if let Some(on_page) = &self.options.on_page {
+            // Pages read again are not counted past the number selected.
+            let total = self.pages.len();
+            let done = (self.pages_done.fetch_add(1, Ordering::Relaxed) + 1).min(total);
+            (on_page.0)(PageProgress { page_num, done, total, elapsed: self.opened.elapsed() });
+        }
+        page.map(Some)
+    }
+
+    /// Lists the operations of a selected page's content as they are run, with those of the
+    /// forms it paints, or `None` if the page is not selected: each operator with its operands
+    /// and, for those that show text, the text shown and where. The limits of the options
+    /// apply, but for the time limit; what cannot be read is left out with a warning.
+    pub fn operations(&self, page_num: u32) -> Result<Option<Vec<ContentOperation>>, Error> {
+        let Some(&object_id) = self.pages.get(&page_num) else { return Ok(None) };
+        let (doc, options) = (&self.doc, &self.options);
+        let page = Page::load(doc, object_id, page_num);
+        let mut errors = Vec::new();
+        let content_data = processor::page_content(doc, object_id, page_num, options.max_stream_size, &mut errors);
+        let (content, offsets, skipped) = content::decode(&content_data);
+        if skipped > 0 {
+            log::warn!("cannot decode the content of page {} in full: skipped {} bytes that are not content operations", page_num, skipped);
+        }
+        if let Some(err) = errors.first().filter(|_| options.strict) {
+            return Err(err.clone().into());
+        }
+        let resources = Resources::page(doc, object_id, &self.fonts);
+        Ok(Some(processor::content_operations(doc, &content, &offsets, page_num, &page, &resources, &self.fonts, options)))
+    }
+
+    /// Guesses the title of the document from its first page, selected or not, as
+    /// [`layout::title::guess`] does; `None` when the page has no text to guess from.
+    pub fn guess_title(&self) -> Result<Option<layout::title::TitleGuess>, Error> {
+        let Some(object_id) = self.page_numbers.iter().find_map(|(&id, &page_num)| (page_num == 1).then_some(id)) else {
+            return Ok(None);
+        };
+        let page = self.read_page(1, object_id, None)?;
+        let pages = layout_pages_on(vec![page], &self.options, &Threads::One);
+        Ok(pages.first().and_then(|page| layout::title::guess(page, self.options.origin)))
+    }
+
+    /// Reads the items of a page, given its object, those within `budget` if one is given.
+    fn read_page(&self, page_num: u32, object_id: ObjectId, budget: Option<&mut Budget>) -> Result<PageItems, Error> {
+        let (doc, options) = (&self.doc, &self.options);
+        let page = Page::load(doc, object_id, page_num);
+        let (media_box, crop_box) = (page.upright(&page.media_box), page.upright(&page.crop_box));
+        let (media_box, crop_box) = match options.origin {
+            Origin::BottomLeft => (media_box, crop_box),
+            Origin::TopLeft => (page.top_left(&media_box), page.top_left(&crop_box)),
+        };
+        if options.debug_ops {
+            log::debug!("Processing Page {} ({}x{} pt, rotated {}, origin {:?}) media box {} crop box {}",
+                page_num, debug::length(page.width()), debug::length(page.height()), page.rotation, options.origin,
+                debug::rect(&media_box), debug::rect(&crop_box));
+        }
+        let mut errors = Vec::new();
+        let deadline = options.time_limit.map(|limit| self.opened + limit);
+        let content_data = if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+            let err = PageError::limit(page_num, Limit::Time(options.time_limit.unwrap_or_default()));
+            log::warn!("{}", err);
+            errors.push(err);
+            Vec::new()
+        } else {
+            processor::page_content(doc, object_id, page_num, options.max_stream_size, &mut errors)
+        };
+        // Operations that cannot be parsed are left out rather than failing the page.
+        let (content, _, skipped) = content::decode(&content_data);
+        if skipped > 0 {
+            let message = format!("skipped {} bytes that are not content operations", skipped);
+            log::warn!("cannot decode the content of page {} in full: {}", page_num, message);
+            errors.push(PageError { page: page_num, message, limit: None });
+        }
+        if let Some(err) = errors.first().filter(|_| options.strict) {
+            return Err(err.clone().into());
+        }
+        if options.debug_ops {
+            for operation in &content.operations {
+                let mut line: Vec<String> = operation.operands.iter().map(debug::print_with_layout).collect();
+                line.push(operation.operator.clone());
+                log::trace!("  {}", line.join(" ").replace('\n', "\n  "));
             }
+        }
 
-            // "Tf": Set Text Font and Size.
-            "Tf" => {
-                if operands.len() >= 2 {
-                    if let Ok(size) = operands[1].as_f32() {
-                        current_font_size = size;
-                    }
-                }
+        let resources = Resources::page(doc, object_id, &self.fonts);
+        let (items, rules, images, stopped) =
+            processor::process_content_stream(doc, &content, page_num, &page, &resources, &self.fonts, options, deadline);
+        if let Some(limit) = stopped {
+            let err = PageError::limit(page_num, limit);
+            log::warn!("{}", err);
+            errors.push(err);
+        }
+        if let Some(err) = errors.first().filter(|_| options.strict) {
+            return Err(err.clone().into());
+        }
+        let mut items = layout::duplicates::collapse(items, options.duplicate_tolerance);
+        if options.merge_fragments {
+            items = layout::fragments::merge(items);
+        }
+        // Scans are told by the text they have of their own, before OCR adds to it.
+        let kind = PageKind::classify(quality::characters(&items), &images, &crop_box);
+        if let Some(provider) = options.ocr_provider.as_deref().filter(|_| options.ocr == Ocr::Always || kind == PageKind::ImageOnly) {
+            items.extend(self.ocr_page(provider, page_num, &images, &mut errors));
+            if let Some(err) = errors.first().filter(|_| options.strict) {
+                return Err(err.clone().into());
             }
+        }
+        // Items are cut in stream order, before they are sorted, for the same to be kept
+        // whatever the order asked for.
+        let truncated = budget.is_some_and(|budget| budget.take(&mut items));
+        match options.order {
+            Order::Stream => {}
+            // Sorting items before grouping them keeps each line's items together, and ties
+            // keep drawing order, as the sort is stable.
+            Order::Layout => sort_by_position(&mut items, options.origin),
+            Order::Structure => sort_by_structure(&mut items, &self.reading_order, object_id),
+        }
+        if options.debug_ops {
+            for item in &items {
+                log::debug!("  Found: '{:?}' at ({}, {}) size {} box {}x{} {} rise {} ({:?}) mode {}{}{}{}{}{} on page {}",
+                    item.text, debug::length(item.x), debug::length(item.y), debug::length(item.font_size),
+                    debug::length(item.width), debug::length(item.height), debug::rect(&item.bbox),
+                    debug::length(item.rise), item.baseline, item.render_mode,
+                    if item.vertical { " vertical" } else { "" }, if item.artifact { " artifact" } else { "" }, if item.synthetic_bold { " synthetic-bold" } else { "" },
+                    item.role.as_ref().map(|role| format!(" role {}", role)).unwrap_or_default(),
+                    if item.fragment_ends.is_empty() { String::new() } else { format!(" fragments ending at {:?}", item.fragment_ends) }, item.page_num);
+            }
+        }
+        let quality = PageQuality::measure(&items, &crop_box, options.line_tolerance, options.word_gap);
+        Ok(PageItems {
+            page_num,
+            label: self.page_label(page_num).map(str::to_string),
+            width: page.width(),
+            height: page.height(),
+            rotation: page.rotation,
+            media_box,
+            crop_box,
+            annotations: annotations::read(doc, object_id, &page, options, &items),
+            links: annotations::links(&self.resolver(), object_id, &page, options, &items),
+            items,
+            rules,
+            images,
+            errors,
+            truncated,
+            quality,
+            kind,
+        })
+    }
 
-            // "Td": Move Text Position.
-            "Td" | "TD" => {
-                if operands.len() >= 2 {
-                    if let (Ok(tx), Ok(ty)) = (operands[0].as_f32(), operands[1].as_f32()) {
-                        current_x += tx;
-                        current_y += ty;
-                    }
+    /// The words `provider` reads from the image XObjects painted on a page, as its items.
+    /// Images that cannot be read, or read through it, are left out and told in `errors`;
+    /// inline images are left out, their data being in the content.
+    fn ocr_page(&self, provider: &dyn OcrProvider, page_num: u32, images: &[PlacedImage], errors: &mut Vec<PageError>) -> Vec<TextItem> {
+        let mut items = Vec::new();
+        for image in images {
+            let Some(object) = image.object else { continue };
+            let words = images::read(&self.doc, object, self.options.max_stream_size).and_then(|data| provider.ocr_image(page_num, &data));
+            match words {
+                Ok(words) => items.extend(ocr::place(words, image, &self.options, page_num)),
+                Err(err) => {
+                    let message = format!("cannot read image {} {} R through OCR: {}", object.0, object.1, err);
+                    let err = PageError { page: page_num, message, limit: None };
+                    log::warn!("{}", err);
+                    errors.push(err);
                 }
             }
+        }
+        items
+    }
+
+    /// Reads the items of the selected pages in order, each when the iterator gets to it.
+    pub fn page_items(&self) -> impl Iterator<Item = Result<PageItems, Error>> + '_ {
+        self.read_pages(|page| page)
+    }
+
+    /// Reads the text of the selected pages in order, each when the iterator gets to it and
+    /// laid out on its own: unlike `text`, headers and footers are not told by their
+    /// repeating on other pages, nor are words hyphenated across pages joined.
+    pub fn pages(&self) -> impl Iterator<Item = Result<PageText, Error>> + '_ {
+        self.read_pages(|page| layout_pages_on(vec![page], &self.options, &Threads::One)).flat_map(|pages| match pages {
+            Ok(pages) => pages.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        })
+    }
 
-            // "Tm": Set Text Matrix (absolute positioning).
-            "Tm" => {
-                if operands.len() >= 6 {
-                    if let (Ok(e), Ok(f)) = (operands[4].as_f32(), operands[5].as_f32()) {
-                        current_x = e;
-                        current_y = f;
+    /// Reads the selected pages a batch at a time, the pages of a batch and `f` of each on
+    /// threads of their own, and gives the results in page order. With `max_chars` or
+    /// `max_items`, pages are read one at a time instead, until they have given as much.
+    fn read_pages<'a, R: Send + 'a>(
+        &'a self,
+        f: impl Fn(PageItems) -> R + Send + Sync + 'a,
+    ) -> Box<dyn Iterator<Item = Result<R, Error>> + 'a> {
+        if let Some(mut budget) = Budget::of(&self.options) {
+            let mut page_nums = self.page_numbers().into_iter();
+            return Box::new(std::iter::from_fn(move || loop {
+                if budget.spent() {
+                    return None;
+                }
+                let page_num = page_nums.next()?;
+                match self.page_within(page_num, Some(&mut budget)) {
+                    Ok(Some(mut page)) => {
+                        page.truncated |= budget.spent() && page_nums.len() > 0;
+                        return Some(Ok(f(page)));
                     }
+                    Ok(None) => continue,
+                    Err(err) => return Some(Err(err)),
                 }
+            }));
+        }
+        let batch = match self.threads.count() {
+            1 => 1,
+            count => count * PAGES_PER_THREAD,
+        };
+        let batches: Vec<Vec<u32>> = self.page_numbers().chunks(batch).map(<[u32]>::to_vec).collect();
+        Box::new(
+            batches
+                .into_iter()
+                .flat_map(move |batch| self.threads.map(batch, |page_num| self.page(page_num).map(|page| page.map(&f))))
+                .filter_map(Result::transpose),
+        )
+    }
+
+    /// Reads the selected pages in order, giving their items to `sink`, and stops at the first
+    /// error it returns.
+    pub fn visit(&self, sink: &mut impl TextSink) -> Result<(), Error> {
+        for page in self.page_items() {
+            let page = page?;
+            sink.page_start(PageInfo::of(&page))?;
+            for item in &page.items {
+                sink.item(item)?;
             }
+            sink.page_end()?;
+        }
+        Ok(())
+    }
 
-            // "Tj": Show Text.
-            "Tj" => {
-                if let Some(text_obj) = operands.first() {
-                    let text = extract_text_from_object(text_obj);
-                    
-                    extracted_items.push(TextItem {
-                        text,
-                        x: current_x,
-                        y: current_y,
-                        font_size: current_font_size,
-                        page_num: page_num  ,
-
-                    });
-                }
+    /// Reads every selected page and lays out their text.
+    pub fn text(&self) -> Result<DocumentText, Error> {
+        let mut collector = sink::Collector::default();
+        self.visit(&mut collector)?;
+        let errors = collector.pages.iter().flat_map(|page| page.errors.iter().cloned()).collect();
+        let truncated = collector.pages.iter().any(|page| page.truncated);
+        let pages = layout_pages_on(collector.pages, &self.options, &self.threads);
+        Ok(DocumentText { version: self.doc.version.clone(), info: self.info(), outline: self.outline(), destinations: self.destinations(), pages, errors, truncated })
+    }
+}
+
+/// What is left to read of `ExtractOptions::max_chars` and `max_items`.
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    chars: Option<usize>,
+    items: Option<usize>,
+}
+
+impl Budget {
+    fn of(options: &ExtractOptions) -> Option<Budget> {
+        (options.max_chars.is_some() || options.max_items.is_some()).then_some(Budget { chars: options.max_chars, items: options.max_items })
+    }
+
+    fn spent(&self) -> bool {
+        self.chars == Some(0) || self.items == Some(0)
+    }
+
+    /// Keeps the items up to the one that spends the budget, taking them from it, and tells
+    /// whether any were left out.
+    fn take(&mut self, items: &mut Vec<TextItem>) -> bool {
+        for (index, item) in items.iter().enumerate() {
+            if self.spent() {
+                items.truncate(index);
+                return true;
+            }
+            self.chars = self.chars.map(|chars| chars.saturating_sub(item.text.chars().count()));
+            self.items = self.items.map(|left| left - 1);
+        }
+        false
+    }
+}
+
+/// Gives `f` the contents of the file at `path`, mapped into memory rather than read.
+pub(crate) fn with_mapped<T>(path: &Path, f: impl FnOnce(&[u8]) -> Result<T, Error>) -> Result<T, Error> {
+    let file = File::open(path)?;
+    // Empty files cannot be mapped.
+    if file.metadata()?.len() == 0 {
+        return f(&[]);
+    }
+    // SAFETY: the map is only read while the document is parsed, and is dropped before it is
+    // returned. A file truncated by another process meanwhile faults, as any mapped file does.
+    let data = unsafe { memmap2::Mmap::map(&file)? };
+    f(&data)
+}
+
+/// What the objects of a document are passed through as they are read, to leave out what is
+/// not needed of them.
+pub(crate) type ObjectFilter = fn(ObjectId, &mut Object) -> Option<(ObjectId, Object)>;
+
+/// Parses a PDF, its objects passed through `filter` as they are read, and decrypts it with
+/// `password` if need be.
+pub(crate) fn read_document(data: &[u8], password: Option<&str>, filter: Option<ObjectFilter>) -> Result<lopdf::Document, Error> {
+    let reader = lopdf::Reader { buffer: data, document: lopdf::Document::new(), encryption_state: None, raw_objects: BTreeMap::new() };
+    let mut doc = reader.read(filter)?;
+    if security::is_locked(&doc) {
+        security::unlock(&mut doc, data, password)?;
+    }
+    Ok(doc)
+}
+
+/// The objects of pages by page number.
+type PageObjects = BTreeMap<u32, ObjectId>;
+
+/// The objects of the pages `options` selects, and the labels of all the document's pages,
+/// when it gives them.
+pub(crate) fn selected_pages(doc: &lopdf::Document, options: &ExtractOptions) -> Result<(PageObjects, Option<Vec<String>>), Error> {
+    let mut pages = doc.get_pages();
+    let labels = labels::read(doc, pages.len());
+    if let Some(selection) = &options.pages {
+        // Documents without labels show their pages' numbers.
+        let selection = match &labels {
+            Some(labels) => selection.resolve(labels)?,
+            None => selection.resolve(&(1..=pages.len()).map(|page_num| page_num.to_string()).collect::<Vec<_>>())?,
+        };
+        selection.check(pages.len())?;
+        pages.retain(|&page_num, _| selection.contains(page_num));
+    }
+    Ok((pages, labels))
+}
+
+/// Keeps the objects of a document as read, but for image XObjects, which hold no text and are
+/// most of the data of scanned documents: only their dictionary is kept.
+fn without_image_data(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image") {
+            let dict = std::mem::take(&mut stream.dict);
+            *object = Object::Dictionary(dict);
+        }
+        // lopdf keeps the object as changed in place; what is returned only stands in for the
+        // objects of object streams, which are never streams themselves.
+        return Some((id, Object::Null));
+    }
+    Some((id, object.clone()))
+}
+
+/// Groups the items of pages read in order into lines and paragraphs. Headers and footers are
+/// told by repeating across these pages, and hyphenated words are joined across their breaks.
+pub fn layout_pages(pages: Vec<PageItems>, options: &ExtractOptions) -> Vec<PageText> {
+    layout_pages_on(pages, options, &Threads::new(options).unwrap_or(Threads::One))
+}
+
+/// `layout_pages`, grouping each page's lines on `threads`.
+fn layout_pages_on(mut pages: Vec<PageItems>, options: &ExtractOptions, threads: &Threads) -> Vec<PageText> {
+    let page_nums: Vec<u32> = pages.iter().map(|page| page.page_num).collect();
+    // What is not text is kept aside while the text is laid out.
+    let annotations: Vec<(Vec<Annotation>, Vec<Link>, Vec<PlacedImage>)> = pages
+        .iter_mut()
+        .map(|page| (std::mem::take(&mut page.annotations), std::mem::take(&mut page.links), std::mem::take(&mut page.images)))
+        .collect();
+    let mut page_lines =
+        threads.map(pages, |page| layout::furniture::PageLines { crop_box: page.crop_box, lines: page_lines(page.items, options) });
+
+    layout::furniture::mark_repeated(&mut page_lines, options.origin);
+    let mut all_paragraphs = Vec::new();
+    let mut paragraph_counts = Vec::new();
+    for (page_num, page) in page_nums.into_iter().zip(page_lines) {
+        let crop_box = page.crop_box;
+        if options.debug_ops {
+            log::debug!("Lines of Page {}", page_num);
+            for line in &page.lines {
+                log::debug!("  Line: '{:?}' box {} column {}{} from {} items", line.text, debug::rect(&line.bbox), line.column,
+                    if line.repeated { " repeated" } else { "" }, line.items.len());
             }
+        }
+        let lines = page.lines.into_iter().filter(|line| !(options.strip_headers_footers && line.repeated)).collect();
+        let paragraphs = layout::paragraphs::group(lines, options.paragraph_gap);
+        if options.debug_ops {
+            for paragraph in &paragraphs {
+                log::debug!("  Paragraph: box {} from {} lines", debug::rect(&paragraph.bbox), paragraph.lines.len());
+            }
+        }
+        paragraph_counts.push((page_num, crop_box, paragraphs.len()));
+        all_paragraphs.extend(paragraphs);
+    }
+    if options.dehyphenate {
+        layout::paragraphs::dehyphenate(&mut all_paragraphs);
+    }
+    let mut all_paragraphs = all_paragraphs.into_iter();
+    paragraph_counts
+        .into_iter()
+        .zip(annotations)
+        .map(|((page_num, crop_box, count), (annotations, links, images))| {
+            PageText { page_num, crop_box, paragraphs: all_paragraphs.by_ref().take(count).collect(), annotations, links, images }
+        })
+        .collect()
+}
 
-            // // "TJ": Show Text with Adjustments (kerning).
-            // "TJ" => {
-            //     // TJ is complex because it mixes strings and numbers (spacing).
-            //     if let Some(Object::Array(arr)) = operands.first() {
-            //         let mut combined_text = String::new();
-            //         for item in arr {
-            //             if let Object::String(bytes, _) = item {
-
-            //                 combined_text.push_str(&String::from_utf8_lossy(bytes));
-
-            //                 // combined_text.push_str(&String::from_utf8(bytes.clone()).unwrap_or_default());
-            //             }
-            //         }
-                    
-            //         extracted_items.push(TextItem {
-            //             text: combined_text,
-            //             x: current_x,
-            //             y: current_y,
-            //             font_size: current_font_size,
-            //             page_num: page_num  ,
-            //         });
-            //     }
-            // }
-
-            _ => {} 
+/// Groups the items of a page into lines as `layout_pages` does, before headers and footers
+/// are told: reordered for `options.bidi` and arranged in columns for `Order::Layout`.
+pub fn page_lines(items: Vec<TextItem>, options: &ExtractOptions) -> Vec<layout::lines::Line> {
+    let mut lines = Vec::new();
+    // The lines of each region are built from its items alone, region after region.
+    for items in by_region(items) {
+        let mut region_lines = layout::lines::group(items, options.origin, options.line_tolerance, options.word_gap, options.max_skew);
+        if options.bidi {
+            layout::bidi::reorder(&mut region_lines);
+        }
+        if options.order == Order::Layout {
+            layout::columns::arrange(&mut region_lines, options.columns);
         }
+        lines.extend(region_lines);
     }
-    extracted_items.sort_by(|a, b| {
-        b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-           .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-    });
+    lines
+}
 
-    extracted_items
+/// The items of each region, in the order of the regions, each in the order given: all of
+/// them at once without regions.
+fn by_region(mut items: Vec<TextItem>) -> Vec<Vec<TextItem>> {
+    items.sort_by_key(|item| item.region);
+    let mut regions: Vec<Vec<TextItem>> = Vec::new();
+    for item in items {
+        match regions.last_mut() {
+            Some(region) if region[0].region == item.region => region.push(item),
+            _ => regions.push(vec![item]),
+        }
+    }
+    regions
 }
 
-fn extract_text_from_object(obj: &Object) -> String {
-    match obj {
-        Object::String(bytes, _) => {
+/// Sorts items top to bottom, then left to right. When most items are written vertically,
+/// sorts them into columns instead: right to left, then top to bottom.
+fn sort_by_position(items: &mut [TextItem], origin: Origin) {
+    let vertical_page = items.iter().filter(|item| item.vertical).count() * 2 > items.len();
+    items.sort_by(|a, b| {
+        let vertical = match origin {
+            Origin::BottomLeft => b.y.partial_cmp(&a.y),
+            Origin::TopLeft => a.y.partial_cmp(&b.y),
+        };
+        let vertical = vertical.unwrap_or(std::cmp::Ordering::Equal);
+        let horizontal = a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal);
+        if vertical_page {
+            horizontal.reverse().then(vertical)
+        } else {
+            vertical.then(horizontal)
+        }
+    });
+}
+
+/// Puts a page's items, given in drawing order, into the structure tree's reading order and
+/// records their roles. Items outside any referenced sequence stay after the item drawn just
+/// before them, so untagged pages and stray text keep their drawing order.
+fn sort_by_structure(items: &mut Vec<TextItem>, reading_order: &[structure::StructureRef], page_id: ObjectId) {
+    let mut positions = HashMap::new();
+    for (index, entry) in reading_order.iter().enumerate().filter(|(_, entry)| entry.page == page_id) {
+        positions.entry(entry.content).or_insert((index, entry.role.as_str()));
+    }
+    let mut last = 0;
+    let mut keyed: Vec<_> = items
+        .drain(..)
+        .map(|mut item| {
+            if let Some(&(index, role)) = item.content_id.and_then(|id| positions.get(&id)) {
+                last = index;
+                item.role = Some(role.to_string());
+            }
+            (last, item)
+        })
+        .collect();
+    keyed.sort_by_key(|&(key, _)| key);
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
 
-            // String::from_utf8_lossy(bytes).to_string()
-            String::from_utf8(bytes.clone()).unwrap_or_default()
-        },
-        _ => String::new(),
+/// Decodes a text string outside content streams (PDF 32000-1:2008, 7.9.2.2): UTF-16BE or
+/// UTF-8 with a byte order mark, otherwise PDFDocEncoding. A lone byte left at the end
+/// of UTF-16BE becomes U+FFFD.
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let pairs = rest.chunks_exact(2);
+            let odd = !pairs.remainder().is_empty();
+            let units: Vec<u16> = pairs.map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            let mut text = String::from_utf16_lossy(&units);
+            if odd {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            text
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => pdf_doc_decode(bytes),
     }
 }
 
-// fn print_with_layout(obj: &Object) -> String{
+#[cfg(test)]
+mod tests {
+    use super::decode_text_string;
 
+    #[test]
+    fn decodes_surrogate_pairs_of_utf16() {
+        assert_eq!(decode_text_string(&[0xFE, 0xFF, 0xD8, 0x3D, 0xDE, 0x00, 0x00, 0x21]), "\u{1F600}!");
+    }
 
-// }
\ No newline at end of file
+    #[test]
+    fn replaces_the_lone_byte_ending_utf16() {
+        assert_eq!(decode_text_string(&[0xFE, 0xFF, 0x00, 0x41, 0x00]), "A\u{FFFD}");
+    }
+
+    #[test]
+    fn decodes_text_without_a_byte_order_mark_as_pdf_doc_encoding() {
+        // Latin-1 where the two agree, PDFDocEncoding's own characters from 0x80 to 0xA0.
+        assert_eq!(decode_text_string(b"Caf\xE9 \x84 \xA05, na\xEFve"), "Café — €5, naïve");
+        assert_eq!(decode_text_string(b"\xEF\xBB\xBFCaf\xC3\xA9"), "Café");
+    }
+}
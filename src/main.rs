@@ -1,327 +1,1889 @@
-use lopdf::{Document, Object};
+mod alto;
+mod diff;
+mod hocr;
+mod html;
+mod markdown;
+mod report;
+mod search;
+mod stats;
+mod tuning;
+
+use clap::{ArgAction, ArgGroup, Parser, ValueEnum};
+use regex::{Regex, RegexBuilder};
+use lopdf::ObjectId;
+use md5::{Digest, Md5};
+use rapid_pdf::{layout, Annotation, Attachment, Destination, DocumentInfo, Error, ExtractOptions, Extractor, FieldKind, FieldValue, Fit, FormField, Granularity, Highlight, Metadata, Ocr, OnPage, Order, Origin, OutlineItem, PageError, PageItems, PageKind, PageProgress, PageSelection, PageText, PageTree, Rect, Region, RegionMode, Rgb};
+use report::{DocumentReport, ErrorDocument, ErrorReport, FileReport, FontSizes, ImageEntry, ImageManifest, PageFile, PageManifest, PageReport, Record, Summary};
+use stats::{DocumentStats, PageStats};
+use tuning::TuningReport;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
-use std::io::BufWriter;
-use std::path::Path;
-
-
-#[derive(Debug, Clone)] 
-struct TextItem {
-    
-    text: String,
-    
-    x: f32,
-    
-    y: f32,
-    
-    font_size: f32,
-    page_num: u32,
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut doc = Document::load("test.pdf")?;
-    
-    // doc.replace_text(1, "Company", "", None)?;
-    // doc.replace_text(1, "Statement", " Testing ", None)?;
-    // doc.replace_text(1, "Company Statement of Changes in Shareholders’ Equity", " Testing ", None)?;
-    // doc.replace_text(1, "Company Statement of Changes in Shareholders’ Equity", " Testing ", None)?;
-    replace_text_at_position(
-        &mut doc,
-        1,
-        "CORPORATE",
-        "Testing",
-        277.895, 451.84802,
-        0.00,   
-    )?;
-    let mut all_text_items = Vec::new();
-    
-    for (page_num, object_id) in doc.get_pages(){
-        println!("Processing Page {}", page_num);
-        let content_data = doc.get_page_content(object_id)?;
-        let content = lopdf::content::Content::decode(&content_data)?;
-        
-        let mut text_items = process_content_stream(&content, page_num);
-        
-        text_items.sort_by(|a, b| {
-            b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-            .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-        });
-        for item in &text_items {
-            println!("  Found: '{:?}' at ({:.2}, {:.2}) size {:.2}", 
-                item.text, item.x, item.y, item.font_size);
-        }
-        all_text_items.extend(text_items);
-    }
-    all_text_items.sort_by(|a, b| {
-        b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-        .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-    });
-    println!("Print with layout preserved:");
-    let mut last_test_y = all_text_items.first().unwrap().y;
-    for item in all_text_items{
-        if (last_test_y - item.y).abs() > 5.0 {
-            println!();
-            last_test_y = item.y;
-        }
-        print!("{} ", item.text.trim());
-    }
-    // let path = Path::new("output.pdf");
-    // let mut file = BufWriter::new(File::create(path)?);
-    // doc.save_modern(&mut file)?;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
 
+/// What the exit status tells, which scripts may rely on as much as on the output.
+const EXIT_STATUSES: &str = "Exit status:
+  0  success
+  1  with --find, nothing was found; with --diff, the texts differ; otherwise any
+     failure not below, such as output that could not be written or something the
+     PDF uses that is not supported
+  2  content of some pages could not be read, or went over a limit: the rest was
+     written, or with --strict, nothing after the first such page; reading several
+     files, some could not be read at all
+  3  invalid arguments, or pages asked for that the document does not have
+  4  the file is not there or could not be read
+  5  the PDF is encrypted and the password is missing or wrong, or it cannot be
+     decrypted
+  6  the file is not a PDF, or too broken to read
+  7  with --fail-if-image-only, some pages are scans without text, and nothing
+     else failed
+Reading several files, none of which could be read, the status is that of their
+failure when they all failed alike, otherwise 1. With --format json, a failure
+before anything is written writes {\"errors\": [...]} instead, each error with a
+code that does not change, such as \"file-not-found\" or \"wrong-password\", its
+message, and the file and page it is of; with --format jsonl, a file-error record.";
 
-    Ok(())
+/// Extract the text of a PDF, in reading order, to stdout.
+#[derive(Debug, Parser)]
+#[command(version, about, after_help = EXIT_STATUSES)]
+#[command(group(ArgGroup::new("mode").multiple(false)))]
+struct Cli {
+    /// The PDF files to read, directories to read the `.pdf` files of, or `-` alone to read a
+    /// PDF from standard input.
+    #[arg(required = true, num_args = 1..)]
+    paths: Vec<PathBuf>,
+    /// Also read the PDF files in subdirectories of directories given.
+    #[arg(short, long)]
+    recursive: bool,
+    /// Write to this file instead of stdout, or, reading several files, into this directory,
+    /// one file each named after the PDF with the extension of the format.
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Overwrite output files that already exist.
+    #[arg(long)]
+    force: bool,
+    /// The password of encrypted PDFs that do not open without one, their user or owner
+    /// password; ignored for the others.
+    #[arg(long, env = "RAPID_DOC_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+    /// Read only these pages, counted from 1: pages and ranges separated by commas, such as
+    /// `1-5,12,40-`, where `40-` runs to the last page. Pages and ranges prefixed `label:`,
+    /// such as `label:iv-vi` or `label:A-1`, go by the labels the document shows its pages with.
+    #[arg(long, value_name = "RANGES")]
+    pages: Option<PageSelection>,
+    /// Leave out invisible text (render mode 3), such as the OCR layer of a scanned page.
+    #[arg(long)]
+    skip_invisible: bool,
+    /// Leave out text marked as an artifact, such as running headers and page numbers.
+    #[arg(long)]
+    skip_artifacts: bool,
+    /// Leave out text lying entirely outside the page's crop box, such as printers' marks.
+    #[arg(long)]
+    skip_outside_crop_box: bool,
+    /// Leave out text whose baseline is turned more than this many degrees from horizontal,
+    /// either way, such as diagonal watermarks and notes up the margin.
+    #[arg(long, value_name = "DEGREES")]
+    skip_rotated: Option<f32>,
+    /// Leave out text shown smaller than this many points, such as all but headings. Sizes
+    /// are those text is seen at, the font size scaled by the text matrix and the CTM.
+    #[arg(long, value_name = "POINTS")]
+    min_font_size: Option<f32>,
+    /// Leave out text shown larger than this many points, such as all but fine print.
+    #[arg(long, value_name = "POINTS")]
+    max_font_size: Option<f32>,
+    /// Leave out text filled in white, or nearly, with no filled shape of another color
+    /// behind it: a cheap test for text hidden on a white page.
+    #[arg(long)]
+    drop_invisible_color: bool,
+    /// Keep only the text in this region of each page: its corner nearest the origin, then
+    /// its width and height, in `--units` and measured as `--origin` says. Given more than
+    /// once, the text of each region is laid out on its own, and text output heads it with
+    /// `[region N]`, the regions counted in the order given.
+    #[arg(long = "rect", value_name = "X,Y,W,H", value_parser = parse_rect)]
+    rects: Vec<[f32; 4]>,
+    /// Which text `--rect` keeps: that whose box meets the region, or lies inside it.
+    #[arg(long, value_enum, default_value_t = RegionMode::Intersect, requires = "rects")]
+    rect_mode: RegionMode,
+    /// Keep text exactly as decoded, without spelling out ligatures or composing accents.
+    #[arg(long)]
+    no_normalize: bool,
+    /// Write non-breaking spaces as ordinary ones.
+    #[arg(long)]
+    nbsp_as_space: bool,
+    /// Keep right-to-left text in the visual order it is drawn in, and Arabic letters in
+    /// their presentation forms, rather than in logical order.
+    #[arg(long)]
+    no_bidi: bool,
+    /// The order text is listed in.
+    #[arg(long, value_enum, default_value_t = Order::Stream)]
+    order: Order,
+    /// What each item of the output holds: the text of a string shown, or with chars, the
+    /// characters of a single glyph, a ligature's all together, with its own position and
+    /// advance. Lines and words are put together from them as from strings.
+    #[arg(long, value_enum, default_value_t = Granularity::Items)]
+    granularity: Granularity,
+    /// Where coordinates are measured from.
+    #[arg(long, value_enum, default_value_t = Origin::BottomLeft)]
+    origin: Origin,
+    /// The unit positions and sizes are written in; font sizes and text rise stay in points.
+    #[arg(long, value_enum, default_value_t = Units::Pt)]
+    units: Units,
+    /// Decimal places of the numbers written.
+    #[arg(long, default_value_t = 2)]
+    precision: usize,
+    /// How close, as a fraction of the font size, copies of the same text must be to be
+    /// collapsed into one item, as drawn for fake bold and shadows.
+    #[arg(long, default_value_t = layout::duplicates::DEFAULT_TOLERANCE)]
+    duplicate_tolerance: f32,
+    /// How far apart baselines may be, as a fraction of the font size, for text to share a line.
+    #[arg(long, default_value_t = layout::lines::DEFAULT_TOLERANCE)]
+    line_tolerance: f32,
+    /// The smallest gap between fragments of a line, as a fraction of the font size, that
+    /// separates words.
+    #[arg(long, visible_alias = "space-threshold", default_value_t = layout::lines::DEFAULT_WORD_GAP)]
+    word_gap: f32,
+    /// How many degrees a line's baseline may lean by, from 0 to 45, for text drawn a little
+    /// askew to be grouped into lines by how far it is from the baseline fitted through it
+    /// rather than by its y. hOCR output gives the slope of each line's baseline.
+    #[arg(long, value_name = "DEGREES", default_value_t = layout::lines::DEFAULT_MAX_SKEW)]
+    max_skew: f32,
+    /// How far apart baselines may be, as a multiple of the leading most lines of the page
+    /// are set with, for lines to be in one paragraph; 1 or more.
+    #[arg(long, default_value_t = layout::paragraphs::DEFAULT_GAP)]
+    paragraph_gap: f32,
+    /// How far forward, in thousandths of an em, a number in a TJ array must move the text for
+    /// a space to be put there; those moving it less are kerning, and those moving it back
+    /// never are spaces.
+    #[arg(long, value_name = "THOUSANDTHS", default_value_t = ExtractOptions::default().tj_space_threshold)]
+    tj_space_threshold: f32,
+    /// How `--order layout` finds columns to read one after another: off, auto, or a number
+    /// of columns to split every page into.
+    #[arg(long, default_value = "auto")]
+    columns: layout::columns::Columns,
+    /// Leave out running headers, footers and page numbers repeated on most pages.
+    #[arg(long)]
+    strip_headers_footers: bool,
+    /// Join words hyphenated at the end of a line, such as "extrac-" "tion".
+    #[arg(long)]
+    dehyphenate: bool,
+    /// Join the fragments a word is drawn in, such as "W" "ork" "flow" kerned apart by
+    /// separate show-string operators, into one item, in the items of csv, tsv and json
+    /// output as in the text; json tells where each fragment ended.
+    #[arg(long)]
+    merge_fragments: bool,
+    /// Find tables drawn with ruling lines and write each as page-<n>-table-<m>.csv into
+    /// this directory, prefixed with the name of the file when reading several.
+    #[arg(long, value_name = "DIR")]
+    tables: Option<PathBuf>,
+    /// Write the metadata of each PDF instead of its text: the title, author, dates and such
+    /// of its information dictionary and of its XMP packet, as lines of text, or as JSON with
+    /// `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    metadata: bool,
+    /// Write the outline, or bookmarks, of each PDF instead of its text: each entry's title
+    /// and page, indented under its parent, or as JSON with `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    outline: bool,
+    /// Write the fields of the form of each PDF instead of its text: each field's name, kind
+    /// and value, and the pages it is shown on, or as JSON with `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    form_fields: bool,
+    /// List the files embedded in each PDF instead of its text: each file's name, MIME type,
+    /// size and description, or as JSON with `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    attachments: bool,
+    /// Write the named destinations of each PDF instead of its text, those links to it may
+    /// lead to: each name with its page and the position or fit it is shown at, or as JSON
+    /// with `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    destinations: bool,
+    /// Guess the title of each PDF from the text of its first page instead of writing its
+    /// text, for when its metadata has none: the lines in the largest type near the top, with
+    /// how sure the guess is, from 0 to 1, or as JSON with `--format json` or `jsonl`.
+    /// `--metadata` writes the guess too.
+    #[arg(long, group = "mode")]
+    guess_title: bool,
+    /// Write the headings of each PDF instead of its text, as a table of contents: each
+    /// heading with its page, indented by its level, or as JSON with `--format json` or
+    /// `jsonl`, with where it is on the page. Levels come from the type headings are set in,
+    /// or from their section numbers, such as `3.2.1`, when they have them.
+    #[arg(long, group = "mode")]
+    headings: bool,
+    /// Write how many pages each PDF has instead of its text, by its page tree alone, without
+    /// decoding the content of any page: fast, however large the PDF. A number alone for one
+    /// PDF, after the name of each reading several; or as JSON with `--format json` or `jsonl`.
+    #[arg(long, group = "mode")]
+    page_count: bool,
+    /// Write the pages of each PDF instead of its text, by its page tree alone as
+    /// `--page-count` does, a row each: the page's number and label, the width and height of
+    /// its crop box as displayed, its rotation, whether it is landscape and whether it has
+    /// content; or as JSON with `--format json` or `jsonl`, with its boxes.
+    #[arg(long, group = "mode")]
+    page_info: bool,
+    /// Count the text of each PDF instead of writing it, a row for each page and the totals:
+    /// characters, words, lines, items, the font size most text is set in and the part of
+    /// the page text covers, then the fonts used and how many pages have no text; or as JSON
+    /// with `--format json` or `jsonl`. Pages are counted as they are read, each laid out on
+    /// its own.
+    #[arg(long, group = "mode")]
+    stats: bool,
+    /// Find this text in the lines, or with `--find-in paragraphs` the paragraphs, of each
+    /// PDF instead of writing its text, and write each match a line: the page, the box of the
+    /// text matched and its line, with the match highlighted; or as JSON with `--format json`
+    /// or `jsonl`, with the corners of the text matched in each line, as the `/QuadPoints` of
+    /// a highlight over it. Exits with 0 when something is found and 1 when nothing is.
+    #[arg(long, value_name = "PATTERN", group = "mode")]
+    find: Option<String>,
+    /// Compare the text of two PDFs, the paths given, instead of writing it, and write the
+    /// words the second inserts, deletes and changes, page by page: as unified diff, each
+    /// change headed by its page and the boxes of the words on the pages of the first (-) and
+    /// the second (+), with words both have around it; or as JSON with `--format json`. Pages
+    /// of the same number are compared, their paragraphs in reading order; differences in
+    /// white space, and words hyphenated across lines, are not told. Exits with 0 when the
+    /// texts are the same and 1 when they differ.
+    #[arg(long, group = "mode")]
+    diff: bool,
+    /// Write the operations of the content of each page instead of its text, and of the forms
+    /// it paints, as they are run: a line each with its number, its offset in the stream
+    /// decompressed and the operation as PDF syntax, those of forms after the form, and for
+    /// those that show text, the text decoded and where it starts; or as JSON with `--format
+    /// json` or `jsonl`, with the type of each operand. For seeing why a page reads as it does.
+    #[arg(long, group = "mode")]
+    dump_ops: bool,
+    /// Write how far apart the text of each PDF is instead of the text, against the
+    /// thresholds of --word-gap, --line-tolerance and --paragraph-gap: histograms of the gaps
+    /// between fragments of a line, of the offsets between the baselines of fragments drawn
+    /// one after another, and of the distances between lines, each threshold marked; or as
+    /// JSON with `--format json` or `jsonl`. For choosing thresholds for documents the
+    /// defaults do not suit.
+    #[arg(long, group = "mode")]
+    tuning_report: bool,
+    /// Take the pattern of `--find` as a regular expression rather than literal text.
+    #[arg(long, requires = "find")]
+    regex: bool,
+    /// Leave out the pages whose text scores below this quality, from 0 to 1, naming each on
+    /// stderr: a score of how trustworthy the text looks, from the glyphs that could not be
+    /// decoded, the characters of stray scripts, the part of the page text covers, text
+    /// drawn over text and the spaces told by position alone. JSON output gives each page's
+    /// quality with those signals.
+    #[arg(long, value_name = "SCORE")]
+    min_quality: Option<f32>,
+    /// Exit with status 7 when some page read is a scan without text: images cover most of it
+    /// and it has next to no text, which only OCR can read. Whatever this flag, such pages
+    /// are counted on stderr, and JSON output gives each page's kind: text, image-only or
+    /// blank.
+    #[arg(long)]
+    fail_if_image_only: bool,
+    /// Read the words of scans with the `tesseract` command, from the image XObjects of the
+    /// pages that are scans without text, or with always, of every page painting images;
+    /// they are then text like the rest. Images compressed as CCITT fax or JBIG2 cannot be
+    /// given to it. Only in builds with the tesseract feature.
+    #[arg(long, value_enum, value_name = "WHEN")]
+    ocr: Option<Ocr>,
+    /// The languages `--ocr` reads, as tesseract's `-l` takes them, such as eng+deu.
+    #[arg(long, value_name = "LANGS", requires = "ocr")]
+    ocr_languages: Option<String>,
+    /// Find the pattern of `--find` whatever the case of its letters.
+    #[arg(short, long, requires = "find")]
+    ignore_case: bool,
+    /// What `--find` searches, one at a time: lines, or paragraphs, their lines joined so
+    /// that matches may run on from one line to the next.
+    #[arg(long, value_enum, default_value_t = FindIn::Lines, requires = "find")]
+    find_in: FindIn,
+    /// Write a copy of the PDF with a highlight annotation over each match of `--find`, its
+    /// text the text matched, to this file, or reading several, into this directory under the
+    /// name of each PDF. The PDF read is never written to.
+    #[arg(long, value_name = "PATH", requires = "find")]
+    annotate_output: Option<PathBuf>,
+    /// The color of the highlights of `--annotate-output`, as `rrggbb` in hexadecimal.
+    #[arg(long, value_name = "RRGGBB", default_value = "ffff00", value_parser = parse_color, requires = "annotate_output")]
+    highlight_color: Rgb,
+    /// Whether `--find` highlights matches in color: by default, when writing to a terminal.
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+    /// Write the files embedded in each PDF into this directory, under names made safe to
+    /// write and unique, prefixed with the name of the PDF when reading several.
+    #[arg(long, value_name = "DIR")]
+    extract_attachments: Option<PathBuf>,
+    /// Write the image XObjects painted on the pages into this directory, JPEG and JPEG 2000
+    /// images as they are and others as PNG, each once under the MD5 hash of its file, with a
+    /// manifest, images.json, of the pages each is painted on and where, prefixed with the
+    /// name of the PDF when reading several.
+    #[arg(long, value_name = "DIR")]
+    extract_images: Option<PathBuf>,
+    /// Write the comments, notes and markup of each page after its text, a line each: the
+    /// kind, author and date, the text marked up, and the comment.
+    #[arg(long)]
+    annotations: bool,
+    /// What to write between pages of text.
+    #[arg(long, value_name = "STRING", default_value = "\u{c}", hide_default_value = true)]
+    page_delimiter: String,
+    /// Stop at the first page whose content cannot be read in full, instead of writing what
+    /// can be read of it and going on.
+    #[arg(long)]
+    strict: bool,
+    /// Read up to this many pages at once, each on a thread of its own; by default, as many
+    /// as there are cores. Output is the same, in page order, whatever the number.
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+    /// Stop reading a page after this many operators, those of its forms included.
+    #[arg(long, value_name = "N", default_value_t = ExtractOptions::default().max_operations)]
+    max_operations: usize,
+    /// Stop reading a page at form XObjects nested deeper than this.
+    #[arg(long, value_name = "N", default_value_t = ExtractOptions::default().max_form_depth)]
+    max_form_depth: usize,
+    /// Stop reading a page at a content stream decompressing to more than this many bytes.
+    #[arg(long, value_name = "BYTES", default_value_t = ExtractOptions::default().max_stream_size)]
+    max_stream_size: usize,
+    /// Stop reading the pages of a PDF, in order, once they have given this many characters
+    /// of text, for a preview: the page reaching it keeps the text its content shows first,
+    /// before it is put in order, and no page after it is read. Output cut short is marked:
+    /// by a last line "[truncated]" in text, a comment in Markdown, and `truncated` in JSON.
+    #[arg(long, value_name = "N")]
+    max_chars: Option<usize>,
+    /// Stop reading the pages of a PDF once they have given this many items, as `--max-chars`
+    /// does characters.
+    #[arg(long, value_name = "N")]
+    max_items: Option<usize>,
+    /// Stop reading pages once a PDF has taken this many seconds; by default, no limit.
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+    /// Write the content stream operators, the strings they show and the items, lines and
+    /// paragraphs built from them to stderr, lengths in points, as `-vvv` does.
+    #[arg(long)]
+    debug_ops: bool,
+    /// Write more of what is done to stderr: `-v` the files read, `-vv` the items, lines and
+    /// paragraphs of each page and what the PDF library warns of, `-vvv` the operators too.
+    /// Otherwise only warnings and errors are, or as `RUST_LOG` says: error, warn, info,
+    /// debug or trace.
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Write only errors to stderr, not warnings of what could not be read.
+    #[arg(short, long)]
+    quiet: bool,
+    /// Show how far the reading of each PDF has got on stderr: a bar of the pages read, the
+    /// time left and the page just read, when stderr is a terminal, otherwise a line every few
+    /// seconds. What is written to stdout is not changed.
+    #[arg(long)]
+    progress: bool,
+    /// What to write to stdout.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// With `--output DIR`, write each page read into a file of its own in the directory
+    /// instead of one output, as it would be in the output of the whole: page-0001.txt and on,
+    /// with the extension of the format and the page's number in the document, and a
+    /// manifest, manifest.json, of the pages, the size of their files and what of them could
+    /// not be read; prefixed with the name of the PDF when reading several.
+    #[arg(long, requires = "output", group = "mode")]
+    split_pages: bool,
+    /// Write JSON on one line instead of indented.
+    #[arg(long)]
+    compact: bool,
+    /// End JSON Lines output with a record of the totals: pages, items and pages whose
+    /// content could not be read.
+    #[arg(long)]
+    summary: bool,
+    /// Mark page breaks in Markdown output with a rule and a comment naming the page.
+    #[arg(long)]
+    page_breaks: bool,
 }
 
-fn extract_text_from_pdf(path: String) -> Result<Vec<TextItem>, Box<dyn std::error::Error>> {
-    let doc = Document::load(path)?;
-    let mut all_items = Vec::new();
+impl Cli {
+    /// Whether a mode is given that writes something of the document else than its text.
+    fn document_mode(&self) -> bool {
+        self.metadata || self.outline || self.form_fields || self.attachments || self.destinations || self.guess_title || self.page_tree_mode()
+    }
+
+    /// Whether a mode is given that reads only the page tree of the document.
+    fn page_tree_mode(&self) -> bool {
+        self.page_count || self.page_info
+    }
+
+    /// The pattern of `--find`, if given.
+    fn pattern(&self) -> Result<Option<Regex>, Error> {
+        let Some(find) = &self.find else { return Ok(None) };
+        if find.is_empty() {
+            return Err(Error::InvalidOptions("--find needs something to find".to_string()));
+        }
+        let pattern = if self.regex { find.clone() } else { regex::escape(find) };
+        let pattern = RegexBuilder::new(&pattern).case_insensitive(self.ignore_case).build();
+        pattern.map(Some).map_err(|err| Error::InvalidOptions(format!("--find {:?} is not a regular expression: {}", find, err)))
+    }
+
+    /// The regions of `--rect`, in points.
+    fn regions(&self) -> Vec<Region> {
+        let points = |value: f32| value / self.units.per_point();
+        let rect = |[x, y, width, height]: [f32; 4]| Rect { x0: points(x), y0: points(y), x1: points(x + width), y1: points(y + height) };
+        self.rects.iter().map(|&value| Region::new(rect(value), self.rect_mode)).collect()
+    }
 
-    for (_page_num, object_id) in doc.get_pages() { 
-        if let Ok(content_data) = doc.get_page_content(object_id) {
-            if let Ok(content) = lopdf::content::Content::decode(&content_data) {
-                let mut items = process_content_stream(&content, _page_num);
-                all_items.append(&mut items);
+    /// The settings of the library the arguments stand for.
+    fn extract_options(&self) -> Result<ExtractOptions, Error> {
+        let builder = ExtractOptions::builder()
+            .page_selection(self.pages.clone())
+            .skip_invisible(self.skip_invisible)
+            .skip_artifacts(self.skip_artifacts)
+            .skip_outside_crop_box(self.skip_outside_crop_box)
+            .skip_rotated(self.skip_rotated)
+            .min_font_size(self.min_font_size)
+            .max_font_size(self.max_font_size)
+            .drop_invisible_color(self.drop_invisible_color)
+            .regions(self.regions())
+            .normalize(!self.no_normalize)
+            .nbsp_as_space(self.nbsp_as_space)
+            .bidi(!self.no_bidi)
+            .order(self.order)
+            .origin(self.origin)
+            .duplicate_tolerance(self.duplicate_tolerance)
+            .line_tolerance(self.line_tolerance)
+            .word_gap(self.word_gap)
+            .max_skew(self.max_skew)
+            .paragraph_gap(self.paragraph_gap)
+            .tj_space_threshold(self.tj_space_threshold)
+            .granularity(self.granularity)
+            .columns(self.columns)
+            .strip_headers_footers(self.strip_headers_footers)
+            .dehyphenate(self.dehyphenate)
+            .merge_fragments(self.merge_fragments)
+            .password(self.password.as_deref())
+            .strict(self.strict)
+            .threads(self.threads)
+            .max_operations(self.max_operations)
+            .max_form_depth(self.max_form_depth)
+            .max_stream_size(self.max_stream_size)
+            .time_limit(self.time_limit)
+            .max_chars(self.max_chars)
+            .max_items(self.max_items)
+            .images(self.extract_images.is_some())
+            .debug_ops(self.debug_ops || self.verbose >= 2);
+        match self.ocr {
+            None => builder.build(),
+            #[cfg(feature = "tesseract")]
+            Some(ocr) => {
+                let tesseract = rapid_pdf::Tesseract::new();
+                let tesseract = match &self.ocr_languages {
+                    Some(languages) => tesseract.languages(languages.as_str()),
+                    None => tesseract,
+                };
+                builder.ocr(ocr).ocr_provider(tesseract).build()
             }
+            #[cfg(not(feature = "tesseract"))]
+            Some(_) => Err(Error::InvalidOptions("--ocr runs tesseract, which this build leaves out; build with --features tesseract".to_string())),
         }
     }
+}
 
-    all_items.sort_by(|a, b| {
-        b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-           .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-    });
+/// Parses a color written `rrggbb` in hexadecimal, or `#rrggbb`.
+fn parse_color(value: &str) -> Result<Rgb, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let component = |index: usize| hex.get(index..index + 2).and_then(|digits| u8::from_str_radix(digits, 16).ok());
+    match (hex.len(), component(0), component(2), component(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(Rgb([r, g, b].map(|component| component as f32 / 255.0))),
+        _ => Err(format!("{} is not a color written rrggbb, such as ffff00 for yellow", value)),
+    }
+}
 
+/// Parses a rectangle written `x,y,width,height`, such as `400,50,150,80`.
+fn parse_rect(value: &str) -> Result<[f32; 4], String> {
+    let numbers: Vec<f32> = value.split(',').map(|number| number.trim().parse()).collect::<Result<_, _>>().map_err(|_| format!("{} is not a rectangle written x,y,width,height", value))?;
+    match <[f32; 4]>::try_from(numbers) {
+        Ok(rect) if rect.iter().all(|number| number.is_finite()) && rect[2] >= 0.0 && rect[3] >= 0.0 => Ok(rect),
+        _ => Err(format!("{} is not a rectangle written x,y,width,height, its width and height 0 or more", value)),
+    }
+}
 
-    Ok(all_items)
+/// Parses a number of seconds, such as `30` or `0.5`.
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|_| format!("{} is not a number of seconds", value))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("{} is not a time to wait, 0 or more seconds", value))
 }
 
-fn process_content_stream(content: &lopdf::content::Content, page_num: u32) -> Vec<TextItem> {
-    let mut extracted_items = Vec::new();
-    let mut extracted_larget_set :Vec<TextItem> = Vec::new();
-    let mut current_font_size: f32 = 0.0;
-    let mut current_x: f32 = 0.0;
-    let mut current_y: f32 = 0.0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// The text of each page in paragraphs.
+    Text,
+    /// The document, its pages and their text items with positions and fonts.
+    Json,
+    /// One JSON object per page, written as each page is read.
+    Jsonl,
+    /// One row per text item with its page, text, position, size and font.
+    Csv,
+    /// As `csv`, separated by tabs.
+    Tsv,
+    /// hOCR 1.2 (XHTML), as OCR engines write: pages, content areas, paragraphs, lines and
+    /// words with their boxes in points from the top-left corner.
+    Hocr,
+    /// ALTO 4 XML, as library and archive systems ingest: text blocks, lines and words with
+    /// their positions from the top-left corner, in `--units`.
+    Alto,
+    /// Markdown: paragraphs, with headings told by their larger or bold type, and bulleted
+    /// or numbered lines as list items.
+    Markdown,
+    /// HTML for previewing in a browser: pages the size of their crop box, with each text
+    /// item placed where it appears.
+    Html,
+}
 
-    for operation in &content.operations {
-        let operator = &operation.operator; // e.g., "Tf", "Tj", "Tm"
-        let operands = &operation.operands; 
+impl Format {
+    /// The file name extension of output in the format.
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Hocr => "hocr",
+            Format::Alto => "xml",
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
 
+    /// The field separator of the delimited formats.
+    fn separator(self) -> Option<char> {
+        match self {
+            Format::Csv => Some(','),
+            Format::Tsv => Some('\t'),
+            Format::Text | Format::Json | Format::Jsonl | Format::Hocr | Format::Alto | Format::Markdown | Format::Html => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FindIn {
+    Lines,
+    Paragraphs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Units {
+    /// PDF points, 1/72 inch.
+    Pt,
+    Mm,
+    In,
+}
+
+impl Units {
+    /// How many of the unit make one point.
+    fn per_point(self) -> f32 {
+        match self {
+            Units::Pt => 1.0,
+            Units::Mm => 25.4 / 72.0,
+            Units::In => 1.0 / 72.0,
+        }
+    }
+}
+
+/// How numbers are written out: lengths in `units`, everything with `precision` decimals.
+#[derive(Debug, Clone, Copy)]
+struct NumberFormat {
+    units: Units,
+    precision: usize,
+}
+
+impl NumberFormat {
+    fn length(&self, points: f32) -> String {
+        self.number(points * self.units.per_point())
+    }
+
+    fn number(&self, value: f32) -> String {
+        format!("{:.*}", self.precision, value)
+    }
 
-        match operator.as_str() {
-            
-            // "BT": Begin Text Object. Resets the text matrix.
-            "BT" => {
-                current_x = 0.0;
-                current_y = 0.0;
+    fn rect(&self, rect: &Rect) -> String {
+        format!("[{} {} {} {}]", self.length(rect.x0), self.length(rect.y0), self.length(rect.x1), self.length(rect.y1))
+    }
+}
+
+/// Writes the records of `log` to stderr, warnings and errors prefixed so and the rest as they
+/// are, and nothing to stdout, which is left to the results. Those of other crates, such as
+/// lopdf, are only written from debug level on.
+struct StderrLogger {
+    level: log::LevelFilter,
+}
+
+impl StderrLogger {
+    /// Installs the logger at the level `-v`, `--quiet`, `--debug-ops` or `RUST_LOG` asks for.
+    fn install(cli: &Cli) {
+        let level = match (cli.quiet, cli.verbose) {
+            _ if cli.debug_ops => log::LevelFilter::Trace,
+            (true, _) => log::LevelFilter::Error,
+            (false, 0) => std::env::var("RUST_LOG").ok().and_then(|level| level.parse().ok()).unwrap_or(log::LevelFilter::Warn),
+            (false, 1) => log::LevelFilter::Info,
+            (false, 2) => log::LevelFilter::Debug,
+            (false, _) => log::LevelFilter::Trace,
+        };
+        if log::set_logger(Box::leak(Box::new(StderrLogger { level }))).is_ok() {
+            log::set_max_level(level);
+        }
+    }
+}
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let ours = metadata.target().split("::").next() == Some(env!("CARGO_CRATE_NAME"));
+        metadata.level() <= self.level && (ours || self.level >= log::LevelFilter::Debug)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let prefix = match record.level() {
+            log::Level::Error => "Error: ",
+            log::Level::Warn => "Warning: ",
+            log::Level::Info | log::Level::Debug | log::Level::Trace => "",
+        };
+        eprintln!("{}{}", prefix, record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            // Help and the version are written as asked for, not as errors.
+            if !err.use_stderr() {
+                return ExitCode::SUCCESS;
+            }
+            if let Some(format) = asked_format() {
+                // The error without the usage and tips clap follows it with, a paragraph on.
+                let message = err.to_string();
+                let message: Vec<&str> = message.lines().take_while(|line| !line.is_empty()).map(str::trim).collect();
+                let message = message.join(" ").trim_start_matches("error: ").to_string();
+                let report = ErrorReport { path: None, code: "invalid-arguments", message, page: None, status: INVALID_ARGUMENTS };
+                let mut out = io::stdout().lock();
+                let written = match format {
+                    Format::Json => write_json(&mut out, &ErrorDocument { errors: vec![report] }, std::env::args_os().any(|arg| arg == "--compact")),
+                    _ => write_record(&mut out, &Record::FileError(report)),
+                };
+                let _ = written.and_then(|()| out.flush());
+            }
+            return ExitCode::from(INVALID_ARGUMENTS);
+        }
+    };
+    StderrLogger::install(&cli);
+    match run(&cli) {
+        Ok(status) => status,
+        // A reader such as `head` closing the pipe early is not a failure.
+        Err(err) if is_broken_pipe(err.as_ref()) => ExitCode::SUCCESS,
+        Err(err) => {
+            log::error!("{}", err);
+            // The files that failed were told of, and written in JSON, as they were read.
+            if let Some(failed) = err.downcast_ref::<FilesFailed>() {
+                return ExitCode::from(failed.status);
+            }
+            if let Some(Error::PasswordRequired) = library_error(err.as_ref()) {
+                log::error!("give the password with --password or RAPID_DOC_PASSWORD");
+            }
+            let report = match err.downcast_ref::<FileError>() {
+                Some(FileError { path, err }) => ErrorReport::new(Some(path), err.as_ref()),
+                None => ErrorReport::new(None, err.as_ref()),
+            };
+            let status = report.status;
+            let mut out = io::stdout().lock();
+            let written = match cli.format {
+                Format::Json => write_json(&mut out, &ErrorDocument { errors: vec![report] }, cli.compact),
+                Format::Jsonl => write_record(&mut out, &Record::FileError(report)),
+                _ => Ok(()),
+            };
+            if let Err(err) = written.and_then(|()| out.flush()) {
+                log::error!("{}", err);
             }
+            ExitCode::from(status)
+        }
+    }
+}
 
-            // "Tf": Set Text Font and Size.
-            "Tf" => {
-                if operands.len() >= 2 {
-                    if let Ok(size) = operands[1].as_f32() {
-                        current_font_size = size;
-                    }
+/// `--format json` or `jsonl` among arguments that could not be parsed, for the error to be
+/// written as the output asked for would have been.
+fn asked_format() -> Option<Format> {
+    let args: Vec<String> = std::env::args_os().skip(1).map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let format = args.iter().enumerate().rev().find_map(|(index, arg)| match arg.strip_prefix("--format") {
+        Some("") => args.get(index + 1).map(String::as_str),
+        Some(value) => value.strip_prefix('='),
+        None => None,
+    })?;
+    match format {
+        "json" => Some(Format::Json),
+        "jsonl" => Some(Format::Jsonl),
+        _ => None,
+    }
+}
+
+/// The exit statuses of failures; see `EXIT_STATUSES`.
+const PARTIAL: u8 = 2;
+const INVALID_ARGUMENTS: u8 = 3;
+const UNREADABLE: u8 = 4;
+const ENCRYPTED: u8 = 5;
+const MALFORMED: u8 = 6;
+const IMAGE_ONLY: u8 = 7;
+
+/// The exit status an error makes, when it ends the run.
+fn exit_status(err: &(dyn std::error::Error + 'static)) -> u8 {
+    match library_error(err) {
+        Some(Error::InvalidOptions(_) | Error::PageOutOfRange { .. } | Error::UnknownPageLabel(_)) => INVALID_ARGUMENTS,
+        Some(Error::Io(_)) => UNREADABLE,
+        Some(Error::PasswordRequired | Error::WrongPassword | Error::Decryption(_)) => ENCRYPTED,
+        Some(Error::Pdf(_)) => MALFORMED,
+        Some(Error::Content { .. } | Error::Limit { .. }) => PARTIAL,
+        _ => 1,
+    }
+}
+
+/// Files of several that could not be read, each told of already, and the exit status they
+/// make.
+#[derive(Debug)]
+struct FilesFailed {
+    failed: usize,
+    total: usize,
+    status: u8,
+}
+
+impl fmt::Display for FilesFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} of {} files failed", self.failed, self.total)
+    }
+}
+
+impl std::error::Error for FilesFailed {}
+
+/// What could not be read, told once all that could be is written.
+#[derive(Debug, Default)]
+struct Failures {
+    /// Files that could not be read at all, and why.
+    files: Vec<(PathBuf, ErrorReport)>,
+    /// Content of the files read that could not be, by file.
+    pages: Vec<(PathBuf, PageError)>,
+}
+
+/// The library error an error is, or was caused by.
+fn library_error<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a Error> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<Error>() {
+            return Some(err);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// An error reading a file, which it names.
+#[derive(Debug)]
+struct FileError {
+    path: PathBuf,
+    err: Box<dyn std::error::Error>,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.err)
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.err.as_ref())
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    if cli.paths.len() > 1 && cli.paths.iter().any(|path| path.as_os_str() == STDIN_PATH) {
+        return Err(Error::InvalidOptions("- reads standard input, which cannot be mixed with other files".to_string()).into());
+    }
+    if cli.document_mode() && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        let mode = if cli.metadata {
+            "--metadata"
+        } else if cli.outline {
+            "--outline"
+        } else if cli.form_fields {
+            "--form-fields"
+        } else if cli.attachments {
+            "--attachments"
+        } else if cli.guess_title {
+            "--guess-title"
+        } else if cli.page_count {
+            "--page-count"
+        } else if cli.page_info {
+            "--page-info"
+        } else {
+            "--destinations"
+        };
+        return Err(Error::InvalidOptions(format!("{} is written as text, json or jsonl", mode)).into());
+    }
+    if cli.find.is_some() && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        return Err(Error::InvalidOptions("--find is written as text, json or jsonl".to_string()).into());
+    }
+    if cli.headings && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        return Err(Error::InvalidOptions("--headings is written as text, json or jsonl".to_string()).into());
+    }
+    if cli.stats && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        return Err(Error::InvalidOptions("--stats is written as text, json or jsonl".to_string()).into());
+    }
+    if cli.dump_ops && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        return Err(Error::InvalidOptions("--dump-ops is written as text, json or jsonl".to_string()).into());
+    }
+    if cli.tuning_report && !matches!(cli.format, Format::Text | Format::Json | Format::Jsonl) {
+        return Err(Error::InvalidOptions("--tuning-report is written as text, json or jsonl".to_string()).into());
+    }
+    if cli.min_quality.is_some_and(|min| !(0.0..=1.0).contains(&min)) {
+        return Err(Error::InvalidOptions("--min-quality is a score from 0 to 1".to_string()).into());
+    }
+    if cli.diff && !matches!(cli.format, Format::Text | Format::Json) {
+        return Err(Error::InvalidOptions("--diff is written as text or json".to_string()).into());
+    }
+    cli.pattern()?;
+    let options = cli.extract_options()?;
+    if cli.diff {
+        return diff_files(cli, &options);
+    }
+    let (files, failed_dirs) = input_files(&cli.paths, cli.recursive);
+    let total = files.len() + failed_dirs.len();
+    let mut failures = Failures { files: failed_dirs, pages: Vec::new() };
+    // A directory stands for any number of files, so its output is segmented even for one.
+    let batch = total > 1 || cli.paths.iter().any(|path| path.is_dir());
+    let mut matches = 0;
+    let mut image_only = 0;
+    let mut add = |summary: Summary| {
+        matches += summary.matches.unwrap_or_default();
+        image_only += summary.image_only_pages;
+    };
+    match &cli.output {
+        // The pages are written into the directory, and nothing to stdout.
+        Some(_) if cli.split_pages => add(write_results(cli, &options, &files, batch, &mut io::sink(), &mut failures)?),
+        // Each file read gets an output file of its own, complete in itself.
+        Some(dir) if batch => {
+            let mut targets = HashSet::new();
+            for path in &files {
+                let mut name = path.file_stem().unwrap_or(path.as_os_str()).to_os_string();
+                name.push(".");
+                name.push(cli.format.extension());
+                let target = dir.join(name);
+                let result = if targets.insert(target.clone()) {
+                    write_output(cli, &options, std::slice::from_ref(path), false, &target, &mut failures)
+                } else {
+                    Err(format!("{} is already the output of another file", target.display()).into())
+                };
+                match result {
+                    Ok(summary) => add(summary),
+                    Err(err) => failures.files.push((path.clone(), ErrorReport::new(None, err.as_ref()))),
                 }
             }
+        }
+        Some(target) => add(write_output(cli, &options, &files, batch, target, &mut failures)?),
+        None if batch && matches!(cli.format, Format::Hocr | Format::Alto | Format::Html) => {
+            let name = format!("{:?}", cli.format).to_lowercase();
+            return Err(Error::InvalidOptions(format!("--format {} writes a single document; give --output a directory to read several files", name)).into());
+        }
+        None => {
+            add(write_results(cli, &options, &files, batch, &mut io::BufWriter::new(io::stdout().lock()), &mut failures)?);
+        }
+    }
+    if !failures.pages.is_empty() {
+        let pages: Vec<String> = failures
+            .pages
+            .iter()
+            .map(|(path, err)| if batch { format!("{} page {}", path.display(), err.page) } else { format!("page {}", err.page) })
+            .collect();
+        log::warn!("{} could not be read in full, the rest was written: {}",
+            if pages.len() == 1 { "1 page".to_string() } else { format!("{} pages", pages.len()) }, pages.join(", "));
+    }
+    for (path, err) in &failures.files {
+        log::error!("{}: {}", path.display(), err.message);
+    }
+    if let Some((_, first)) = failures.files.first() {
+        let status = if failures.files.len() < total {
+            PARTIAL
+        } else if failures.files.iter().all(|(_, err)| err.status == first.status) {
+            first.status
+        } else {
+            1
+        };
+        Err(FilesFailed { failed: failures.files.len(), total, status }.into())
+    } else if !failures.pages.is_empty() {
+        Ok(ExitCode::from(PARTIAL))
+    } else if cli.fail_if_image_only && image_only > 0 {
+        Ok(ExitCode::from(IMAGE_ONLY))
+    } else if cli.find.is_some() {
+        Ok(ExitCode::from(if matches == 0 { 1 } else { 0 }))
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
 
-            // "Td": Move Text Position.
-            "Td" | "TD" => {
-                if operands.len() >= 2 {
-                    if let (Ok(tx), Ok(ty)) = (operands[0].as_f32(), operands[1].as_f32()) {
-                        current_x += tx;
-                        current_y += ty;
-                    }
+/// Extracts `files` into one output, returning the totals. With `batch`, a file that fails is
+/// added to `failures` and the rest are still read; otherwise its error is returned. Pages
+/// that could not be read in full are added to `failures` either way.
+fn write_results(
+    cli: &Cli,
+    options: &ExtractOptions,
+    files: &[PathBuf],
+    batch: bool,
+    out: &mut impl Write,
+    failures: &mut Failures,
+) -> Result<Summary, Box<dyn std::error::Error>> {
+    if let Some(separator) = cli.format.separator() {
+        write_rows(out, &[report::delimited(&report::ITEM_COLUMNS, separator)])?;
+    }
+    let mut summary = Summary { matches: cli.find.as_ref().map(|_| 0), ..Summary::default() };
+    let mut reports = Vec::new();
+    let mut records = Vec::new();
+    // With `--format json`, the reports of the files read and the errors of those that could
+    // not be, in order.
+    let mut file_reports = Vec::new();
+    for path in files {
+        match extract_file(cli, options, path, batch, out, &mut summary, &mut reports, &mut records, &mut failures.pages) {
+            Ok(()) => summary.files += 1,
+            Err(err) if is_broken_pipe(err.as_ref()) => return Err(err),
+            Err(err) if !batch => return Err(FileError { path: path.clone(), err }.into()),
+            Err(err) => {
+                let error = ErrorReport::new(None, err.as_ref());
+                let file_error = ErrorReport { path: Some(path.display().to_string()), ..error.clone() };
+                match cli.format {
+                    Format::Json if json_records(cli) => records.push(Record::FileError(file_error)),
+                    Format::Json => file_reports.push(FileReport::Failed { path: path.display().to_string(), errors: vec![error.clone()] }),
+                    Format::Jsonl => write_record(out, &Record::FileError(file_error))?,
+                    _ => {}
                 }
+                failures.files.push((path.clone(), error));
+                summary.failed += 1;
             }
+        }
+        file_reports.extend(reports.drain(..).map(|report| FileReport::Read(Box::new(report))));
+    }
+    match cli.format {
+        Format::Json if json_records(cli) => write_json(out, &records, cli.compact)?,
+        Format::Json if batch => write_json(out, &file_reports, cli.compact)?,
+        Format::Json => {
+            if let Some(FileReport::Read(report)) = file_reports.first() {
+                write_json(out, report, cli.compact)?;
+            }
+        }
+        Format::Jsonl if cli.summary => write_record(out, &Record::Summary(summary.clone()))?,
+        _ => {}
+    }
+    out.flush()?;
+    Ok(summary)
+}
 
-            // "Tm": Set Text Matrix (absolute positioning).
-            "Tm" => {
-                if operands.len() >= 6 {
-                    if let (Ok(e), Ok(f)) = (operands[4].as_f32(), operands[5].as_f32()) {
-                        current_x = e;
-                        current_y = f;
-                    }
+/// Whether `--format json` writes a list of records rather than of the reports of files.
+fn json_records(cli: &Cli) -> bool {
+    cli.find.is_some() || cli.headings || cli.stats || cli.dump_ops || cli.tuning_report || cli.page_tree_mode()
+}
+
+/// Extracts `files` into the file at `target`, as `write_results` does, returning the totals.
+/// A file that could not be read leaves no output behind.
+fn write_output(
+    cli: &Cli,
+    options: &ExtractOptions,
+    files: &[PathBuf],
+    batch: bool,
+    target: &Path,
+    failures: &mut Failures,
+) -> Result<Summary, Box<dyn std::error::Error>> {
+    let mut out = create_output(target, cli.force)?;
+    write_results(cli, options, files, batch, &mut out, failures).inspect_err(|_| {
+        let _ = std::fs::remove_file(target);
+    })
+}
+
+/// Opens a file to write results to, creating its directory. A file already there is only
+/// replaced with `force`.
+fn create_output(path: &Path, force: bool) -> io::Result<io::BufWriter<File>> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = if force { File::create(path) } else { File::create_new(path) };
+    match file {
+        Ok(file) => Ok(io::BufWriter::new(file)),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            Err(io::Error::new(err.kind(), format!("{} exists; give --force to overwrite it", path.display())))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// The path that stands for standard input.
+const STDIN_PATH: &str = "-";
+
+fn is_broken_pipe(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.downcast_ref::<io::Error>().is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// The files to read for the paths given: files as they are, and the `.pdf` files of
+/// directories, in name order, with those of subdirectories when `recursive`. Directories
+/// that cannot be listed are returned with the error.
+fn input_files(paths: &[PathBuf], recursive: bool) -> (Vec<PathBuf>, Vec<(PathBuf, ErrorReport)>) {
+    let mut files = Vec::new();
+    let mut failures = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_pdf_files(path, recursive, &mut files, &mut failures);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    (files, failures)
+}
+
+fn collect_pdf_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>, failures: &mut Vec<(PathBuf, ErrorReport)>) {
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(err) => {
+            failures.push((dir.to_path_buf(), ErrorReport::new(None, &Error::Io(err))));
+            return;
+        }
+    };
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            if recursive {
+                collect_pdf_files(&entry, recursive, files, failures);
+            }
+        } else if entry.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pdf")) {
+            files.push(entry);
+        }
+    }
+}
+
+/// Extracts one file, writing what it can as it goes and adding its report, for `--format
+/// json`, to `reports`, or its matches of `--find` or its headings to `records`, and content
+/// it could not read to `page_errors`; with `batch`, the output names the file.
+#[allow(clippy::too_many_arguments)]
+fn extract_file(
+    cli: &Cli,
+    options: &ExtractOptions,
+    path: &Path,
+    batch: bool,
+    out: &mut impl Write,
+    summary: &mut Summary,
+    reports: &mut Vec<DocumentReport>,
+    records: &mut Vec<Record>,
+    page_errors: &mut Vec<(PathBuf, PageError)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    log::info!("Reading {}", path.display());
+    let format = NumberFormat { units: cli.units, precision: cli.precision };
+    let stdin_data = if path.as_os_str() == STDIN_PATH {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data).map_err(Error::Io)?;
+        Some(data)
+    } else {
+        None
+    };
+    if cli.page_tree_mode() {
+        let tree = match &stdin_data {
+            Some(data) => PageTree::from_bytes(data, options)?,
+            None => PageTree::from_path(path, options)?,
+        };
+        return write_page_tree(cli, out, &path.display().to_string(), batch, tree, records);
+    }
+    let progress_options = cli.progress.then(|| with_progress(options, path));
+    let options = progress_options.as_ref().unwrap_or(options);
+    let extractor = match &stdin_data {
+        Some(data) => Extractor::from_bytes(data, options)?,
+        None => Extractor::open(path, options)?,
+    };
+    let file_name = path.display().to_string();
+    if cli.dump_ops {
+        return write_operations(cli, out, &file_name, batch, &extractor, records);
+    }
+    let mut pages = Vec::new();
+    let mut report = DocumentReport::new(&extractor, path, format.units, options.origin);
+    if options.min_font_size.is_some() || options.max_font_size.is_some() {
+        report.font_sizes = Some(FontSizes { min: options.min_font_size, max: options.max_font_size });
+    }
+    if let Some(dir) = &cli.extract_attachments {
+        let prefix = path.file_stem().filter(|_| batch).map(|stem| format!("{}-", stem.to_string_lossy()));
+        for attachment in report.attachments.iter().filter(|attachment| attachment.error.is_none()) {
+            let target = dir.join(prefix.clone().unwrap_or_default() + &attachment.file_name);
+            let mut file = create_output(&target, cli.force)?;
+            file.write_all(&attachment.data)?;
+            file.flush()?;
+        }
+    }
+    if cli.metadata || cli.guess_title {
+        report.guessed_title = extractor.guess_title()?.map(|guess| report::scaled_title(guess, format.units));
+    }
+    // The modes that write something else than the text, without reading the pages.
+    if cli.document_mode() {
+        match cli.format {
+            Format::Json => reports.push(report),
+            Format::Jsonl => {
+                let path = file_name;
+                let record = if cli.metadata {
+                    Record::Metadata { path, metadata: Box::new(report.metadata), guessed_title: report.guessed_title }
+                } else if cli.guess_title {
+                    Record::Title { path, guessed_title: report.guessed_title }
+                } else if cli.outline {
+                    Record::Outline { path, outline: report.outline }
+                } else if cli.form_fields {
+                    Record::FormFields { path, form_fields: report.form_fields }
+                } else if cli.attachments {
+                    Record::Attachments { path, attachments: report.attachments }
+                } else {
+                    Record::Destinations { path, destinations: report.destinations }
+                };
+                write_record(out, &record)?;
+            }
+            _ => {
+                if batch {
+                    writeln!(out, "== {} ==", file_name)?;
+                }
+                if cli.metadata {
+                    write_metadata(out, &report.metadata)?;
+                    write_guessed_title(out, report.guessed_title.as_ref())?;
+                } else if cli.guess_title {
+                    write_guessed_title(out, report.guessed_title.as_ref())?;
+                } else if cli.outline {
+                    write_outline(out, &report.outline)?;
+                } else if cli.form_fields {
+                    write_form_fields(out, &report.form_fields, &format)?;
+                } else if cli.attachments {
+                    write_attachments(out, &report.attachments)?;
+                } else {
+                    write_destinations(out, &report.destinations, &format)?;
                 }
             }
+        }
+        return Ok(());
+    }
 
-            // "Tj": Show Text.
-            "Tj" => {
-                if let Some(text_obj) = operands.first() {
-                    let text = extract_text_from_object(text_obj);
-                    println!("Extracted text (Tj): {}", text);
-                    extracted_larget_set.push(TextItem {
-                        text: text.clone(),
-                        x: current_x,
-                        y: current_y,
-                        font_size: current_font_size,
-                        page_num
-                    });
-                    for(i, ch) in text.chars().enumerate(){
-                        let char_x = current_x + (i as f32 * current_font_size * 0.6); // Approximate width (adjust factor as needed)
-                        extracted_items.push(TextItem {
-                            text: ch.to_string(),
-                            x: char_x,
-                            y: current_y,
-                            font_size: current_font_size,
-                            page_num,
-                        });
-                        println!("  Char '{}' at ({:.2}, {:.2})", ch, char_x, current_y);
+    // The file each image was written to, by object, or `None` for those that could not be.
+    let mut image_files = HashMap::new();
+    let mut manifest = Vec::new();
+    // With `--split-pages`, the directory the pages are written into, the prefix of their
+    // names, and the manifest of those written.
+    let split = cli.output.as_deref().filter(|_| cli.split_pages);
+    let split_prefix = path.file_stem().filter(|_| batch).map(|stem| format!("{}-", stem.to_string_lossy())).unwrap_or_default();
+    let split_digits = extractor.page_count().to_string().len().max(SPLIT_DIGITS);
+    let mut page_files = Vec::new();
+    // With `--stats`, each page is laid out alone, as soon as it is read, on this thread.
+    let mut stats = cli.stats.then(DocumentStats::default);
+    let mut page_options = options.clone();
+    page_options.threads = Some(1);
+    let mut tuning = cli.tuning_report.then(|| TuningReport::new(options.word_gap, options.line_tolerance, options.paragraph_gap));
+    if stats.is_some() && cli.format == Format::Text {
+        if batch {
+            writeln!(out, "== {} ==", file_name)?;
+        }
+        writeln!(out, "{}", stats::HEADER)?;
+    }
+    for page in extractor.page_items() {
+        let page = page?;
+        if !page.errors.is_empty() {
+            summary.errors += 1;
+        }
+        report.errors.extend(page.errors.iter().cloned());
+        report.truncated |= page.truncated;
+        page_errors.extend(page.errors.iter().map(|err| (path.to_path_buf(), err.clone())));
+        report.page_kinds.add(page.kind);
+        if page.kind == PageKind::ImageOnly {
+            log::info!("{}: page {} is image-only", file_name, page.page_num);
+            summary.image_only_pages += 1;
+        }
+        if let Some(min) = cli.min_quality.filter(|&min| page.quality.quality < min) {
+            log::warn!("{}: page {} left out, its quality {:.3} is below {}", file_name, page.page_num, page.quality.quality, min);
+            continue;
+        }
+        if let Some(dir) = &cli.tables {
+            let tables = layout::tables::detect(&page.rules, &page.items, options.origin, options.line_tolerance, options.word_gap, options.max_skew);
+            std::fs::create_dir_all(dir)?;
+            for (index, table) in tables.iter().enumerate() {
+                let name = format!("page-{}-table-{}.csv", page.page_num, index + 1);
+                let prefix = path.file_stem().filter(|_| batch).map(|stem| format!("{}-", stem.to_string_lossy()));
+                let path = dir.join(prefix.unwrap_or_default() + &name);
+                std::fs::write(&path, report::table_csv(table))?;
+                let points = NumberFormat { units: Units::Pt, precision: 2 };
+                log::debug!("  Table: {}x{} cells box {} written to {}",
+                    table.rows.len(), table.rows.first().map_or(0, Vec::len), points.rect(&table.bbox), path.display());
+            }
+        }
+
+        if let Some(dir) = &cli.extract_images {
+            manifest.extend(write_images(&extractor, &page, dir, &mut image_files)?);
+        }
+
+        summary.pages += 1;
+        summary.items += page.items.len();
+        if let Some(stats) = &mut stats {
+            let page_stats = rapid_pdf::layout_pages(vec![page], &page_options).first().map(|page| PageStats::new(page, options.origin, options.word_gap));
+            let Some(page_stats) = page_stats else { continue };
+            match cli.format {
+                Format::Json => {}
+                Format::Jsonl => write_record(out, &Record::PageStats { path: file_name.clone(), stats: page_stats.clone() })?,
+                _ => writeln!(out, "{}", page_stats.counts.row(&page_stats.page.to_string()))?,
+            }
+            stats.add(page_stats, cli.format == Format::Json);
+            continue;
+        }
+        if let Some(tuning) = &mut tuning {
+            let items = page.items.clone();
+            if let Some(page) = rapid_pdf::layout_pages(vec![page], &page_options).first() {
+                tuning.add(&items, page, options.origin);
+            }
+            continue;
+        }
+        if cli.find.is_some() || cli.headings {
+            pages.push(page);
+            continue;
+        }
+        if let Some(dir) = split {
+            let name = format!("{}page-{:0width$}.{}", split_prefix, page.page_num, cli.format.extension(), width = split_digits);
+            page_files.push(PageFile { page: page.page_num, label: page.label.clone(), file: name, bytes: 0, errors: page.errors.clone() });
+            // Pages written as they were read are written now; the others once laid out.
+            let mut data = Vec::new();
+            match cli.format {
+                Format::Json => write_json(&mut data, &PageReport::new(page, format.units, options), cli.compact)?,
+                Format::Jsonl => {
+                    let errors = page.errors.clone();
+                    write_record(&mut data, &Record::Page { path: file_name.clone(), page: PageReport::new(page, format.units, options) })?;
+                    for error in errors {
+                        write_record(&mut data, &Record::Error { path: file_name.clone(), error })?;
                     }
                 }
+                Format::Csv | Format::Tsv => {
+                    let separator = cli.format.separator().unwrap_or(',');
+                    let mut rows = vec![report::delimited(&report::ITEM_COLUMNS, separator)];
+                    rows.extend(page.items.iter().map(|item| report::delimited(&report::item_fields(&file_name, page.page_num, item, &format), separator)));
+                    write_rows(&mut data, &rows)?;
+                }
+                Format::Text | Format::Markdown | Format::Hocr | Format::Alto | Format::Html => {
+                    pages.push(page);
+                    continue;
+                }
+            }
+            write_page_file(dir, page_files.last_mut(), &data, cli.force)?;
+            continue;
+        }
+        match cli.format {
+            Format::Text | Format::Csv | Format::Tsv | Format::Hocr | Format::Alto | Format::Markdown | Format::Html => {}
+            Format::Json => {
+                report.pages.push(PageReport::new(page, format.units, options));
+                continue;
+            }
+            // Each page is written as soon as it is read; nothing is kept for later pages.
+            Format::Jsonl => {
+                let errors = page.errors.clone();
+                let page = PageReport::new(page, format.units, options);
+                write_record(out, &Record::Page { path: file_name.clone(), page })?;
+                for error in errors {
+                    write_record(out, &Record::Error { path: file_name.clone(), error })?;
+                }
+                continue;
             }
+        }
+        if let Some(separator) = cli.format.separator() {
+            let rows: Vec<String> = page
+                .items
+                .iter()
+                .map(|item| report::delimited(&report::item_fields(&file_name, page.page_num, item, &format), separator))
+                .collect();
+            write_rows(out, &rows)?;
+            continue;
+        }
+        pages.push(page);
+    }
+    let kinds = report.page_kinds;
+    if kinds.image_only > 0 {
+        let verb = if kinds.image_only == 1 { "is" } else { "are" };
+        log::warn!("{}: {} of {} pages {} image-only, scans without a text layer", file_name, kinds.image_only, kinds.total(), verb);
+    }
 
-            // // "TJ": Show Text with Adjustments (kerning).
-            // "TJ" => {
-            //     // TJ is complex because it mixes strings and numbers (spacing).
-            //     if let Some(Object::Array(arr)) = operands.first() {
-            //         let mut combined_text = String::new();
-            //         for item in arr {
-            //             if let Object::String(bytes, _) = item {
+    if let Some(dir) = &cli.extract_images {
+        let prefix = path.file_stem().filter(|_| batch).map(|stem| format!("{}-", stem.to_string_lossy()));
+        let images = manifest.into_iter().map(|image| image.scaled(format.units)).collect();
+        let manifest = ImageManifest { path: file_name.clone(), units: format.units, origin: options.origin, images };
+        let mut file = create_output(&dir.join(prefix.unwrap_or_default() + "images.json"), cli.force)?;
+        write_json(&mut file, &manifest, cli.compact)?;
+        file.flush()?;
+    }
 
-            //                 combined_text.push_str(&String::from_utf8_lossy(bytes));
+    if let Some(stats) = stats {
+        match cli.format {
+            Format::Json => records.push(Record::Stats { path: file_name, stats }),
+            Format::Jsonl => write_record(out, &Record::Stats { path: file_name, stats })?,
+            _ => write!(out, "{}", stats.footer())?,
+        }
+        return Ok(());
+    }
+    if let Some(tuning) = tuning {
+        match cli.format {
+            Format::Json => records.push(Record::Tuning { path: file_name, tuning }),
+            Format::Jsonl => write_record(out, &Record::Tuning { path: file_name, tuning })?,
+            _ => {
+                if batch {
+                    writeln!(out, "== {} ==", file_name)?;
+                }
+                write!(out, "{}", tuning.text())?;
+            }
+        }
+        return Ok(());
+    }
+    let page_texts: Vec<PageText> = rapid_pdf::layout_pages(pages, options);
+    if let Some(dir) = split {
+        for page in &page_texts {
+            let page_file = page_files.iter_mut().find(|page_file| page_file.page == page.page_num);
+            let pages = std::slice::from_ref(page);
+            let mut data = Vec::new();
+            match cli.format {
+                Format::Text => write_text(&mut data, pages, &cli.page_delimiter, cli.annotations, cli.rects.len() > 1)?,
+                Format::Markdown => data.extend(markdown::document(pages, cli.page_breaks, options.origin).into_bytes()),
+                Format::Hocr => data.extend(hocr::document(pages, path, options.origin, cli.word_gap).into_bytes()),
+                Format::Alto => data.extend(alto::document(pages, path, format.units, options.origin, cli.word_gap).into_bytes()),
+                Format::Html => data.extend(html::document(pages, path, options.origin).into_bytes()),
+                Format::Json | Format::Jsonl | Format::Csv | Format::Tsv => continue,
+            }
+            write_page_file(dir, page_file, &data, cli.force)?;
+        }
+        let manifest = PageManifest { path: file_name, pages: page_files };
+        let mut file = create_output(&dir.join(split_prefix + "manifest.json"), cli.force)?;
+        write_json(&mut file, &manifest, cli.compact)?;
+        file.flush()?;
+        return Ok(());
+    }
+    if let Some(pattern) = cli.pattern()? {
+        let found = search::find(&page_texts, &pattern, cli.find_in == FindIn::Paragraphs, options.origin, options.word_gap);
+        summary.matches = Some(summary.matches.unwrap_or_default() + found.len());
+        let color = match cli.color {
+            Color::Auto => cli.output.is_none() && io::stdout().is_terminal(),
+            Color::Always => true,
+            Color::Never => false,
+        };
+        // As grep colors them.
+        let highlight = if color { ("\x1b[01;31m", "\x1b[0m") } else { ("", "") };
+        if let Some(target) = &cli.annotate_output {
+            let highlights: Vec<Highlight> = found
+                .iter()
+                .map(|found| Highlight::new(found.page, found.quads.clone(), cli.highlight_color, Some(found.text[found.start..found.end].to_string())))
+                .collect();
+            let target = match path.file_name() {
+                Some(name) if batch => target.join(name),
+                _ => target.clone(),
+            };
+            write_highlighted(path, stdin_data, &highlights, options.origin, &target, cli.force)?;
+        }
+        for found in found {
+            match cli.format {
+                Format::Json => records.push(Record::Match { path: file_name.clone(), found: found.scaled(format.units) }),
+                Format::Jsonl => write_record(out, &Record::Match { path: file_name.clone(), found: found.scaled(format.units) })?,
+                _ => {
+                    let boxes: Vec<String> = found.boxes.iter().map(|rect| format.rect(rect)).collect();
+                    writeln!(out, "{}", search::line(&found, batch.then_some(file_name.as_str()), &boxes, highlight))?;
+                }
+            }
+        }
+        return Ok(());
+    }
+    if cli.headings {
+        if batch && cli.format == Format::Text {
+            writeln!(out, "== {} ==", file_name)?;
+        }
+        for mut heading in layout::headings::detect(&page_texts, options.origin) {
+            heading.y = report::finite(heading.y * format.units.per_point());
+            match cli.format {
+                Format::Json => records.push(Record::Heading { path: file_name.clone(), heading }),
+                Format::Jsonl => write_record(out, &Record::Heading { path: file_name.clone(), heading })?,
+                _ => {
+                    let text: String = heading.text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+                    writeln!(out, "{}{} (page {})", "  ".repeat(heading.level - 1), text, heading.page)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+    match cli.format {
+        Format::Text => {
+            if batch {
+                writeln!(out, "== {} ==", file_name)?;
+            }
+            write_text(out, &page_texts, &cli.page_delimiter, cli.annotations, cli.rects.len() > 1)?;
+            if report.truncated {
+                writeln!(out, "\n{}", TRUNCATED)?;
+            }
+        }
+        Format::Markdown => {
+            let banner = if batch { format!("<!-- {} -->\n\n", file_name.replace("--", "- -")) } else { String::new() };
+            write!(out, "{}{}", banner, markdown::document(&page_texts, cli.page_breaks, options.origin))?;
+            if report.truncated {
+                writeln!(out, "\n<!-- {} -->", TRUNCATED)?;
+            }
+        }
+        Format::Hocr => out.write_all(hocr::document(&page_texts, path, options.origin, cli.word_gap).as_bytes())?,
+        Format::Alto => out.write_all(alto::document(&page_texts, path, format.units, options.origin, cli.word_gap).as_bytes())?,
+        Format::Html => out.write_all(html::document(&page_texts, path, options.origin).as_bytes())?,
+        Format::Json => reports.push(report),
+        Format::Jsonl | Format::Csv | Format::Tsv => {}
+    }
+    Ok(())
+}
+
+/// What ends text cut short by `--max-chars` or `--max-items`.
+const TRUNCATED: &str = "[truncated]";
+
+/// The fewest digits of the page numbers in the names of `--split-pages`, which documents of
+/// more pages have as many of as their last page has.
+const SPLIT_DIGITS: usize = 4;
 
-            //                 // combined_text.push_str(&String::from_utf8(bytes.clone()).unwrap_or_default());
-            //             }
-            //         }
-            //         println!("Extracted Combined text (TJ): {}", combined_text);
-                    
-            //         extracted_items.push(TextItem {
-            //             text: combined_text,
-            //             x: current_x,
-            //             y: current_y,
-            //             font_size: current_font_size,
-            //             page_num,
-            //         });
-            //     }
-            // }
+/// Writes the file of a page of `--split-pages` into `dir`, as named in its entry of the
+/// manifest, which is given the size.
+fn write_page_file(dir: &Path, page_file: Option<&mut PageFile>, data: &[u8], force: bool) -> io::Result<()> {
+    let Some(page_file) = page_file else { return Ok(()) };
+    let mut file = create_output(&dir.join(&page_file.file), force)?;
+    file.write_all(data)?;
+    file.flush()?;
+    page_file.bytes = data.len();
+    Ok(())
+}
 
-            _ => {} 
+/// Compares the text of the two PDFs of `--diff`, writing the differences to `--output` or
+/// stdout, and tells by the exit status whether there are any.
+fn diff_files(cli: &Cli, options: &ExtractOptions) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let [a, b] = &cli.paths[..] else {
+        return Err(Error::InvalidOptions("--diff compares two PDFs: give the paths of both".to_string()).into());
+    };
+    if let Some(dir) = [a, b].into_iter().find(|path| path.is_dir()) {
+        return Err(Error::InvalidOptions(format!("--diff compares two PDFs, and {} is a directory", dir.display())).into());
+    }
+    let text = |path: &PathBuf| {
+        let progress_options = cli.progress.then(|| with_progress(options, path));
+        rapid_pdf::extract_path(path, progress_options.as_ref().unwrap_or(options)).map_err(|err| FileError { path: path.clone(), err: err.into() })
+    };
+    let (a_text, b_text) = (text(a)?, text(b)?);
+    let format = NumberFormat { units: cli.units, precision: cli.precision };
+    let changes = diff::compare(&a_text.pages, &b_text.pages, options.origin, options.word_gap);
+    let status = ExitCode::from(if changes.is_empty() { 0 } else { 1 });
+    let mut out: Box<dyn Write> = match &cli.output {
+        Some(target) => Box::new(create_output(target, cli.force)?),
+        None => Box::new(io::BufWriter::new(io::stdout().lock())),
+    };
+    match cli.format {
+        Format::Json => {
+            let changes = changes.into_iter().map(|change| change.scaled(format.units)).collect();
+            let report = diff::DiffReport::new(a.display().to_string(), b.display().to_string(), format.units, options.origin, changes);
+            write_json(&mut out, &report, cli.compact)?;
+        }
+        _ if changes.is_empty() => {}
+        _ => {
+            writeln!(out, "--- {}", a.display())?;
+            writeln!(out, "+++ {}", b.display())?;
+            for change in &changes {
+                writeln!(out, "{}", change.unified(|rect| format.rect(rect)))?;
+            }
         }
     }
-    extracted_items.sort_by(|a, b| {
-        b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal)
-           .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
-    });
+    out.flush()?;
+    Ok(status)
+}
 
-    extracted_items
+/// How often `--progress` redraws its bar on a terminal, and writes a line otherwise.
+const PROGRESS_REDRAW: Duration = Duration::from_millis(100);
+const PROGRESS_LINES: Duration = Duration::from_secs(5);
+
+/// The options with `on_page` showing `--progress` of reading the file at `path` on stderr.
+/// Pages read on several threads report out of order, so only progress past what is shown
+/// already is shown.
+fn with_progress(options: &ExtractOptions, path: &Path) -> ExtractOptions {
+    let name = path.display().to_string();
+    let terminal = io::stderr().is_terminal();
+    let interval = if terminal { PROGRESS_REDRAW } else { PROGRESS_LINES };
+    // The pages shown as read, and when.
+    let shown: Mutex<(usize, Option<Duration>)> = Mutex::new((0, None));
+    let mut options = options.clone();
+    options.on_page = Some(OnPage::new(move |progress: PageProgress| {
+        let mut shown = shown.lock().unwrap_or_else(PoisonError::into_inner);
+        let finished = progress.done == progress.total;
+        let due = shown.1.is_none_or(|time| progress.elapsed >= time + interval);
+        if progress.done <= shown.0 || !(finished || due) {
+            return;
+        }
+        *shown = (progress.done, Some(progress.elapsed));
+        let percent = progress.done * 100 / progress.total.max(1);
+        let left = progress.remaining().map_or("-".to_string(), short_duration);
+        if terminal {
+            const WIDTH: usize = 30;
+            let filled = WIDTH * progress.done / progress.total.max(1);
+            eprint!("\r\x1b[K{} [{}{}] {}/{} pages {:>3}% ETA {} page {}",
+                name, "#".repeat(filled), "-".repeat(WIDTH - filled), progress.done, progress.total, percent, left, progress.page_num);
+            if finished {
+                eprintln!();
+            }
+        } else {
+            eprintln!("Progress: {}: {} of {} pages ({}%), page {}, {} left", name, progress.done, progress.total, percent, progress.page_num, left);
+        }
+    }));
+    options
 }
-fn extract_text_from_object(obj: &Object) -> String {
-    match obj {
-        Object::String(bytes, _) => {
 
-            // String::from_utf8_lossy(bytes).to_string()
-            String::from_utf8(bytes.clone()).unwrap_or_default()
-        },
-        _ => String::new(),
+/// A duration to the second, such as `45s`, `2m13s` or `1h02m`.
+fn short_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
     }
 }
 
+/// Writes the page count of `--page-count` or the pages of `--page-info`, adding them to
+/// `records` for `--format json`; with `batch`, the output names the file.
+fn write_page_tree(cli: &Cli, out: &mut impl Write, path: &str, batch: bool, tree: PageTree, records: &mut Vec<Record>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.to_string();
+    let record = if cli.page_count {
+        Record::PageCount { path, page_count: tree.page_count }
+    } else {
+        let pages = tree.pages.into_iter().map(|page| report::scaled_page(page, cli.units)).collect();
+        Record::PageInfo { path, page_count: tree.page_count, units: cli.units, origin: cli.origin, pages }
+    };
+    match (cli.format, &record) {
+        (Format::Json, _) => records.push(record),
+        (Format::Jsonl, _) => write_record(out, &record)?,
+        (_, Record::PageCount { path, page_count }) if batch => writeln!(out, "{}: {}", path, page_count)?,
+        (_, Record::PageCount { page_count, .. }) => writeln!(out, "{}", page_count)?,
+        (_, Record::PageInfo { path, pages, .. }) => {
+            if batch {
+                writeln!(out, "== {} ==", path)?;
+            }
+            let format = NumberFormat { units: cli.units, precision: cli.precision };
+            writeln!(out, "{:>6} {:>8} {:>9} {:>9} {:>8} {:>11} {:>7}", "page", "label", "width", "height", "rotation", "orientation", "content")?;
+            for page in pages {
+                let label: String = page.label.as_deref().unwrap_or("-").chars().map(|c| if c.is_control() || c == ' ' { '_' } else { c }).collect();
+                let orientation = if page.width > page.height { "landscape" } else { "portrait" };
+                writeln!(out, "{:>6} {:>8} {:>9} {:>9} {:>8} {:>11} {:>7}", page.page_num, label, format.number(page.width), format.number(page.height),
+                    page.rotation, orientation, if page.has_content { "yes" } else { "no" })?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
 
-fn replace_text_at_position(
-    doc: &mut Document,
-    page_num: u32,
-    target_text: &str,
-    replacement: &str,
-    target_x: f32,
-    target_y: f32,
-    target_font_size: f32,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pages = doc.get_pages();
-    println!("Replacing text {}", target_text);
-    if let Some(&object_id) = pages.get(&page_num) {
-        let content_data = doc.get_page_content(object_id)?;
-        let mut content = lopdf::content::Content::decode(&content_data)?;
-        
-        let mut current_font_size: f32 = 0.0;
-        let mut current_x: f32 = 0.0;
-        let mut current_y: f32 = 0.0;
-        
-        for operation in &mut content.operations {
-            let operator = &operation.operator;
-            let operands = &operation.operands;
-            
-            match operator.as_str() {
-                "BT" => {
-                    current_x = 0.0;
-                    current_y = 0.0;
-                }
-                "Tf" => {
-                    if operands.len() >= 2 {
-                        if let Ok(size) = operands[1].as_f32() {
-                            current_font_size = size;
-                        }
+/// Writes the operations of the selected pages for `--dump-ops`, or adds them to `records` for
+/// `--format json`.
+fn write_operations(cli: &Cli, out: &mut impl Write, path: &str, batch: bool, extractor: &Extractor, records: &mut Vec<Record>) -> Result<(), Box<dyn std::error::Error>> {
+    let format = NumberFormat { units: cli.units, precision: cli.precision };
+    if batch && cli.format == Format::Text {
+        writeln!(out, "== {} ==", path)?;
+    }
+    for page in extractor.page_numbers() {
+        let Some(operations) = extractor.operations(page)? else { continue };
+        if cli.format == Format::Text {
+            writeln!(out, "Page {}", page)?;
+        }
+        let mut form = None;
+        for operation in operations {
+            let operation = report::scaled_operation(operation, cli.units);
+            match cli.format {
+                Format::Json | Format::Jsonl => {
+                    let record = Record::Operation { path: path.to_string(), page, units: cli.units, operation };
+                    if cli.format == Format::Json {
+                        records.push(record);
+                    } else {
+                        write_record(out, &record)?;
                     }
+                    continue;
                 }
-                "Td" | "TD" => {
-                    if operands.len() >= 2 {
-                        if let (Ok(tx), Ok(ty)) = (operands[0].as_f32(), operands[1].as_f32()) {
-                            current_x += tx;
-                            current_y += ty;
-                        }
-                    }
+                _ => {}
+            }
+            if operation.form != form {
+                form = operation.form;
+                match form {
+                    Some((id, generation)) => writeln!(out, "{:>6} {:>8}  form {} {} R:", "", "", id, generation)?,
+                    None => writeln!(out, "{:>6} {:>8}  page:", "", "")?,
                 }
-                "Tm" => {
-                    if operands.len() >= 6 {
-                        if let (Ok(e), Ok(f)) = (operands[4].as_f32(), operands[5].as_f32()) {
-                            current_x = e;
-                            current_y = f;
+            }
+            // Operations of forms are indented under the form, and the lines of an operation
+            // laid out on several under its first.
+            let indent = if form.is_some() { "  " } else { "" };
+            let layout = operation.layout().replace('\n', &format!("\n{:>17}{}", "", indent));
+            writeln!(out, "{:>6} {:>8}  {}{}", operation.index, operation.offset, indent, layout)?;
+            if let Some(shown) = &operation.shown {
+                writeln!(out, "{:>17}{}  shows {:?} at {} {}, size {}", "", indent, shown.text,
+                    format.number(shown.x), format.number(shown.y), format.number(shown.font_size))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a copy of the PDF read from `path`, or `data` when from standard input, with
+/// `highlights` added, to `target`, which must not be the PDF read. A copy that could not be
+/// written in full is removed.
+fn write_highlighted(path: &Path, data: Option<Vec<u8>>, highlights: &[Highlight], origin: Origin, target: &Path, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let same = |target: &Path| Some(std::fs::canonicalize(target).ok()? == std::fs::canonicalize(path).ok()?);
+    if data.is_none() && same(target) == Some(true) {
+        return Err(format!("--annotate-output {} is the PDF read, which is never written to", target.display()).into());
+    }
+    let data = match data {
+        Some(data) => data,
+        None => std::fs::read(path)?,
+    };
+    let mut file = create_output(target, force)?;
+    let written = rapid_pdf::write_highlights(&data, highlights, origin, &mut file).map_err(Box::from).and_then(|()| Ok(file.flush()?));
+    written.inspect_err(|_| {
+        let _ = std::fs::remove_file(target);
+    })
+}
+
+/// Writes the images of a page into `dir` that are not there yet, named by the hash of
+/// their file so that one painted on several pages is written once, and returns where the
+/// page paints each written. Images that cannot be written are left out with a warning.
+fn write_images(
+    extractor: &Extractor,
+    page: &PageItems,
+    dir: &Path,
+    files: &mut HashMap<ObjectId, Option<String>>,
+) -> io::Result<Vec<ImageEntry>> {
+    let mut entries = Vec::new();
+    // Inline images are small, and mostly of text drawn as pixels; only image XObjects are
+    // written.
+    for placed in &page.images {
+        let Some(object) = placed.object else { continue };
+        let file = match files.get(&object) {
+            Some(file) => file.clone(),
+            None => {
+                let file = match extractor.image(object) {
+                    Ok(image) => {
+                        let hash: String = Md5::digest(&image.data).iter().map(|byte| format!("{:02x}", byte)).collect();
+                        let name = format!("{}.{}", hash, image.format.extension());
+                        let target = dir.join(&name);
+                        // The name tells the contents, so a file of it is already this image.
+                        if !target.exists() {
+                            std::fs::create_dir_all(dir)?;
+                            std::fs::write(&target, &image.data)?;
                         }
+                        Some(name)
                     }
-                }
-                "Tj" => {
-                    if let Some(Object::String(bytes, _)) = operands.first() {
-                        println!("Checking text at ({}, {}): '{}'", current_x, current_y, String::from_utf8_lossy(bytes));
-                        if current_x == target_x && current_y == target_y {
-                            println!("Positions match for Tj at ({}, {})", current_x, current_y);
-                            if String::from_utf8_lossy(bytes).contains(target_text){
-                                let mut original_text = String::from_utf8_lossy(bytes).to_string();
-                                original_text = original_text.replace(target_text, replacement);
-                                operation.operands[0] = Object::String(original_text.as_bytes().to_vec(), lopdf::StringFormat::Literal);
-                                println!("Replaced text at ({}, {})", current_x, current_y);    
-                                break;  // Replace first exact match
-                                }
-                        }
-                        if String::from_utf8_lossy(bytes) == target_text
-                            && (current_x - target_x).abs() < 0.01  // Allow small floating-point tolerance
-                            && (current_y - target_y).abs() < 0.01
-                            // && (current_font_size - target_font_size).abs() < 0.01
-                             {
-                            operation.operands[0] = Object::String(replacement.as_bytes().to_vec(), lopdf::StringFormat::Literal);
-                            println!("Replaced text at ({}, {})", current_x, current_y);    
-                            break;  // Replace first exact match
-                        }
+                    Err(err) => {
+                        log::warn!("not writing image {} {} R of page {}: {}", object.0, object.1, page.page_num, err);
+                        None
                     }
+                };
+                files.insert(object, file.clone());
+                file
+            }
+        };
+        if let Some(file) = file {
+            entries.push(ImageEntry { page: page.page_num, file, rect: placed.rect, width: placed.width, height: placed.height });
+        }
+    }
+    Ok(entries)
+}
+
+/// Writes records of delimited text, each ended by CRLF as in RFC 4180.
+fn write_rows(out: &mut impl Write, rows: &[String]) -> io::Result<()> {
+    for row in rows {
+        write!(out, "{}\r\n", row)?;
+    }
+    Ok(())
+}
+
+/// Writes one line of JSON Lines output and flushes it, so readers see each page at once.
+fn write_record(out: &mut impl Write, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+fn write_json(out: &mut impl Write, report: &impl Serialize, compact: bool) -> io::Result<()> {
+    if compact {
+        serde_json::to_writer(&mut *out, report)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, report)?;
+    }
+    writeln!(out)
+}
+
+/// Writes the fields of the information dictionary, then those the XMP packet has that it
+/// does not, or gives otherwise, marked `(XMP)`. Dates that are not valid are written as they
+/// are, marked.
+fn write_metadata(out: &mut impl Write, metadata: &Metadata) -> io::Result<()> {
+    fn write_fields(out: &mut impl Write, info: &DocumentInfo, source: &str, fields: &[(&str, &str)]) -> io::Result<()> {
+        for &(name, value) in fields {
+            let date = match name {
+                "creation_date" => info.creation_date.as_ref(),
+                "mod_date" => info.mod_date.as_ref(),
+                _ => None,
+            };
+            let note = if date.is_some_and(|date| date.malformed) { " (not a valid date)" } else { "" };
+            let label = name.replace('_', " ");
+            writeln!(out, "{}{}{}: {}{}", label[..1].to_uppercase(), &label[1..], source, value, note)?;
+        }
+        Ok(())
+    }
+    let fields = metadata.info.fields();
+    write_fields(out, &metadata.info, "", &fields)?;
+    if let Some(xmp) = &metadata.xmp {
+        let xmp_fields: Vec<_> = xmp
+            .fields()
+            .into_iter()
+            .filter(|&(name, _)| metadata.conflicts.contains(&name) || !fields.iter().any(|&(info, _)| info == name))
+            .collect();
+        write_fields(out, xmp, " (XMP)", &xmp_fields)?;
+    }
+    Ok(())
+}
+
+/// Writes the title guessed from the first page, if any, as a line with how sure the guess is.
+fn write_guessed_title(out: &mut impl Write, guess: Option<&layout::title::TitleGuess>) -> io::Result<()> {
+    match guess {
+        Some(guess) => writeln!(out, "Guessed title: {} (confidence {:.2})", guess.title, guess.confidence),
+        None => Ok(()),
+    }
+}
+
+/// Writes a field of the form a line: its name and kind, its value, and the boxes it is shown
+/// in, which are already in the units of `format`.
+fn write_form_fields(out: &mut impl Write, fields: &[FormField], format: &NumberFormat) -> io::Result<()> {
+    for field in fields {
+        let kind = match field.kind {
+            FieldKind::Text => "text",
+            FieldKind::Checkbox => "checkbox",
+            FieldKind::Radio => "radio",
+            FieldKind::Choice => "choice",
+            FieldKind::Signature => "signature",
+            _ => "field",
+        };
+        let value = match &field.value {
+            Some(FieldValue::Text(text)) => text.split_whitespace().collect::<Vec<_>>().join(" "),
+            Some(FieldValue::Checked(checked)) => checked.to_string(),
+            Some(FieldValue::Choices(choices)) => choices.join(", "),
+            Some(_) | None => String::new(),
+        };
+        let widgets: Vec<String> = field
+            .widgets
+            .iter()
+            .map(|widget| {
+                let Rect { x0, y0, x1, y1 } = widget.rect;
+                let rect = format!("[{} {} {} {}]", format.number(x0), format.number(y0), format.number(x1), format.number(y1));
+                match widget.page {
+                    Some(page) => format!("page {} {}", page, rect),
+                    None => format!("no page {}", rect),
                 }
-                // "TJ" => {
-                //     // For TJ, combine text and check if it matches
-                //     if let Some(Object::Array(arr)) = operands.first() {
-                //         let mut combined_text = String::new();
-                //         for item in arr {
-                //             if let Object::String(bytes, _) = item {
-                //                 combined_text.push_str(&String::from_utf8_lossy(bytes));
-                //             }
-                //         }
-                //         if combined_text == target_text
-                //             && (current_x - target_x).abs() < 0.01
-                //             && (current_y - target_y).abs() < 0.01
-                //             // && (current_font_size - target_font_size).abs() < 0.01 
-                //             {
-                //             // Replace the entire array with a single string (simplified; assumes no kerning adjustments)
-                //             operation.operands[0] = Object::String(replacement.as_bytes().to_vec(), lopdf::StringFormat::Literal);
-                //             println!("Replaced text at ({}, {})", current_x, current_y);
-                //             break;
-                //         }
-                //     }
-                // }
-                _ => {}
+            })
+            .collect();
+        writeln!(out, "{} ({}): {}{}", field.name, kind, value,
+            if widgets.is_empty() { String::new() } else { format!(" [{}]", widgets.join("; ")) })?;
+    }
+    Ok(())
+}
+
+/// Writes an embedded file a line: its name, MIME type and size, the page it is attached to,
+/// if any, and its description.
+fn write_attachments(out: &mut impl Write, attachments: &[Attachment]) -> io::Result<()> {
+    for attachment in attachments {
+        let one_line = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut details = vec![attachment.mime_type.clone().unwrap_or_else(|| "unknown type".to_string())];
+        details.extend(attachment.size.map(|size| if size == 1 { "1 byte".to_string() } else { format!("{} bytes", size) }));
+        details.extend(attachment.page.map(|page| format!("page {}", page)));
+        details.extend(attachment.error.as_ref().map(|error| format!("not readable: {}", error)));
+        let name = if attachment.name.is_empty() { attachment.file_name.clone() } else { one_line(&attachment.name) };
+        match &attachment.description {
+            Some(description) => writeln!(out, "{} ({}): {}", name, details.join(", "), one_line(description))?,
+            None => writeln!(out, "{} ({})", name, details.join(", "))?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a named destination a line: its name, its page and how the page is shown, with
+/// the coordinates given, which are already in the units of `format`.
+fn write_destinations(out: &mut impl Write, destinations: &BTreeMap<String, Destination>, format: &NumberFormat) -> io::Result<()> {
+    for (name, destination) in destinations {
+        let fit = match destination.fit {
+            Fit::Xyz => "xyz",
+            Fit::FitH => "fit-h",
+            Fit::FitV => "fit-v",
+            Fit::FitR => "fit-r",
+            Fit::FitB => "fit-b",
+            Fit::FitBH => "fit-bh",
+            Fit::FitBV => "fit-bv",
+            _ => "fit",
+        };
+        let mut details = vec![destination.page.map_or("no page".to_string(), |page| format!("page {}", page)), fit.to_string()];
+        let coordinates = [("left", destination.left), ("bottom", destination.bottom), ("right", destination.right), ("top", destination.top)];
+        details.extend(coordinates.iter().filter_map(|&(label, value)| Some(format!("{} {}", label, format.number(value?)))));
+        details.extend(destination.zoom.map(|zoom| format!("zoom {}", zoom)));
+        let name: String = name.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        writeln!(out, "{}: {}", name, details.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Writes an entry of the outline a line, its title indented by its depth and followed by
+/// its page, if it has one, then the entries under it.
+fn write_outline(out: &mut impl Write, items: &[OutlineItem]) -> io::Result<()> {
+    for item in items {
+        let title: String = item.title.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+        match item.page {
+            Some(page) => writeln!(out, "{}{} (page {})", "  ".repeat(item.depth), title.trim(), page)?,
+            None => writeln!(out, "{}{}", "  ".repeat(item.depth), title.trim())?,
+        }
+        write_outline(out, &item.children)?;
+    }
+    Ok(())
+}
+
+/// Writes the text of each page, paragraphs separated by blank lines and pages by
+/// `delimiter`, with its annotations after a blank line. With `regions`, the paragraphs of
+/// each region are headed by `[region N]`.
+fn write_text(out: &mut impl Write, pages: &[PageText], delimiter: &str, annotations: bool, regions: bool) -> io::Result<()> {
+    for (index, page) in pages.iter().enumerate() {
+        if index > 0 {
+            write!(out, "{}", delimiter)?;
+        }
+        for (index, paragraph) in page.paragraphs.iter().enumerate() {
+            if index > 0 {
+                writeln!(out)?;
+            }
+            let region = paragraph.region.filter(|_| regions);
+            if let Some(region) = region.filter(|_| index == 0 || page.paragraphs[index - 1].region != paragraph.region) {
+                writeln!(out, "[region {}]", region)?;
+            }
+            writeln!(out, "{}", paragraph.text)?;
+        }
+        if annotations && !page.annotations.is_empty() {
+            if !page.paragraphs.is_empty() {
+                writeln!(out)?;
+            }
+            for annotation in &page.annotations {
+                writeln!(out, "{}", annotation_line(annotation))?;
             }
         }
-        
-        let new_content_data = content.encode()?;
-        doc.change_page_content(object_id, new_content_data)?;
     }
-    let path = Path::new("output.pdf");
-    let mut file = BufWriter::new(File::create(path)?);
-    doc.save_modern(&mut file)?;
     Ok(())
 }
 
+/// An annotation as a line of text, such as `[Highlight, Ann, 2024-01-31T12:00:00Z] "the
+/// text marked up" the comment`.
+fn annotation_line(annotation: &Annotation) -> String {
+    let one_line = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut heading = vec![annotation.kind.clone()];
+    heading.extend(annotation.author.as_deref().map(one_line));
+    heading.extend(annotation.modified.as_ref().map(|date| date.value.clone()));
+    let mut line = format!("[{}]", heading.join(", "));
+    if let Some(quoted) = &annotation.quoted {
+        line += &format!(" \"{}\"", one_line(quoted));
+    }
+    if let Some(contents) = &annotation.contents {
+        line += &format!(" {}", one_line(contents));
+    }
+    line
+}
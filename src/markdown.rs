@@ -0,0 +1,179 @@
+//! Markdown output: paragraphs as blocks, headings as `layout::headings` finds them, and
+//! bulleted or numbered lines as list items.
+
+use rapid_pdf::layout::lines::Line;
+use rapid_pdf::layout::paragraphs;
+use rapid_pdf::layout::{figures, headings};
+use rapid_pdf::{Origin, PageText, Rect};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Markdown has six heading levels; deeper headings are written at the sixth.
+const MAX_LEVEL: usize = 6;
+
+/// Glyphs that start a bulleted list item.
+const BULLETS: [char; 6] = ['•', '◦', '▪', '‣', '–', '-'];
+
+enum Block {
+    Heading(usize, String),
+    /// A list item: its marker, as `-` or the original number such as `3.`, and its text.
+    Item(String, String),
+    Paragraph(String),
+    /// An image painted on the page, by the anchor naming it, such as `page3-img2` for the
+    /// second image of page 3.
+    Image(String),
+}
+
+/// The document as Markdown; with `page_breaks`, pages after the first start with a rule and
+/// a comment naming the page. Images are placeholders among the text, where they are read.
+pub fn document(pages: &[PageText], page_breaks: bool, origin: Origin) -> String {
+    // The level of each heading, by page and paragraph.
+    let levels: HashMap<(u32, usize), usize> =
+        headings::detect(pages, origin).into_iter().map(|heading| ((heading.page, heading.paragraph), heading.level)).collect();
+    let mut markdown = String::new();
+    for (index, page) in pages.iter().enumerate() {
+        // A rule opening the document would read as the start of front matter.
+        if page_breaks && index > 0 && !markdown.is_empty() {
+            let _ = write!(markdown, "\n---\n\n<!-- page {} -->\n", page.page_num);
+        }
+        let mut previous_item = None;
+        for block in page_blocks(page, &levels, origin) {
+            // Whether the block is a bulleted or a numbered item.
+            let item = match &block {
+                Block::Item(marker, _) => Some(marker == "-"),
+                _ => None,
+            };
+            // Items of one list are on consecutive lines; everything else is set apart.
+            if !markdown.is_empty() && (item.is_none() || item != previous_item) {
+                markdown.push('\n');
+            }
+            match block {
+                Block::Heading(level, text) => {
+                    let _ = writeln!(markdown, "{} {}", "#".repeat(level), text);
+                }
+                Block::Item(marker, text) => {
+                    let _ = writeln!(markdown, "{} {}", marker, text);
+                }
+                Block::Paragraph(text) => {
+                    let _ = writeln!(markdown, "{}", escape_start(&text));
+                }
+                Block::Image(anchor) => {
+                    let _ = writeln!(markdown, "![image](#{})", anchor);
+                }
+            }
+            previous_item = item;
+        }
+    }
+    markdown
+}
+
+fn page_blocks(page: &PageText, levels: &HashMap<(u32, usize), usize>, origin: Origin) -> Vec<Block> {
+    let numbered_indents = numbered_indents(page);
+    let boxes: Vec<Rect> = page.paragraphs.iter().map(|paragraph| paragraph.bbox).collect();
+    // The paragraph each image goes before, and its anchor, numbered in drawing order.
+    let mut images: Vec<(usize, String)> = page
+        .images
+        .iter()
+        .enumerate()
+        .map(|(index, image)| (figures::position(&image.rect, &boxes, origin), format!("page{}-img{}", page.page_num, index + 1)))
+        .collect();
+    images.sort_by_key(|&(position, _)| position);
+    let mut images = images.into_iter().peekable();
+    let mut blocks = Vec::new();
+    // The left edge of the last list item's marker line, while the item may go on.
+    let mut item_start: Option<f32> = None;
+    for (index, paragraph) in page.paragraphs.iter().enumerate() {
+        while let Some((_, anchor)) = images.next_if(|&(position, _)| position == index) {
+            blocks.push(Block::Image(anchor));
+            item_start = None;
+        }
+        if let Some(&level) = levels.get(&(page.page_num, index)) {
+            blocks.push(Block::Heading(level.min(MAX_LEVEL), paragraph.text.clone()));
+            item_start = None;
+            continue;
+        }
+        let markers: Vec<Option<String>> = paragraph.lines.iter().map(|line| marker(line, &numbered_indents)).collect();
+        if markers[0].is_none() {
+            // Lines hung under an item's text, indented past its marker, continue the item.
+            let first = &paragraph.lines[0];
+            let hanging = item_start.is_some_and(|x0| first.bbox.x0 > x0 && first.bbox.x0 - x0 <= 2.0 * first.font_size());
+            match blocks.last_mut() {
+                Some(Block::Item(_, text)) if hanging => {
+                    text.push(' ');
+                    text.push_str(&paragraph.text);
+                }
+                _ => {
+                    blocks.push(Block::Paragraph(paragraph.text.clone()));
+                    item_start = None;
+                }
+            }
+            continue;
+        }
+        // Each marked line starts an item; the lines after it, up to the next, continue it.
+        let mut start = 0;
+        while start < markers.len() {
+            let end = (start + 1..markers.len()).find(|&i| markers[i].is_some()).unwrap_or(markers.len());
+            let text = paragraphs::join_lines(&paragraph.lines[start..end], None);
+            let text = strip_marker(&text);
+            blocks.push(Block::Item(markers[start].clone().unwrap_or_default(), text));
+            item_start = Some(paragraph.lines[start].bbox.x0);
+            start = end;
+        }
+    }
+    blocks.extend(images.map(|(_, anchor)| Block::Image(anchor)));
+    blocks
+}
+
+/// The Markdown list marker for a line: `-` when it starts with a bullet glyph, or its number
+/// with a period or parenthesis when another numbered line of the page has the same indent.
+fn marker(line: &Line, numbered_indents: &[f32]) -> Option<String> {
+    let text = line.text.trim_start();
+    let first = text.chars().next()?;
+    if BULLETS.contains(&first) && text[first.len_utf8()..].starts_with(char::is_whitespace) {
+        return Some("-".to_string());
+    }
+    // Long numbers, such as years, start sentences more often than lists.
+    let number = numbered(text).filter(|number| number.len() <= 4)?;
+    let indent = line.font_size() / 2.0;
+    let aligned = numbered_indents.iter().filter(|&&x| (x - line.bbox.x0).abs() <= indent).count() >= 2;
+    aligned.then(|| number.to_string())
+}
+
+/// The left edges of the page's lines that start with a number and a period or parenthesis.
+fn numbered_indents(page: &PageText) -> Vec<f32> {
+    page.paragraphs
+        .iter()
+        .flat_map(|paragraph| &paragraph.lines)
+        .filter(|line| numbered(line.text.trim_start()).is_some())
+        .map(|line| line.bbox.x0)
+        .collect()
+}
+
+/// The `12.` or `3)` a text starts with, if a space follows it: what CommonMark reads as an
+/// ordered list marker.
+fn numbered(text: &str) -> Option<&str> {
+    let digits = text.find(|c: char| !c.is_ascii_digit())?;
+    let rest = &text[digits..];
+    let numbered = digits > 0 && digits <= 9 && (rest.starts_with(". ") || rest.starts_with(") "));
+    numbered.then(|| &text[..digits + 1])
+}
+
+/// Item text without its marker.
+fn strip_marker(text: &str) -> String {
+    let text = text.trim_start();
+    let rest = match numbered(text) {
+        Some(number) => &text[number.len()..],
+        None => text.strip_prefix(BULLETS).unwrap_or(text),
+    };
+    rest.trim_start().to_string()
+}
+
+/// Escapes what would make a paragraph's start read as other Markdown: a heading, quote,
+/// list item or rule.
+fn escape_start(text: &str) -> String {
+    match numbered(text) {
+        Some(number) => format!("{}\\{}", &number[..number.len() - 1], &text[number.len() - 1..]),
+        None if text.starts_with(['#', '>', '+', '*', '-', '=']) => format!("\\{}", text),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,141 @@
+use serde::Serialize;
+
+/// A PDF transformation matrix `[a b c d e f]`, standing for the 3x3 matrix
+///
+/// ```text
+/// | a b 0 |
+/// | c d 0 |
+/// | e f 1 |
+/// ```
+///
+/// Points are row vectors, so `p' = p × M`, and `m1.multiply(&m2)` applies `m1` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Matrix {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f32, ty: f32) -> Matrix {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    /// Builds a matrix from the six numeric operands of `Tm` or `cm`.
+    pub fn from_operands(operands: &[lopdf::Object]) -> Option<Matrix> {
+        if operands.len() < 6 {
+            return None;
+        }
+        let mut values = [0.0f32; 6];
+        for (value, operand) in values.iter_mut().zip(operands) {
+            *value = operand.as_float().ok()?;
+        }
+        let [a, b, c, d, e, f] = values;
+        Some(Matrix::new(a, b, c, d, e, f))
+    }
+
+    /// Returns `self × other`: the transformation that applies `self`, then `other`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            e: self.e * other.a + self.f * other.c + other.e,
+            f: self.e * other.b + self.f * other.d + other.f,
+        }
+    }
+
+    pub fn transform_point(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.a + y * self.c + self.e, x * self.b + y * self.d + self.f)
+    }
+
+    /// The transformation that undoes this one, or `None` when it flattens space.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Matrix { a, b, c, d, e: -(self.e * a + self.f * c), f: -(self.e * b + self.f * d) })
+    }
+
+    /// How much the matrix scales glyph height: the distance it puts between the transformed x
+    /// axis and the line one unit above it, so that shearing the glyphs leaves it unchanged.
+    pub fn vertical_scale(&self) -> f32 {
+        let width = (self.a * self.a + self.b * self.b).sqrt();
+        if width < f32::EPSILON {
+            return (self.c * self.c + self.d * self.d).sqrt();
+        }
+        (self.a * self.d - self.b * self.c).abs() / width
+    }
+
+    /// The angle, in degrees counterclockwise, the matrix turns the x axis by.
+    pub fn rotation(&self) -> f32 {
+        self.b.atan2(self.a).to_degrees()
+    }
+}
+
+/// An axis-aligned rectangle in page space, `(x0, y0)` being the lower-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Rect {
+    pub x0: f32,
+    pub y0: f32,
+    pub x1: f32,
+    pub y1: f32,
+}
+
+impl Rect {
+    /// The smallest rectangle enclosing the text-space box `(x0, y0)`-`(x1, y1)` once it is
+    /// transformed by `matrix`, which may rotate or skew it.
+    pub(crate) fn transformed(x0: f32, y0: f32, x1: f32, y1: f32, matrix: &Matrix) -> Rect {
+        let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].map(|(x, y)| matrix.transform_point(x, y));
+        let mut rect = Rect { x0: f32::INFINITY, y0: f32::INFINITY, x1: f32::NEG_INFINITY, y1: f32::NEG_INFINITY };
+        for (x, y) in corners {
+            rect.x0 = rect.x0.min(x);
+            rect.y0 = rect.y0.min(y);
+            rect.x1 = rect.x1.max(x);
+            rect.y1 = rect.y1.max(y);
+        }
+        rect
+    }
+
+    /// The smallest rectangle enclosing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    /// The overlap of both, or `None` when they do not meet. Rectangles that only touch
+    /// give an empty one.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect {
+            x0: self.x0.max(other.x0),
+            y0: self.y0.max(other.y0),
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+        };
+        (rect.x0 <= rect.x1 && rect.y0 <= rect.y1).then_some(rect)
+    }
+
+    pub fn width(&self) -> f32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> f32 {
+        self.y1 - self.y0
+    }
+}
@@ -0,0 +1,483 @@
+//! What a document says about itself: the trailer's `/Info` dictionary (PDF 32000-1:2008,
+//! 14.3.3) and the XMP packet of the Catalog's `/Metadata` stream (14.3.2), which newer tools
+//! may fill in alone.
+
+use crate::decode_text_string;
+use lopdf::{Document, Object};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The metadata of a document, as both the information dictionary and the XMP packet give
+/// it. Where they disagree, both values are kept and the field is named in `conflicts`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[non_exhaustive]
+pub struct Metadata {
+    /// From the information dictionary.
+    pub info: DocumentInfo,
+    /// From the XMP packet, when there is one.
+    pub xmp: Option<DocumentInfo>,
+    /// The XMP packet as it is.
+    pub xmp_packet: Option<String>,
+    /// The fields, such as `title`, that both give different values for.
+    pub conflicts: Vec<&'static str>,
+}
+
+/// The standard fields of document metadata. XMP gives them as `dc:title`, `dc:creator`,
+/// `dc:description`, `pdf:Keywords` (or `dc:subject`), `xmp:CreateDate`, `xmp:ModifyDate`,
+/// `pdf:Producer` and `xmp:CreatorTool`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct DocumentInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creation_date: Option<Date>,
+    pub mod_date: Option<Date>,
+    pub producer: Option<String>,
+    /// The application the document was first made in, before being converted to PDF.
+    pub creator: Option<String>,
+}
+
+impl DocumentInfo {
+    /// The fields with a value, by name, dates in RFC 3339 or as written.
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        let text = [("title", &self.title), ("author", &self.author), ("subject", &self.subject), ("keywords", &self.keywords)];
+        let dates = [("creation_date", &self.creation_date), ("mod_date", &self.mod_date)];
+        let tools = [("producer", &self.producer), ("creator", &self.creator)];
+        text.into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+            .chain(dates.into_iter().filter_map(|(name, date)| Some((name, date.as_ref()?.value.as_str()))))
+            .chain(tools.into_iter().filter_map(|(name, value)| Some((name, value.as_deref()?))))
+            .collect()
+    }
+}
+
+/// A date in RFC 3339, such as `2024-01-31T12:00:00+01:00`, or, when it is not a valid date,
+/// as written. Parts of a date a document leaves out are the first of their range, and an
+/// unknown offset from UTC is `-00:00`.
+///
+/// ```
+/// use rapid_pdf::Date;
+///
+/// assert_eq!(Date::from_pdf("D:20240131120000+01'00'").value, "2024-01-31T12:00:00+01:00");
+/// assert_eq!(Date::from_pdf("D:2024").value, "2024-01-01T00:00:00-00:00");
+/// assert_eq!(Date::from_pdf("20240131120000Z00'00'").value, "2024-01-31T12:00:00Z");
+/// assert_eq!(Date::from_xmp("2024-01-31T12:00-05:00").value, "2024-01-31T12:00:00-05:00");
+/// let date = Date::from_pdf("D:20241301");
+/// assert!(date.malformed);
+/// assert_eq!(date.value, "D:20241301");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Date {
+    pub value: String,
+    /// Whether `value` is as written, for not being a valid date.
+    pub malformed: bool,
+}
+
+impl Date {
+    /// A date in the format of PDF dates (7.9.4), such as `D:20240131120000+01'00'`.
+    pub fn from_pdf(text: &str) -> Date {
+        Date::parse(text, parse_pdf_date)
+    }
+
+    /// A date in the subset of ISO 8601 XMP uses, such as `2024-01-31T12:00:00+01:00`.
+    pub fn from_xmp(text: &str) -> Date {
+        Date::parse(text, parse_xmp_date)
+    }
+
+    fn parse(text: &str, parse: fn(&str) -> Option<DateTime>) -> Date {
+        match parse(text.trim()) {
+            Some(date) => Date { value: date.to_string(), malformed: false },
+            None => Date { value: text.to_string(), malformed: true },
+        }
+    }
+}
+
+/// The parts of a date, checked to be in range.
+struct DateTime {
+    year: u32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// Minutes east of UTC, or `None` when unknown.
+    offset: Option<i32>,
+}
+
+impl DateTime {
+    fn checked(self) -> Option<DateTime> {
+        let days = match self.month {
+            2 if self.year.is_multiple_of(4) && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400)) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        let in_range = (1..=days).contains(&self.day) && self.hour < 24 && self.minute < 60 && self.second < 60;
+        let offset_in_range = self.offset.is_none_or(|offset| offset.abs() < 24 * 60);
+        (in_range && offset_in_range).then_some(self)
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)?;
+        match self.offset {
+            Some(0) => write!(f, "Z"),
+            Some(offset) => write!(f, "{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 60, offset.abs() % 60),
+            None => write!(f, "-00:00"),
+        }
+    }
+}
+
+/// Reads digits off the front of `text`: exactly `len` of them, or none when `text` ends or
+/// goes on with something else first, which gives `default`.
+fn take_number(text: &mut &str, len: usize, default: u32) -> Option<u32> {
+    if text.is_empty() || !text.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(default);
+    }
+    let digits = text.get(..len).filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))?;
+    *text = &text[len..];
+    digits.parse().ok()
+}
+
+/// `D:YYYYMMDDHHmmSSOHH'mm'`, where everything after the year may be left out and `O` is
+/// `+`, `-` or `Z`. The `D:` and the apostrophes are often missing.
+fn parse_pdf_date(text: &str) -> Option<DateTime> {
+    let mut rest = text.strip_prefix("D:").unwrap_or(text);
+    if !rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let year = take_number(&mut rest, 4, 0)?;
+    let month = take_number(&mut rest, 2, 1)?;
+    let day = take_number(&mut rest, 2, 1)?;
+    let hour = take_number(&mut rest, 2, 0)?;
+    let minute = take_number(&mut rest, 2, 0)?;
+    let second = take_number(&mut rest, 2, 0)?;
+    let offset = match rest.chars().next() {
+        None => None,
+        Some('Z') => {
+            rest = &rest[1..];
+            // Some producers write `Z00'00'`.
+            rest = rest.trim_start_matches(['0', '\'']);
+            Some(0)
+        }
+        Some(sign @ ('+' | '-')) => {
+            rest = &rest[1..];
+            let hours = take_number(&mut rest, 2, 0)?;
+            rest = rest.strip_prefix('\'').unwrap_or(rest);
+            let minutes = take_number(&mut rest, 2, 0)?;
+            rest = rest.strip_prefix('\'').unwrap_or(rest);
+            let offset = (hours * 60 + minutes) as i32;
+            Some(if sign == '-' { -offset } else { offset })
+        }
+        Some(_) => return None,
+    };
+    rest.is_empty().then_some(())?;
+    DateTime { year, month, day, hour, minute, second, offset }.checked()
+}
+
+/// `YYYY[-MM[-DD[Thh:mm[:ss[.s]]TZD]]]`, where `TZD` is `Z` or `±hh:mm`.
+fn parse_xmp_date(text: &str) -> Option<DateTime> {
+    let mut rest = text;
+    let mut part = |separator: Option<char>, default: u32| -> Option<u32> {
+        if let Some(separator) = separator {
+            match rest.strip_prefix(separator) {
+                Some(after) => rest = after,
+                None => return Some(default),
+            }
+        }
+        let value = take_number(&mut rest, if separator.is_none() { 4 } else { 2 }, u32::MAX)?;
+        (value != u32::MAX).then_some(value)
+    };
+    let year = part(None, 0)?;
+    let month = part(Some('-'), 1)?;
+    let day = part(Some('-'), 1)?;
+    let mut offset = None;
+    let (mut hour, mut minute, mut second) = (0, 0, 0);
+    if let Some(time) = rest.strip_prefix('T') {
+        rest = time;
+        hour = take_number(&mut rest, 2, u32::MAX)?;
+        rest = rest.strip_prefix(':')?;
+        minute = take_number(&mut rest, 2, u32::MAX)?;
+        if let Some(after) = rest.strip_prefix(':') {
+            rest = after;
+            second = take_number(&mut rest, 2, u32::MAX)?;
+            if let Some(fraction) = rest.strip_prefix('.') {
+                rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+            }
+        }
+        offset = match rest.chars().next() {
+            None => None,
+            Some('Z') => {
+                rest = &rest[1..];
+                Some(0)
+            }
+            Some(sign @ ('+' | '-')) => {
+                rest = &rest[1..];
+                let hours = take_number(&mut rest, 2, u32::MAX)?;
+                rest = rest.strip_prefix(':')?;
+                let minutes = take_number(&mut rest, 2, u32::MAX)?;
+                let offset = (hours * 60 + minutes) as i32;
+                Some(if sign == '-' { -offset } else { offset })
+            }
+            Some(_) => return None,
+        };
+    }
+    rest.is_empty().then_some(())?;
+    DateTime { year, month, day, hour, minute, second, offset }.checked()
+}
+
+/// Reads the metadata of a document. Either source may be missing or unreadable, which
+/// leaves its fields empty.
+pub(crate) fn read(doc: &Document) -> Metadata {
+    let info = doc.trailer.get_deref(b"Info", doc).and_then(Object::as_dict).ok().map_or_else(DocumentInfo::default, |info| {
+        let text = |key: &[u8]| {
+            let value = doc.dereference(info.get(key).ok()?).ok()?.1.as_str().ok()?;
+            Some(decode_text_string(value)).filter(|text| !text.trim().is_empty())
+        };
+        DocumentInfo {
+            title: text(b"Title"),
+            author: text(b"Author"),
+            subject: text(b"Subject"),
+            keywords: text(b"Keywords"),
+            creation_date: text(b"CreationDate").map(|date| Date::from_pdf(&date)),
+            mod_date: text(b"ModDate").map(|date| Date::from_pdf(&date)),
+            producer: text(b"Producer"),
+            creator: text(b"Creator"),
+        }
+    });
+    let xmp_packet = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"Metadata", doc))
+        .and_then(Object::as_stream)
+        .and_then(|stream| stream.get_plain_content())
+        .ok()
+        .map(|data| {
+            let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&data);
+            String::from_utf8_lossy(data).into_owned()
+        });
+    let xmp = xmp_packet.as_deref().map(xmp_info);
+    let conflicts = match &xmp {
+        Some(xmp) => {
+            let xmp_fields: BTreeMap<_, _> = xmp.fields().into_iter().collect();
+            info.fields()
+                .into_iter()
+                .filter(|(name, value)| xmp_fields.get(name).is_some_and(|xmp| xmp.trim() != value.trim()))
+                .map(|(name, _)| name)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    Metadata { info, xmp, xmp_packet, conflicts }
+}
+
+/// The standard fields of an XMP packet, written either as elements or as attributes of
+/// `rdf:Description`, under the usual namespace prefixes.
+fn xmp_info(packet: &str) -> DocumentInfo {
+    let properties = xmp_properties(packet);
+    let first = |name: &str| properties.get(name).and_then(|values| values.first()).cloned();
+    let joined = |name: &str, separator: &str| properties.get(name).map(|values| values.join(separator));
+    DocumentInfo {
+        title: first("dc:title"),
+        author: joined("dc:creator", "; "),
+        subject: first("dc:description"),
+        keywords: first("pdf:Keywords").or_else(|| joined("dc:subject", ", ")),
+        creation_date: first("xmp:CreateDate").map(|date| Date::from_xmp(&date)),
+        mod_date: first("xmp:ModifyDate").map(|date| Date::from_xmp(&date)),
+        producer: first("pdf:Producer"),
+        creator: first("xmp:CreatorTool"),
+    }
+}
+
+/// The values of the properties of an XMP packet, by qualified name: the text of each
+/// property element, or of each `rdf:li` inside it, and the attributes of elements that are
+/// not namespace declarations or RDF's own. Not a full XML parser, but enough for packets
+/// as PDF producers write them.
+fn xmp_properties(packet: &str) -> BTreeMap<String, Vec<String>> {
+    let mut properties: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // The property elements open around the current position; RDF's own are not properties.
+    let mut open: Vec<Option<String>> = Vec::new();
+    let mut rest = packet;
+    while let Some(start) = rest.find('<') {
+        let text = rest[..start].trim();
+        if let (false, Some(Some(property))) = (text.is_empty(), open.iter().rev().find(|name| name.is_some())) {
+            properties.entry(property.clone()).or_default().push(unescape(text));
+        }
+        rest = &rest[start..];
+        let end = if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            if let Some(Some(property)) = open.iter().rev().find(|name| name.is_some()) {
+                properties.entry(property.clone()).or_default().push(cdata[..end].trim().to_string());
+            }
+            "<![CDATA[".len() + end + "]]>".len()
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |end| end + 3)
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else if rest.starts_with("</") {
+            open.pop();
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else {
+            let Some(end) = tag_end(rest) else { break };
+            let tag = &rest[1..end - 1];
+            let empty = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let name_len = tag.find(|c: char| c.is_ascii_whitespace()).unwrap_or(tag.len());
+            let name = &tag[..name_len];
+            for (attribute, value) in attributes(&tag[name_len..]) {
+                if !(attribute.starts_with("xmlns") || attribute.starts_with("rdf:") || attribute.starts_with("xml:")) {
+                    properties.entry(attribute.to_string()).or_default().push(unescape(value));
+                }
+            }
+            if !empty {
+                open.push(Some(name.to_string()).filter(|name| !name.starts_with("rdf:") && !name.starts_with("x:")));
+            }
+            end
+        };
+        rest = rest.get(end.min(rest.len())..).unwrap_or_default();
+    }
+    properties
+}
+
+/// Where the tag at the start of `text` ends, just past its `>`, skipping any in quoted
+/// attribute values; `None` when it does not.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The `name="value"` attributes of a tag, values still escaped.
+fn attributes(mut text: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    while let Some(eq) = text.find('=') {
+        let name = text[..eq].trim();
+        let after = text[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| matches!(c, '"' | '\'')) else { break };
+        let Some(len) = after[1..].find(quote) else { break };
+        attributes.push((name, &after[1..1 + len]));
+        text = &after[len + 2..];
+    }
+    attributes
+}
+
+/// Replaces the character and entity references of XML text.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else { break };
+        let reference = &rest[1..end];
+        let c = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use lopdf::{dictionary, Dictionary, Stream};
+
+    const PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:pdf="http://ns.adobe.com/pdf/1.3/" pdf:Producer="Typesetter 2.1" xmp:CreateDate="2024-01-31T12:00:00+01:00">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Annual Report &amp; Accounts</rdf:li></rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>Ada Lovelace</rdf:li><rdf:li>Alan Turing</rdf:li></rdf:Seq></dc:creator>
+   <dc:subject><rdf:Bag><rdf:li>finance</rdf:li><rdf:li>annual</rdf:li></rdf:Bag></dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    /// A document of one page, with `info` as its information dictionary and `packet` as its
+    /// XMP packet.
+    fn document(info: Dictionary, packet: Option<&str>) -> Document {
+        let mut fixture = Fixture::new();
+        fixture.page("");
+        let mut catalog = Dictionary::new();
+        if let Some(packet) = packet {
+            let stream = Stream::new(dictionary! { "Type" => "Metadata", "Subtype" => "XML" }, packet.as_bytes().to_vec());
+            catalog.set("Metadata", fixture.object(stream));
+        }
+        let info = fixture.object(info);
+        let mut doc = fixture.document(catalog);
+        doc.trailer.set("Info", info);
+        doc
+    }
+
+    #[test]
+    fn reads_the_information_dictionary() {
+        let info = dictionary! {
+            "Title" => Object::string_literal("Quarterly Figures"),
+            // UTF-16BE, as text strings out of PDFDocEncoding are written.
+            "Author" => Object::String(b"\xFE\xFF\x00Z\x00o\x00\xEB".to_vec(), lopdf::StringFormat::Hexadecimal),
+            "Subject" => Object::string_literal("  "),
+            "CreationDate" => Object::string_literal("D:20240131120000+01'00'"),
+            "Producer" => Object::string_literal("Typesetter 2.1"),
+        };
+        let metadata = read(&document(info, None));
+        assert_eq!(metadata.info.title.as_deref(), Some("Quarterly Figures"));
+        assert_eq!(metadata.info.author.as_deref(), Some("Zoë"));
+        assert_eq!(metadata.info.subject, None);
+        assert_eq!(metadata.info.creation_date.unwrap().value, "2024-01-31T12:00:00+01:00");
+        assert!(metadata.xmp.is_none() && metadata.conflicts.is_empty());
+    }
+
+    #[test]
+    fn reads_the_xmp_packet_and_where_it_disagrees() {
+        let info = dictionary! {
+            "Title" => Object::string_literal("Draft"),
+            "Producer" => Object::string_literal("Typesetter 2.1"),
+            "CreationDate" => Object::string_literal("D:20240131120000+01'00'"),
+        };
+        let metadata = read(&document(info, Some(PACKET)));
+        let xmp = metadata.xmp.unwrap();
+        assert_eq!(xmp.title.as_deref(), Some("Annual Report & Accounts"));
+        assert_eq!(xmp.author.as_deref(), Some("Ada Lovelace; Alan Turing"));
+        assert_eq!(xmp.keywords.as_deref(), Some("finance, annual"));
+        assert_eq!(xmp.producer.as_deref(), Some("Typesetter 2.1"));
+        assert_eq!(xmp.creation_date.unwrap().value, "2024-01-31T12:00:00+01:00");
+        assert_eq!(metadata.conflicts, ["title"]);
+        assert!(metadata.xmp_packet.unwrap().starts_with("<?xpacket"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Normalizing extracted text so it compares equal to the same text typed in: ligature
+//! glyphs spelt out as letters, composed accents (NFC).
+//!
+//! It works on item text only, after positions and widths were taken from the glyphs, so a
+//! normalized item still covers exactly the glyphs it was shown with.
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
+
+pub fn normalize(text: &str, nbsp_as_space: bool) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            // Alphabetic Presentation Forms, U+FB00-U+FB06.
+            '\u{FB00}' => expanded.push_str("ff"),
+            '\u{FB01}' => expanded.push_str("fi"),
+            '\u{FB02}' => expanded.push_str("fl"),
+            '\u{FB03}' => expanded.push_str("ffi"),
+            '\u{FB04}' => expanded.push_str("ffl"),
+            '\u{FB05}' | '\u{FB06}' => expanded.push_str("st"),
+            '\u{A0}' | '\u{202F}' if nbsp_as_space => expanded.push(' '),
+            _ => expanded.push(c),
+        }
+    }
+    expanded.nfc().collect()
+}
+
+/// Where the characters of `normalized`, the normalized `text`, end, from `ends`, where those
+/// of `text` do: a ligature's extent is shared by the letters spelt out of it, and a letter's
+/// and its accents' by what they compose to. Empty when `ends` does not fit `text`.
+pub(crate) fn char_ends(text: &str, ends: &[f32], normalized: &str, nbsp_as_space: bool) -> Vec<f32> {
+    if ends.len() != text.chars().count() {
+        return Vec::new();
+    }
+    // A starter and the combining marks after it compose on their own.
+    let mut clusters: Vec<(String, f32, f32)> = Vec::new();
+    let mut start = 0.0;
+    for (c, &end) in text.chars().zip(ends) {
+        match clusters.last_mut() {
+            Some((cluster, _, cluster_end)) if canonical_combining_class(c) != 0 => {
+                cluster.push(c);
+                *cluster_end = cluster_end.max(end);
+            }
+            // Only decomposed, not composed yet.
+            _ => clusters.push((normalize(&c.to_string(), nbsp_as_space).nfd().collect(), start, end)),
+        }
+        start = end;
+    }
+    let mut shared = Vec::new();
+    for (cluster, start, end) in clusters {
+        let count = cluster.nfc().count();
+        shared.extend((1..=count).map(|index| start + (end - start) * index as f32 / count as f32));
+    }
+    if shared.len() == normalized.chars().count() {
+        shared
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, Fixture};
+    use crate::ExtractOptions;
+    use lopdf::{dictionary, Object, Stream};
+
+    /// A page showing `shown` in a font `/F2` whose codes 1 to 3 map to the ligatures ﬁ and
+    /// ﬀ and an e followed by a combining acute accent.
+    fn ligatures(shown: &str) -> Vec<u8> {
+        let to_unicode = b"begincmap 1 begincodespacerange <00> <FF> endcodespacerange \
+            3 beginbfchar <01> <FB01> <02> <FB00> <03> <00650301> endbfchar endcmap";
+        let mut fixture = Fixture::new();
+        let to_unicode = fixture.object(Stream::new(Default::default(), to_unicode.to_vec()));
+        fixture.font("F2", dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica", "ToUnicode" => to_unicode });
+        fixture.page(format!("BT /F2 12 Tf 72 700 Td {} Tj ET", shown));
+        fixture.bytes()
+    }
+
+    #[test]
+    fn spells_out_ligatures() {
+        assert_eq!(normalize("\u{FB01}nancial e\u{FB00}ort", false), "financial effort");
+        assert_eq!(normalize("o\u{FB03}ce \u{FB04}uent \u{FB02}ow \u{FB06}", false), "office ffluent flow st");
+    }
+
+    #[test]
+    fn composes_accents() {
+        assert_eq!(normalize("cafe\u{301} n\u{303}u", false), "café ñu");
+        assert_eq!(normalize("A\u{30A}ngstro\u{308}m", false), "Ångström");
+    }
+
+    #[test]
+    fn turns_no_break_spaces_into_spaces_when_asked() {
+        assert_eq!(normalize("10\u{A0}km", false), "10\u{A0}km");
+        assert_eq!(normalize("10\u{A0}km 5\u{202F}%", true), "10 km 5 %");
+    }
+
+    #[test]
+    fn shares_the_extent_of_a_ligature_among_its_letters() {
+        // "ﬁx": the ligature and the x a glyph each; "é" decomposed, two characters to one.
+        assert_eq!(char_ends("\u{FB01}x", &[0.6, 1.0], "fix", false), [0.3, 0.6, 1.0]);
+        assert_eq!(char_ends("e\u{301}t", &[0.5, 0.5, 1.0], "ét", false), [0.5, 1.0]);
+        assert!(char_ends("ab", &[1.0], "ab", false).is_empty());
+    }
+
+    #[test]
+    fn normalizes_the_text_of_items() {
+        // "ﬁnancial eﬀort" and "é"; the 0x01 to 0x03 codes are drawn by no glyph of
+        // Helvetica, so the words are as wide as the other letters make them.
+        let data = ligatures("<016E616E6369616C2065026F72742003>");
+        let items = testing::items_with(&data, &ExtractOptions::default());
+        assert_eq!(items[0].text, "financial effort é");
+        assert_eq!(items[0].char_ends.len(), items[0].text.chars().count());
+        let options = ExtractOptions::builder().normalize(false).build().unwrap();
+        assert_eq!(testing::items_with(&data, &options)[0].text, "\u{FB01}nancial e\u{FB00}ort e\u{301}");
+    }
+
+    #[test]
+    fn decodes_accented_latin_from_differences() {
+        let mut fixture = Fixture::new();
+        let differences: Vec<Object> = vec![1.into(), "eacute".into(), "ntilde".into(), "Adieresis".into(), "ccedilla".into(), "fi".into()];
+        let encoding = dictionary! { "Type" => "Encoding", "BaseEncoding" => "WinAnsiEncoding", "Differences" => differences };
+        fixture.font("F2", dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Times-Roman", "Encoding" => encoding });
+        fixture.page("BT /F2 12 Tf 72 700 Td <636166012061F16F200320676172E76F6E2005726D65> Tj ET");
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(items[0].text, "café año Ä garçon firme");
+        assert!(items[0].width > 0.0);
+    }
+}
@@ -0,0 +1,126 @@
+//! Reading the text of scans through an OCR engine given to `ExtractOptions::ocr_provider`,
+//! whose words join the items of the page to be laid out, written and searched like the rest.
+
+#[cfg(feature = "tesseract")]
+mod tesseract;
+
+#[cfg(feature = "tesseract")]
+pub use tesseract::Tesseract;
+
+use crate::matrix::Rect;
+use crate::{normalize, Baseline, Error, ExtractOptions, Image, Origin, PlacedImage, TextItem};
+use std::fmt;
+
+/// An OCR engine, reading the words of an image painted on a page.
+///
+/// The extractor calls it for each image XObject of the pages `ExtractOptions::ocr` asks for,
+/// on the thread reading the page, and places the words it returns on the page by where the
+/// image is painted. Words are given in pixels of the image rather than as [`TextItem`]s, which
+/// only the extractor can place.
+///
+/// ```no_run
+/// use rapid_pdf::{Error, ExtractOptions, Image, OcrProvider, OcrWord, Rect};
+///
+/// /// Reads every image as the one word "scan", across the whole of it.
+/// #[derive(Debug)]
+/// struct Stub;
+///
+/// impl OcrProvider for Stub {
+///     fn ocr_image(&self, page_num: u32, image: &Image) -> Result<Vec<OcrWord>, Error> {
+///         let rect = Rect { x0: 0.0, y0: 0.0, x1: image.width as f32, y1: image.height as f32 };
+///         Ok(vec![OcrWord::new("scan", rect)])
+///     }
+/// }
+///
+/// let options = ExtractOptions::builder().ocr_provider(Stub).build()?;
+/// let text = rapid_pdf::extract_path("scan.pdf", &options)?;
+/// # Ok::<(), Error>(())
+/// ```
+pub trait OcrProvider: fmt::Debug + Send + Sync {
+    /// The words of an image of page `page_num`, such as an `Error::Ocr` when the engine fails;
+    /// the page is then read without them, the error told as one of its own.
+    fn ocr_image(&self, page_num: u32, image: &Image) -> Result<Vec<OcrWord>, Error>;
+}
+
+/// A word read from an image, with its box in pixels from the image's top-left corner.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct OcrWord {
+    pub text: String,
+    pub rect: Rect,
+}
+
+impl OcrWord {
+    pub fn new(text: impl Into<String>, rect: Rect) -> OcrWord {
+        OcrWord { text: text.into(), rect }
+    }
+}
+
+/// The provider that reads nothing, for keeping the OCR of pages switched off where an engine
+/// is expected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOcr;
+
+impl OcrProvider for NoOcr {
+    fn ocr_image(&self, _page_num: u32, _image: &Image) -> Result<Vec<OcrWord>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// The words read from `image`, as items of page `page_num` placed like its others. The image
+/// is taken as painted upright, its first row of pixels at the top of its box; words are
+/// normalized and kept to the regions of `options` as items are, and set in a font size of
+/// the height of their box.
+pub(crate) fn place(words: Vec<OcrWord>, image: &PlacedImage, options: &ExtractOptions, page_num: u32) -> Vec<TextItem> {
+    if image.width == 0 || image.height == 0 {
+        return Vec::new();
+    }
+    let (scale_x, scale_y) = (image.rect.width() / image.width as f32, image.rect.height() / image.height as f32);
+    let mut items = Vec::with_capacity(words.len());
+    for word in words.into_iter().filter(|word| !word.text.trim().is_empty()) {
+        let x0 = image.rect.x0 + word.rect.x0 * scale_x;
+        let x1 = image.rect.x0 + word.rect.x1 * scale_x;
+        let (y0, y1) = match options.origin {
+            Origin::TopLeft => (image.rect.y0 + word.rect.y0 * scale_y, image.rect.y0 + word.rect.y1 * scale_y),
+            Origin::BottomLeft => (image.rect.y1 - word.rect.y1 * scale_y, image.rect.y1 - word.rect.y0 * scale_y),
+        };
+        let bbox = Rect { x0, y0, x1, y1 };
+        let baseline = match options.origin {
+            Origin::TopLeft => y1,
+            Origin::BottomLeft => y0,
+        };
+        let text = if options.normalize { normalize::normalize(&word.text, options.nbsp_as_space) } else { word.text };
+        let region = options.regions.iter().position(|region| region.holds(&bbox)).map(|index| index + 1);
+        if !options.regions.is_empty() && region.is_none() {
+            continue;
+        }
+        items.push(TextItem {
+            text,
+            x: x0,
+            y: baseline,
+            font_size: bbox.height(),
+            width: bbox.width(),
+            height: bbox.height(),
+            bbox,
+            font_resource: None,
+            font: None,
+            bold: false,
+            italic: false,
+            color: None,
+            rise: 0.0,
+            baseline: Baseline::Normal,
+            render_mode: 0,
+            vertical: false,
+            angle: 0.0,
+            artifact: false,
+            synthetic_bold: false,
+            char_ends: Vec::new(),
+            fragment_ends: Vec::new(),
+            content_id: None,
+            role: None,
+            region,
+            page_num,
+        });
+    }
+    items
+}
@@ -0,0 +1,82 @@
+//! An [`OcrProvider`] running the `tesseract` command, mostly to show how one is written.
+
+use super::{OcrProvider, OcrWord};
+use crate::matrix::Rect;
+use crate::{Error, Image};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Reads images with the `tesseract` command (4.0 or later), given each on standard input,
+/// its words read from the TSV it writes.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Tesseract {
+    /// The command to run, `tesseract` on the `PATH` by default.
+    pub command: PathBuf,
+    /// The languages to read, as `-l` takes them, such as `eng+deu`; tesseract's default when
+    /// `None`.
+    pub languages: Option<String>,
+}
+
+impl Default for Tesseract {
+    fn default() -> Tesseract {
+        Tesseract { command: PathBuf::from("tesseract"), languages: None }
+    }
+}
+
+impl Tesseract {
+    pub fn new() -> Tesseract {
+        Tesseract::default()
+    }
+
+    pub fn languages(mut self, languages: impl Into<String>) -> Tesseract {
+        self.languages = Some(languages.into());
+        self
+    }
+}
+
+impl OcrProvider for Tesseract {
+    fn ocr_image(&self, page_num: u32, image: &Image) -> Result<Vec<OcrWord>, Error> {
+        let mut command = Command::new(&self.command);
+        command.args(["stdin", "stdout"]);
+        if let Some(languages) = &self.languages {
+            command.args(["-l", languages]);
+        }
+        let mut child = command
+            .arg("tsv")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| Error::Ocr(format!("cannot run {}: {}", self.command.display(), err).into()))?;
+        // Tesseract reads the whole image before it writes anything.
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&image.data).map_err(|err| Error::Ocr(err.into()))?;
+        }
+        let output = child.wait_with_output().map_err(|err| Error::Ocr(err.into()))?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Ocr(format!("tesseract failed on page {} ({}): {}", page_num, output.status, message.trim()).into()));
+        }
+        Ok(words(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// The words of tesseract's TSV: the rows of level 5, with the left, top, width and height of
+/// their box and their text in the 7th to 10th and 12th columns.
+fn words(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|row| {
+            let columns: Vec<&str> = row.split('\t').collect();
+            if columns.len() < 12 || columns[0] != "5" {
+                return None;
+            }
+            let number = |index: usize| columns[index].parse::<f32>().ok();
+            let (left, top, width, height) = (number(6)?, number(7)?, number(8)?, number(9)?);
+            let rect = Rect { x0: left, y0: top, x1: left + width, y1: top + height };
+            Some(OcrWord::new(columns[11], rect))
+        })
+        .collect()
+}
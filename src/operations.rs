@@ -0,0 +1,105 @@
+//! The operations of a page's content as they are run, for seeing why a page reads as it
+//! does: each operator with its operands, and the text the showing ones show.
+
+use crate::debug;
+use lopdf::{Object, ObjectId, StringFormat};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An operation of a page's content, or of a form XObject it paints, as
+/// [`Extractor::operations`](crate::Extractor::operations) lists them.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct ContentOperation {
+    /// The place of the operation among those run for the page, those of forms included,
+    /// from 0.
+    pub index: usize,
+    /// Where the operation starts in its stream decompressed: for the page's own operations,
+    /// in its content streams joined with a space between each.
+    pub offset: usize,
+    /// The form XObject the operation is in, or `None` for the page's own content.
+    pub form: Option<ObjectId>,
+    pub operator: String,
+    pub operands: Vec<Operand>,
+    /// For the operators that show text, what they show and where.
+    pub shown: Option<ShownText>,
+    #[serde(skip)]
+    objects: Vec<Object>,
+}
+
+impl ContentOperation {
+    pub(crate) fn new(index: usize, offset: usize, form: Option<ObjectId>, operator: &str, operands: &[Object]) -> ContentOperation {
+        ContentOperation {
+            index,
+            offset,
+            form,
+            operator: operator.to_string(),
+            operands: operands.iter().map(Operand::new).collect(),
+            shown: None,
+            objects: operands.to_vec(),
+        }
+    }
+
+    /// The operation as PDF syntax: its operands, arrays and dictionaries laid out an entry
+    /// a line, then its operator.
+    pub fn layout(&self) -> String {
+        let mut parts: Vec<String> = self.objects.iter().map(debug::print_with_layout).collect();
+        parts.push(self.operator.clone());
+        parts.join(" ")
+    }
+}
+
+/// The text an operation shows, decoded by its font.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ShownText {
+    pub text: String,
+    /// Where the text starts on its baseline, on the page as `ExtractOptions::origin` measures
+    /// it.
+    pub x: f32,
+    pub y: f32,
+    /// The size the text is seen at, its font size scaled by the text and graphics matrices.
+    pub font_size: f32,
+}
+
+/// An operand of an operation, with its type.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Operand {
+    Null,
+    Boolean { value: bool },
+    Integer { value: i64 },
+    Real { value: f32 },
+    /// A name, without its slash.
+    Name { value: String },
+    /// A string's bytes, in hexadecimal, and whether it was written in hexadecimal.
+    String { value: String, hexadecimal: bool },
+    Array { value: Vec<Operand> },
+    Dictionary { value: BTreeMap<String, Operand> },
+    Reference { value: ObjectId },
+}
+
+impl Operand {
+    fn new(object: &Object) -> Operand {
+        match object {
+            Object::Null => Operand::Null,
+            Object::Boolean(value) => Operand::Boolean { value: *value },
+            Object::Integer(value) => Operand::Integer { value: *value },
+            Object::Real(value) => Operand::Real { value: *value },
+            Object::Name(name) => Operand::Name { value: String::from_utf8_lossy(name).into_owned() },
+            Object::String(bytes, format) => Operand::String {
+                value: bytes.iter().map(|byte| format!("{:02X}", byte)).collect(),
+                hexadecimal: *format == StringFormat::Hexadecimal,
+            },
+            Object::Array(items) => Operand::Array { value: items.iter().map(Operand::new).collect() },
+            Object::Dictionary(dict) => Operand::Dictionary { value: dictionary(dict) },
+            // Inline images are rewritten into a dictionary, but streams are never operands.
+            Object::Stream(stream) => Operand::Dictionary { value: dictionary(&stream.dict) },
+            Object::Reference(id) => Operand::Reference { value: *id },
+        }
+    }
+}
+
+fn dictionary(dict: &lopdf::Dictionary) -> BTreeMap<String, Operand> {
+    dict.iter().map(|(key, value)| (String::from_utf8_lossy(key).into_owned(), Operand::new(value))).collect()
+}
@@ -0,0 +1,700 @@
+//! What to extract and how to put it in order, set up with [`ExtractOptions::builder`].
+
+use crate::layout;
+use crate::matrix::Rect;
+use crate::ocr::OcrProvider;
+use crate::page::PageSelection;
+use crate::progress::{OnPage, PageProgress};
+use crate::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Order {
+    /// The order the content stream draws text in, which may be anything at all.
+    #[default]
+    Stream,
+    /// Lines top to bottom in upright page space, then left to right, with each column
+    /// (see `--columns`) read in full before the next.
+    Layout,
+    /// The reading order of a tagged PDF's structure tree; untagged pages keep drawing order.
+    Structure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Origin {
+    /// PDF's own: y grows upwards from the bottom of the page.
+    #[default]
+    BottomLeft,
+    /// As in images and HTML: y grows downwards from the top-left corner of the crop box.
+    TopLeft,
+}
+
+/// What each text item holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Granularity {
+    /// The text of a show-string operator, or of a part of a `TJ` array.
+    #[default]
+    Items,
+    /// The characters of one glyph, a ligature's all together, at the glyph's own origin and
+    /// as wide as its advance; strings whose characters cannot be told apart by glyph stay
+    /// whole.
+    Chars,
+}
+
+/// Which pages `ExtractOptions::ocr_provider` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ocr {
+    /// Pages that are scans without text, as `PageKind::ImageOnly` tells them.
+    #[default]
+    ImageOnly,
+    /// Every page painting an image, its words added to the text the page has.
+    Always,
+}
+
+/// How an item's box must lie on a [`Region`] for the item to be kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegionMode {
+    /// The box meets the region.
+    #[default]
+    Intersect,
+    /// The box lies entirely inside the region.
+    Contain,
+}
+
+/// A part of the page to read the text of, placed like the items: upright and measured from
+/// the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct Region {
+    pub rect: Rect,
+    pub mode: RegionMode,
+}
+
+impl Region {
+    pub fn new(rect: Rect, mode: RegionMode) -> Region {
+        Region { rect, mode }
+    }
+
+    /// Whether an item with this box is in the region.
+    pub fn holds(&self, bbox: &Rect) -> bool {
+        match self.mode {
+            RegionMode::Intersect => bbox.intersection(&self.rect).is_some(),
+            RegionMode::Contain => bbox.x0 >= self.rect.x0 && bbox.y0 >= self.rect.y0 && bbox.x1 <= self.rect.x1 && bbox.y1 <= self.rect.y1,
+        }
+    }
+}
+
+/// Settings that change which text is extracted and how it is put in order. The defaults
+/// read every page in drawing order, keeping all text and normalizing it:
+///
+/// ```
+/// use rapid_pdf::{ExtractOptions, Order, Origin};
+///
+/// let options = ExtractOptions::default();
+/// assert_eq!(options.pages, None);
+/// assert!(!options.skip_invisible && !options.skip_artifacts && !options.skip_outside_crop_box);
+/// assert_eq!(options.skip_rotated, None);
+/// assert_eq!((options.min_font_size, options.max_font_size), (None, None));
+/// assert!(!options.drop_invisible_color && options.regions.is_empty());
+/// assert!(options.normalize && !options.nbsp_as_space && options.bidi);
+/// assert_eq!((options.order, options.origin), (Order::Stream, Origin::BottomLeft));
+/// assert_eq!(options.granularity, rapid_pdf::Granularity::Items);
+/// assert_eq!((options.duplicate_tolerance, options.line_tolerance, options.word_gap), (0.1, 0.3, 0.25));
+/// assert_eq!((options.paragraph_gap, options.tj_space_threshold, options.max_skew), (1.3, 100.0, 0.5));
+/// assert_eq!(options.columns, rapid_pdf::layout::columns::Columns::Auto);
+/// assert!(!options.strip_headers_footers && !options.dehyphenate && !options.merge_fragments);
+/// assert_eq!(options.password, None);
+/// assert!(!options.strict);
+/// assert_eq!(options.threads, None);
+/// assert_eq!((options.max_operations, options.max_form_depth), (10_000_000, 32));
+/// assert_eq!((options.max_stream_size, options.time_limit), (256 << 20, None));
+/// assert_eq!((options.max_chars, options.max_items), (None, None));
+/// assert!(!options.debug_ops && options.on_page.is_none());
+/// assert!(options.ocr == rapid_pdf::Ocr::ImageOnly && options.ocr_provider.is_none());
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ExtractOptions {
+    /// Which pages to read; `None` for all of them.
+    pub pages: Option<PageSelection>,
+    /// Drop text drawn with render mode 3, which is neither filled nor stroked.
+    pub skip_invisible: bool,
+    /// Drop text inside `/Artifact` marked content: pagination, headers, backgrounds.
+    pub skip_artifacts: bool,
+    /// Drop text whose box does not meet the crop box, which viewers never show.
+    pub skip_outside_crop_box: bool,
+    /// Drop text turned more than this many degrees from horizontal.
+    pub skip_rotated: Option<f32>,
+    /// Drop text smaller or larger than these font sizes, in points: the sizes text is shown
+    /// at, `Tf` scaled by the text matrix and the CTM, as `TextItem::font_size` gives them.
+    pub min_font_size: Option<f32>,
+    pub max_font_size: Option<f32>,
+    /// Drop white text with nothing but white behind it.
+    pub drop_invisible_color: bool,
+    /// Keep only the text in these regions of each page, which lines and paragraphs are then
+    /// built from, region by region; each item keeps which it is in, as `TextItem::region`.
+    /// Empty for all the text.
+    pub regions: Vec<Region>,
+    /// Whether item text is normalized (see `normalize`); positions still refer to the
+    /// glyphs as shown.
+    pub normalize: bool,
+    /// With `normalize`, also turn non-breaking spaces into spaces.
+    pub nbsp_as_space: bool,
+    /// Whether right-to-left lines are put in logical order.
+    pub bidi: bool,
+    pub order: Order,
+    pub origin: Origin,
+    /// How close, as a fraction of the font size, copies of the same text must be to be
+    /// collapsed into one item.
+    pub duplicate_tolerance: f32,
+    /// How far apart baselines may be, as a fraction of the font size, for text to share a line.
+    pub line_tolerance: f32,
+    /// The smallest gap between fragments of a line, as a fraction of the font size, that
+    /// separates words.
+    pub word_gap: f32,
+    /// How many degrees a line's baseline may lean by for its items to be measured from the
+    /// baseline fitted through them rather than by their y, as text drawn a little askew is.
+    pub max_skew: f32,
+    /// How far apart baselines may be, as a multiple of the leading most lines of the page
+    /// are set with, for lines to be in one paragraph.
+    pub paragraph_gap: f32,
+    /// How far forward, in thousandths of an em, a number in a `TJ` array must move the text
+    /// for a space to be put there, as the word spaces of many producers are drawn; numbers
+    /// moving it less than that are kerning.
+    pub tj_space_threshold: f32,
+    /// Whether items are the strings shown or their characters.
+    pub granularity: Granularity,
+    /// How `Order::Layout` finds columns.
+    pub columns: layout::columns::Columns,
+    /// Drop lines repeated at the same place on most pages.
+    pub strip_headers_footers: bool,
+    /// Join words hyphenated at the end of a line.
+    pub dehyphenate: bool,
+    /// Join the fragments a word is drawn in, kerned apart by separate show-string operators,
+    /// into one item, as `layout::fragments::merge` does.
+    pub merge_fragments: bool,
+    /// The user or owner password of an encrypted document that does not open without one.
+    pub password: Option<String>,
+    /// Stop at the first page whose content cannot be read in full, with `Error::Content`,
+    /// rather than reading what can be of it and going on.
+    pub strict: bool,
+    /// How many pages to read at once, each on a thread of its own; `None` for as many as
+    /// there are cores. Results come in page order whatever the number.
+    pub threads: Option<usize>,
+    /// Limits on the work a page may take, for documents crafted to make it endless: the
+    /// operators run on a page, those of its forms included; how deeply form XObjects nest;
+    /// the decompressed size of a content stream in bytes; and the time to read the whole
+    /// document, unlimited with `None`. A page going over one is read no further, which
+    /// `PageItems::errors` tells with the `Limit`.
+    pub max_operations: usize,
+    pub max_form_depth: usize,
+    pub max_stream_size: usize,
+    pub time_limit: Option<Duration>,
+    /// Stop reading the selected pages, in order, once they have given this many characters
+    /// or items, for a preview of a document without reading the whole. The page reaching
+    /// the limit keeps the items its content shows first, in stream order, up to the one
+    /// reaching it, which are then put in `order`; `PageItems::truncated` marks it. Pages are
+    /// read one after another for this, and no page after it is read or decompressed.
+    pub max_chars: Option<usize>,
+    pub max_items: Option<usize>,
+    /// Keep the data of image XObjects when the document is read, for `Extractor::image`;
+    /// otherwise only their dictionaries are kept, as text never needs more.
+    pub images: bool,
+    /// Log the operators of each page and the strings they show at trace level, and the
+    /// items, lines and paragraphs built from them at debug level, lengths in points, reading
+    /// pages one at a time for it not to interleave. Warnings are logged whatever this is.
+    pub debug_ops: bool,
+    /// Called with the progress of the reading each time a selected page is read, for showing
+    /// it, as `--progress` does.
+    pub on_page: Option<OnPage>,
+    /// Which pages `ocr_provider` reads the image XObjects of.
+    pub ocr: Ocr,
+    /// The OCR engine the words of scans are read with, which are then items of their page;
+    /// pages are read without OCR when `None`. The data of images is kept for it, as with
+    /// `images`.
+    pub ocr_provider: Option<Arc<dyn OcrProvider>>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> ExtractOptions {
+        ExtractOptions {
+            pages: None,
+            skip_invisible: false,
+            skip_artifacts: false,
+            skip_outside_crop_box: false,
+            skip_rotated: None,
+            min_font_size: None,
+            max_font_size: None,
+            drop_invisible_color: false,
+            regions: Vec::new(),
+            normalize: true,
+            nbsp_as_space: false,
+            bidi: true,
+            order: Order::default(),
+            origin: Origin::default(),
+            duplicate_tolerance: layout::duplicates::DEFAULT_TOLERANCE,
+            line_tolerance: layout::lines::DEFAULT_TOLERANCE,
+            word_gap: layout::lines::DEFAULT_WORD_GAP,
+            max_skew: layout::lines::DEFAULT_MAX_SKEW,
+            paragraph_gap: layout::paragraphs::DEFAULT_GAP,
+            tj_space_threshold: 100.0,
+            granularity: Granularity::Items,
+            columns: layout::columns::Columns::Auto,
+            strip_headers_footers: false,
+            dehyphenate: false,
+            merge_fragments: false,
+            password: None,
+            strict: false,
+            threads: None,
+            max_operations: 10_000_000,
+            max_form_depth: 32,
+            max_stream_size: 256 << 20,
+            time_limit: None,
+            max_chars: None,
+            max_items: None,
+            images: false,
+            debug_ops: false,
+            on_page: None,
+            ocr: Ocr::ImageOnly,
+            ocr_provider: None,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Starts from the defaults; settings left alone keep them.
+    ///
+    /// ```
+    /// use rapid_pdf::{ExtractOptions, Order, Origin};
+    ///
+    /// let options = ExtractOptions::builder().pages("1-3").order(Order::Layout).origin(Origin::TopLeft).build()?;
+    /// assert!(options.pages.is_some_and(|pages| pages.contains(3) && !pages.contains(4)));
+    /// # Ok::<(), rapid_pdf::Error>(())
+    /// ```
+    pub fn builder() -> ExtractOptionsBuilder {
+        ExtractOptionsBuilder { options: ExtractOptions::default(), error: None }
+    }
+
+    /// Checks that the settings make sense together, as `ExtractOptionsBuilder::build` does.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.nbsp_as_space && !self.normalize {
+            return Err(Error::InvalidOptions("non-breaking spaces are only turned into spaces when text is normalized".to_string()));
+        }
+        if self.merge_fragments && self.granularity == Granularity::Chars {
+            return Err(Error::InvalidOptions("fragments are not merged into items of single characters".to_string()));
+        }
+        if let Some(degrees) = self.skip_rotated {
+            if !(0.0..=90.0).contains(&degrees) {
+                return Err(Error::InvalidOptions(format!("text can lean from 0 to 90 degrees from horizontal, got {}", degrees)));
+            }
+        }
+        if !(0.0..=45.0).contains(&self.max_skew) {
+            return Err(Error::InvalidOptions(format!("a line's baseline can lean from 0 to 45 degrees, got {}", self.max_skew)));
+        }
+        for size in [self.min_font_size, self.max_font_size].into_iter().flatten() {
+            if !size.is_finite() || size < 0.0 {
+                return Err(Error::InvalidOptions(format!("a font size is a number of points, 0 or more, got {}", size)));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_font_size, self.max_font_size) {
+            if min > max {
+                return Err(Error::InvalidOptions(format!("the smallest font size kept, {}, is larger than the largest, {}", min, max)));
+            }
+        }
+        for region in &self.regions {
+            let Rect { x0, y0, x1, y1 } = region.rect;
+            if ![x0, y0, x1, y1].iter().all(|value| value.is_finite()) || x1 < x0 || y1 < y0 {
+                return Err(Error::InvalidOptions(format!("a region is a rectangle of finite size, 0 or more, got [{} {} {} {}]", x0, y0, x1, y1)));
+            }
+        }
+        let fractions = [
+            ("duplicate tolerance", self.duplicate_tolerance),
+            ("line tolerance", self.line_tolerance),
+            ("word gap", self.word_gap),
+        ];
+        for (name, value) in fractions {
+            if !value.is_finite() || value < 0.0 {
+                return Err(Error::InvalidOptions(format!("the {} is a fraction of the font size, 0 or more, got {}", name, value)));
+            }
+        }
+        if self.max_chars == Some(0) || self.max_items == Some(0) {
+            return Err(Error::InvalidOptions("a preview stops after at least 1 character or item".to_string()));
+        }
+        if !self.paragraph_gap.is_finite() || self.paragraph_gap < 1.0 {
+            return Err(Error::InvalidOptions(format!("the paragraph gap is a multiple of the leading, 1 or more, got {}", self.paragraph_gap)));
+        }
+        if !self.tj_space_threshold.is_finite() || self.tj_space_threshold < 0.0 {
+            return Err(Error::InvalidOptions(format!("the TJ space threshold is in thousandths of an em, 0 or more, got {}", self.tj_space_threshold)));
+        }
+        if self.threads == Some(0) {
+            return Err(Error::InvalidOptions("pages are read on at least 1 thread".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Sets up `ExtractOptions` one setting at a time, checking them together in `build`.
+///
+/// ```
+/// use rapid_pdf::ExtractOptions;
+///
+/// let options = ExtractOptions::builder().skip_artifacts(true).strip_headers_footers(true).build()?;
+/// assert!(options.skip_artifacts && options.strip_headers_footers);
+///
+/// assert!(ExtractOptions::builder().pages("3-1").build().is_err());
+/// assert!(ExtractOptions::builder().normalize(false).nbsp_as_space(true).build().is_err());
+/// # Ok::<(), rapid_pdf::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractOptionsBuilder {
+    options: ExtractOptions,
+    /// The first setting that could not be parsed, reported by `build`.
+    error: Option<String>,
+}
+
+impl ExtractOptionsBuilder {
+    /// Reads only these pages, counted from 1, such as `pages("1-5,12,40-")`, where `40-`
+    /// runs to the last page.
+    pub fn pages(mut self, ranges: &str) -> ExtractOptionsBuilder {
+        match ranges.parse() {
+            Ok(pages) => self.options.pages = Some(pages),
+            Err(err) => {
+                self.error.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    /// Reads only the pages of a selection already parsed, or all of them with `None`.
+    pub fn page_selection(mut self, pages: Option<PageSelection>) -> ExtractOptionsBuilder {
+        self.options.pages = pages;
+        self
+    }
+
+    /// Drops invisible text, such as the OCR layer of a scanned page: `skip_invisible(true)`.
+    pub fn skip_invisible(mut self, skip: bool) -> ExtractOptionsBuilder {
+        self.options.skip_invisible = skip;
+        self
+    }
+
+    /// Drops text marked as an artifact, such as running headers: `skip_artifacts(true)`.
+    pub fn skip_artifacts(mut self, skip: bool) -> ExtractOptionsBuilder {
+        self.options.skip_artifacts = skip;
+        self
+    }
+
+    /// Drops text entirely outside the crop box, such as printers' marks:
+    /// `skip_outside_crop_box(true)`.
+    pub fn skip_outside_crop_box(mut self, skip: bool) -> ExtractOptionsBuilder {
+        self.options.skip_outside_crop_box = skip;
+        self
+    }
+
+    /// Drops text turned more than this many degrees from horizontal, either way, such as
+    /// diagonal watermarks: `skip_rotated(Some(10.0))`.
+    pub fn skip_rotated(mut self, degrees: Option<f32>) -> ExtractOptionsBuilder {
+        self.options.skip_rotated = degrees;
+        self
+    }
+
+    /// Drops text shown smaller than this many points, such as all but headings:
+    /// `min_font_size(Some(14.0))`.
+    pub fn min_font_size(mut self, size: Option<f32>) -> ExtractOptionsBuilder {
+        self.options.min_font_size = size;
+        self
+    }
+
+    /// Drops text shown larger than this many points, such as all but fine print:
+    /// `max_font_size(Some(7.0))`.
+    pub fn max_font_size(mut self, size: Option<f32>) -> ExtractOptionsBuilder {
+        self.options.max_font_size = size;
+        self
+    }
+
+    /// Drops white text with nothing but white behind it: `drop_invisible_color(true)`.
+    pub fn drop_invisible_color(mut self, drop: bool) -> ExtractOptionsBuilder {
+        self.options.drop_invisible_color = drop;
+        self
+    }
+
+    /// Keeps only the text in these regions: `regions(vec![Region::new(rect, RegionMode::Contain)])`.
+    pub fn regions(mut self, regions: Vec<Region>) -> ExtractOptionsBuilder {
+        self.options.regions = regions;
+        self
+    }
+
+    /// Keeps text exactly as decoded, ligatures and all, with `normalize(false)`.
+    pub fn normalize(mut self, normalize: bool) -> ExtractOptionsBuilder {
+        self.options.normalize = normalize;
+        self
+    }
+
+    /// Turns non-breaking spaces into ordinary ones as text is normalized:
+    /// `nbsp_as_space(true)`.
+    pub fn nbsp_as_space(mut self, nbsp_as_space: bool) -> ExtractOptionsBuilder {
+        self.options.nbsp_as_space = nbsp_as_space;
+        self
+    }
+
+    /// Keeps right-to-left text in the visual order it is drawn in with `bidi(false)`.
+    pub fn bidi(mut self, bidi: bool) -> ExtractOptionsBuilder {
+        self.options.bidi = bidi;
+        self
+    }
+
+    /// The order items are listed in: `order(Order::Layout)`.
+    pub fn order(mut self, order: Order) -> ExtractOptionsBuilder {
+        self.options.order = order;
+        self
+    }
+
+    /// Where coordinates are measured from: `origin(Origin::TopLeft)`.
+    pub fn origin(mut self, origin: Origin) -> ExtractOptionsBuilder {
+        self.options.origin = origin;
+        self
+    }
+
+    /// How close copies of the same text must be to be collapsed, as a fraction of the font
+    /// size: `duplicate_tolerance(0.2)`.
+    pub fn duplicate_tolerance(mut self, tolerance: f32) -> ExtractOptionsBuilder {
+        self.options.duplicate_tolerance = tolerance;
+        self
+    }
+
+    /// How far apart baselines on one line may be, as a fraction of the font size:
+    /// `line_tolerance(0.5)`.
+    pub fn line_tolerance(mut self, tolerance: f32) -> ExtractOptionsBuilder {
+        self.options.line_tolerance = tolerance;
+        self
+    }
+
+    /// The smallest gap between words, as a fraction of the font size: `word_gap(0.15)`.
+    pub fn word_gap(mut self, word_gap: f32) -> ExtractOptionsBuilder {
+        self.options.word_gap = word_gap;
+        self
+    }
+
+    /// How many degrees a line may lean by and still be fitted, rather than grouped by y:
+    /// `max_skew(1.0)`.
+    pub fn max_skew(mut self, degrees: f32) -> ExtractOptionsBuilder {
+        self.options.max_skew = degrees;
+        self
+    }
+
+    /// How far apart the baselines of a paragraph may be, as a multiple of the page's leading:
+    /// `paragraph_gap(1.6)`.
+    pub fn paragraph_gap(mut self, gap: f32) -> ExtractOptionsBuilder {
+        self.options.paragraph_gap = gap;
+        self
+    }
+
+    /// How far forward, in thousandths of an em, a `TJ` adjustment moves the text to be a
+    /// space: `tj_space_threshold(200.0)`.
+    pub fn tj_space_threshold(mut self, threshold: f32) -> ExtractOptionsBuilder {
+        self.options.tj_space_threshold = threshold;
+        self
+    }
+
+    /// Whether items are the strings shown or their characters: `granularity(Granularity::Chars)`.
+    pub fn granularity(mut self, granularity: Granularity) -> ExtractOptionsBuilder {
+        self.options.granularity = granularity;
+        self
+    }
+
+    /// How `Order::Layout` finds columns: `columns(Columns::Count(2))`.
+    pub fn columns(mut self, columns: layout::columns::Columns) -> ExtractOptionsBuilder {
+        self.options.columns = columns;
+        self
+    }
+
+    /// Drops running headers, footers and page numbers repeated on most pages:
+    /// `strip_headers_footers(true)`.
+    pub fn strip_headers_footers(mut self, strip: bool) -> ExtractOptionsBuilder {
+        self.options.strip_headers_footers = strip;
+        self
+    }
+
+    /// Joins the kerned fragments of words into one item each: `merge_fragments(true)`.
+    pub fn merge_fragments(mut self, merge: bool) -> ExtractOptionsBuilder {
+        self.options.merge_fragments = merge;
+        self
+    }
+
+    /// Joins words hyphenated at the end of a line: `dehyphenate(true)`.
+    pub fn dehyphenate(mut self, dehyphenate: bool) -> ExtractOptionsBuilder {
+        self.options.dehyphenate = dehyphenate;
+        self
+    }
+
+    /// The user or owner password of an encrypted document: `password(Some("secret"))`.
+    pub fn password(mut self, password: Option<&str>) -> ExtractOptionsBuilder {
+        self.options.password = password.map(str::to_string);
+        self
+    }
+
+    /// Fails on the first page whose content cannot be read in full: `strict(true)`.
+    pub fn strict(mut self, strict: bool) -> ExtractOptionsBuilder {
+        self.options.strict = strict;
+        self
+    }
+
+    /// Reads at most this many pages at once, or one per core with `None`: `threads(Some(4))`.
+    pub fn threads(mut self, threads: Option<usize>) -> ExtractOptionsBuilder {
+        self.options.threads = threads;
+        self
+    }
+
+    /// Stops reading a page after this many operators: `max_operations(100_000)`.
+    pub fn max_operations(mut self, max: usize) -> ExtractOptionsBuilder {
+        self.options.max_operations = max;
+        self
+    }
+
+    /// Stops reading a page at form XObjects nested deeper than this: `max_form_depth(8)`.
+    pub fn max_form_depth(mut self, max: usize) -> ExtractOptionsBuilder {
+        self.options.max_form_depth = max;
+        self
+    }
+
+    /// Stops reading a page at a content stream decompressing to more than this many bytes:
+    /// `max_stream_size(16 << 20)`.
+    pub fn max_stream_size(mut self, max: usize) -> ExtractOptionsBuilder {
+        self.options.max_stream_size = max;
+        self
+    }
+
+    /// Stops reading pages once the document has taken this long:
+    /// `time_limit(Some(Duration::from_secs(30)))`.
+    pub fn time_limit(mut self, limit: Option<Duration>) -> ExtractOptionsBuilder {
+        self.options.time_limit = limit;
+        self
+    }
+
+    /// Stops reading pages once they have given this many characters:
+    /// `max_chars(Some(2000))`.
+    pub fn max_chars(mut self, max: Option<usize>) -> ExtractOptionsBuilder {
+        self.options.max_chars = max;
+        self
+    }
+
+    /// Stops reading pages once they have given this many items: `max_items(Some(100))`.
+    pub fn max_items(mut self, max: Option<usize>) -> ExtractOptionsBuilder {
+        self.options.max_items = max;
+        self
+    }
+
+    /// Keeps the data of images, to write them as files: `images(true)`.
+    pub fn images(mut self, keep: bool) -> ExtractOptionsBuilder {
+        self.options.images = keep;
+        self
+    }
+
+    /// Logs what is read and built from each page: `debug_ops(true)`.
+    pub fn debug_ops(mut self, debug: bool) -> ExtractOptionsBuilder {
+        self.options.debug_ops = debug;
+        self
+    }
+
+    /// Calls `f` with the progress of the reading each time a page is read:
+    /// `on_page(|progress| eprintln!("{} of {} pages", progress.done, progress.total))`.
+    pub fn on_page(mut self, f: impl Fn(PageProgress) + Send + Sync + 'static) -> ExtractOptionsBuilder {
+        self.options.on_page = Some(OnPage::new(f));
+        self
+    }
+
+    /// Which pages are read through the OCR provider: `ocr(Ocr::Always)`.
+    pub fn ocr(mut self, ocr: Ocr) -> ExtractOptionsBuilder {
+        self.options.ocr = ocr;
+        self
+    }
+
+    /// Reads the words of scans with an OCR engine: `ocr_provider(Tesseract::new())`.
+    pub fn ocr_provider(mut self, provider: impl OcrProvider + 'static) -> ExtractOptionsBuilder {
+        self.options.ocr_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// The options, or what is wrong with them.
+    pub fn build(self) -> Result<ExtractOptions, Error> {
+        if let Some(err) = self.error {
+            return Err(Error::InvalidOptions(err));
+        }
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(builder: ExtractOptionsBuilder) -> String {
+        match builder.build() {
+            Err(Error::InvalidOptions(message)) => message,
+            other => panic!("expected the options rejected, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn builds_the_defaults_untouched() {
+        let options = ExtractOptions::builder().build().unwrap();
+        assert_eq!(format!("{:?}", options), format!("{:?}", ExtractOptions::default()));
+        assert!(ExtractOptions::default().validate().is_ok());
+        // The layout module's own defaults, which the options hand it.
+        assert_eq!(options.line_tolerance, layout::lines::DEFAULT_TOLERANCE);
+        assert_eq!(options.word_gap, layout::lines::DEFAULT_WORD_GAP);
+        assert_eq!(options.max_skew, layout::lines::DEFAULT_MAX_SKEW);
+        assert_eq!(options.paragraph_gap, layout::paragraphs::DEFAULT_GAP);
+    }
+
+    #[test]
+    fn keeps_each_setting() {
+        let options = ExtractOptions::builder()
+            .pages("2,4-")
+            .origin(Origin::TopLeft)
+            .order(Order::Layout)
+            .skip_invisible(true)
+            .min_font_size(Some(6.0))
+            .line_tolerance(0.5)
+            .threads(Some(2))
+            .password(Some("secret"))
+            .build()
+            .unwrap();
+        let pages = options.pages.as_ref().unwrap();
+        assert!(!pages.contains(1) && pages.contains(2) && !pages.contains(3) && pages.contains(400));
+        assert_eq!((options.origin, options.order), (Origin::TopLeft, Order::Layout));
+        assert!(options.skip_invisible && !options.skip_artifacts);
+        assert_eq!((options.min_font_size, options.line_tolerance, options.threads), (Some(6.0), 0.5, Some(2)));
+        assert_eq!(options.password.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn rejects_settings_that_contradict_each_other() {
+        assert!(invalid(ExtractOptions::builder().normalize(false).nbsp_as_space(true)).contains("normalized"));
+        assert!(invalid(ExtractOptions::builder().granularity(Granularity::Chars).merge_fragments(true)).contains("single characters"));
+        assert!(invalid(ExtractOptions::builder().min_font_size(Some(12.0)).max_font_size(Some(8.0))).contains("larger than the largest"));
+    }
+
+    #[test]
+    fn rejects_settings_out_of_range() {
+        assert!(invalid(ExtractOptions::builder().pages("3-1")).contains("ends before it starts"));
+        assert!(invalid(ExtractOptions::builder().skip_rotated(Some(120.0))).contains("90 degrees"));
+        assert!(invalid(ExtractOptions::builder().min_font_size(Some(-1.0))).contains("0 or more"));
+        assert!(invalid(ExtractOptions::builder().line_tolerance(f32::NAN)).contains("line tolerance"));
+        assert!(invalid(ExtractOptions::builder().paragraph_gap(0.5)).contains("paragraph gap"));
+        assert!(invalid(ExtractOptions::builder().threads(Some(0))).contains("thread"));
+    }
+
+    #[test]
+    fn tells_the_first_error_of_the_builder() {
+        let message = invalid(ExtractOptions::builder().pages("0").pages("x").threads(Some(0)));
+        assert!(message.contains("\"0\""), "{}", message);
+    }
+}
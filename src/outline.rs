@@ -0,0 +1,166 @@
+//! The document outline, or bookmarks (PDF 32000-1:2008, 12.3.3): a tree of titles, each
+//! leading to a page, such as a report's table of contents.
+
+use crate::decode_text_string;
+use crate::destination::Destinations;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How deeply outline items may nest before the rest of a branch is ignored.
+const MAX_DEPTH: usize = 256;
+
+/// An entry of the outline, with the entries under it.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct OutlineItem {
+    pub title: String,
+    /// The page the entry leads to, counted from 1, or `None` when its destination is missing,
+    /// broken, or not in the document, such as a link to a web page.
+    pub page: Option<u32>,
+    /// How many entries it is under: 0 at the top level.
+    pub depth: usize,
+    /// Whether its children are shown when the document is opened, rather than collapsed.
+    pub open: bool,
+    pub children: Vec<OutlineItem>,
+}
+
+/// Reads the outline of a document, in order, given the numbers of its pages by page object.
+/// Documents without one give an empty list.
+pub(crate) fn read(doc: &Document, pages: &HashMap<ObjectId, u32>) -> Vec<OutlineItem> {
+    let Ok(outlines) = doc.catalog().and_then(|catalog| catalog.get_deref(b"Outlines", doc)).and_then(Object::as_dict) else {
+        return Vec::new();
+    };
+    let mut walker = Walker { doc, destinations: Destinations { doc, pages }, visited: HashSet::new() };
+    walker.items(outlines, 0)
+}
+
+struct Walker<'a> {
+    doc: &'a Document,
+    destinations: Destinations<'a>,
+    /// Items already walked, so a sibling or child link back to one ends the list there.
+    visited: HashSet<ObjectId>,
+}
+
+impl Walker<'_> {
+    /// The children of an item or of the outline dictionary, from `/First` along `/Next`.
+    fn items(&mut self, parent: &Dictionary, depth: usize) -> Vec<OutlineItem> {
+        let mut items = Vec::new();
+        if depth > MAX_DEPTH {
+            return items;
+        }
+        let mut next = parent.get(b"First").and_then(Object::as_reference).ok();
+        while let Some(id) = next.filter(|id| self.visited.insert(*id)) {
+            let Ok(item) = self.doc.get_dictionary(id) else { break };
+            let title = item.get_deref(b"Title", self.doc).and_then(Object::as_str).map(decode_text_string).unwrap_or_default();
+            // A negative count is that of the descendants hidden while the item is closed.
+            let open = item.get(b"Count").and_then(Object::as_i64).is_ok_and(|count| count > 0);
+            let page = self.item_page(item);
+            let children = self.items(item, depth + 1);
+            items.push(OutlineItem { title, page, depth, open, children });
+            next = item.get(b"Next").and_then(Object::as_reference).ok();
+        }
+        items
+    }
+
+    /// The page of an item's `/Dest`, or of the destination of its `/A` go-to action.
+    fn item_page(&self, item: &Dictionary) -> Option<u32> {
+        if let Ok(dest) = item.get(b"Dest") {
+            return self.destinations.page(dest);
+        }
+        self.destinations.action_page(item.get_deref(b"A", self.doc).and_then(Object::as_dict).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use lopdf::dictionary;
+
+    /// Adds `items` to the outline under `parent`, linked in order, and returns their objects.
+    fn add_items(doc: &mut Document, parent: ObjectId, items: Vec<Dictionary>) -> Vec<ObjectId> {
+        let ids: Vec<ObjectId> = items.iter().map(|_| doc.new_object_id()).collect();
+        for (index, mut item) in items.into_iter().enumerate() {
+            item.set("Parent", parent);
+            if let Some(&next) = ids.get(index + 1) {
+                item.set("Next", next);
+            }
+            doc.objects.insert(ids[index], Object::Dictionary(item));
+        }
+        let parent = doc.get_dictionary_mut(parent).unwrap();
+        parent.set("First", ids[0]);
+        parent.set("Last", ids[ids.len() - 1]);
+        ids
+    }
+
+    fn titled(title: &str, entries: Dictionary) -> Dictionary {
+        let mut item = dictionary! { "Title" => Object::string_literal(title) };
+        item.extend(&entries);
+        item
+    }
+
+    /// The title, page, depth and openness of each item, depth first.
+    fn flatten(items: &[OutlineItem]) -> Vec<(&str, Option<u32>, usize, bool)> {
+        items.iter().flat_map(|item| std::iter::once((item.title.as_str(), item.page, item.depth, item.open)).chain(flatten(&item.children))).collect()
+    }
+
+    #[test]
+    fn reads_the_nesting_and_pages_of_the_outline() {
+        let mut fixture = Fixture::new();
+        fixture.page("").page("").page("");
+        let mut doc = fixture.document(Dictionary::new());
+        let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+        let page = |page_num: u32| pages.iter().find_map(|(&id, &num)| (num == page_num).then_some(id)).unwrap();
+        let (first, second, third) = (page(1), page(2), page(3));
+        let root = doc.add_object(dictionary! { "Type" => "Outlines" });
+        doc.catalog_mut().unwrap().set("Outlines", root);
+        doc.catalog_mut().unwrap().set("Dests", dictionary! { "data" => vec![second.into(), "FitH".into(), 700.into()] });
+        let top = add_items(
+            &mut doc,
+            root,
+            vec![
+                titled("Introduction", dictionary! { "Dest" => vec![first.into(), "XYZ".into(), 0.into(), 792.into(), 0.into()] }),
+                titled("Methods", dictionary! { "Count" => 2 }),
+                titled("Results", dictionary! { "Count" => -1 }),
+            ],
+        );
+        add_items(
+            &mut doc,
+            top[1],
+            vec![
+                // By name, and by a go-to action.
+                titled("Data", dictionary! { "Dest" => "data" }),
+                titled("Analysis", dictionary! { "A" => dictionary! { "S" => "GoTo", "D" => vec![third.into(), "Fit".into()] } }),
+            ],
+        );
+        add_items(&mut doc, top[2], vec![titled("Online appendix", dictionary! { "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") } })]);
+
+        let outline = read(&doc, &pages);
+        assert_eq!(
+            flatten(&outline),
+            [
+                ("Introduction", Some(1), 0, false),
+                ("Methods", None, 0, true),
+                ("Data", Some(2), 1, false),
+                ("Analysis", Some(3), 1, false),
+                ("Results", None, 0, false),
+                ("Online appendix", None, 1, false),
+            ]
+        );
+        assert_eq!(outline.len(), 3);
+    }
+
+    #[test]
+    fn ends_a_list_that_loops_back() {
+        let mut fixture = Fixture::new();
+        fixture.page("");
+        let mut doc = fixture.document(Dictionary::new());
+        let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(page_num, id)| (id, page_num)).collect();
+        let root = doc.add_object(dictionary! { "Type" => "Outlines" });
+        doc.catalog_mut().unwrap().set("Outlines", root);
+        let items = add_items(&mut doc, root, vec![titled("One", Dictionary::new()), titled("Two", Dictionary::new())]);
+        doc.get_dictionary_mut(items[1]).unwrap().set("Next", items[0]);
+        assert_eq!(flatten(&read(&doc, &pages)).iter().map(|item| item.0).collect::<Vec<_>>(), ["One", "Two"]);
+    }
+}
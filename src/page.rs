@@ -0,0 +1,315 @@
+//! Page attributes that decide how page space is shown (PDF 32000-1:2008, 7.7.3.3 and 14.11.2),
+//! and the choice of pages to read.
+
+use crate::matrix::{Matrix, Rect};
+use crate::Error;
+use lopdf::{Document, Object, ObjectId};
+use std::str::FromStr;
+
+/// How many page tree levels are searched for an inherited attribute.
+const MAX_TREE_DEPTH: usize = 64;
+
+/// US Letter, which readers assume for pages without a usable `/MediaBox`.
+const DEFAULT_MEDIA_BOX: Rect = Rect { x0: 0.0, y0: 0.0, x1: 612.0, y1: 792.0 };
+
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// The boundaries of the physical medium, in (unrotated) page space.
+    pub media_box: Rect,
+    /// The visible region: `/CropBox` clipped to the media box, or the media box itself.
+    pub crop_box: Rect,
+    /// Clockwise rotation for display: 0, 90, 180 or 270.
+    pub rotation: u16,
+}
+
+impl Page {
+    pub fn load(doc: &Document, page_id: ObjectId, page_num: u32) -> Page {
+        let media_box = inherited(doc, page_id, b"MediaBox").and_then(|obj| read_rect(doc, obj)).unwrap_or(DEFAULT_MEDIA_BOX);
+        let crop_box = inherited(doc, page_id, b"CropBox")
+            .and_then(|obj| read_rect(doc, obj))
+            .and_then(|crop_box| crop_box.intersection(&media_box))
+            .unwrap_or(media_box);
+        let rotate = inherited(doc, page_id, b"Rotate").and_then(|obj| obj.as_i64().ok()).unwrap_or(0);
+        let rotation = match rotate.rem_euclid(360) {
+            degrees @ (0 | 90 | 180 | 270) => degrees as u16,
+            _ => {
+                log::warn!("/Rotate {} is not a multiple of 90 on page {}, ignoring it", rotate, page_num);
+                0
+            }
+        };
+        Page { media_box, crop_box, rotation }
+    }
+
+    /// Width of the crop box as displayed, i.e. after rotation.
+    pub fn width(&self) -> f32 {
+        if self.rotation.is_multiple_of(180) { self.crop_box.width() } else { self.crop_box.height() }
+    }
+
+    pub fn height(&self) -> f32 {
+        if self.rotation.is_multiple_of(180) { self.crop_box.height() } else { self.crop_box.width() }
+    }
+
+    /// Maps page space to the upright space the page is displayed in: rotated by
+    /// `rotation` and moved back so the crop box keeps its lower-left corner.
+    pub fn upright_matrix(&self) -> Matrix {
+        let Rect { x0, y0, x1, y1 } = self.crop_box;
+        match self.rotation {
+            90 => Matrix::new(0.0, -1.0, 1.0, 0.0, x0 - y0, y0 + x1),
+            180 => Matrix::new(-1.0, 0.0, 0.0, -1.0, x0 + x1, y0 + y1),
+            270 => Matrix::new(0.0, 1.0, -1.0, 0.0, x0 + y1, y0 - x0),
+            _ => Matrix::IDENTITY,
+        }
+    }
+
+    /// A page-space rectangle, such as one of the boxes, in upright space.
+    pub fn upright(&self, rect: &Rect) -> Rect {
+        Rect::transformed(rect.x0, rect.y0, rect.x1, rect.y1, &self.upright_matrix())
+    }
+
+    /// Maps upright space to one whose origin is the top-left corner of the crop box, with y
+    /// growing downwards.
+    pub fn top_left_matrix(&self) -> Matrix {
+        let crop_box = self.upright(&self.crop_box);
+        Matrix::new(1.0, 0.0, 0.0, -1.0, -crop_box.x0, crop_box.y1)
+    }
+
+    /// An upright rectangle in top-left space; `(x0, y0)` becomes its top-left corner.
+    pub fn top_left(&self, rect: &Rect) -> Rect {
+        Rect::transformed(rect.x0, rect.y0, rect.x1, rect.y1, &self.top_left_matrix())
+    }
+}
+
+/// Pages chosen by number, counted from 1, as given to `--pages`: comma-separated pages and
+/// ranges such as `1-5,12,40-`, where a range without an end runs to the last page. Pages
+/// and ranges prefixed `label:`, such as `label:iv-vi` or `label:A-1`, name pages by their
+/// labels, and are only found once the selection is resolved against a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSelection {
+    /// First and last page of each range, `None` for the last page of the document.
+    ranges: Vec<(u32, Option<u32>)>,
+    /// The text after `label:` of each range named by labels, not yet resolved.
+    labels: Vec<String>,
+}
+
+impl PageSelection {
+    /// Whether the page is chosen by number, or by a label the selection was resolved with.
+    pub fn contains(&self, page_num: u32) -> bool {
+        self.ranges.iter().any(|&(first, last)| first <= page_num && last.is_none_or(|last| page_num <= last))
+    }
+
+    /// Checks that every page named is in a document of `page_count` pages.
+    pub fn check(&self, page_count: usize) -> Result<(), Error> {
+        for &(first, last) in &self.ranges {
+            let page = last.unwrap_or(first);
+            if page as usize > page_count {
+                return Err(Error::PageOutOfRange { page, page_count });
+            }
+        }
+        Ok(())
+    }
+
+    /// The selection with the ranges named by labels turned into page numbers, given the
+    /// label of each page in order. A label given to several pages stands for the first.
+    /// Labels may hold hyphens, so a range is split at the first hyphen that leaves a label,
+    /// or nothing, on either side.
+    pub(crate) fn resolve(&self, labels: &[String]) -> Result<PageSelection, Error> {
+        let page = |label: &str| labels.iter().position(|page| page == label).map(|index| index as u32 + 1);
+        let mut ranges = self.ranges.clone();
+        for text in &self.labels {
+            let range = page(text).map(|page| (page, Some(page))).or_else(|| {
+                text.match_indices('-').find_map(|(at, _)| {
+                    let (first, last) = (&text[..at], &text[at + 1..]);
+                    match last.is_empty() {
+                        true => Some((page(first)?, None)),
+                        false => Some((page(first)?, Some(page(last)?))),
+                    }
+                })
+            });
+            match range {
+                Some((first, Some(last))) if last < first => {
+                    return Err(Error::InvalidOptions(format!("pages label:{} end before they start", text)));
+                }
+                Some(range) => ranges.push(range),
+                None => return Err(Error::UnknownPageLabel(text.clone())),
+            }
+        }
+        Ok(PageSelection { ranges, labels: Vec::new() })
+    }
+}
+
+impl FromStr for PageSelection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<PageSelection, String> {
+        let page = |text: &str| match text.trim().parse() {
+            Ok(0) | Err(_) => Err(format!("expected a page number from 1, or label: and a page label, got {:?}", text.trim())),
+            Ok(page) => Ok(page),
+        };
+        let mut ranges = Vec::new();
+        let mut labels = Vec::new();
+        for part in value.split(',') {
+            if let Some(label) = part.trim().strip_prefix("label:") {
+                if label.is_empty() {
+                    return Err("expected a page label after label:".to_string());
+                }
+                labels.push(label.to_string());
+                continue;
+            }
+            let range = match part.split_once('-') {
+                Some((first, last)) if last.trim().is_empty() => (page(first)?, None),
+                Some((first, last)) => (page(first)?, Some(page(last)?)),
+                None => (page(part)?, Some(page(part)?)),
+            };
+            if let (first, Some(last)) = range {
+                if last < first {
+                    return Err(format!("range {:?} ends before it starts", part.trim()));
+                }
+            }
+            ranges.push(range);
+        }
+        Ok(PageSelection { ranges, labels })
+    }
+}
+
+/// Looks up an attribute on the page or, failing that, the nearest ancestor that has it.
+fn inherited<'a>(doc: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_dictionary(page_id).ok()?;
+    for _ in 0..MAX_TREE_DEPTH {
+        if let Ok(value) = node.get_deref(key, doc) {
+            return Some(value);
+        }
+        node = node.get_deref(b"Parent", doc).and_then(Object::as_dict).ok()?;
+    }
+    None
+}
+
+/// Reads a rectangle array, possibly indirect, whose corners may come in either order.
+pub fn read_rect(doc: &Document, obj: &Object) -> Option<Rect> {
+    let array = obj.as_array().ok()?;
+    if array.len() != 4 {
+        return None;
+    }
+    let mut values = [0.0f32; 4];
+    for (value, obj) in values.iter_mut().zip(array) {
+        *value = doc.dereference(obj).ok()?.1.as_float().ok()?;
+    }
+    let [ax, ay, bx, by] = values;
+    Some(Rect { x0: ax.min(bx), y0: ay.min(by), x1: ax.max(bx), y1: ay.max(by) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, near, Fixture};
+    use crate::{ExtractOptions, PageItems};
+    use lopdf::{dictionary, Dictionary};
+
+    const CONTENT: &str = "BT /F1 12 Tf 100 700 Td (Top) Tj ET";
+
+    /// The page showing `CONTENT`, `/Rotate` set to `rotate` on the page itself, or on the
+    /// Pages node it inherits from.
+    fn rotated(rotate: i64, inherited: bool) -> PageItems {
+        let mut fixture = Fixture::new();
+        if inherited {
+            fixture.page(CONTENT);
+        } else {
+            fixture.page_with(CONTENT, dictionary! { "Rotate" => rotate });
+        }
+        let mut doc = fixture.document(Dictionary::new());
+        if inherited {
+            let pages = doc.catalog().unwrap().get(b"Pages").unwrap().as_reference().unwrap();
+            doc.get_dictionary_mut(pages).unwrap().set("Rotate", rotate);
+        }
+        testing::pages_with(&testing::save(doc), &ExtractOptions::default()).remove(0)
+    }
+
+    #[test]
+    fn turns_the_text_of_each_rotation_upright() {
+        // Shown 90 degrees clockwise, the left edge of the page is at the top; and so on.
+        let expected = [
+            (0, 612.0, 792.0, 100.0, 700.0, 0.0),
+            (90, 792.0, 612.0, 700.0, 512.0, -90.0),
+            (180, 612.0, 792.0, 512.0, 92.0, 180.0),
+            (270, 792.0, 612.0, 92.0, 100.0, 90.0),
+        ];
+        for (rotate, width, height, x, y, angle) in expected {
+            for inherited in [false, true] {
+                let page = rotated(rotate, inherited);
+                let item = &page.items[0];
+                assert_eq!(page.rotation, rotate as u16);
+                assert!(near(page.width, width) && near(page.height, height), "/Rotate {}: {} x {}", rotate, page.width, page.height);
+                assert!(near(item.x, x) && near(item.y, y), "/Rotate {}: ({}, {})", rotate, item.x, item.y);
+                assert!(near(item.angle, angle), "/Rotate {}: angle {}", rotate, item.angle);
+            }
+        }
+    }
+
+    #[test]
+    fn normalizes_the_rotation() {
+        let load = |rotate: i64| {
+            let mut fixture = Fixture::new();
+            fixture.page_with(CONTENT, dictionary! { "Rotate" => rotate });
+            let doc = fixture.document(Dictionary::new());
+            let page_id = doc.page_iter().next().unwrap();
+            Page::load(&doc, page_id, 1).rotation
+        };
+        assert_eq!((load(-90), load(450), load(720), load(45)), (270, 90, 0, 0));
+    }
+
+    #[test]
+    fn prefers_the_page_rotation_to_the_inherited_one() {
+        let mut fixture = Fixture::new();
+        fixture.page_with(CONTENT, dictionary! { "Rotate" => 180 });
+        let mut doc = fixture.document(Dictionary::new());
+        let pages = doc.catalog().unwrap().get(b"Pages").unwrap().as_reference().unwrap();
+        doc.get_dictionary_mut(pages).unwrap().set("Rotate", 90);
+        let page_id = doc.page_iter().next().unwrap();
+        assert_eq!(Page::load(&doc, page_id, 1).rotation, 180);
+    }
+
+    fn pages(selection: &str, count: u32) -> Vec<u32> {
+        let selection: PageSelection = selection.parse().unwrap();
+        (1..=count).filter(|&page| selection.contains(page)).collect()
+    }
+
+    #[test]
+    fn parses_pages_and_ranges() {
+        assert_eq!(pages("1-5,12,40-", 42), [1, 2, 3, 4, 5, 12, 40, 41, 42]);
+        assert_eq!(pages(" 3 , 7 - 8 ", 10), [3, 7, 8]);
+        assert_eq!(pages("2-2,1", 3), [1, 2]);
+        assert_eq!(pages("5-", 3), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn rejects_malformed_selections() {
+        for selection in ["", "0", "1-0", "5-3", "a", "1,,2", "-3", "1-2-3", "label:"] {
+            assert!(selection.parse::<PageSelection>().is_err(), "{:?} parses", selection);
+        }
+        assert_eq!("5-3".parse::<PageSelection>().unwrap_err(), "range \"5-3\" ends before it starts");
+    }
+
+    #[test]
+    fn checks_the_pages_are_in_the_document() {
+        let selection: PageSelection = "1-5,12,40-".parse().unwrap();
+        assert!(selection.check(40).is_ok());
+        assert!(matches!(selection.check(39), Err(Error::PageOutOfRange { page: 40, page_count: 39 })));
+        assert!(matches!("2-9".parse::<PageSelection>().unwrap().check(5), Err(Error::PageOutOfRange { page: 9, page_count: 5 })));
+        let message = "12".parse::<PageSelection>().unwrap().check(3).unwrap_err().to_string();
+        assert!(message.contains("12") && message.contains('3'), "{}", message);
+    }
+
+    #[test]
+    fn resolves_page_labels() {
+        let labels: Vec<String> = ["i", "ii", "iii", "1", "2", "A-1", "A-2"].iter().map(|label| label.to_string()).collect();
+        let resolve = |selection: &str| {
+            let selection = selection.parse::<PageSelection>().unwrap().resolve(&labels)?;
+            Ok::<_, Error>((1..=7).filter(|&page| selection.contains(page)).collect::<Vec<u32>>())
+        };
+        assert_eq!(resolve("label:ii-1").unwrap(), [2, 3, 4]);
+        assert_eq!(resolve("label:A-1").unwrap(), [6]);
+        assert_eq!(resolve("label:A-1-A-2,1").unwrap(), [1, 6, 7]);
+        assert_eq!(resolve("label:2-").unwrap(), [5, 6, 7]);
+        assert!(matches!(resolve("label:x"), Err(Error::UnknownPageLabel(label)) if label == "x"));
+        assert!(matches!(resolve("label:2-i"), Err(Error::InvalidOptions(_))));
+    }
+}
@@ -0,0 +1,141 @@
+//! The pages of a document as its page tree describes them, read without their content: for
+//! telling how many pages a document has and how big they are, fast, however large it is.
+
+use crate::matrix::Rect;
+use crate::page::Page;
+use crate::{read_document, selected_pages, with_mapped, Error, ExtractOptions, Origin};
+use lopdf::{Document, Object, ObjectId};
+use serde::Serialize;
+use std::path::Path;
+
+/// The pages of a document, from its page tree and the attributes pages inherit from it.
+/// Reading it parses the document's objects but keeps no stream's data, so that content
+/// streams, fonts and images are never decompressed, nor even copied.
+///
+/// ```no_run
+/// use rapid_pdf::{ExtractOptions, PageTree};
+///
+/// let tree = PageTree::from_path("report.pdf", &ExtractOptions::default())?;
+/// let landscape = tree.pages.iter().filter(|page| page.width > page.height).count();
+/// println!("{} pages, {} of them landscape", tree.page_count, landscape);
+/// # Ok::<(), rapid_pdf::Error>(())
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct PageTree {
+    /// The PDF version of the header, such as `1.7`.
+    pub version: String,
+    /// How many pages the document has, selected or not.
+    pub page_count: usize,
+    /// The pages `ExtractOptions::pages` selects, in order.
+    pub pages: Vec<PageAttributes>,
+}
+
+/// What the page tree tells of a page.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct PageAttributes {
+    pub page_num: u32,
+    /// The page's label from the document's `/PageLabels`, such as `iv` or `A-1`.
+    pub label: Option<String>,
+    /// Size of the crop box, turned upright by `/Rotate`.
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation for display: 0, 90, 180 or 270.
+    pub rotation: u16,
+    /// The page's boxes, upright and measured from `ExtractOptions::origin`.
+    pub media_box: Rect,
+    pub crop_box: Rect,
+    /// Whether the page has `/Contents`; a page without is blank.
+    pub has_content: bool,
+}
+
+impl PageTree {
+    /// Reads the page tree of the PDF at `path`, mapping the file into memory rather than
+    /// reading it. Of `options`, only the password, the pages and the origin are used.
+    pub fn from_path(path: impl AsRef<Path>, options: &ExtractOptions) -> Result<PageTree, Error> {
+        with_mapped(path.as_ref(), |data| PageTree::from_bytes(data, options))
+    }
+
+    /// Reads the page tree of a PDF held in memory.
+    pub fn from_bytes(data: &[u8], options: &ExtractOptions) -> Result<PageTree, Error> {
+        let doc = read_document(data, options.password.as_deref(), Some(without_stream_data))?;
+        let (pages, labels) = selected_pages(&doc, options)?;
+        let page_count = doc.get_pages().len();
+        let pages = pages
+            .into_iter()
+            .map(|(page_num, object_id)| {
+                let label = labels.as_ref().and_then(|labels| labels.get(page_num as usize - 1)).cloned();
+                PageAttributes::read(&doc, object_id, page_num, label, options.origin)
+            })
+            .collect();
+        Ok(PageTree { version: doc.version.clone(), page_count, pages })
+    }
+}
+
+impl PageAttributes {
+    fn read(doc: &Document, object_id: ObjectId, page_num: u32, label: Option<String>, origin: Origin) -> PageAttributes {
+        let page = Page::load(doc, object_id, page_num);
+        let (media_box, crop_box) = (page.upright(&page.media_box), page.upright(&page.crop_box));
+        let (media_box, crop_box) = match origin {
+            Origin::BottomLeft => (media_box, crop_box),
+            Origin::TopLeft => (page.top_left(&media_box), page.top_left(&crop_box)),
+        };
+        let contents = doc.get_dictionary(object_id).ok().and_then(|dict| dict.get(b"Contents").ok());
+        let has_content = match contents {
+            Some(Object::Reference(id)) => doc.objects.contains_key(id),
+            Some(Object::Array(streams)) => !streams.is_empty(),
+            Some(Object::Null) | None => false,
+            Some(_) => true,
+        };
+        PageAttributes { page_num, label, width: page.width(), height: page.height(), rotation: page.rotation, media_box, crop_box, has_content }
+    }
+}
+
+/// Keeps the objects of a document as read, but for the data of streams: only their
+/// dictionary is kept, but for object streams, which hold the objects the page tree may be
+/// made of.
+fn without_stream_data(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        if stream.dict.get(b"Type").and_then(Object::as_name).ok() != Some(b"ObjStm") {
+            let dict = std::mem::take(&mut stream.dict);
+            *object = Object::Dictionary(dict);
+        }
+        // As with `without_image_data`, the object is changed in place.
+        return Some((id, Object::Null));
+    }
+    Some((id, object.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::DECOMPRESSED;
+    use crate::testing::Fixture;
+    use crate::Extractor;
+    use lopdf::{dictionary, Stream};
+
+    #[test]
+    fn reads_no_stream() {
+        let mut fixture = Fixture::new();
+        let first = fixture.object(Stream::new(dictionary! { "Filter" => "FlateDecode" }, b"not flate data".to_vec()));
+        let second = fixture.object(Stream::new(dictionary! { "Filter" => "FlateDecode" }, b"not flate data".to_vec()));
+        fixture.page_with("", dictionary! { "Contents" => first });
+        fixture.page_with("", dictionary! { "Contents" => second, "Rotate" => 90 });
+        let data = fixture.bytes();
+        let options = ExtractOptions::builder().threads(Some(1)).build().unwrap();
+
+        DECOMPRESSED.with(|count| count.set(0));
+        let tree = PageTree::from_bytes(&data, &options).unwrap();
+        assert_eq!(DECOMPRESSED.with(|count| count.get()), 0);
+        assert_eq!(tree.page_count, 2);
+        let sizes: Vec<(f32, f32, u16, bool)> = tree.pages.iter().map(|page| (page.width, page.height, page.rotation, page.has_content)).collect();
+        assert_eq!(sizes, [(612.0, 792.0, 0, true), (792.0, 612.0, 90, true)]);
+
+        // Reading the text decompresses them, and fails to.
+        let extractor = Extractor::from_bytes(&data, &options).unwrap();
+        let pages: Vec<_> = extractor.page_items().collect::<Result<_, _>>().unwrap();
+        assert_eq!(DECOMPRESSED.with(|count| count.get()), 2);
+        assert!(pages.iter().all(|page| page.errors.len() == 1));
+    }
+}
@@ -0,0 +1,85 @@
+//! Path construction (PDF 32000-1:2008, 8.5.2), kept only as far as finding ruling lines,
+//! the straight, axis-aligned strokes and thin filled rectangles tables are drawn with, and
+//! the areas fills cover.
+
+use crate::layout::tables::MAX_RULE_THICKNESS;
+use crate::matrix::{Matrix, Rect};
+
+/// How far, in points, a segment may lean and still count as horizontal or vertical.
+const AXIS_TOLERANCE: f32 = 0.5;
+
+#[derive(Debug, Default)]
+pub struct Path {
+    /// Subpaths as page-space points, and whether each was closed.
+    subpaths: Vec<(Vec<(f32, f32)>, bool)>,
+}
+
+impl Path {
+    /// `m`: begin a new subpath.
+    pub fn move_to(&mut self, x: f32, y: f32, ctm: &Matrix) {
+        self.subpaths.push((vec![ctm.transform_point(x, y)], false));
+    }
+
+    /// `l`: a straight segment from the current point.
+    pub fn line_to(&mut self, x: f32, y: f32, ctm: &Matrix) {
+        match self.subpaths.last_mut() {
+            Some((points, false)) => points.push(ctm.transform_point(x, y)),
+            _ => self.move_to(x, y, ctm),
+        }
+    }
+
+    /// `c` / `v` / `y`: a curve, which is never a rule, so only its end point is kept.
+    pub fn curve_to(&mut self, x: f32, y: f32, ctm: &Matrix) {
+        self.move_to(x, y, ctm);
+    }
+
+    /// `re`: a closed rectangle subpath.
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, ctm: &Matrix) {
+        let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+        self.subpaths.push((corners.iter().map(|&(x, y)| ctm.transform_point(x, y)).collect(), true));
+    }
+
+    /// `h`: close the current subpath.
+    pub fn close(&mut self) {
+        if let Some((_, closed)) = self.subpaths.last_mut() {
+            *closed = true;
+        }
+    }
+
+    /// Ends the path with a painting operator, returning the rules it draws: its straight
+    /// axis-aligned segments if stroked, its thin rectangles if filled.
+    pub fn paint(&mut self, stroke: bool, fill: bool) -> Vec<Rect> {
+        let mut rules = Vec::new();
+        for (points, closed) in std::mem::take(&mut self.subpaths) {
+            if stroke {
+                let closing = (closed && points.len() > 2).then(|| (points[points.len() - 1], points[0]));
+                for (start, end) in points.windows(2).map(|pair| (pair[0], pair[1])).chain(closing) {
+                    if (start.0 - end.0).abs() <= AXIS_TOLERANCE || (start.1 - end.1).abs() <= AXIS_TOLERANCE {
+                        rules.push(Rect::transformed(start.0, start.1, end.0, end.1, &Matrix::IDENTITY));
+                    }
+                }
+            }
+            if fill && points.len() >= 4 {
+                let bounds = points.iter().skip(1).fold(Rect { x0: points[0].0, y0: points[0].1, x1: points[0].0, y1: points[0].1 }, |rect, &(x, y)| {
+                    rect.union(&Rect { x0: x, y0: y, x1: x, y1: y })
+                });
+                if bounds.width().min(bounds.height()) <= MAX_RULE_THICKNESS {
+                    rules.push(bounds);
+                }
+            }
+        }
+        rules
+    }
+
+    /// The box enclosing every point of the path, or `None` for an empty path.
+    pub fn bounds(&self) -> Option<Rect> {
+        let mut points = self.subpaths.iter().flat_map(|(points, _)| points);
+        let &(x, y) = points.next()?;
+        Some(points.fold(Rect { x0: x, y0: y, x1: x, y1: y }, |rect, &(x, y)| rect.union(&Rect { x0: x, y0: y, x1: x, y1: y })))
+    }
+
+    /// `n`: end the path without painting it.
+    pub fn clear(&mut self) {
+        self.subpaths.clear();
+    }
+}
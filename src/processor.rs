@@ -0,0 +1,1516 @@
+//! Walking a page's content stream (PDF 32000-1:2008, 8 and 9) to collect the text items it
+//! shows and the ruling lines it draws.
+
+use crate::color::{ColorSpace, Rgb};
+use crate::font::{self, Font, FontCache, FontMap};
+use crate::matrix::{Matrix, Rect};
+use crate::page::Page;
+use crate::resources::Resources;
+use crate::images::{self, PlacedImage};
+use crate::operations::{ContentOperation, ShownText};
+use crate::{content, decode_text_string, normalize, path, structure, Baseline, ExtractOptions, Granularity, Limit, Origin, PageError, TextItem};
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, ObjectId};
+use std::io::Read;
+use std::time::Instant;
+use std::sync::Arc;
+
+/// A `TJ` adjustment moving the text forward by more than this, in thousandths of an em, about
+/// as wide as a character and more than any word space, ends the item: the text after it is an
+/// item of its own, where it is drawn, rather than part of one stretched over the gap.
+const TJ_ITEM_BREAK: f32 = 500.0;
+
+/// The `Tr` mode that paints nothing (PDF 32000-1:2008, 9.3.6).
+const RENDER_MODE_INVISIBLE: u8 = 3;
+
+/// Joins the streams of a page's `/Contents` array into one buffer. Producers may split the
+/// page's content anywhere, even inside an operation, so the streams are only meaningful
+/// decoded as a whole; a space keeps the last token of one from running into the next.
+/// Streams that fail to decompress are skipped with a warning, and those cut short read as
+/// far as they go; either is added to `errors`. A stream over `max_size` bytes decompressed
+/// ends the content there.
+pub(crate) fn page_content(doc: &Document, page_id: ObjectId, page_num: u32, max_size: usize, errors: &mut Vec<PageError>) -> Vec<u8> {
+    let mut content = Vec::new();
+    for id in doc.get_page_contents(page_id) {
+        let data = match doc.get_object(id).and_then(Object::as_stream) {
+            Ok(stream) => stream_data(stream, max_size),
+            Err(err) => Err(StreamError { message: err.to_string(), data: Vec::new(), too_large: false }),
+        };
+        let data = match data {
+            Ok(data) => data,
+            Err(err) if err.too_large => {
+                let err = PageError::limit(page_num, Limit::StreamSize(max_size));
+                log::warn!("{}", err);
+                errors.push(err);
+                break;
+            }
+            Err(err) => {
+                if err.data.is_empty() {
+                    log::warn!("skipping content stream {} {} R on page {}: {}", id.0, id.1, page_num, err.message);
+                } else {
+                    log::warn!("reading only the start of content stream {} {} R on page {}: {}", id.0, id.1, page_num, err.message);
+                }
+                errors.push(PageError { page: page_num, message: format!("content stream {} {} R: {}", id.0, id.1, err.message), limit: None });
+                err.data
+            }
+        };
+        if data.is_empty() {
+            continue;
+        }
+        if !content.is_empty() {
+            content.push(b' ');
+        }
+        content.extend_from_slice(&data);
+    }
+    content
+}
+
+/// Why a stream could not be decompressed, with what of it could be.
+pub(crate) struct StreamError {
+    pub message: String,
+    pub data: Vec<u8>,
+    /// Whether the stream decompresses to more than was allowed, leaving no data.
+    pub too_large: bool,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// How many streams `stream_data` was asked for on this thread, for tests to tell which
+    /// reads decompress none.
+    pub(crate) static DECOMPRESSED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Decompressed data of a stream of at most `max_size` bytes. Unlike `get_plain_content`,
+/// which falls back to the raw bytes for unsupported filters, this fails; corrupt Flate data,
+/// which lopdf decodes to nothing, fails too, as does Flate data cut short, which lopdf
+/// decodes as far as it goes.
+pub(crate) fn stream_data(stream: &lopdf::Stream, max_size: usize) -> Result<Vec<u8>, StreamError> {
+    #[cfg(test)]
+    DECOMPRESSED.with(|count| count.set(count.get() + 1));
+    let error = |message: String, data: Vec<u8>| StreamError { message, data, too_large: false };
+    let checked = |data: Vec<u8>| match data.len() > max_size {
+        true => Err(StreamError { message: String::new(), data: Vec::new(), too_large: true }),
+        false => Ok(data),
+    };
+    if stream.dict.get(b"Filter").is_err() || stream.content.is_empty() {
+        return checked(stream.content.clone());
+    }
+    // lopdf does not tell where Flate data stops short, nor stop at a size, so the common case
+    // of Flate alone is decompressed here, a byte past the limit at most.
+    if stream.filters().is_ok_and(|filters| filters == [b"FlateDecode"]) && stream.dict.get(b"DecodeParms").is_err() {
+        let mut data = Vec::new();
+        let decoder = flate2::read::ZlibDecoder::new(stream.content.as_slice());
+        return match decoder.take(max_size as u64 + 1).read_to_end(&mut data) {
+            Ok(_) => checked(data),
+            Err(_) if data.is_empty() => Err(error("stream data is corrupt".to_string(), data)),
+            Err(err) => Err(error(format!("stream data is cut short: {}", err), data)),
+        };
+    }
+    match stream.decompressed_content() {
+        Ok(data) if data.is_empty() => Err(error("stream data is corrupt".to_string(), data)),
+        Ok(data) => checked(data),
+        Err(err) => Err(error(err.to_string(), Vec::new())),
+    }
+}
+
+/// Graphics state parameters saved and restored by `q`/`Q` (PDF 32000-1:2008, 8.4).
+/// The text state parameters (9.3) are part of it; the text matrices are not.
+#[derive(Debug, Clone)]
+struct GraphicsState {
+    /// The current transformation matrix, mapping user space to page space.
+    ctm: Matrix,
+    /// Resource name of the font selected by `Tf`.
+    font: Option<Vec<u8>>,
+    font_size: f32,
+    leading: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    /// `Tz` as a fraction: 1.0 for the default `100 Tz`.
+    horizontal_scaling: f32,
+    /// `Ts`: how far the baseline is raised, in unscaled text space units.
+    rise: f32,
+    /// `Tr`: the text rendering mode.
+    render_mode: u8,
+    /// Whether the font selected by `Tf` writes vertically (`/WMode 1`).
+    vertical: bool,
+    /// The nonstroking color space and color, which text is filled with; `None` for colors
+    /// that cannot be told, such as patterns.
+    fill_space: ColorSpace,
+    fill_color: Option<Rgb>,
+}
+
+impl Default for GraphicsState {
+    fn default() -> GraphicsState {
+        GraphicsState {
+            ctm: Matrix::IDENTITY,
+            font: None,
+            font_size: 0.0,
+            leading: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            rise: 0.0,
+            render_mode: 0,
+            vertical: false,
+            fill_space: ColorSpace::Gray,
+            fill_color: Some(Rgb::BLACK),
+        }
+    }
+}
+
+/// Text state tracked while walking a content stream (PDF 32000-1:2008, 9.3 and 9.4).
+struct TextState {
+    graphics: GraphicsState,
+    /// Graphics states pushed by `q`.
+    saved: Vec<GraphicsState>,
+    /// The text matrix `Tm`.
+    matrix: Matrix,
+    /// The text line matrix `Tlm`: where the current line started.
+    line_matrix: Matrix,
+    /// The graphics state the content starts with, which an unbalanced `Q` returns to: the
+    /// default one with a CTM mapping the page to upright page space, or the state a form
+    /// XObject is painted in.
+    initial: GraphicsState,
+}
+
+impl TextState {
+    fn new(initial_ctm: Matrix) -> TextState {
+        let initial = GraphicsState { ctm: initial_ctm, ..GraphicsState::default() };
+        TextState { graphics: initial.clone(), saved: Vec::new(), initial, matrix: Matrix::IDENTITY, line_matrix: Matrix::IDENTITY }
+    }
+
+    /// The state the content of a form XObject painted with `matrix` starts in: this one with
+    /// `matrix` on the CTM and nothing saved, so that the form's `Q`s cannot pop the states
+    /// its caller saved.
+    fn form(&self, matrix: Matrix) -> TextState {
+        let initial = GraphicsState { ctm: matrix.multiply(&self.graphics.ctm), ..self.graphics.clone() };
+        TextState { graphics: initial.clone(), saved: Vec::new(), initial, matrix: self.matrix, line_matrix: self.line_matrix }
+    }
+
+    /// `q`: push a copy of the current graphics state.
+    fn save(&mut self) {
+        self.saved.push(self.graphics.clone());
+    }
+
+    /// `Q`: pop the graphics state. Returns false when there was nothing to pop, in which
+    /// case the state is clamped to the initial one.
+    fn restore(&mut self) -> bool {
+        match self.saved.pop() {
+            Some(graphics) => {
+                self.graphics = graphics;
+                true
+            }
+            None => {
+                self.graphics = self.initial.clone();
+                false
+            }
+        }
+    }
+
+    /// `cm`: concatenate `matrix` onto the CTM.
+    fn concat(&mut self, matrix: Matrix) {
+        self.graphics.ctm = matrix.multiply(&self.graphics.ctm);
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix) {
+        self.matrix = matrix;
+        self.line_matrix = matrix;
+    }
+
+    /// `Td`: offset the start of the current line by `(tx, ty)` in unscaled text space.
+    fn move_line(&mut self, tx: f32, ty: f32) {
+        self.set_matrix(Matrix::translate(tx, ty).multiply(&self.line_matrix));
+    }
+
+    /// `T*`: move to the start of the next line, `leading` below the current one.
+    fn next_line(&mut self) {
+        self.move_line(0.0, -self.graphics.leading);
+    }
+
+    /// Page-space origin of the next glyph, raised by `Ts`, and the effective font size and
+    /// rise (their Tf/Ts values times the scale of text matrix × CTM).
+    fn origin(&self) -> (f32, f32, f32, f32) {
+        let matrix = self.matrix.multiply(&self.graphics.ctm);
+        let (x, y) = matrix.transform_point(0.0, self.graphics.rise);
+        let scale = matrix.vertical_scale();
+        (x, y, self.graphics.font_size * scale, self.graphics.rise * scale)
+    }
+
+    fn current_font<'a>(&self, fonts: &'a FontMap) -> Option<&'a Font> {
+        self.graphics.font.as_ref().and_then(|name| fonts.get(name)).map(Arc::as_ref)
+    }
+
+    /// Decodes a show-string operand through the current font's ToUnicode map, falling back
+    /// to the raw bytes when the font has none, with how many characters each code decodes
+    /// to, if known.
+    fn decode(&self, fonts: &FontMap, text_obj: &Object) -> (String, Vec<usize>) {
+        let Object::String(bytes, _) = text_obj else { return (String::new(), Vec::new()) };
+        self.current_font(fonts).and_then(|font| font.decode(bytes)).unwrap_or_else(|| {
+            // Bytes taken as text are a character each when they are ASCII.
+            let counts = if bytes.is_ascii() { vec![1; bytes.len()] } else { Vec::new() };
+            (text_from_bytes(bytes), counts)
+        })
+    }
+
+    /// Moves the text matrix in the writing direction by `distance` unscaled text space
+    /// units: along the baseline, or down for vertical fonts.
+    fn advance(&mut self, distance: f32) {
+        let (tx, ty) = if self.graphics.vertical { (0.0, -distance) } else { (distance, 0.0) };
+        self.matrix = Matrix::translate(tx, ty).multiply(&self.matrix);
+    }
+
+    /// How far each glyph of a string moves the text position (PDF 32000-1:2008, 9.4.4): its
+    /// width scaled by the font size, plus `Tc`, plus `Tw` for single-byte code 32, all scaled
+    /// by `Tz`. Vertical fonts advance by the glyphs' `w1y` instead, unscaled by `Tz`. Strings
+    /// in an unknown font advance as if every glyph had the default width.
+    fn glyph_advances(&self, fonts: &FontMap, text_obj: &Object) -> Vec<f32> {
+        let Object::String(bytes, _) = text_obj else { return Vec::new() };
+        let fallback = Font::default();
+        let font = self.current_font(fonts).unwrap_or(&fallback);
+        let graphics = &self.graphics;
+        let scaling = if graphics.vertical { 1.0 } else { graphics.horizontal_scaling };
+        font.codes(bytes)
+            .into_iter()
+            .map(|code| {
+                let word_spacing = if code == (32, 1) { graphics.word_spacing } else { 0.0 };
+                let glyph = if graphics.vertical { -font.vertical_advance(code) } else { font.width(code) };
+                (glyph / 1000.0 * graphics.font_size + graphics.char_spacing + word_spacing) * scaling
+            })
+            .collect()
+    }
+
+    /// How far showing a string moves the text position, its glyphs' advances together.
+    ///
+    /// With it comes where each character of the string ends, from its start, given how many
+    /// characters each code decodes to, `counts`: a glyph's advance is shared by its
+    /// characters. Empty when `counts` does not tell them apart by glyph, as for text decoded
+    /// from UTF-8.
+    fn string_advance(&self, fonts: &FontMap, text_obj: &Object, counts: &[usize]) -> (f32, Vec<f32>) {
+        let advances = self.glyph_advances(fonts, text_obj);
+        let by_glyph = advances.len() == counts.len();
+        let mut ends = Vec::with_capacity(if by_glyph { counts.iter().sum() } else { 0 });
+        let mut advance = 0.0;
+        for (index, &glyph_advance) in advances.iter().enumerate() {
+            if let Some(&count) = counts.get(index).filter(|_| by_glyph) {
+                ends.extend((1..=count).map(|share| advance + glyph_advance * share as f32 / count as f32));
+            }
+            advance += glyph_advance;
+        }
+        (advance, ends)
+    }
+
+    /// How far a number in a `TJ` array moves the text position: thousandths of the font
+    /// size, backwards for positive values, except in vertical text, where they move down.
+    fn adjustment_advance(&self, adjustment: f32) -> f32 {
+        if self.graphics.vertical {
+            adjustment / 1000.0 * self.graphics.font_size
+        } else {
+            -adjustment / 1000.0 * self.graphics.font_size * self.graphics.horizontal_scaling
+        }
+    }
+
+    /// How far forward a number in a `TJ` array moves the text position, in thousandths of an
+    /// em: negative numbers move it forward in horizontal text, positive ones in vertical.
+    fn adjustment_jump(&self, adjustment: f32) -> f32 {
+        if self.graphics.vertical {
+            adjustment
+        } else {
+            -adjustment
+        }
+    }
+
+    /// Shows a string: decodes it into an item at the current origin, then advances past it.
+    fn show(&mut self, fonts: &FontMap, text_obj: &Object, page_num: u32) -> TextItem {
+        let (text, counts) = self.decode(fonts, text_obj);
+        let (advance, ends) = self.string_advance(fonts, text_obj, &counts);
+        let item = self.text_item(fonts, text, advance, ends, page_num);
+        self.advance(advance);
+        item
+    }
+
+    /// Shows a string a glyph at a time: an item for the characters each glyph decodes to, at
+    /// the glyph's own origin, advancing past each, so that `Tc`, `Tw`, `Tz` and `Ts` apply to
+    /// every one. Glyphs decoding to nothing only advance. `None`, showing nothing, when the
+    /// characters cannot be told apart by glyph.
+    fn show_glyphs(&mut self, fonts: &FontMap, text_obj: &Object, page_num: u32) -> Option<Vec<TextItem>> {
+        let (text, counts) = self.decode(fonts, text_obj);
+        let advances = self.glyph_advances(fonts, text_obj);
+        if advances.len() != counts.len() || counts.iter().sum::<usize>() != text.chars().count() {
+            return None;
+        }
+        let mut chars = text.chars();
+        let mut items = Vec::with_capacity(advances.len());
+        for (advance, count) in advances.into_iter().zip(counts) {
+            if count > 0 {
+                let glyph: String = chars.by_ref().take(count).collect();
+                let ends = (1..=count).map(|share| advance * share as f32 / count as f32).collect();
+                items.push(self.text_item(fonts, glyph, advance, ends, page_num));
+            }
+            self.advance(advance);
+        }
+        Some(items)
+    }
+
+    /// Builds an item for text starting at the current origin and running `advance` text
+    /// space units along the baseline, or down from the origin at the top centre of its first
+    /// glyph, one em wide, for vertical fonts. `char_ends` are where its characters end, in text
+    /// space units from its start, if known.
+    fn text_item(&self, fonts: &FontMap, text: String, advance: f32, mut char_ends: Vec<f32>, page_num: u32) -> TextItem {
+        let (x, y, font_size, rise) = self.origin();
+        let (ascent, descent) = self.current_font(fonts).map_or(font::DEFAULT_EXTENT, Font::extent);
+        let size = self.graphics.font_size;
+        let (bottom, top) = (descent / 1000.0 * size + self.graphics.rise, ascent / 1000.0 * size + self.graphics.rise);
+        let matrix = self.matrix.multiply(&self.graphics.ctm);
+        let bbox = if self.graphics.vertical {
+            Rect::transformed(-size / 2.0, self.graphics.rise - advance, size / 2.0, self.graphics.rise, &matrix)
+        } else {
+            Rect::transformed(0.0, bottom, advance, top, &matrix)
+        };
+        let baseline = Baseline::from_rise(self.graphics.rise, size);
+        let vertical = self.graphics.vertical;
+        // Rounded so that quarter turns come out as whole degrees, and adding 0 makes -0 plain
+        // 0; -180 is the same direction as 180.
+        let angle = (matrix.rotation() * 100.0).round() / 100.0 + 0.0;
+        let angle = if angle <= -180.0 { angle + 360.0 } else { angle };
+        let font_resource = self.graphics.font.as_deref().map(|name| String::from_utf8_lossy(name).into_owned());
+        let current_font = self.current_font(fonts);
+        let font = current_font.and_then(Font::base_font).map(|name| font::without_subset_prefix(name).to_string());
+        let (bold, italic) = current_font.map_or((false, false), |font| (font.is_bold(), font.is_italic()));
+        if char_ends.len() == text.chars().count() {
+            // Kerning back over the start or past the end leaves characters at the edges.
+            for end in &mut char_ends {
+                *end = if advance.abs() > f32::EPSILON { (*end / advance).clamp(0.0, 1.0) } else { 0.0 };
+            }
+        } else {
+            char_ends.clear();
+        }
+        TextItem { text, x, y, font_size, width: bbox.width(), height: bbox.height(), bbox, font_resource, font, bold, italic, color: self.graphics.fill_color, rise, baseline, render_mode: self.graphics.render_mode, vertical, angle, artifact: false, synthetic_bold: false, char_ends, fragment_ends: Vec::new(), content_id: None, role: None, region: None, page_num }
+    }
+}
+
+/// Returns the text items of a page, the ruling lines drawn on it and the images painted on
+/// it, and the limit of `options` that stopped the page short, if one did; the time limit
+/// ends at `deadline`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_content_stream(
+    doc: &Document,
+    content: &Content,
+    page_num: u32,
+    page: &Page,
+    resources: &Resources,
+    fonts: &FontCache,
+    options: &ExtractOptions,
+    deadline: Option<Instant>,
+) -> (Vec<TextItem>, Vec<Rect>, Vec<PlacedImage>, Option<Limit>) {
+    let mut processor = ContentProcessor::new(doc, fonts, page_num, page, options, deadline);
+    processor.process(&content.operations, &[], resources);
+    let mut extracted_items = processor.items;
+    let mut rules = processor.rules;
+    let mut images = processor.images;
+
+    if options.skip_invisible {
+        extracted_items.retain(|item| item.render_mode != RENDER_MODE_INVISIBLE);
+    }
+    if options.skip_artifacts {
+        extracted_items.retain(|item| !item.artifact);
+    }
+    if let Some(degrees) = options.skip_rotated {
+        // Text upside down is still horizontal: what counts is how far the baseline leans.
+        extracted_items.retain(|item| item.angle.abs().min(180.0 - item.angle.abs()) <= degrees);
+    }
+    // Sizes as shown, so text set at `1 Tf` and scaled by the matrix, as TeX writes it,
+    // compares by the size it is seen at.
+    if let Some(min) = options.min_font_size {
+        extracted_items.retain(|item| item.font_size >= min);
+    }
+    if let Some(max) = options.max_font_size {
+        extracted_items.retain(|item| item.font_size <= max);
+    }
+    if options.drop_invisible_color {
+        let fills = &processor.fills;
+        extracted_items.retain(|item| !is_hidden_by_color(item, fills));
+    }
+    let stopped = processor.stopped;
+    if options.skip_outside_crop_box {
+        let crop_box = page.upright(&page.crop_box);
+        extracted_items.retain(|item| item.bbox.intersection(&crop_box).is_some());
+    }
+    if options.normalize {
+        for item in &mut extracted_items {
+            let text = normalize::normalize(&item.text, options.nbsp_as_space);
+            if text != item.text {
+                item.char_ends = normalize::char_ends(&item.text, &item.char_ends, &text, options.nbsp_as_space);
+                item.text = text;
+            }
+        }
+    }
+    if options.origin == Origin::TopLeft {
+        for item in &mut extracted_items {
+            (item.x, item.y) = page.top_left_matrix().transform_point(item.x, item.y);
+            item.bbox = page.top_left(&item.bbox);
+        }
+        for rule in &mut rules {
+            *rule = page.top_left(rule);
+        }
+        for image in &mut images {
+            image.rect = page.top_left(&image.rect);
+        }
+    }
+    // Regions are placed like the items, so only once the items are.
+    if !options.regions.is_empty() {
+        for item in &mut extracted_items {
+            item.region = options.regions.iter().position(|region| region.holds(&item.bbox)).map(|index| index + 1);
+        }
+        extracted_items.retain(|item| item.region.is_some());
+    }
+    (extracted_items, rules, images, stopped)
+}
+
+/// Runs the operations of a page's content as `process_content_stream` does and returns them,
+/// with those of the forms it paints, each at its offset of `offsets`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn content_operations(
+    doc: &Document,
+    content: &Content,
+    offsets: &[usize],
+    page_num: u32,
+    page: &Page,
+    resources: &Resources,
+    fonts: &FontCache,
+    options: &ExtractOptions,
+) -> Vec<ContentOperation> {
+    let mut processor = ContentProcessor::new(doc, fonts, page_num, page, options, None);
+    processor.dump = Some(Vec::new());
+    processor.process(&content.operations, offsets, resources);
+    if let Some(limit) = processor.stopped {
+        log::warn!("{}", PageError::limit(page_num, limit));
+    }
+    let mut operations = processor.dump.unwrap_or_default();
+    if options.origin == Origin::TopLeft {
+        for shown in operations.iter_mut().filter_map(|operation| operation.shown.as_mut()) {
+            (shown.x, shown.y) = page.top_left_matrix().transform_point(shown.x, shown.y);
+        }
+    }
+    operations
+}
+
+/// How far, in each RGB component, a fill color may be from white and still be white.
+const WHITE_TOLERANCE: f32 = 0.03;
+
+/// Whether text is white, or nearly, with no filled shape of another color behind its
+/// middle: the white-on-white text that shows nothing on an ordinary page.
+fn is_hidden_by_color(item: &TextItem, fills: &[(Rect, Option<Rgb>)]) -> bool {
+    let Some(color) = item.color.filter(|color| color.is_near_white(WHITE_TOLERANCE)) else { return false };
+    let (x, y) = ((item.bbox.x0 + item.bbox.x1) / 2.0, (item.bbox.y0 + item.bbox.y1) / 2.0);
+    let background = fills.iter().any(|(bounds, fill)| {
+        let behind = bounds.x0 <= x && x <= bounds.x1 && bounds.y0 <= y && y <= bounds.y1;
+        behind && fill.is_none_or(|fill| (0..3).any(|i| (fill.0[i] - color.0[i]).abs() > WHITE_TOLERANCE))
+    });
+    !background
+}
+
+/// How many operators run between two looks at the clock for the time limit.
+const OPERATIONS_PER_CLOCK_CHECK: usize = 1024;
+
+/// Walks a page's content stream, following form XObjects, and collects the text it shows.
+struct ContentProcessor<'a> {
+    doc: &'a Document,
+    /// The document's fonts, loaded once for all pages and forms.
+    fonts: &'a FontCache,
+    page_num: u32,
+    /// The limits on the work of the page.
+    options: &'a ExtractOptions,
+    deadline: Option<Instant>,
+    /// Operators run so far, those of forms included.
+    operations: usize,
+    /// The limit the page went over, after which nothing more of it is run.
+    stopped: Option<Limit>,
+    /// Whether shown strings are written to stderr.
+    debug: bool,
+    state: TextState,
+    /// Form XObjects being painted, innermost last; a form already on it would recurse forever.
+    forms: Vec<ObjectId>,
+    /// Open marked-content sequences, innermost last.
+    marked: Vec<MarkedContent>,
+    items: Vec<TextItem>,
+    /// The path under construction.
+    path: path::Path,
+    /// Ruling lines painted so far, for finding tables.
+    rules: Vec<Rect>,
+    /// Images painted so far.
+    images: Vec<PlacedImage>,
+    /// Bounds of the filled paths painted so far and their fill colors, which can be what
+    /// light text is seen against.
+    fills: Vec<(Rect, Option<Rgb>)>,
+    /// The operations run so far, when they are listed.
+    dump: Option<Vec<ContentOperation>>,
+}
+
+/// A marked-content sequence opened by `BMC` or `BDC` (PDF 32000-1:2008, 14.6).
+#[derive(Debug, Default)]
+struct MarkedContent {
+    /// Whether the tag is `/Artifact`.
+    artifact: bool,
+    /// `/ActualText` of the properties: the text the enclosed glyphs stand for (14.9.4).
+    actual_text: Option<String>,
+    /// Index of the item carrying `actual_text`, once the first enclosed show produced it.
+    item: Option<usize>,
+    /// `/MCID` of the properties, linking the sequence to the structure tree (14.7.4.2).
+    mcid: Option<i64>,
+}
+
+impl<'a> ContentProcessor<'a> {
+    fn new(doc: &'a Document, fonts: &'a FontCache, page_num: u32, page: &Page, options: &'a ExtractOptions, deadline: Option<Instant>) -> Self {
+        ContentProcessor {
+            doc,
+            fonts,
+            page_num,
+            options,
+            deadline,
+            operations: 0,
+            stopped: None,
+            debug: options.debug_ops,
+            state: TextState::new(page.upright_matrix()),
+            forms: Vec::new(),
+            marked: Vec::new(),
+            items: Vec::new(),
+            path: path::Path::default(),
+            rules: Vec::new(),
+            images: Vec::new(),
+            fills: Vec::new(),
+            dump: None,
+        }
+    }
+
+    /// Shows a string and adds what it shows: an item, or with `Granularity::Chars` one for
+    /// each glyph. Returns the text shown.
+    fn show(&mut self, fonts: &FontMap, text_obj: &Object) -> String {
+        if self.options.granularity == Granularity::Chars {
+            if let Some(items) = self.state.show_glyphs(fonts, text_obj, self.page_num) {
+                let text = items.iter().map(|item| item.text.as_str()).collect();
+                for item in items {
+                    self.emit(item);
+                }
+                return text;
+            }
+        }
+        let item = self.state.show(fonts, text_obj, self.page_num);
+        let text = item.text.clone();
+        self.emit(item);
+        text
+    }
+
+    /// Adds a shown item. Inside a sequence with `/ActualText` (the outermost one, if nested),
+    /// the first show becomes an item with that text and later shows only widen its box.
+    fn emit(&mut self, mut item: TextItem) {
+        // An operator showing several items shows their text together, from where the first
+        // starts.
+        if let Some(operation) = self.dump.as_mut().and_then(|dump| dump.last_mut()) {
+            match &mut operation.shown {
+                Some(shown) => shown.text.push_str(&item.text),
+                None => operation.shown = Some(ShownText { text: item.text.clone(), x: item.x, y: item.y, font_size: item.font_size }),
+            }
+        }
+        item.artifact = self.marked.iter().any(|marked| marked.artifact);
+        item.content_id = self.marked.iter().rev().find_map(|marked| marked.mcid).map(|mcid| structure::ContentId {
+            stream: self.forms.last().copied(),
+            mcid,
+        });
+        let Some(marked) = self.marked.iter_mut().find(|marked| marked.actual_text.is_some()) else {
+            self.items.push(item);
+            return;
+        };
+        match marked.item {
+            Some(index) => {
+                let first = &mut self.items[index];
+                first.bbox = first.bbox.union(&item.bbox);
+                first.width = first.bbox.width();
+                first.height = first.bbox.height();
+            }
+            None => {
+                marked.item = Some(self.items.len());
+                self.items.push(TextItem { text: marked.actual_text.clone().unwrap_or_default(), char_ends: Vec::new(), ..item });
+            }
+        }
+    }
+
+    /// Counts an operator against the limits, and tells whether the page is to go on.
+    fn within_limits(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+        self.operations += 1;
+        if self.operations > self.options.max_operations {
+            self.stopped = Some(Limit::Operations(self.options.max_operations));
+        } else if let Some((deadline, limit)) = self.deadline.zip(self.options.time_limit) {
+            if self.operations.is_multiple_of(OPERATIONS_PER_CLOCK_CHECK) && Instant::now() > deadline {
+                self.stopped = Some(Limit::Time(limit));
+            }
+        }
+        self.stopped.is_none()
+    }
+
+    /// Runs `operations`, which start in their stream at `offsets` when they are listed.
+    fn process(&mut self, operations: &[Operation], offsets: &[usize], resources: &Resources) {
+        for (index, operation) in operations.iter().enumerate() {
+            if !self.within_limits() {
+                return;
+            }
+            if let Some(dump) = &mut self.dump {
+                let offset = offsets.get(index).copied().unwrap_or_default();
+                dump.push(ContentOperation::new(dump.len(), offset, self.forms.last().copied(), &operation.operator, &operation.operands));
+            }
+            let operator = &operation.operator; // e.g., "Tf", "Tj", "Tm"
+            let operands = &operation.operands;
+
+            match operator.as_str() {
+                // "q" / "Q": Save and restore the graphics state.
+                "q" => {
+                    self.state.save();
+                }
+                "Q" => {
+                    let balanced = self.state.restore();
+                    if !balanced {
+                        log::warn!("unbalanced Q operator on page {}, resetting the graphics state", self.page_num);
+                    }
+                }
+
+                // "cm": Concatenate a matrix onto the current transformation matrix.
+                "cm" => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        self.state.concat(matrix);
+                    }
+                }
+
+                // "m" / "l" / "c" / "v" / "y" / "re" / "h": Construct a path.
+                "m" | "l" | "c" | "v" | "y" => {
+                    let numbers: Vec<f32> = operands.iter().filter_map(|obj| obj.as_float().ok()).collect();
+                    if let [.., x, y] = numbers[..] {
+                        let ctm = self.state.graphics.ctm;
+                        match operator.as_str() {
+                            "m" => self.path.move_to(x, y, &ctm),
+                            "l" => self.path.line_to(x, y, &ctm),
+                            _ => self.path.curve_to(x, y, &ctm),
+                        }
+                    }
+                }
+                "re" => {
+                    let numbers: Vec<f32> = operands.iter().filter_map(|obj| obj.as_float().ok()).collect();
+                    if let [x, y, width, height] = numbers[..] {
+                        self.path.rect(x, y, width, height, &self.state.graphics.ctm);
+                    }
+                }
+                "h" => self.path.close(),
+
+                // Path painting: "S" strokes, "f" fills, "B" does both; "s" and "b" close first.
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                    if matches!(operator.as_str(), "s" | "b" | "b*") {
+                        self.path.close();
+                    }
+                    let stroke = matches!(operator.as_str(), "S" | "s" | "B" | "B*" | "b" | "b*");
+                    let fill = !matches!(operator.as_str(), "S" | "s");
+                    if fill {
+                        if let Some(bounds) = self.path.bounds() {
+                            self.fills.push((bounds, self.state.graphics.fill_color));
+                        }
+                    }
+                    let rules = self.path.paint(stroke, fill);
+                    self.rules.extend(rules);
+                }
+                "n" => self.path.clear(),
+
+                // "g" / "rg" / "k": Set a gray, RGB or CMYK fill color, and its color space.
+                "g" | "rg" | "k" => {
+                    let space = match operator.as_str() {
+                        "g" => ColorSpace::Gray,
+                        "rg" => ColorSpace::Rgb,
+                        _ => ColorSpace::Cmyk,
+                    };
+                    let components: Vec<f32> = operands.iter().filter_map(|obj| obj.as_float().ok()).collect();
+                    self.state.graphics.fill_color = space.to_rgb(&components);
+                    self.state.graphics.fill_space = space;
+                }
+
+                // "cs": Set the fill color space, by device family or `/ColorSpace` resource.
+                "cs" if !operands.is_empty() => {
+                    let space = match ColorSpace::load(self.doc, &operands[0]) {
+                        ColorSpace::Unknown => operands[0]
+                            .as_name()
+                            .ok()
+                            .and_then(|name| resources.color_spaces.get(name))
+                            .cloned()
+                            .unwrap_or(ColorSpace::Unknown),
+                        space => space,
+                    };
+                    self.state.graphics.fill_color = space.initial_color();
+                    self.state.graphics.fill_space = space;
+                }
+
+                // "sc" / "scn": Set the fill color in the current space; a pattern name ends
+                // the operands of "scn" for patterns, whose color cannot be told.
+                "sc" | "scn" => {
+                    let components: Vec<f32> = operands.iter().filter_map(|obj| obj.as_float().ok()).collect();
+                    let pattern = operands.last().is_some_and(|obj| obj.as_name().is_ok());
+                    self.state.graphics.fill_color = if pattern { None } else { self.state.graphics.fill_space.to_rgb(&components) };
+                }
+
+                // "BT": Begin Text Object. Resets the text matrix.
+                "BT" => {
+                    self.state.set_matrix(Matrix::IDENTITY);
+                }
+
+                // "Tf": Set Text Font and Size.
+                "Tf" if operands.len() >= 2 => {
+                    self.state.graphics.font = font::font_name(&operands[0]);
+                    self.state.graphics.vertical = self.state.current_font(&resources.fonts).is_some_and(Font::is_vertical);
+                    if let Ok(size) = operands[1].as_float() {
+                        self.state.graphics.font_size = size;
+                    }
+                }
+
+                // "Tc" / "Tw": Set Character and Word Spacing.
+                "Tc" => {
+                    if let Some(Ok(spacing)) = operands.first().map(Object::as_float) {
+                        self.state.graphics.char_spacing = spacing;
+                    }
+                }
+                "Tw" => {
+                    if let Some(Ok(spacing)) = operands.first().map(Object::as_float) {
+                        self.state.graphics.word_spacing = spacing;
+                    }
+                }
+
+                // "Tz": Set Horizontal Scaling, in percent.
+                "Tz" => {
+                    if let Some(Ok(scale)) = operands.first().map(Object::as_float) {
+                        self.state.graphics.horizontal_scaling = scale / 100.0;
+                    }
+                }
+
+                // "Ts": Set Text Rise.
+                "Ts" => {
+                    if let Some(Ok(rise)) = operands.first().map(Object::as_float) {
+                        self.state.graphics.rise = rise;
+                    }
+                }
+
+                // "Tr": Set Text Rendering Mode.
+                "Tr" => {
+                    if let Some(Ok(mode @ 0..=7)) = operands.first().map(Object::as_i64) {
+                        self.state.graphics.render_mode = mode as u8;
+                    }
+                }
+
+                // "TL": Set Text Leading.
+                "TL" => {
+                    if let Some(Ok(leading)) = operands.first().map(Object::as_float) {
+                        self.state.graphics.leading = leading;
+                    }
+                }
+
+                // "Td": Move Text Position. "TD" also sets the leading to -ty.
+                "Td" | "TD" if operands.len() >= 2 => {
+                    if let (Ok(tx), Ok(ty)) = (operands[0].as_float(), operands[1].as_float()) {
+                        if operator == "TD" {
+                            self.state.graphics.leading = -ty;
+                        }
+                        self.state.move_line(tx, ty);
+                    }
+                }
+
+                // "Tm": Set Text Matrix (absolute positioning).
+                "Tm" => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        self.state.set_matrix(matrix);
+                    }
+                }
+
+                // "T*": Move to the start of the next line.
+                "T*" => {
+                    self.state.next_line();
+                }
+
+                // "Tj": Show Text.
+                "Tj" => {
+                    if let Some(text_obj) = operands.first() {
+                        let text = self.show(&resources.fonts, text_obj);
+                        if self.debug {
+                            log::trace!("Extracted text (Tj): {}", text);
+                        }
+                    }
+                }
+
+                // "'": Move to the next line and show text.
+                "'" => {
+                    self.state.next_line();
+                    if let Some(text_obj) = operands.first() {
+                        let text = self.show(&resources.fonts, text_obj);
+                        if self.debug {
+                            log::trace!("Extracted text ('): {}", text);
+                        }
+                    }
+                }
+
+                // "\"": Set word and character spacing, move to the next line and show text.
+                "\"" if operands.len() >= 3 => {
+                    if let (Ok(aw), Ok(ac)) = (operands[0].as_float(), operands[1].as_float()) {
+                        self.state.graphics.word_spacing = aw;
+                        self.state.graphics.char_spacing = ac;
+                    }
+                    self.state.next_line();
+                    let text = self.show(&resources.fonts, &operands[2]);
+                    if self.debug {
+                        log::trace!("Extracted text (\", Tw {} Tc {}): {}", self.state.graphics.word_spacing, self.state.graphics.char_spacing, text);
+                    }
+                }
+
+                // "TJ": Show Text with Adjustments (kerning).
+                // Glyph by glyph, each string is shown on its own, and a number that is a word
+                // break is a space as wide as the gap it leaves, as in the text of an item.
+                "TJ" if self.options.granularity == Granularity::Chars => {
+                    if let Some(Object::Array(arr)) = operands.first() {
+                        let mut shown = String::new();
+                        for item in arr {
+                            match item {
+                                Object::String(..) => shown.push_str(&self.show(&resources.fonts, item)),
+                                Object::Integer(_) | Object::Real(_) => {
+                                    let adjustment = item.as_float().unwrap_or(0.0);
+                                    let (jump, advance) = (self.state.adjustment_jump(adjustment), self.state.adjustment_advance(adjustment));
+                                    let word_break = jump > self.options.tj_space_threshold && jump <= TJ_ITEM_BREAK;
+                                    if word_break && !shown.is_empty() && !shown.ends_with(char::is_whitespace) {
+                                        let space = self.state.text_item(&resources.fonts, " ".to_string(), advance, vec![advance], self.page_num);
+                                        self.emit(space);
+                                        shown.push(' ');
+                                    }
+                                    self.state.advance(advance);
+                                }
+                                _ => {}
+                            }
+                        }
+                        if self.debug {
+                            log::trace!("Extracted text (TJ): {}", shown);
+                        }
+                    }
+                }
+                "TJ" => {
+                    // TJ is complex because it mixes strings and numbers (spacing).
+                    if let Some(Object::Array(arr)) = operands.first() {
+                        let mut combined_text = String::new();
+                        let mut advance = 0.0;
+                        let mut ends = Vec::new();
+                        let mut split = false;
+                        for item in arr {
+                            match item {
+                                Object::String(..) => {
+                                    let (text, counts) = self.state.decode(&resources.fonts, item);
+                                    let (string_advance, string_ends) = self.state.string_advance(&resources.fonts, item, &counts);
+                                    ends.extend(string_ends.into_iter().map(|end| advance + end));
+                                    combined_text.push_str(&text);
+                                    advance += string_advance;
+                                }
+                                // Numbers shift the next glyph left by thousandths of the font size.
+                                // Those moving it right by more than a kern are how many PDFs
+                                // separate words without a space glyph, and those moving it
+                                // further still, how they skip to text far along the line.
+                                Object::Integer(_) | Object::Real(_) => {
+                                    let adjustment = item.as_float().unwrap_or(0.0);
+                                    let jump = self.state.adjustment_jump(adjustment);
+                                    if jump > TJ_ITEM_BREAK {
+                                        if !combined_text.is_empty() {
+                                            let item = self.state.text_item(&resources.fonts, std::mem::take(&mut combined_text), advance, std::mem::take(&mut ends), self.page_num);
+                                            self.emit(item);
+                                            if let Some(shown) = self.dump.as_mut().and_then(|dump| dump.last_mut()).and_then(|operation| operation.shown.as_mut()) {
+                                                shown.text.push(' ');
+                                            }
+                                        }
+                                        self.state.advance(advance + self.state.adjustment_advance(adjustment));
+                                        advance = 0.0;
+                                        split = true;
+                                        continue;
+                                    }
+                                    if jump > self.options.tj_space_threshold && !combined_text.is_empty() && !combined_text.ends_with(char::is_whitespace) {
+                                        combined_text.push(' ');
+                                        ends.push(advance + self.state.adjustment_advance(adjustment));
+                                    }
+                                    advance += self.state.adjustment_advance(adjustment);
+                                }
+                                _ => {}
+                            }
+                        }
+                        if self.debug {
+                            log::trace!("Extracted Combined text (TJ): {}", combined_text);
+                        }
+                        // Nothing after the last jump is nothing to add to the items before it.
+                        if !(split && combined_text.is_empty()) {
+                            let item = self.state.text_item(&resources.fonts, combined_text, advance, ends, self.page_num);
+                            self.emit(item);
+                        }
+                        self.state.advance(advance);
+                    }
+                }
+
+                // "BMC" / "BDC" / "EMC": Begin and end marked-content sequences.
+                "BMC" => {
+                    let artifact = operands.first().and_then(|tag| tag.as_name().ok()) == Some(b"Artifact");
+                    self.marked.push(MarkedContent { artifact, ..MarkedContent::default() });
+                }
+                "BDC" => {
+                    // Artifacts are recognised by their tag whether the property list is a
+                    // resource name or an inline dictionary such as << /Type /Pagination >>.
+                    let artifact = operands.first().and_then(|tag| tag.as_name().ok()) == Some(b"Artifact");
+                    let properties = match operands.get(1) {
+                        Some(Object::Dictionary(dict)) => Some(dict),
+                        Some(Object::Name(name)) => resources.properties.get(name),
+                        _ => None,
+                    };
+                    let actual_text = properties
+                        .and_then(|dict| dict.get_deref(b"ActualText", self.doc).ok())
+                        .and_then(|obj| obj.as_str().ok())
+                        .map(decode_text_string);
+                    let mcid = properties.and_then(|dict| dict.get(b"MCID").and_then(Object::as_i64).ok());
+                    self.marked.push(MarkedContent { artifact, actual_text, item: None, mcid });
+                }
+                "EMC" => {
+                    self.marked.pop();
+                }
+
+                // "Do": Paint an XObject; form XObjects are content streams of their own.
+                "Do" => {
+                    if let Some(Ok(name)) = operands.first().map(Object::as_name) {
+                        self.paint_xobject(name, resources);
+                    }
+                }
+                // An inline image, as `content::decode` rewrites it: its dictionary and data.
+                "BI" => {
+                    if let Some(Ok(dict)) = operands.first().map(Object::as_dict) {
+                        let (width, height) = images::dimensions(dict);
+                        let rect = Rect::transformed(0.0, 0.0, 1.0, 1.0, &self.state.graphics.ctm);
+                        self.images.push(PlacedImage { name: None, object: None, rect, width, height });
+                    }
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    /// `Do`: paints the named XObject. Form XObjects are processed like inline content with
+    /// their `/Matrix` on the CTM and their own `/Resources` (or the caller's, for old files
+    /// that omit them); images and other XObjects contain no text.
+    fn paint_xobject(&mut self, name: &[u8], resources: &Resources) {
+        let Some(&id) = resources.xobjects.get(name) else { return };
+        let Ok(object) = self.doc.get_object(id) else { return };
+        // Images are painted into the unit square of the CTM.
+        if let Some(dict) = images::image_dict(object) {
+            let (width, height) = images::dimensions(dict);
+            let rect = Rect::transformed(0.0, 0.0, 1.0, 1.0, &self.state.graphics.ctm);
+            let name = Some(String::from_utf8_lossy(name).into_owned());
+            self.images.push(PlacedImage { name, object: Some(id), rect, width, height });
+            return;
+        }
+        let Ok(stream) = object.as_stream() else { return };
+        if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form") {
+            return;
+        }
+        if self.forms.contains(&id) {
+            log::warn!("form XObject {} {} R on page {} paints itself, skipping it", id.0, id.1, self.page_num);
+            return;
+        }
+        if self.forms.len() >= self.options.max_form_depth {
+            self.stopped = Some(Limit::FormDepth(self.options.max_form_depth));
+            return;
+        }
+        let data = match stream_data(stream, self.options.max_stream_size) {
+            Ok(data) => Ok(data),
+            Err(err) if err.too_large => {
+                self.stopped = Some(Limit::StreamSize(self.options.max_stream_size));
+                return;
+            }
+            Err(err) => Err(err.message),
+        };
+        let (content, offsets) = match data {
+            Ok(data) => {
+                let (content, offsets, skipped) = content::decode(&data);
+                if skipped > 0 {
+                    log::warn!("cannot decode form XObject {} {} R on page {} in full: skipped {} bytes that are not content operations",
+                        id.0, id.1, self.page_num, skipped);
+                }
+                (content, offsets)
+            }
+            Err(err) => {
+                log::warn!("cannot decode form XObject {} {} R on page {}: {}", id.0, id.1, self.page_num, err);
+                return;
+            }
+        };
+        let own_resources = stream
+            .dict
+            .get_deref(b"Resources", self.doc)
+            .and_then(Object::as_dict)
+            .ok()
+            .map(|dict| Resources::from_dict(self.doc, dict, self.fonts));
+        let matrix = stream
+            .dict
+            .get_deref(b"Matrix", self.doc)
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|operands| Matrix::from_operands(operands))
+            .unwrap_or(Matrix::IDENTITY);
+
+        // The form's own q/Q should balance, but never let them leak into the caller.
+        let form_state = self.state.form(matrix);
+        let caller_state = std::mem::replace(&mut self.state, form_state);
+        self.forms.push(id);
+        self.process(&content.operations, &offsets, own_resources.as_ref().unwrap_or(resources));
+        self.forms.pop();
+        self.state = caller_state;
+    }
+}
+
+/// The text of a show string in a font that cannot decode it: UTF-8 when it is, otherwise
+/// decoded like a text string, so UTF-16BE after a byte order mark, or PDFDocEncoding.
+pub(crate) fn text_from_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !text.starts_with('\u{FEFF}') => text.to_string(),
+        _ => decode_text_string(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, near, Fixture};
+    use crate::{Baseline, Error, ExtractOptions, Granularity, Limit};
+    use lopdf::{dictionary, Dictionary, Stream};
+
+    #[test]
+    fn scales_the_font_size_by_the_text_matrix() {
+        let items = testing::items("BT /F1 12 Tf 0.5 0 0 0.5 100 700 Tm (Half) Tj ET");
+        assert_eq!(items.len(), 1);
+        assert!(near(items[0].font_size, 6.0), "font size {}", items[0].font_size);
+        assert!(near(items[0].x, 100.0) && near(items[0].y, 700.0));
+        assert_eq!(items[0].angle, 0.0);
+    }
+
+    #[test]
+    fn moves_by_td_in_the_scaled_text_space() {
+        let items = testing::items("BT /F1 12 Tf 2 0 0 2 100 600 Tm (A) Tj 10 -20 Td (B) Tj ET");
+        assert_eq!(items.len(), 2);
+        assert!(near(items[1].x, 120.0) && near(items[1].y, 560.0), "({}, {})", items[1].x, items[1].y);
+        assert!(near(items[1].font_size, 24.0));
+    }
+
+    #[test]
+    fn reads_the_angle_of_rotated_text() {
+        let items = testing::items("BT /F1 10 Tf 0 1 -1 0 300 400 Tm (Up) Tj ET");
+        assert_eq!(items.len(), 1);
+        assert!(near(items[0].x, 300.0) && near(items[0].y, 400.0));
+        assert!(near(items[0].angle, 90.0), "angle {}", items[0].angle);
+        assert!(near(items[0].font_size, 10.0));
+    }
+
+    #[test]
+    fn keeps_the_origin_and_size_of_sheared_text() {
+        // An oblique slant: x grows with y, the baseline stays horizontal.
+        let items = testing::items("BT /F1 10 Tf 1 0 0.3 1 200 500 Tm (Slanted) Tj ET");
+        assert_eq!(items.len(), 1);
+        assert!(near(items[0].x, 200.0) && near(items[0].y, 500.0));
+        assert!(near(items[0].angle, 0.0));
+        assert!(near(items[0].font_size, 10.0), "font size {}", items[0].font_size);
+    }
+
+    #[test]
+    fn moves_each_quoted_string_to_a_new_line() {
+        let items = testing::items("BT /F1 12 Tf 14 TL 72 720 Td (one) ' (two) ' (three) ' ET");
+        let ys: Vec<f32> = items.iter().map(|item| item.y).collect();
+        assert_eq!(testing::texts(&items), ["one", "two", "three"]);
+        assert!(near(ys[0], 706.0) && near(ys[1], 692.0) && near(ys[2], 678.0), "{:?}", ys);
+        assert!(items.iter().all(|item| near(item.x, 72.0)));
+    }
+
+    #[test]
+    fn sets_the_spacing_with_a_double_quote() {
+        // Tw 10 and Tc 2 before the line: "a b" is 3 glyphs wide, one of them a space.
+        let plain = testing::items("BT /F1 10 Tf 12 TL 72 720 Td (a b) ' ET");
+        let spaced = testing::items("BT /F1 10 Tf 12 TL 72 720 Td 10 2 (a b) \" ET");
+        assert!(near(spaced[0].y, 708.0));
+        assert!(near(spaced[0].width - plain[0].width, 10.0 + 3.0 * 2.0), "{} then {}", plain[0].width, spaced[0].width);
+    }
+
+    #[test]
+    fn justifies_a_line_to_the_right_margin() {
+        // 44 glyphs 200.64 points wide in 10-point Helvetica, 8 of them spaces: spread by Tw
+        // and Tc, then condensed by Tz, they end at 540.
+        let content = "BT /F1 10 Tf 37.17 Tw 0.5 Tc 90 Tz 72 720 Td (The quick brown) Tj ( fox jumps over the lazy dog.) Tj ET";
+        let items = testing::items(content);
+        assert_eq!(testing::texts(&items), ["The quick brown", " fox jumps over the lazy dog."]);
+        let last = items.last().unwrap();
+        assert!((last.bbox.x1 - 540.0).abs() < 0.5, "ends at {}", last.bbox.x1);
+        // The second string starts where the first ends: 15 glyphs 73.36 points wide, 2 of
+        // them spaces.
+        assert!(near(items[1].x, 72.0 + (73.36 + 15.0 * 0.5 + 2.0 * 37.17) * 0.9), "{}", items[1].x);
+    }
+
+    #[test]
+    fn spaces_words_only_at_single_byte_spaces() {
+        // Code 32 of a two-byte encoding is not a space (PDF 32000-1:2008, 9.3.3).
+        let mut fixture = Fixture::new();
+        let descendant = fixture.object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "Wide",
+            "CIDSystemInfo" => dictionary! { "Registry" => lopdf::Object::string_literal("Adobe"), "Ordering" => lopdf::Object::string_literal("Identity"), "Supplement" => 0 },
+            "DW" => 500,
+        });
+        fixture.font("F2", dictionary! { "Type" => "Font", "Subtype" => "Type0", "BaseFont" => "Wide", "Encoding" => "Identity-H", "DescendantFonts" => vec![descendant.into()] });
+        fixture.page("BT /F2 10 Tf 100 Tw 72 720 Td <00200020> Tj ET BT /F1 10 Tf 100 Tw 72 700 Td (  ) Tj ET");
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        assert!(near(items[0].width, 2.0 * 5.0), "{}", items[0].width);
+        assert!(near(items[1].width, 2.0 * (2.78 + 100.0)), "{}", items[1].width);
+    }
+
+    #[test]
+    fn spaces_the_words_of_a_latex_line() {
+        // As pdfTeX draws a line: kerns of a few hundredths of an em, interword glue of a
+        // third, and the page number pushed to the margin.
+        let content = "BT /F1 10 Tf 72 720 Td [(The)-333(quic)20(k)-334(bro)28(wn)-333(fo)28(x)-333(jumps)-333(o)28(v)28(er)-333(the)-333(lazy)-333(dog.)-20000(1)] TJ \
+            0 -14 Td [(Name:)-700(Ada)] TJ ET";
+        let items = testing::items(content);
+        assert_eq!(testing::texts(&items), ["The quick brown fox jumps over the lazy dog.", "1", "Name:", "Ada"]);
+        assert!(near(items[1].x - items[0].bbox.x1, 200.0), "{} after {}", items[1].x, items[0].bbox.x1);
+        // Wider than a character, the gap ends the item too.
+        assert!(near(items[3].x - items[2].bbox.x1, 7.0), "{} after {}", items[3].x, items[2].bbox.x1);
+    }
+
+    #[test]
+    fn never_spaces_text_pulled_back() {
+        let content = "BT /F1 10 Tf 72 720 Td [(A)150(V)2000(A)-50(T)] TJ ET";
+        for granularity in [Granularity::Items, Granularity::Chars] {
+            let options = ExtractOptions::builder().granularity(granularity).build().unwrap();
+            let items = testing::items_with(&testing::pdf(&[content]), &options);
+            assert_eq!(testing::texts(&items).concat(), "AVAT", "{:?}", granularity);
+        }
+    }
+
+    #[test]
+    fn sets_the_leading_with_td() {
+        let items = testing::items("BT /F1 12 Tf 72 720 Td 0 -20 TD (a) Tj T* (b) Tj ET");
+        assert_eq!(testing::texts(&items), ["a", "b"]);
+        assert!(near(items[0].y, 700.0) && near(items[1].y, 680.0), "{} then {}", items[0].y, items[1].y);
+    }
+
+    #[test]
+    fn moves_by_the_leading_with_t_star() {
+        let items = testing::items("BT /F1 12 Tf 15 TL 72 720 Td (a) Tj T* (b) Tj 30 TL T* (c) Tj ET");
+        let ys: Vec<f32> = items.iter().map(|item| item.y).collect();
+        assert!(near(ys[1], 705.0) && near(ys[2], 675.0), "{:?}", ys);
+        assert!(near(items[2].x, 72.0));
+    }
+
+    #[test]
+    fn places_text_by_the_ctm() {
+        let shown = "BT /F1 12 Tf 10 20 Td (A) Tj ET";
+        let plain = testing::items(shown);
+        let moved = testing::items(&format!("1 0 0 1 100 200 cm {}", shown));
+        assert!(near(plain[0].x, 10.0) && near(plain[0].y, 20.0));
+        assert!(near(moved[0].x, 110.0) && near(moved[0].y, 220.0), "({}, {})", moved[0].x, moved[0].y);
+        let scaled = testing::items(&format!("2 0 0 2 0 0 cm {}", shown));
+        assert!(near(scaled[0].x, 20.0) && near(scaled[0].y, 40.0) && near(scaled[0].font_size, 24.0));
+    }
+
+    #[test]
+    fn restores_the_ctm_and_text_state_with_q() {
+        let items = testing::items("q 1 0 0 1 100 100 cm BT /F1 12 Tf 2 Tc 0 0 Td (A) Tj ET Q BT /F1 12 Tf 0 0 Td (A) Tj ET");
+        assert!(near(items[0].x, 100.0) && near(items[1].x, 0.0) && near(items[1].y, 0.0));
+        assert!(items[0].width > items[1].width, "Tc is restored with the graphics state");
+    }
+
+    #[test]
+    fn survives_an_unbalanced_q() {
+        let items = testing::items("Q Q 1 0 0 1 50 50 cm BT /F1 12 Tf 10 10 Td (A) Tj ET Q q Q Q BT /F1 12 Tf (B) Tj ET");
+        assert_eq!(testing::texts(&items), ["A", "B"]);
+        assert!(near(items[0].x, 60.0) && near(items[0].y, 60.0));
+        assert!(near(items[1].x, 0.0) && near(items[1].y, 0.0), "an extra Q resets the CTM");
+    }
+
+    #[test]
+    fn measures_helvetica_by_its_widths() {
+        // Thousandths of an em: H 722, e 556, l 222, o 556, r 333, d 556, W 944 and the space 278.
+        let items = testing::items("BT /F1 10 Tf 100 700 Td (Hello) Tj ( World) Tj ET");
+        assert!(near(items[0].width, 22.78), "width {}", items[0].width);
+        assert!(near(items[1].width, 28.89), "width {}", items[1].width);
+        assert!(near(items[1].x, 122.78));
+        // The default extent: 0.8 em above the baseline and 0.2 below.
+        assert!(near(items[0].height, 10.0));
+        assert!(near(items[0].bbox.y0, 698.0) && near(items[0].bbox.y1, 708.0));
+    }
+
+    #[test]
+    fn boxes_rotated_text_on_the_page_axes() {
+        let items = testing::items("BT /F1 10 Tf 0 1 -1 0 300 400 Tm (Hello) Tj ET");
+        let bbox = items[0].bbox;
+        assert!(near(bbox.x0, 292.0) && near(bbox.x1, 302.0), "{:?}", bbox);
+        assert!(near(bbox.y0, 400.0) && near(bbox.y1, 422.78), "{:?}", bbox);
+        assert!(near(items[0].width, 10.0) && near(items[0].height, 22.78));
+        let diagonal = testing::items("BT /F1 10 Tf 0.7071 0.7071 -0.7071 0.7071 300 400 Tm (Hello) Tj ET");
+        let bbox = diagonal[0].bbox;
+        assert!(bbox.x0 < 300.0 && bbox.x1 > 300.0 + 22.78 * 0.7 && bbox.y1 > 400.0 + 22.78 * 0.7, "{:?}", bbox);
+    }
+
+    #[test]
+    fn raises_superscripts_and_lowers_subscripts() {
+        let items = testing::items("BT /F1 10 Tf 100 700 Td (H) Tj -3 Ts (2) Tj 0 Ts (O x) Tj 4 Ts (2) Tj ET");
+        assert_eq!(testing::texts(&items), ["H", "2", "O x", "2"]);
+        let baselines: Vec<Baseline> = items.iter().map(|item| item.baseline).collect();
+        assert_eq!(baselines, [Baseline::Normal, Baseline::Sub, Baseline::Normal, Baseline::Super]);
+        assert!(near(items[1].y, 697.0) && near(items[1].rise, -3.0));
+        assert!(near(items[2].y, 700.0) && near(items[3].y, 704.0));
+    }
+
+    #[test]
+    fn keeps_the_rise_across_text_objects() {
+        // Ts is a text state parameter, which BT leaves alone (PDF 32000-1:2008, 9.3.1); only
+        // the text matrices start over.
+        let items = testing::items("BT /F1 10 Tf 5 Ts 100 700 Td (a) Tj ET BT 100 600 Td (b) Tj ET BT 0 Ts 100 500 Td (c) Tj ET");
+        assert!(near(items[1].y, 605.0) && items[1].baseline == Baseline::Super, "{}", items[1].y);
+        assert!(near(items[1].x, 100.0));
+        assert!(near(items[2].y, 500.0) && items[2].baseline == Baseline::Normal);
+    }
+
+    #[test]
+    fn scales_the_rise_by_the_text_matrix() {
+        let items = testing::items("BT /F1 10 Tf 4 Ts 2 0 0 2 100 700 Tm (x) Tj ET");
+        assert!(near(items[0].y, 708.0) && near(items[0].rise, 8.0), "{} {}", items[0].y, items[0].rise);
+        assert!(near(items[0].font_size, 20.0) && items[0].baseline == Baseline::Super);
+        // Rotated a quarter turn, the rise moves the text left.
+        let turned = testing::items("BT /F1 10 Tf 4 Ts 0 1 -1 0 300 400 Tm (x) Tj ET");
+        assert!(near(turned[0].x, 296.0) && near(turned[0].y, 400.0), "({}, {})", turned[0].x, turned[0].y);
+    }
+
+    #[test]
+    fn skips_invisible_text_when_asked() {
+        let content = "BT /F1 12 Tf 72 700 Td (seen) Tj 3 Tr (hidden) Tj 0 Tr (again) Tj ET";
+        let data = testing::pdf(&[content]);
+        let all = testing::items_with(&data, &ExtractOptions::default());
+        assert_eq!(testing::texts(&all), ["seen", "hidden", "again"]);
+        assert_eq!(all.iter().map(|item| item.render_mode).collect::<Vec<_>>(), [0, 3, 0]);
+        let options = ExtractOptions::builder().skip_invisible(true).build().unwrap();
+        let visible = testing::items_with(&data, &options);
+        assert_eq!(testing::texts(&visible), ["seen", "again"]);
+    }
+
+    #[test]
+    fn extracts_the_text_of_a_form() {
+        let mut fixture = Fixture::new();
+        let courier = fixture.object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Courier" });
+        let entries = dictionary! {
+            "Matrix" => vec![1.into(), 0.into(), 0.into(), 1.into(), 100.into(), 50.into()],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => courier } },
+        };
+        fixture.xobject("Fm0", testing::form("BT /F1 10 Tf 20 30 Td (Letterhead) Tj ET", entries));
+        fixture.page("q 1 0 0 1 0 600 cm /Fm0 Do Q BT /F1 12 Tf 72 500 Td (Body) Tj ET");
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&items), ["Letterhead", "Body"]);
+        assert!(near(items[0].x, 120.0) && near(items[0].y, 680.0), "({}, {})", items[0].x, items[0].y);
+        assert_eq!(items[0].font.as_deref(), Some("Courier"));
+        assert_eq!(items[1].font.as_deref(), Some("Helvetica"));
+    }
+
+    #[test]
+    fn keeps_an_unbalanced_form_from_popping_the_page_state() {
+        let mut fixture = Fixture::new();
+        fixture.xobject("Fm0", testing::form("Q Q 1 0 0 1 200 200 cm", Dictionary::new()));
+        fixture.page("1 0 0 1 50 50 cm q 1 0 0 1 10 10 cm /Fm0 Do BT /F1 12 Tf (A) Tj ET Q BT /F1 12 Tf (B) Tj ET");
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&items), ["A", "B"]);
+        assert!(near(items[0].x, 60.0) && near(items[0].y, 60.0), "({}, {})", items[0].x, items[0].y);
+        assert!(near(items[1].x, 50.0) && near(items[1].y, 50.0), "({}, {})", items[1].x, items[1].y);
+    }
+
+    #[test]
+    fn paints_a_form_that_paints_itself_once() {
+        let mut fixture = Fixture::new();
+        fixture.xobject("Fm0", testing::form("BT /F1 12 Tf 72 700 Td (Once) Tj ET /Fm0 Do", Dictionary::new()));
+        fixture.xobject("Fm1", testing::form("/Fm2 Do BT /F1 12 Tf 72 600 Td (One) Tj ET", Dictionary::new()));
+        fixture.xobject("Fm2", testing::form("/Fm1 Do BT /F1 12 Tf 72 500 Td (Two) Tj ET", Dictionary::new()));
+        fixture.page("/Fm0 Do /Fm1 Do");
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&items), ["Once", "Two", "One"]);
+    }
+
+    /// A page showing "Before" and "After" around an inline image given by `image`, the bytes
+    /// from `BI` to `EI`.
+    fn around_image(image: &[u8]) -> Vec<u8> {
+        let mut content = b"BT /F1 12 Tf 72 700 Td (Before) Tj ET q 20 0 0 10 100 100 cm ".to_vec();
+        content.extend_from_slice(image);
+        content.extend_from_slice(b" Q BT /F1 12 Tf 72 680 Td (After) Tj ET");
+        let mut fixture = Fixture::new();
+        fixture.page(content);
+        fixture.bytes()
+    }
+
+    #[test]
+    fn reads_past_an_inline_image_of_known_size() {
+        // Its four bytes of data hold an EI of their own.
+        let data = around_image(b"BI /W 2 /H 2 /BPC 8 /CS /G ID EI\x20\xFF EI");
+        let page = testing::pages_with(&data, &ExtractOptions::default()).remove(0);
+        assert_eq!(testing::texts(&page.items), ["Before", "After"]);
+        assert!(near(page.items[1].x, 72.0) && near(page.items[1].y, 680.0));
+        assert_eq!(page.images.len(), 1);
+        assert_eq!((page.images[0].width, page.images[0].height), (2, 2));
+        assert!(near(page.images[0].rect.x0, 100.0) && near(page.images[0].rect.x1, 120.0));
+    }
+
+    #[test]
+    fn reads_past_an_inline_image_of_unknown_size() {
+        // Filtered, so only an EI followed by what looks like content ends it.
+        let data = around_image(b"BI /W 4 /H 4 /BPC 8 /CS /G /F /Fl ID x\x9C EI \x01\x02 (Tj) EI\x00\xFE\x80 EI");
+        let page = testing::pages_with(&data, &ExtractOptions::default()).remove(0);
+        assert_eq!(testing::texts(&page.items), ["Before", "After"]);
+        assert!(page.errors.is_empty(), "{:?}", page.errors);
+        assert_eq!(page.images.len(), 1);
+    }
+
+    /// The text of a page showing a word at each of the axis rotations and a diagonal one.
+    const TURNED: &str = "BT /F1 12 Tf 1 0 0 1 300 400 Tm (East) Tj 0 1 -1 0 300 400 Tm (North) Tj \
+        -1 0 0 -1 300 400 Tm (West) Tj 0 -1 1 0 300 400 Tm (South) Tj 0.7071 0.7071 -0.7071 0.7071 100 100 Tm (Diagonal) Tj ET";
+
+    #[test]
+    fn reads_the_angle_of_each_rotation() {
+        let items = testing::items(TURNED);
+        let angles: Vec<f32> = items.iter().map(|item| item.angle).collect();
+        assert_eq!(angles, [0.0, 90.0, 180.0, -90.0, 45.0]);
+        // The CTM turns text too, and angles stay within (-180, 180].
+        let items = testing::items("0 -1 1 0 0 792 cm BT /F1 12 Tf -1 0 0 -1 300 400 Tm (A) Tj 0 -1 1 0 300 400 Tm (B) Tj ET");
+        assert_eq!(items.iter().map(|item| item.angle).collect::<Vec<_>>(), [90.0, 180.0]);
+    }
+
+    #[test]
+    fn skips_text_turned_past_the_limit() {
+        let data = testing::pdf(&[TURNED]);
+        let options = ExtractOptions::builder().skip_rotated(Some(10.0)).build().unwrap();
+        // Upside down is still horizontal.
+        assert_eq!(testing::texts(&testing::items_with(&data, &options)), ["East", "West"]);
+        let options = ExtractOptions::builder().skip_rotated(Some(45.0)).build().unwrap();
+        assert_eq!(testing::texts(&testing::items_with(&data, &options)), ["East", "West", "Diagonal"]);
+    }
+
+    #[test]
+    fn reads_an_operation_split_across_content_streams() {
+        let mut fixture = Fixture::new();
+        let first = fixture.object(Stream::new(Dictionary::new(), b"BT /F1 12 Tf 72 720 Td (Split across)".to_vec()));
+        let second = fixture.object(Stream::new(Dictionary::new(), b"Tj ET".to_vec()));
+        fixture.page_with("", dictionary! { "Contents" => vec![first.into(), second.into()] });
+        let pages = testing::pages_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&pages[0].items), ["Split across"]);
+        assert!(pages[0].errors.is_empty(), "{:?}", pages[0].errors);
+    }
+
+    #[test]
+    fn skips_a_content_stream_that_does_not_decompress() {
+        let mut fixture = Fixture::new();
+        let first = fixture.object(Stream::new(Dictionary::new(), b"BT /F1 12 Tf 72 720 Td (Before) Tj ET".to_vec()));
+        let corrupt = fixture.object(Stream::new(dictionary! { "Filter" => "FlateDecode" }, b"not flate data at all".to_vec()));
+        let last = fixture.object(Stream::new(Dictionary::new(), b"BT /F1 12 Tf 72 700 Td (After) Tj ET".to_vec()));
+        fixture.page_with("", dictionary! { "Contents" => vec![first.into(), corrupt.into(), last.into()] });
+        let pages = testing::pages_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&pages[0].items), ["Before", "After"]);
+        let messages: Vec<&str> = pages[0].errors.iter().map(|err| err.message.as_str()).collect();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with(&format!("content stream {} {} R: ", corrupt.0, corrupt.1)), "{:?}", messages);
+        assert_eq!((pages[0].errors[0].page, pages[0].errors[0].limit), (1, None));
+    }
+
+    /// A Flate stream of `size` spaces, about a thousandth of that compressed.
+    fn zip_bomb(size: usize, entries: Dictionary) -> Stream {
+        use std::io::Write;
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+        let spaces = vec![b' '; 1 << 20];
+        for _ in 0..size >> 20 {
+            encoder.write_all(&spaces).unwrap();
+        }
+        let mut dict = dictionary! { "Filter" => "FlateDecode" };
+        dict.extend(&entries);
+        Stream::new(dict, encoder.finish().unwrap())
+    }
+
+    /// Options stopping a content stream at 1 MiB, the bombs inflating to 16.
+    fn bomb_options(strict: bool) -> ExtractOptions {
+        ExtractOptions::builder().max_stream_size(1 << 20).strict(strict).build().unwrap()
+    }
+
+    #[test]
+    fn stops_a_page_at_a_zip_bomb() {
+        let mut fixture = Fixture::new();
+        let bomb = fixture.object(zip_bomb(16 << 20, Dictionary::new()));
+        fixture.page_with("", dictionary! { "Contents" => bomb });
+        fixture.page("BT /F1 12 Tf 72 700 Td (Unharmed) Tj ET");
+        let data = fixture.bytes();
+        assert!(data.len() < 1 << 20);
+        let pages = testing::pages_with(&data, &bomb_options(false));
+        assert_eq!(pages[0].errors.iter().map(|err| err.limit).collect::<Vec<_>>(), [Some(Limit::StreamSize(1 << 20))]);
+        assert_eq!(testing::texts(&pages[1].items), ["Unharmed"]);
+        assert!(pages[1].errors.is_empty());
+
+        let err = crate::extract_bytes(&data, &bomb_options(true)).unwrap_err();
+        assert!(matches!(err, Error::Limit { page: 1, limit: Limit::StreamSize(_) }), "{:?}", err);
+    }
+
+    #[test]
+    fn stops_a_page_at_a_form_that_is_a_zip_bomb() {
+        let mut fixture = Fixture::new();
+        let form = dictionary! { "Type" => "XObject", "Subtype" => "Form", "BBox" => vec![0.into(), 0.into(), 612.into(), 792.into()] };
+        fixture.xobject("Fm0", zip_bomb(16 << 20, form));
+        fixture.page("BT /F1 12 Tf 72 700 Td (Before) Tj ET /Fm0 Do BT /F1 12 Tf 72 680 Td (After) Tj ET");
+        let pages = testing::pages_with(&fixture.bytes(), &bomb_options(false));
+        assert_eq!(testing::texts(&pages[0].items), ["Before"]);
+        assert_eq!(pages[0].errors.iter().map(|err| err.limit).collect::<Vec<_>>(), [Some(Limit::StreamSize(1 << 20))]);
+    }
+
+    #[test]
+    fn stops_a_page_at_forms_nested_too_deep() {
+        let mut fixture = Fixture::new();
+        for depth in 0..8 {
+            fixture.xobject(&format!("Fm{}", depth), testing::form(&format!("BT /F1 12 Tf 72 {} Td (Depth {}) Tj ET /Fm{} Do", 700 - 20 * depth, depth, depth + 1), Dictionary::new()));
+        }
+        fixture.page("/Fm0 Do");
+        let options = ExtractOptions::builder().max_form_depth(4).build().unwrap();
+        let pages = testing::pages_with(&fixture.bytes(), &options);
+        assert_eq!(testing::texts(&pages[0].items), ["Depth 0", "Depth 1", "Depth 2", "Depth 3"]);
+        assert_eq!(pages[0].errors.iter().map(|err| (err.page, err.limit)).collect::<Vec<_>>(), [(1, Some(Limit::FormDepth(4)))]);
+    }
+
+    #[test]
+    fn reads_a_form_in_its_own_resources_once() {
+        let mut fixture = Fixture::new();
+        // The form's resources are the page's, which hold the form: it paints itself twice
+        // over, which unchecked would be paintings without end.
+        fixture.xobject("Fm0", testing::form("BT /F1 12 Tf 72 700 Td (Self) Tj ET /Fm0 Do /Fm0 Do", Dictionary::new()));
+        fixture.page("/Fm0 Do");
+        let pages = testing::pages_with(&fixture.bytes(), &ExtractOptions::default());
+        assert_eq!(testing::texts(&pages[0].items), ["Self"]);
+        assert!(pages[0].errors.is_empty());
+    }
+
+    #[test]
+    fn places_the_images_of_a_form_by_its_matrix_and_the_ctm() {
+        let mut fixture = Fixture::new();
+        let image = fixture.object(Stream::new(dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 1, "Height" => 1, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8 }, vec![0]));
+        let entries = dictionary! { "Matrix" => vec![1.into(), 0.into(), 0.into(), 1.into(), 50.into(), 0.into()], "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => image } } };
+        fixture.xobject("Fm0", testing::form("q 40 0 0 30 10 20 cm /Im1 Do Q BI /W 1 /H 1 /CS /G /BPC 8 ID x EI", entries));
+        fixture.page("q 2 0 0 2 0 100 cm /Fm0 Do Q");
+        let page = testing::pages_with(&fixture.bytes(), &ExtractOptions::default()).remove(0);
+        let rects: Vec<[f32; 4]> = page.images.iter().map(|image| [image.rect.x0, image.rect.y0, image.rect.x1, image.rect.y1]).collect();
+        assert_eq!(rects, [[120.0, 140.0, 200.0, 200.0], [100.0, 100.0, 102.0, 102.0]]);
+        assert_eq!((page.images[0].name.as_deref(), page.images[0].object), (Some("Im1"), Some(image)));
+    }
+
+    #[test]
+    fn stops_a_page_at_too_many_operations() {
+        let many = "BT /F1 12 Tf 72 700 Td (Shown) Tj ET ".to_string() + &"0 0 m ".repeat(1000) + "BT /F1 12 Tf 72 680 Td (Not shown) Tj ET";
+        let data = testing::pdf(&[&many, "BT /F1 12 Tf 72 700 Td (Next page) Tj ET"]);
+        let options = ExtractOptions::builder().max_operations(500).build().unwrap();
+        let pages = testing::pages_with(&data, &options);
+        assert_eq!(testing::texts(&pages[0].items), ["Shown"]);
+        assert_eq!(pages[0].errors.iter().map(|err| err.limit).collect::<Vec<_>>(), [Some(Limit::Operations(500))]);
+        assert_eq!(testing::texts(&pages[1].items), ["Next page"]);
+    }
+}
@@ -0,0 +1,48 @@
+//! Telling how far the reading of a document has got, for showing progress on long ones.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far the reading of a document has got, given to `ExtractOptions::on_page` each time a
+/// selected page is read. Pages read on several threads may finish in any order, so `page_num`
+/// jumps about, but `done` only ever grows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct PageProgress {
+    /// The page just read.
+    pub page_num: u32,
+    /// How many of the selected pages have been read so far, this one included, out of `total`.
+    pub done: usize,
+    pub total: usize,
+    /// How long the document has been open.
+    pub elapsed: Duration,
+}
+
+impl PageProgress {
+    /// How much longer the rest of the pages should take, going by how long those read took;
+    /// `None` before any is.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.done == 0 {
+            return None;
+        }
+        Some(self.elapsed.mul_f64(self.total.saturating_sub(self.done) as f64 / self.done as f64))
+    }
+}
+
+/// A function given the progress of each page read, shared by the clones of the options it is
+/// set in. It is called on the thread that read the page.
+#[derive(Clone)]
+pub struct OnPage(pub(crate) Arc<dyn Fn(PageProgress) + Send + Sync>);
+
+impl OnPage {
+    pub fn new(f: impl Fn(PageProgress) + Send + Sync + 'static) -> OnPage {
+        OnPage(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for OnPage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("OnPage(..)")
+    }
+}
@@ -0,0 +1,165 @@
+//! The Python module, `rapid_pdf`: the items of a document's pages and replacing the text of
+//! one, over [`Extractor`] as the command-line tool is.
+
+use crate::{content, processor, with_mapped, Error, ExtractOptions, Extractor};
+use lopdf::{Document, Object};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+
+/// A text item of a page, as [`crate::TextItem`] but for what Python is given of it.
+#[pyclass]
+#[derive(Debug, Clone)]
+struct TextItem {
+    #[pyo3(get)]
+    text: String,
+    #[pyo3(get)]
+    x: f32,
+    #[pyo3(get)]
+    y: f32,
+    #[pyo3(get)]
+    font_size: f32,
+    #[pyo3(get)]
+    page_num: u32,
+}
+
+impl From<crate::TextItem> for TextItem {
+    fn from(item: crate::TextItem) -> TextItem {
+        TextItem { text: item.text, x: item.x, y: item.y, font_size: item.font_size, page_num: item.page_num }
+    }
+}
+
+/// Files that cannot be read are `IOError`s, the rest `ValueError`s.
+fn py_err(err: Error) -> PyErr {
+    match err {
+        Error::Io(err) => PyIOError::new_err(err.to_string()),
+        err => PyValueError::new_err(err.to_string()),
+    }
+}
+
+/// The items of every page of the PDF at `path`, page after page, each page's in the order
+/// its content draws them.
+#[pyfunction]
+fn extract_text_from_pdf(path: String) -> PyResult<Vec<TextItem>> {
+    items(&path).map_err(py_err)
+}
+
+/// Writes a copy of the PDF at `path` to `output_path` with `target_text` replaced by
+/// `replacement` in the first string of page `page_num` that shows it, starting within a
+/// hundredth of a point of (`target_x`, `target_y`) and, unless `target_font_size` is 0, set
+/// in that size. The page's own content is searched, not that of the forms it paints. The
+/// replacement is written in UTF-16 when the string replaced was, and in UTF-8 otherwise, so
+/// it only reads as written in fonts that decode the string that way.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn replace_text_by_pos(
+    path: String,
+    output_path: String,
+    page_num: u32,
+    target_text: &str,
+    replacement: &str,
+    target_x: f32,
+    target_y: f32,
+    target_font_size: f32,
+) -> PyResult<String> {
+    let target = Target { page_num, text: target_text, x: target_x, y: target_y, font_size: target_font_size };
+    replace_text(&path, &output_path, &target, replacement).map_err(py_err)?;
+    log::info!("Saved modified PDF to {}", output_path);
+    Ok(format!("Saved modified PDF to {}", output_path))
+}
+
+fn items(path: &str) -> Result<Vec<TextItem>, Error> {
+    let extractor = Extractor::open(path, &ExtractOptions::default())?;
+    let mut items = Vec::new();
+    for page in extractor.page_items() {
+        items.extend(page?.items.into_iter().map(TextItem::from));
+    }
+    Ok(items)
+}
+
+/// The text `replace_text_by_pos` looks for, and where.
+struct Target<'a> {
+    page_num: u32,
+    text: &'a str,
+    x: f32,
+    y: f32,
+    /// 0 for any size.
+    font_size: f32,
+}
+
+fn replace_text(path: &str, output_path: &str, target: &Target, replacement: &str) -> Result<(), Error> {
+    let options = ExtractOptions::default();
+    let extractor = Extractor::open(path, &options)?;
+    let operations = extractor.operations(target.page_num)?.unwrap_or_default();
+    let found = operations.iter().find_map(|operation| {
+        let shown = operation.shown.as_ref().filter(|_| operation.form.is_none())?;
+        let matches = shown.text.contains(target.text)
+            && (shown.x - target.x).abs() < 0.01
+            && (shown.y - target.y).abs() < 0.01
+            && (target.font_size == 0.0 || (shown.font_size - target.font_size).abs() < 0.01);
+        matches.then_some((operation.offset, shown))
+    });
+    let mut doc = with_mapped(path.as_ref(), |data| Ok(Document::load_mem(data)?))?;
+    if doc.is_encrypted() {
+        return Err(Error::Unsupported("replacing the text of encrypted PDFs".to_string()));
+    }
+    if let Some((offset, shown)) = found {
+        let page_id = doc.get_pages()[&target.page_num];
+        // The page's content decoded as the extractor decodes it, for its offsets to match.
+        let data = processor::page_content(&doc, page_id, target.page_num, options.max_stream_size, &mut Vec::new());
+        let (mut content, offsets, _) = content::decode(&data);
+        let index = offsets.iter().position(|&start| start == offset);
+        // The string shown is the last operand of `Tj`, `'` and `"` alike.
+        if let Some(Object::String(bytes, _)) = index.and_then(|index| content.operations[index].operands.last_mut()) {
+            *bytes = encode_like(bytes, &shown.text.replacen(target.text, replacement, 1));
+            doc.change_page_content(page_id, content.encode()?)?;
+            log::debug!("replaced {:?} on page {} at ({}, {})", target.text, target.page_num, target.x, target.y);
+        }
+    }
+    doc.save_modern(&mut BufWriter::new(File::create(output_path)?))?;
+    Ok(())
+}
+
+#[pymodule]
+fn rapid_pdf(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<TextItem>()?;
+    m.add_function(wrap_pyfunction!(extract_text_from_pdf, m)?)?;
+    m.add_function(wrap_pyfunction!(replace_text_by_pos, m)?)?;
+    Ok(())
+}
+
+/// Encodes replacement text the way the string it replaces was: UTF-16BE, byte order mark
+/// first, when that one was, otherwise UTF-8.
+fn encode_like(original: &[u8], text: &str) -> Vec<u8> {
+    if original.starts_with(&[0xFE, 0xFF]) {
+        [0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect()
+    } else {
+        text.as_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn replaces_the_text_shown_at_a_position() {
+        let files = tempfile::tempdir().unwrap();
+        let (path, output) = (files.path().join("in.pdf"), files.path().join("out.pdf"));
+        let content = "BT /F1 12 Tf 72 700 Td (CORPORATE NAME) Tj ET BT /F1 12 Tf 1 0 0 1 72 680 Tm (CORPORATE NAME) Tj ET";
+        std::fs::write(&path, testing::pdf(&[content])).unwrap();
+        let (path, output) = (path.display().to_string(), output.display().to_string());
+
+        let read = items(&path).unwrap();
+        let found: Vec<(&str, f32, f32, f32)> = read.iter().map(|item| (item.text.as_str(), item.x, item.y, item.font_size)).collect();
+        assert_eq!(found, [("CORPORATE NAME", 72.0, 700.0, 12.0), ("CORPORATE NAME", 72.0, 680.0, 12.0)]);
+
+        let target = Target { page_num: 1, text: "CORPORATE", x: 72.0, y: 680.0, font_size: 0.0 };
+        replace_text(&path, &output, &target, "Testing").unwrap();
+        let replaced = items(&output).unwrap();
+        let texts: Vec<&str> = replaced.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(texts, ["CORPORATE NAME", "Testing NAME"]);
+    }
+}
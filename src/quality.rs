@@ -0,0 +1,310 @@
+//! How trustworthy the text of a page looks, from signals that can be measured on its items,
+//! for telling pages whose text layer is junk from those worth reading, and pages that are
+//! scans without one from pages left blank.
+
+use crate::matrix::Rect;
+use crate::{PlacedImage, TextItem};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// What is measured of the text of a page, each as a fraction from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct QualitySignals {
+    /// The characters of the page, white space aside.
+    pub characters: usize,
+    /// Of those, the part that are U+FFFD, glyphs no Unicode could be found for.
+    pub unmapped: f32,
+    /// The part that are control or private-use characters, or letters of another script
+    /// than most of the page's are.
+    pub unexpected_script: f32,
+    /// The part of the crop box the boxes of the items cover.
+    pub coverage: f32,
+    /// The part of the items whose box lies for the most part over that of another with
+    /// other text.
+    pub overlapping: f32,
+    /// Of the breaks between words, the part told only by a gap between items rather than by
+    /// a space in their text.
+    pub synthesized_spaces: f32,
+}
+
+/// The quality of the text of a page, from 0 for junk to 1, with what it was worked out from.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct PageQuality {
+    pub quality: f32,
+    #[serde(flatten)]
+    pub signals: QualitySignals,
+}
+
+/// What a page holds, as far as its text goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageKind {
+    /// A page of text, images or not.
+    #[default]
+    Text,
+    /// Images over most of the page and next to no text: a scan without a text layer, whose
+    /// text only OCR can read.
+    ImageOnly,
+    /// Neither text nor images over most of the page.
+    Blank,
+}
+
+impl PageKind {
+    /// The part of the crop box images must cover for a page to be a scan.
+    pub const IMAGE_COVERAGE: f32 = 0.5;
+    /// The most characters a scan may have, as a page number or a stamp added after scanning.
+    pub const STRAY_CHARACTERS: usize = 10;
+
+    /// The kind of a page with `characters` characters, white space aside, and the images
+    /// placed on it within its crop box. Images overlapping one another count as much as
+    /// they cover each on their own, as a scan's tiles do not overlap.
+    pub(crate) fn classify(characters: usize, images: &[PlacedImage], crop_box: &Rect) -> PageKind {
+        let area = crop_box.width() * crop_box.height();
+        let covered: f32 = images.iter().filter_map(|image| image.rect.intersection(crop_box)).map(|part| part.width() * part.height()).sum();
+        if area > 0.0 && covered >= PageKind::IMAGE_COVERAGE * area && characters <= PageKind::STRAY_CHARACTERS {
+            PageKind::ImageOnly
+        } else if characters == 0 {
+            PageKind::Blank
+        } else {
+            PageKind::Text
+        }
+    }
+}
+
+impl QualitySignals {
+    /// The score of the signals, from 0 to 1: 1 less a penalty for each, weighted by how
+    /// surely it tells junk.
+    ///
+    /// - Unmapped glyphs, weighing 0.4, fully so from a quarter of the characters: text no
+    ///   one can read.
+    /// - Unexpected characters, weighing 0.25, fully so from a quarter: text decoded through
+    ///   the wrong encoding, which comes out as symbols and letters of stray scripts.
+    /// - Overlapping items, weighing 0.15, fully so from a quarter: text drawn over text, as
+    ///   hidden OCR layers misplaced over born-digital text are.
+    /// - Synthesized spaces, weighing 0.1, as their part: words told apart only by position,
+    ///   which is less sure than a space.
+    /// - Coverage, weighing 0.1, fully so with none, and not at all from 5% of the page:
+    ///   a page of a few scattered characters says little.
+    ///
+    /// A page without characters scores 0.
+    ///
+    /// ```
+    /// use rapid_pdf::QualitySignals;
+    ///
+    /// let clean = QualitySignals { characters: 2000, coverage: 0.3, synthesized_spaces: 0.1, ..QualitySignals::default() };
+    /// assert!((clean.score() - 0.99).abs() < 1e-6);
+    ///
+    /// let mangled = QualitySignals { unmapped: 0.3, unexpected_script: 0.2, overlapping: 0.1, synthesized_spaces: 1.0, ..clean };
+    /// assert!((mangled.score() - 0.24).abs() < 1e-6);
+    /// assert_eq!(QualitySignals::default().score(), 0.0);
+    /// ```
+    pub fn score(&self) -> f32 {
+        if self.characters == 0 {
+            return 0.0;
+        }
+        let penalties = [
+            (0.4, self.unmapped * 4.0),
+            (0.25, self.unexpected_script * 4.0),
+            (0.15, self.overlapping * 4.0),
+            (0.1, self.synthesized_spaces),
+            (0.1, 1.0 - self.coverage / 0.05),
+        ];
+        let penalty: f32 = penalties.iter().map(|&(weight, badness): &(f32, f32)| weight * badness.clamp(0.0, 1.0)).sum();
+        (1.0 - penalty).clamp(0.0, 1.0)
+    }
+}
+
+impl PageQuality {
+    /// Measures the items of a page, in the order they are listed, within its crop box.
+    /// Items are taken as on one line when their baselines are within `line_tolerance` font
+    /// sizes, and as words apart when more than `word_gap` font sizes separate them.
+    pub(crate) fn measure(items: &[TextItem], crop_box: &Rect, line_tolerance: f32, word_gap: f32) -> PageQuality {
+        let mut characters = 0;
+        let mut unmapped = 0;
+        let mut unexpected = 0;
+        let mut scripts: HashMap<Script, usize> = HashMap::new();
+        for c in items.iter().flat_map(|item| item.text.chars()).filter(|c| !c.is_whitespace()) {
+            characters += 1;
+            match script(c) {
+                Script::Unmapped => unmapped += 1,
+                Script::Unexpected => unexpected += 1,
+                Script::Common => {}
+                script => *scripts.entry(script).or_default() += 1,
+            }
+        }
+        // Letters of any script but the one most are in are unexpected.
+        let main = scripts.values().copied().max().unwrap_or(0);
+        unexpected += scripts.values().sum::<usize>() - main;
+
+        let area = crop_box.width() * crop_box.height();
+        let covered: f32 = items.iter().filter_map(|item| item.bbox.intersection(crop_box)).map(|part| part.width() * part.height()).sum();
+
+        let mut spaces = 0;
+        let mut synthesized = 0;
+        for (i, item) in items.iter().enumerate() {
+            spaces += item.text.split_whitespace().count().saturating_sub(1);
+            let Some(next) = items.get(i + 1) else { continue };
+            let size = item.font_size.max(next.font_size);
+            let same_line = !item.vertical && !next.vertical && (item.y - next.y).abs() <= line_tolerance * size;
+            if !same_line || next.bbox.x0 - item.bbox.x1 <= word_gap * size {
+                continue;
+            }
+            if item.text.ends_with(char::is_whitespace) || next.text.starts_with(char::is_whitespace) {
+                spaces += 1;
+            } else {
+                synthesized += 1;
+            }
+        }
+
+        let fraction = |count: usize, of: usize| if of > 0 { count as f32 / of as f32 } else { 0.0 };
+        let signals = QualitySignals {
+            characters,
+            unmapped: fraction(unmapped, characters),
+            unexpected_script: fraction(unexpected, characters),
+            coverage: if area > 0.0 { (covered / area).clamp(0.0, 1.0) } else { 0.0 },
+            overlapping: fraction(overlapping(items), items.len()),
+            synthesized_spaces: fraction(synthesized, spaces + synthesized),
+        };
+        PageQuality { quality: signals.score(), signals }
+    }
+}
+
+/// How many characters items have, white space aside.
+pub(crate) fn characters(items: &[TextItem]) -> usize {
+    items.iter().flat_map(|item| item.text.chars()).filter(|c| !c.is_whitespace()).count()
+}
+
+/// How many items lie for more than half of their box over another item with other text.
+fn overlapping(items: &[TextItem]) -> usize {
+    let mut order: Vec<usize> = (0..items.len()).filter(|&i| !items[i].text.trim().is_empty()).collect();
+    order.sort_by(|&a, &b| items[a].bbox.x0.total_cmp(&items[b].bbox.x0));
+    let mut over = vec![false; items.len()];
+    for (position, &a) in order.iter().enumerate() {
+        for &b in &order[position + 1..] {
+            let (first, second) = (&items[a], &items[b]);
+            if second.bbox.x0 >= first.bbox.x1 {
+                break;
+            }
+            let Some(part) = first.bbox.intersection(&second.bbox) else { continue };
+            if first.text == second.text {
+                continue;
+            }
+            let shared = part.width() * part.height();
+            for (index, item) in [(a, first), (b, second)] {
+                if shared * 2.0 > item.bbox.width() * item.bbox.height() {
+                    over[index] = true;
+                }
+            }
+        }
+    }
+    over.iter().filter(|&&over| over).count()
+}
+
+/// The writing system of a character, coarsely, as far as telling stray ones goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    /// Digits, punctuation and symbols, at home in any script.
+    Common,
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Indic,
+    Thai,
+    /// Chinese, Japanese and Korean, which mix.
+    Cjk,
+    /// Letters of the scripts not told apart here.
+    Other,
+    /// U+FFFD.
+    Unmapped,
+    /// Control and private-use characters, which text never holds.
+    Unexpected,
+}
+
+fn script(c: char) -> Script {
+    match c {
+        '\u{FFFD}' => Script::Unmapped,
+        c if c.is_control() => Script::Unexpected,
+        '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{10FFFF}' => Script::Unexpected,
+        c if !c.is_alphabetic() => Script::Common,
+        'a'..='z' | 'A'..='Z' | '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}' | '\u{FB00}'..='\u{FB06}' => Script::Latin,
+        '\u{370}'..='\u{3FF}' | '\u{1F00}'..='\u{1FFF}' => Script::Greek,
+        '\u{400}'..='\u{52F}' => Script::Cyrillic,
+        '\u{590}'..='\u{5FF}' | '\u{FB1D}'..='\u{FB4F}' => Script::Hebrew,
+        '\u{600}'..='\u{6FF}' | '\u{750}'..='\u{77F}' | '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}' => Script::Arabic,
+        '\u{900}'..='\u{DFF}' => Script::Indic,
+        '\u{E00}'..='\u{E7F}' => Script::Thai,
+        '\u{1100}'..='\u{11FF}' | '\u{3040}'..='\u{30FF}' | '\u{3130}'..='\u{318F}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}' => Script::Cjk,
+        _ => Script::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::lines::{DEFAULT_TOLERANCE, DEFAULT_WORD_GAP};
+    use crate::testing::{self, Fixture};
+    use crate::ExtractOptions;
+    use lopdf::{dictionary, Object};
+
+    const LETTER: Rect = Rect { x0: 0.0, y0: 0.0, x1: 612.0, y1: 792.0 };
+
+    /// Ten lines of 12-point Helvetica, their words spaced in their text.
+    fn body() -> String {
+        let line = "(The quick brown fox jumps over the lazy dog, then back again.) Tj T*";
+        format!("BT /F1 12 Tf 14 TL 72 720 Td {} ET", [line; 10].join(" "))
+    }
+
+    /// The quality of the first page of `fixture`, its items measured as the extractor does.
+    fn measure(fixture: Fixture) -> PageQuality {
+        let items = testing::items_with(&fixture.bytes(), &ExtractOptions::default());
+        PageQuality::measure(&items, &LETTER, DEFAULT_TOLERANCE, DEFAULT_WORD_GAP)
+    }
+
+    #[test]
+    fn scores_a_clean_page_in_full() {
+        let mut fixture = Fixture::new();
+        fixture.page(body());
+        let quality = measure(fixture);
+        let signals = QualitySignals { characters: 500, coverage: quality.signals.coverage, ..QualitySignals::default() };
+        assert_eq!(quality, PageQuality { quality: 1.0, signals });
+        // Ten lines 12 points high, each about 330 points long.
+        assert!((quality.signals.coverage - 0.0822).abs() < 1e-4, "{}", quality.signals.coverage);
+    }
+
+    #[test]
+    fn scores_a_mangled_page_low() {
+        let mut fixture = Fixture::new();
+        let descendant = fixture.object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "Subset",
+            "CIDSystemInfo" => dictionary! { "Registry" => Object::string_literal("Adobe"), "Ordering" => Object::string_literal("Identity"), "Supplement" => 0 },
+            "DW" => 500,
+        });
+        // Identity-H without a `/ToUnicode` map, its glyphs unmapped.
+        fixture.font("F2", dictionary! { "Type" => "Font", "Subtype" => "Type0", "BaseFont" => "Subset", "Encoding" => "Identity-H", "DescendantFonts" => vec![Object::Reference(descendant)] });
+        fixture.page(
+            "BT /F2 12 Tf 72 720 Td <0024002500260027002800290030003100320033> Tj ET \
+            BT /F1 12 Tf 72 700 Td (\\001\\002\\003 Ctrl) Tj ET \
+            BT /F1 12 Tf 72 680 Td (over) Tj ET BT /F1 12 Tf 72 680 Td (under) Tj ET \
+            BT /F1 12 Tf 72 660 Td (gap) Tj 40 0 Td (ped) Tj 40 0 Td (words) Tj ET",
+        );
+        let quality = measure(fixture);
+        let signals = quality.signals;
+        // 10 unmapped glyphs, 3 control characters and 24 letters; "over" and "under" over
+        // each other, of 7 items; "gap", "ped" and "words" apart by position alone, against
+        // the one space of "Ctrl".
+        assert_eq!(signals.characters, 37);
+        let near = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(near(signals.unmapped, 10.0 / 37.0), "{:?}", signals);
+        assert!(near(signals.unexpected_script, 3.0 / 37.0), "{:?}", signals);
+        assert!(near(signals.overlapping, 2.0 / 7.0), "{:?}", signals);
+        assert!(near(signals.synthesized_spaces, 2.0 / 3.0), "{:?}", signals);
+        assert!(near(signals.coverage, 0.0056), "{:?}", signals);
+        // Penalties of 0.4, 0.25 * 12 / 37, 0.15, 0.1 * 2 / 3 and 0.1 * (1 - 0.0056 / 0.05).
+        assert!(near(quality.quality, 0.2134), "{:?}", quality);
+        assert_eq!(quality.quality, signals.score());
+    }
+}
@@ -0,0 +1,567 @@
+//! The extraction results in machine-readable form: JSON for `--format json` and `jsonl`,
+//! delimited rows for `--format csv` and `tsv`.
+
+use crate::search::Match;
+use crate::stats::{DocumentStats, PageStats};
+use crate::tuning::TuningReport;
+use crate::{NumberFormat, Units};
+use rapid_pdf::layout::figures;
+use rapid_pdf::layout::headings::Heading;
+use rapid_pdf::layout::tables::Table;
+use rapid_pdf::layout::title::TitleGuess;
+use rapid_pdf::{Annotation, Attachment, ContentOperation, Destination, Error, ExtractOptions, Extractor, FormField, Link, Metadata, Origin, OutlineItem, PageAttributes, PageError, PageItems, PageKind, PageQuality, PlacedImage, Rect, TextItem};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub struct DocumentReport {
+    /// The file the document was read from, as given.
+    pub path: String,
+    /// The PDF version from the file header, such as `1.7`.
+    pub version: String,
+    /// The text entries of the trailer's `/Info` dictionary (PDF 32000-1:2008, 14.3.3), such
+    /// as `Title` and `Author`.
+    pub info: BTreeMap<String, String>,
+    /// The standard fields of the information dictionary and of the XMP packet, dates in
+    /// RFC 3339.
+    pub metadata: Metadata,
+    /// The title guessed from the text of the first page, with `--guess-title` or
+    /// `--metadata`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guessed_title: Option<TitleGuess>,
+    /// The outline, or bookmarks, with the pages its entries lead to.
+    pub outline: Vec<OutlineItem>,
+    /// The fields of the form, with their values and the boxes they are shown in.
+    pub form_fields: Vec<FormField>,
+    /// The named destinations, by name, with the pages they lead to.
+    pub destinations: BTreeMap<String, Destination>,
+    /// The files embedded in the document, without their contents.
+    pub attachments: Vec<Attachment>,
+    /// The unit of all lengths but font sizes and rises, which are in points.
+    pub units: Units,
+    pub origin: Origin,
+    /// The font sizes text was kept in, when some was left out for its size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_sizes: Option<FontSizes>,
+    pub pages: Vec<PageReport>,
+    /// How many of the pages read are of text, scans without text, or blank.
+    pub page_kinds: PageKinds,
+    /// Content of the pages that could not be read, whose items are those of the rest.
+    pub errors: Vec<PageError>,
+    /// Whether reading stopped short at `--max-chars` or `--max-items`.
+    pub truncated: bool,
+}
+
+/// The smallest and largest font size of the text kept, in points; `None` for no limit.
+#[derive(Debug, Serialize)]
+pub struct FontSizes {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl DocumentReport {
+    pub fn new(extractor: &Extractor, path: &Path, units: Units, origin: Origin) -> DocumentReport {
+        let scale = units.per_point();
+        let mut form_fields = extractor.form_fields();
+        for widget in form_fields.iter_mut().flat_map(|field| &mut field.widgets) {
+            let rect = widget.rect;
+            widget.rect = Rect { x0: finite(rect.x0 * scale), y0: finite(rect.y0 * scale), x1: finite(rect.x1 * scale), y1: finite(rect.y1 * scale) };
+        }
+        let mut destinations = extractor.destinations();
+        for destination in destinations.values_mut() {
+            for coordinate in [&mut destination.left, &mut destination.top, &mut destination.right, &mut destination.bottom].into_iter().flatten() {
+                *coordinate = finite(*coordinate * scale);
+            }
+        }
+        DocumentReport {
+            path: path.display().to_string(),
+            version: extractor.version().to_string(),
+            info: extractor.info(),
+            metadata: extractor.metadata(),
+            guessed_title: None,
+            outline: extractor.outline(),
+            form_fields,
+            destinations,
+            attachments: extractor.attachments(),
+            units,
+            origin,
+            font_sizes: None,
+            pages: Vec::new(),
+            page_kinds: PageKinds::default(),
+            errors: Vec::new(),
+            truncated: false,
+        }
+    }
+}
+
+/// How many pages are of each kind.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PageKinds {
+    pub text: usize,
+    pub image_only: usize,
+    pub blank: usize,
+}
+
+impl PageKinds {
+    pub fn add(&mut self, kind: PageKind) {
+        match kind {
+            PageKind::Text => self.text += 1,
+            PageKind::ImageOnly => self.image_only += 1,
+            PageKind::Blank => self.blank += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.text + self.image_only + self.blank
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageReport {
+    pub page: u32,
+    /// The page's label, such as `iv` or `A-1`, when the document gives them.
+    pub label: Option<String>,
+    /// Size of the crop box, turned upright by `/Rotate`.
+    pub width: f32,
+    pub height: f32,
+    pub rotation: u16,
+    pub media_box: Rect,
+    pub crop_box: Rect,
+    /// The text of the page, with the images painted on it where they are read among it.
+    pub items: Vec<ReportItem>,
+    /// The lines the text items are grouped into, as the text output reads them.
+    pub lines: Vec<LineReport>,
+    pub annotations: Vec<Annotation>,
+    pub links: Vec<Link>,
+    /// Whether reading stopped at the page for `--max-chars` or `--max-items`.
+    pub truncated: bool,
+    /// How trustworthy the text of the page looks, from 0 to 1, and the signals it is scored
+    /// from.
+    pub quality: PageQuality,
+    /// Whether the page is of text, a scan without text, or blank.
+    pub kind: PageKind,
+}
+
+/// An entry of a page's `items`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ReportItem {
+    Text(TextItem),
+    Image(PlacedImage),
+}
+
+/// An entry of a page's `lines`.
+#[derive(Debug, Serialize)]
+pub struct LineReport {
+    pub text: String,
+    pub bbox: Rect,
+    /// Which column of its band the line is in, counted from the left; 0 unless the text is
+    /// read in layout order.
+    pub column: usize,
+    /// How many degrees the line's baseline leans, counterclockwise on the page as displayed.
+    pub skew: f32,
+}
+
+impl PageReport {
+    /// The page with its boxes and items, given in points, converted to `units`, and its
+    /// items grouped into lines as `options` groups them. Numbers that are not finite, as
+    /// broken matrices produce, are written as 0. Images go before the first item read after
+    /// them, as `layout::figures::position` tells.
+    pub fn new(page: PageItems, units: Units, options: &ExtractOptions) -> PageReport {
+        let scale = units.per_point();
+        let length = |points: f32| finite(points * scale);
+        let rect = |rect: Rect| Rect { x0: length(rect.x0), y0: length(rect.y0), x1: length(rect.x1), y1: length(rect.y1) };
+        let lines = rapid_pdf::page_lines(page.items.clone(), options)
+            .into_iter()
+            .map(|line| LineReport { text: line.text, bbox: rect(line.bbox), column: line.column, skew: finite(line.skew) })
+            .collect();
+        let mut items = page.items;
+        for item in &mut items {
+            item.x = length(item.x);
+            item.y = length(item.y);
+            item.width = length(item.width);
+            item.height = length(item.height);
+            item.bbox = rect(item.bbox);
+            item.font_size = finite(item.font_size);
+            item.rise = finite(item.rise);
+            item.angle = finite(item.angle);
+        }
+        let mut annotations = page.annotations;
+        for annotation in &mut annotations {
+            annotation.rect = rect(annotation.rect);
+            for quad in &mut annotation.quads {
+                *quad = rect(*quad);
+            }
+        }
+        let mut links = page.links;
+        for link in &mut links {
+            link.rect = rect(link.rect);
+        }
+        let boxes: Vec<Rect> = items.iter().map(|item| item.bbox).collect();
+        let mut images: Vec<(usize, PlacedImage)> =
+            page.images.into_iter().map(|image| (figures::position(&image.rect, &boxes, options.origin), image)).collect();
+        images.sort_by_key(|&(position, _)| position);
+        let mut images = images.into_iter().peekable();
+        let mut entries = Vec::with_capacity(items.len() + images.len());
+        for (index, item) in items.into_iter().enumerate() {
+            while let Some((_, mut image)) = images.next_if(|&(position, _)| position == index) {
+                image.rect = rect(image.rect);
+                entries.push(ReportItem::Image(image));
+            }
+            entries.push(ReportItem::Text(item));
+        }
+        for (_, mut image) in images {
+            image.rect = rect(image.rect);
+            entries.push(ReportItem::Image(image));
+        }
+        let (truncated, quality, kind) = (page.truncated, page.quality, page.kind);
+        PageReport {
+            page: page.page_num,
+            label: page.label,
+            width: length(page.width),
+            height: length(page.height),
+            rotation: page.rotation,
+            media_box: rect(page.media_box),
+            crop_box: rect(page.crop_box),
+            items: entries,
+            lines,
+            annotations,
+            links,
+            truncated,
+            quality,
+            kind,
+        }
+    }
+}
+
+/// The manifest of `--extract-images`: the images painted on the pages of a document, in
+/// order, with the files they were written to.
+#[derive(Debug, Serialize)]
+pub struct ImageManifest {
+    pub path: String,
+    pub units: Units,
+    pub origin: Origin,
+    pub images: Vec<ImageEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageEntry {
+    pub page: u32,
+    /// The name of the file in the directory.
+    pub file: String,
+    /// Where the page paints the image.
+    pub rect: Rect,
+    /// Pixel dimensions of the image.
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageEntry {
+    /// The entry with its box, given in points, converted to `units`.
+    pub fn scaled(self, units: Units) -> ImageEntry {
+        let scale = units.per_point();
+        let Rect { x0, y0, x1, y1 } = self.rect;
+        ImageEntry { rect: Rect { x0: finite(x0 * scale), y0: finite(y0 * scale), x1: finite(x1 * scale), y1: finite(y1 * scale) }, ..self }
+    }
+}
+
+/// A guessed title with its box in `units` rather than points.
+pub fn scaled_title(mut guess: TitleGuess, units: Units) -> TitleGuess {
+    let scale = units.per_point();
+    let Rect { x0, y0, x1, y1 } = guess.bbox;
+    guess.bbox = Rect { x0: finite(x0 * scale), y0: finite(y0 * scale), x1: finite(x1 * scale), y1: finite(y1 * scale) };
+    guess
+}
+
+/// A page of `--page-info` with its size and boxes in `units` rather than points.
+pub fn scaled_page(mut page: PageAttributes, units: Units) -> PageAttributes {
+    let scale = units.per_point();
+    let rect = |rect: Rect| Rect { x0: finite(rect.x0 * scale), y0: finite(rect.y0 * scale), x1: finite(rect.x1 * scale), y1: finite(rect.y1 * scale) };
+    page.width = finite(page.width * scale);
+    page.height = finite(page.height * scale);
+    page.media_box = rect(page.media_box);
+    page.crop_box = rect(page.crop_box);
+    page
+}
+
+/// The manifest of `--split-pages`: the file each page of a document was written to.
+#[derive(Debug, Serialize)]
+pub struct PageManifest {
+    pub path: String,
+    pub pages: Vec<PageFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageFile {
+    pub page: u32,
+    pub label: Option<String>,
+    /// The name of the file in the directory, and its size in bytes.
+    pub file: String,
+    pub bytes: usize,
+    /// What of the page's content could not be read.
+    pub errors: Vec<PageError>,
+}
+
+/// An operation of `--dump-ops` with where its text is shown in `units` rather than points.
+pub fn scaled_operation(mut operation: ContentOperation, units: Units) -> ContentOperation {
+    let scale = units.per_point();
+    if let Some(shown) = &mut operation.shown {
+        shown.x = finite(shown.x * scale);
+        shown.y = finite(shown.y * scale);
+        shown.font_size = finite(shown.font_size * scale);
+    }
+    operation
+}
+
+/// A file that could not be read, or the run that failed, as `--format json` and `jsonl` tell
+/// it: the code of `Error::code` for errors of the library, `invalid-arguments` for arguments
+/// that could not be parsed, `output` for output that could not be written and `other` for
+/// the rest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    /// The file that could not be read, if the error is of one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub code: &'static str,
+    pub message: String,
+    /// The page the error is of, if it is of one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// The exit status the error makes; see `EXIT_STATUSES`.
+    #[serde(skip)]
+    pub status: u8,
+}
+
+impl ErrorReport {
+    pub fn new(path: Option<&Path>, err: &(dyn std::error::Error + 'static)) -> ErrorReport {
+        let library = crate::library_error(err);
+        let code = match library {
+            Some(err) => err.code(),
+            None if err.downcast_ref::<std::io::Error>().is_some() => "output",
+            None => "other",
+        };
+        let page = match library {
+            Some(Error::Content { page, .. } | Error::Limit { page, .. } | Error::PageOutOfRange { page, .. }) => Some(*page),
+            _ => None,
+        };
+        ErrorReport { path: path.map(|path| path.display().to_string()), code, message: err.to_string(), page, status: crate::exit_status(err) }
+    }
+}
+
+/// What `--format json` writes when the run fails before writing anything else.
+#[derive(Debug, Serialize)]
+pub struct ErrorDocument {
+    pub errors: Vec<ErrorReport>,
+}
+
+/// The entry of a file in the list `--format json` writes of several: its report, or why it
+/// could not be read.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum FileReport {
+    Read(Box<DocumentReport>),
+    Failed { path: String, errors: Vec<ErrorReport> },
+}
+
+/// A line of `--format jsonl` output.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Record {
+    Page {
+        /// The file the page is in, as given.
+        path: String,
+        #[serde(flatten)]
+        page: PageReport,
+    },
+    /// The metadata of a file, alone, with `--metadata`.
+    Metadata {
+        path: String,
+        #[serde(flatten)]
+        metadata: Box<Metadata>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        guessed_title: Option<TitleGuess>,
+    },
+    /// The title guessed from the first page of a file, alone, with `--guess-title`.
+    Title {
+        path: String,
+        guessed_title: Option<TitleGuess>,
+    },
+    /// The outline of a file, alone, with `--outline`.
+    Outline {
+        path: String,
+        outline: Vec<OutlineItem>,
+    },
+    /// The fields of the form of a file, alone, with `--form-fields`.
+    FormFields {
+        path: String,
+        form_fields: Vec<FormField>,
+    },
+    /// The files embedded in a file, without their contents, alone, with `--attachments`.
+    Attachments {
+        path: String,
+        attachments: Vec<Attachment>,
+    },
+    /// The named destinations of a file, alone, with `--destinations`.
+    Destinations {
+        path: String,
+        destinations: BTreeMap<String, Destination>,
+    },
+    /// How many pages a file has, with `--page-count`.
+    PageCount {
+        path: String,
+        page_count: usize,
+    },
+    /// The pages of a file as its page tree describes them, with `--page-info`.
+    PageInfo {
+        path: String,
+        page_count: usize,
+        /// The unit of the sizes and boxes.
+        units: Units,
+        origin: Origin,
+        pages: Vec<PageAttributes>,
+    },
+    /// An operation of a page's content, with `--dump-ops`.
+    Operation {
+        path: String,
+        page: u32,
+        /// The unit of where text is shown.
+        units: Units,
+        #[serde(flatten)]
+        operation: ContentOperation,
+    },
+    /// The counts of a page, with `--stats`.
+    PageStats {
+        path: String,
+        #[serde(flatten)]
+        stats: PageStats,
+    },
+    /// The counts of a whole file, with `--stats`, after those of its pages.
+    Stats {
+        path: String,
+        #[serde(flatten)]
+        stats: DocumentStats,
+    },
+    /// The distances of the text of a whole file, with `--tuning-report`.
+    Tuning {
+        path: String,
+        #[serde(flatten)]
+        tuning: TuningReport,
+    },
+    /// A heading, with `--headings`.
+    Heading {
+        path: String,
+        #[serde(flatten)]
+        heading: Heading,
+    },
+    /// A match of `--find`, with the page and the line or paragraph it is in.
+    Match {
+        path: String,
+        #[serde(flatten)]
+        found: Match,
+    },
+    /// Content of a page that could not be read, after the page's record.
+    Error {
+        path: String,
+        #[serde(flatten)]
+        error: PageError,
+    },
+    /// A file that could not be read, or the run that failed.
+    FileError(ErrorReport),
+    Summary(Summary),
+}
+
+/// Totals over all files read, the last record of `--format jsonl --summary`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    /// Files read, and files that could not be read at all.
+    pub files: usize,
+    pub failed: usize,
+    pub pages: usize,
+    pub items: usize,
+    /// Pages whose content could not be read in full.
+    pub errors: usize,
+    /// Pages that are scans without text.
+    pub image_only_pages: usize,
+    /// Matches found, with `--find`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<usize>,
+}
+
+/// The columns of `--format csv` and `tsv`, one row per item. Columns are only ever added at
+/// the end, so readers going by position keep working.
+pub const ITEM_COLUMNS: [&str; 13] =
+    ["page", "text", "x", "y", "width", "height", "font_size", "font", "font_resource", "bold", "italic", "color", "file"];
+
+/// An item's fields in the order of [`ITEM_COLUMNS`], lengths written with `format`; `file`
+/// is the path of the document.
+pub fn item_fields(file: &str, page_num: u32, item: &TextItem, format: &NumberFormat) -> Vec<String> {
+    vec![
+        page_num.to_string(),
+        item.text.clone(),
+        format.length(item.x),
+        format.length(item.y),
+        format.length(item.width),
+        format.length(item.height),
+        format.number(item.font_size),
+        item.font.clone().unwrap_or_default(),
+        item.font_resource.clone().unwrap_or_default(),
+        item.bold.to_string(),
+        item.italic.to_string(),
+        item.color.map(|color| color.hex()).unwrap_or_default(),
+        file.to_string(),
+    ]
+}
+
+/// Joins fields into one record of delimited text (RFC 4180): fields holding the separator,
+/// a quote or a line break are quoted, with their quotes doubled.
+pub fn delimited(fields: &[impl AsRef<str>], separator: char) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([separator, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    fields.join(&separator.to_string())
+}
+
+/// A table as CSV, one record per row.
+pub fn table_csv(table: &Table) -> String {
+    let mut csv = String::new();
+    for row in &table.rows {
+        csv.push_str(&delimited(row, ','));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Escapes text for XML content and attribute values.
+pub fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            // Control characters other than white space are not allowed in XML.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => escaped.push(char::REPLACEMENT_CHARACTER),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn finite(value: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
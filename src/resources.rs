@@ -0,0 +1,73 @@
+//! Named resources available to a content stream (PDF 32000-1:2008, 7.8.3).
+
+use crate::color::ColorSpace;
+use crate::font::{self, FontCache, FontMap};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct Resources {
+    pub fonts: FontMap,
+    /// XObjects by name. They are streams, so always indirect objects.
+    pub xobjects: BTreeMap<Vec<u8>, ObjectId>,
+    /// Property lists named by `BDC` operators.
+    pub properties: BTreeMap<Vec<u8>, Dictionary>,
+    /// Color spaces named by `cs` operators.
+    pub color_spaces: BTreeMap<Vec<u8>, ColorSpace>,
+}
+
+impl Resources {
+    /// Resources of a page, including those inherited from its ancestors in the page tree.
+    pub fn page(doc: &Document, page_id: ObjectId, fonts: &FontCache) -> Resources {
+        let mut xobjects = BTreeMap::new();
+        let mut properties = BTreeMap::new();
+        let mut color_spaces = BTreeMap::new();
+        if let Ok((direct, inherited)) = doc.get_page_resources(page_id) {
+            let inherited = inherited.into_iter().filter_map(|id| doc.get_dictionary(id).ok());
+            for resources in direct.into_iter().chain(inherited) {
+                collect_xobjects(doc, resources, &mut xobjects);
+                collect_properties(doc, resources, &mut properties);
+                collect_color_spaces(doc, resources, &mut color_spaces);
+            }
+        }
+        Resources { fonts: font::load_page_fonts(doc, page_id, fonts), xobjects, properties, color_spaces }
+    }
+
+    /// Resources from a `/Resources` dictionary, such as a form XObject's own.
+    pub fn from_dict(doc: &Document, resources: &Dictionary, fonts: &FontCache) -> Resources {
+        let mut xobjects = BTreeMap::new();
+        let mut properties = BTreeMap::new();
+        let mut color_spaces = BTreeMap::new();
+        collect_xobjects(doc, resources, &mut xobjects);
+        collect_properties(doc, resources, &mut properties);
+        collect_color_spaces(doc, resources, &mut color_spaces);
+        Resources { fonts: font::load_fonts(doc, resources, fonts), xobjects, properties, color_spaces }
+    }
+}
+
+/// Adds the entries of `/XObject` that are not yet in `xobjects`, so nearer resource
+/// dictionaries take precedence over inherited ones.
+fn collect_xobjects(doc: &Document, resources: &Dictionary, xobjects: &mut BTreeMap<Vec<u8>, ObjectId>) {
+    let Ok(dict) = resources.get_deref(b"XObject", doc).and_then(Object::as_dict) else { return };
+    for (name, obj) in dict {
+        if let Ok(id) = obj.as_reference() {
+            xobjects.entry(name.clone()).or_insert(id);
+        }
+    }
+}
+
+fn collect_properties(doc: &Document, resources: &Dictionary, properties: &mut BTreeMap<Vec<u8>, Dictionary>) {
+    let Ok(dict) = resources.get_deref(b"Properties", doc).and_then(Object::as_dict) else { return };
+    for (name, obj) in dict {
+        if let Ok((_, Object::Dictionary(list))) = doc.dereference(obj) {
+            properties.entry(name.clone()).or_insert_with(|| list.clone());
+        }
+    }
+}
+
+fn collect_color_spaces(doc: &Document, resources: &Dictionary, color_spaces: &mut BTreeMap<Vec<u8>, ColorSpace>) {
+    let Ok(dict) = resources.get_deref(b"ColorSpace", doc).and_then(Object::as_dict) else { return };
+    for (name, obj) in dict {
+        color_spaces.entry(name.clone()).or_insert_with(|| ColorSpace::load(doc, obj));
+    }
+}
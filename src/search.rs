@@ -0,0 +1,142 @@
+//! `--find`: matches of a pattern in the lines or paragraphs of pages, with the boxes of the
+//! text matched.
+
+use crate::report::finite;
+use crate::Units;
+use rapid_pdf::layout::lines::Line;
+use rapid_pdf::{Origin, PageText, Rect};
+use regex::Regex;
+use serde::Serialize;
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// A match of the pattern.
+#[derive(Debug, Clone, Serialize)]
+pub struct Match {
+    pub page: u32,
+    /// The line, or paragraph, the match is in.
+    pub text: String,
+    /// Where the match starts and ends in `text`, in bytes.
+    pub start: usize,
+    pub end: usize,
+    /// The box of the text matched in each line it is in: one, unless a match in a paragraph
+    /// runs on from one line to the next.
+    pub boxes: Vec<Rect>,
+    /// The corners of each of `boxes` as a Highlight annotation's `/QuadPoints` give them
+    /// (PDF 32000-1:2008, 12.5.6.10), and as viewers write them: top left, top right, bottom
+    /// left, bottom right, each as x and y.
+    pub quads: Vec<[f32; 8]>,
+    /// The `--rect` region the match is in, counted from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<usize>,
+}
+
+impl Match {
+    /// The match with its boxes in `units` rather than points.
+    pub fn scaled(self, units: Units) -> Match {
+        let scale = units.per_point();
+        let boxes = self
+            .boxes
+            .iter()
+            .map(|rect| Rect { x0: finite(rect.x0 * scale), y0: finite(rect.y0 * scale), x1: finite(rect.x1 * scale), y1: finite(rect.y1 * scale) })
+            .collect();
+        let quads = self.quads.iter().map(|quad| quad.map(|value| finite(value * scale))).collect();
+        Match { boxes, quads, ..self }
+    }
+}
+
+/// The matches of `pattern` in the pages, in reading order. With `paragraphs`, each
+/// paragraph is searched as one text, its lines joined by spaces, so that matches may run
+/// across the ends of lines; a soft hyphen ending a line is dropped instead.
+pub fn find(pages: &[PageText], pattern: &Regex, paragraphs: bool, origin: Origin, word_gap: f32) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for page in pages {
+        for paragraph in &page.paragraphs {
+            let texts: Vec<&[Line]> = match paragraphs {
+                true => vec![&paragraph.lines[..]],
+                false => paragraph.lines.chunks(1).collect(),
+            };
+            for lines in texts {
+                let (text, glyphs) = joined(lines, origin, word_gap);
+                // Patterns that may match nothing, such as `a*`, would otherwise match everywhere.
+                for found in pattern.find_iter(&text).filter(|found| !found.is_empty()) {
+                    let mut boxes: Vec<(usize, Rect)> = Vec::new();
+                    let inside = glyphs.iter().filter(|glyph| (found.start()..found.end()).contains(&glyph.offset));
+                    for glyph in inside {
+                        let Some(rect) = glyph.rect else { continue };
+                        match boxes.last_mut() {
+                            Some((line, bbox)) if *line == glyph.line => *bbox = bbox.union(&rect),
+                            _ => boxes.push((glyph.line, rect)),
+                        }
+                    }
+                    let boxes: Vec<Rect> = boxes.into_iter().map(|(_, rect)| rect).collect();
+                    let quads = boxes.iter().map(|rect| quad(rect, origin)).collect();
+                    matches.push(Match { page: page.page_num, text: text.clone(), start: found.start(), end: found.end(), boxes, quads, region: paragraph.region });
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// The corners of a box, top left first: the top is the larger y unless `origin` measures
+/// from the top.
+fn quad(rect: &Rect, origin: Origin) -> [f32; 8] {
+    let (top, bottom) = match origin {
+        Origin::BottomLeft => (rect.y1, rect.y0),
+        Origin::TopLeft => (rect.y0, rect.y1),
+    };
+    [rect.x0, top, rect.x1, top, rect.x0, bottom, rect.x1, bottom]
+}
+
+/// A character of the text searched.
+struct Glyph {
+    /// Where it starts in the text, in bytes.
+    offset: usize,
+    /// Which of the lines it is of.
+    line: usize,
+    rect: Option<Rect>,
+}
+
+/// The text of lines, trimmed and joined as paragraphs join them, with its characters.
+fn joined(lines: &[Line], origin: Origin, word_gap: f32) -> (String, Vec<Glyph>) {
+    let mut text = String::new();
+    let mut glyphs = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let line_glyphs = line.glyphs(origin, word_gap);
+        let start = line_glyphs.iter().position(|(c, _)| !c.is_whitespace());
+        let end = line_glyphs.iter().rposition(|(c, _)| !c.is_whitespace());
+        let (Some(start), Some(end)) = (start, end) else { continue };
+        if text.ends_with(SOFT_HYPHEN) {
+            text.pop();
+            glyphs.pop();
+        } else if !text.is_empty() {
+            glyphs.push(Glyph { offset: text.len(), line: index, rect: None });
+            text.push(' ');
+        }
+        for &(c, rect) in &line_glyphs[start..=end] {
+            glyphs.push(Glyph { offset: text.len(), line: index, rect });
+            text.push(c);
+        }
+    }
+    (text, glyphs)
+}
+
+/// A match as a line of text: the page and boxes, then its line with the match highlighted
+/// by `highlight`, a pair of strings to write around it. Control characters are written as
+/// spaces, so each match stays one line.
+pub fn line(found: &Match, path: Option<&str>, boxes: &[String], highlight: (&str, &str)) -> String {
+    let one_line = |text: &str| text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect::<String>();
+    let prefix = path.map(|path| format!("{}: ", path)).unwrap_or_default();
+    format!(
+        "{}page {} {}: {}{}{}{}{}",
+        prefix,
+        found.page,
+        boxes.join(" "),
+        one_line(&found.text[..found.start]),
+        highlight.0,
+        one_line(&found.text[found.start..found.end]),
+        highlight.1,
+        one_line(&found.text[found.end..])
+    )
+}
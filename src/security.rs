@@ -0,0 +1,209 @@
+//! Encrypted documents (PDF 32000-1:2008, 7.6). Most are encrypted only to restrict printing
+//! or copying, with an empty user password, and open like any other: lopdf decrypts them as
+//! it loads them, and what it fails to set up is tried again here. The rest open given their
+//! user or owner password, with the RC4 and AES ciphers of the standard security handler
+//! lopdf provides. Permissions are not enforced, as extracting text is what they restrict.
+
+use lopdf::encryption::{self, DecryptionError, PasswordAlgorithm};
+use lopdf::xref::XrefEntry;
+use crate::Error;
+use lopdf::{Document, EncryptionState, Object, ObjectStream, Reader};
+use md5::{Digest, Md5};
+use std::collections::{BTreeMap, HashSet};
+
+/// What passwords shorter than 32 bytes are padded with (7.6.3.3, algorithm 2).
+const PADDING: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08, 0x2E, 0x2E, 0x00, 0xB6,
+    0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+/// Whether `doc` is encrypted and lopdf did not decrypt it as it loaded it.
+pub fn is_locked(doc: &Document) -> bool {
+    doc.is_encrypted() && doc.encryption_state.is_none()
+}
+
+/// Decrypts a locked `doc`, read from `buffer`, with `password`, either its user or its owner
+/// password, or without one with the empty user password, as viewers do. lopdf keeps no
+/// objects of a document it could not decrypt, so they are parsed again from `buffer`, the
+/// xref table the load found telling where.
+pub fn unlock(doc: &mut Document, buffer: &[u8], password: Option<&str>) -> Result<(), Error> {
+    let filter = doc.get_encrypted().and_then(|dict| dict.get(b"Filter")).and_then(Object::as_name).unwrap_or(b"Standard");
+    if filter != b"Standard" {
+        return Err(Error::Unsupported(format!(
+            "encryption: the /{} security handler; only the standard, password-based one is supported",
+            String::from_utf8_lossy(filter)
+        )));
+    }
+    let algorithm = PasswordAlgorithm::try_from(&*doc).map_err(describe)?;
+    let given = password.is_some();
+    let password = algorithm.sanitize_password(password.unwrap_or_default()).map_err(|err| describe(err.into()))?;
+    // lopdf derives the key from the user password; before AES-256 the owner password only
+    // encrypts it, in /O (7.6.3.4).
+    let password = match doc.authenticate_raw_user_password(&password) {
+        Ok(()) => password,
+        Err(_) if !given => return Err(Error::PasswordRequired),
+        Err(_) => {
+            doc.authenticate_raw_owner_password(&password).map_err(describe)?;
+            match revision(doc) {
+                Some(revision) if revision <= 4 => user_password(doc, &password, revision).ok_or(Error::Decryption(DecryptionError::InvalidType.into()))?,
+                _ => password,
+            }
+        }
+    };
+    let state = EncryptionState::decode(&*doc, &password).map_err(describe)?;
+    let encrypt_id = doc.trailer.get(b"Encrypt").and_then(Object::as_reference).ok();
+
+    let reader = Reader { buffer, document: std::mem::take(doc), encryption_state: None, raw_objects: BTreeMap::new() };
+    let mut objects = BTreeMap::new();
+    for (&number, entry) in &reader.document.reference_table.entries {
+        let XrefEntry::Normal { generation, .. } = *entry else {
+            continue;
+        };
+        let id = (number, generation);
+        if Some(id) == encrypt_id {
+            continue;
+        }
+        // Objects that do not parse are left out, as lopdf does for unencrypted documents.
+        let Ok(mut object) = reader.get_object(id, &mut HashSet::new()) else {
+            continue;
+        };
+        if encryption::decrypt_object(&state, id, &mut object).is_ok() {
+            objects.insert(id, object);
+        }
+    }
+    // Objects in object streams were encrypted as part of their stream (7.5.7).
+    let mut compressed = BTreeMap::new();
+    for object in objects.values_mut() {
+        if let Ok(stream) = object.as_stream_mut() {
+            if stream.dict.has_type(b"ObjStm") {
+                if let Ok(object_stream) = ObjectStream::new(stream) {
+                    compressed.extend(object_stream.objects);
+                }
+            }
+        }
+    }
+    for (id, object) in compressed {
+        objects.entry(id).or_insert(object);
+    }
+
+    *doc = reader.document;
+    doc.objects = objects;
+    doc.trailer.remove(b"Encrypt");
+    doc.encryption_state = Some(state);
+    Ok(())
+}
+
+fn revision(doc: &Document) -> Option<i64> {
+    doc.get_encrypted().and_then(|dict| dict.get(b"R")).and_then(Object::as_i64).ok()
+}
+
+/// The padded user password the owner password of a revision 2 to 4 handler decrypts /O to
+/// (7.6.3.4, algorithm 7).
+fn user_password(doc: &Document, owner_password: &[u8], revision: i64) -> Option<Vec<u8>> {
+    let dict = doc.get_encrypted().ok()?;
+    let owner = dict.get(b"O").and_then(Object::as_str).ok()?;
+    let length = owner_password.len().min(32);
+    let mut hash = Md5::new().chain_update(&owner_password[..length]).chain_update(&PADDING[..32 - length]).finalize();
+    let key_length = if revision >= 3 {
+        for _ in 0..50 {
+            hash = Md5::digest(hash);
+        }
+        (dict.get(b"Length").and_then(Object::as_i64).unwrap_or(40) / 8) as usize
+    } else {
+        5
+    };
+    let key = hash.get(..key_length)?;
+    let mut user = owner.to_vec();
+    // Revision 3 and later encrypt 20 times, with the key's bytes XORed with 19 down to 0.
+    let rounds = if revision >= 3 { 19 } else { 0 };
+    for round in (0..=rounds).rev() {
+        let round_key: Vec<u8> = key.iter().map(|byte| byte ^ round).collect();
+        user = rc4(&round_key, &user);
+    }
+    Some(user)
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+/// Tells a wrong password apart from encryption lopdf cannot decrypt.
+fn describe(err: lopdf::Error) -> Error {
+    match err {
+        lopdf::Error::Decryption(DecryptionError::IncorrectPassword) => Error::WrongPassword,
+        lopdf::Error::Decryption(
+            err @ (DecryptionError::UnsupportedEncryption
+            | DecryptionError::UnsupportedVersion
+            | DecryptionError::UnsupportedRevision
+            | DecryptionError::InvalidVersion
+            | DecryptionError::InvalidRevision),
+        ) => Error::Unsupported(format!("encryption: {}", err)),
+        err => Error::Decryption(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, Fixture};
+    use crate::{Error, ExtractOptions, Extractor};
+    use lopdf::{dictionary, Dictionary, Document};
+
+    /// A document showing one line, its title in its information dictionary.
+    fn document() -> Document {
+        let mut fixture = Fixture::new();
+        fixture.page("BT /F1 12 Tf 72 700 Td (Only printing is restricted.) Tj ET");
+        let mut doc = fixture.document(Dictionary::new());
+        let info = doc.add_object(dictionary! { "Title" => lopdf::Object::string_literal("Restricted") });
+        doc.trailer.set("Info", info);
+        doc
+    }
+
+    fn open(data: &[u8], password: Option<&str>) -> Result<Vec<String>, Error> {
+        let options = ExtractOptions::builder().password(password).build()?;
+        let extractor = Extractor::from_bytes(data, &options)?;
+        let pages = extractor.page_items().collect::<Result<Vec<_>, _>>()?;
+        Ok(pages.into_iter().flat_map(|page| page.items).map(|item| item.text).collect())
+    }
+
+    #[test]
+    fn opens_an_empty_user_password_without_one() {
+        for key_length in [40, 128] {
+            let data = testing::encrypted(document(), "", "owner", key_length);
+            assert!(data.windows(8).any(|window| window == b"/Encrypt"));
+            assert!(!data.windows(8).any(|window| window == b"printing"), "the content is encrypted");
+            assert_eq!(open(&data, None).unwrap(), ["Only printing is restricted."], "{}-bit key", key_length);
+            // A password given is of no matter when none is needed.
+            assert_eq!(open(&data, Some("anything")).unwrap(), ["Only printing is restricted."]);
+        }
+    }
+
+    #[test]
+    fn decrypts_the_strings_of_an_empty_user_password() {
+        let data = testing::encrypted(document(), "", "owner", 128);
+        let extractor = Extractor::from_bytes(&data, &ExtractOptions::default()).unwrap();
+        assert_eq!(extractor.metadata().info.title.as_deref(), Some("Restricted"));
+    }
+
+    #[test]
+    fn needs_a_user_password_that_is_not_empty() {
+        let data = testing::encrypted(document(), "secret", "owner", 128);
+        assert!(matches!(open(&data, None), Err(Error::PasswordRequired)));
+        assert!(matches!(open(&data, Some("wrong")), Err(Error::WrongPassword)));
+        assert_eq!(open(&data, Some("secret")).unwrap(), ["Only printing is restricted."]);
+        assert_eq!(open(&data, Some("owner")).unwrap(), ["Only printing is restricted."]);
+    }
+}
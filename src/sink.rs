@@ -0,0 +1,124 @@
+//! Receiving text items page by page as they are read, rather than a document's worth at once.
+
+use crate::{Annotation, Error, Link, PageError, PageItems, PageKind, PageQuality, PlacedImage, Rect, TextItem};
+
+/// A page about to be given to a `TextSink`, without its items.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PageInfo {
+    pub page_num: u32,
+    /// The page's label, such as `iv`, when the document gives them.
+    pub label: Option<String>,
+    /// Size of the crop box, turned upright by `/Rotate`.
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation for display: 0, 90, 180 or 270.
+    pub rotation: u16,
+    /// The page's boxes, upright and measured from the origin like the items.
+    pub media_box: Rect,
+    pub crop_box: Rect,
+    /// What of the page's content could not be read; the items are those of the rest.
+    pub errors: Vec<PageError>,
+    pub annotations: Vec<Annotation>,
+    pub links: Vec<Link>,
+    pub images: Vec<PlacedImage>,
+    /// Whether reading stopped at this page for `ExtractOptions::max_chars` or `max_items`.
+    pub truncated: bool,
+    /// How trustworthy the text of its items looks.
+    pub quality: PageQuality,
+    /// Whether it is a page of text, a scan without text, or blank.
+    pub kind: PageKind,
+}
+
+impl PageInfo {
+    pub(crate) fn of(page: &PageItems) -> PageInfo {
+        PageInfo {
+            page_num: page.page_num,
+            label: page.label.clone(),
+            width: page.width,
+            height: page.height,
+            rotation: page.rotation,
+            media_box: page.media_box,
+            crop_box: page.crop_box,
+            errors: page.errors.clone(),
+            annotations: page.annotations.clone(),
+            links: page.links.clone(),
+            images: page.images.clone(),
+            truncated: page.truncated,
+            quality: page.quality,
+            kind: page.kind,
+        }
+    }
+}
+
+/// What `Extractor::visit` and `extract_with` give each page to: its start, its items in
+/// order, then its end. Only one page's items are held at a time. An error from any call,
+/// such as an `Error::Sink` with the sink's own, stops the extraction there and is returned.
+///
+/// ```no_run
+/// use rapid_pdf::{Error, ExtractOptions, TextItem, TextSink};
+///
+/// /// Counts the characters of a document.
+/// struct Count(usize);
+///
+/// impl TextSink for Count {
+///     fn item(&mut self, item: &TextItem) -> Result<(), Error> {
+///         self.0 += item.text.chars().count();
+///         Ok(())
+///     }
+/// }
+///
+/// let mut count = Count(0);
+/// rapid_pdf::extract_with("report.pdf", &ExtractOptions::default(), &mut count)?;
+/// println!("{} characters", count.0);
+/// # Ok::<(), Error>(())
+/// ```
+pub trait TextSink {
+    fn page_start(&mut self, page: PageInfo) -> Result<(), Error> {
+        let _ = page;
+        Ok(())
+    }
+
+    fn item(&mut self, item: &TextItem) -> Result<(), Error>;
+
+    fn page_end(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Keeps every page given to it, for the APIs that return a whole document.
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    pub pages: Vec<PageItems>,
+}
+
+impl TextSink for Collector {
+    fn page_start(&mut self, page: PageInfo) -> Result<(), Error> {
+        self.pages.push(PageItems {
+            page_num: page.page_num,
+            label: page.label,
+            width: page.width,
+            height: page.height,
+            rotation: page.rotation,
+            media_box: page.media_box,
+            crop_box: page.crop_box,
+            items: Vec::new(),
+            rules: Vec::new(),
+            images: page.images,
+            errors: page.errors,
+            annotations: page.annotations,
+            links: page.links,
+            truncated: page.truncated,
+            quality: page.quality,
+            kind: page.kind,
+        });
+        Ok(())
+    }
+
+    fn item(&mut self, item: &TextItem) -> Result<(), Error> {
+        if let Some(page) = self.pages.last_mut() {
+            page.items.push(item.clone());
+        }
+        Ok(())
+    }
+}
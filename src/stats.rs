@@ -0,0 +1,167 @@
+//! `--stats`: counts of the text of pages and of whole documents, gathered a page at a time so
+//! that no more than a page's text is ever held.
+
+use rapid_pdf::{Origin, PageText, Rect};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// The counts of some text: of a page, or of all the pages of a document.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Counts {
+    /// Characters other than white space.
+    pub characters: usize,
+    /// Words as the lines' spacing tells them, not by the spaces in the items' text, which
+    /// some documents never draw.
+    pub words: usize,
+    pub lines: usize,
+    pub items: usize,
+    /// The characters set in each font, by its name.
+    pub fonts: BTreeMap<String, usize>,
+    /// The font size most characters are set in, to the nearest half point.
+    pub font_size: Option<f32>,
+    /// The fraction of the area of the crop box covered by the boxes of the items.
+    pub coverage: f32,
+    /// Characters by font size, in half points.
+    #[serde(skip)]
+    sizes: HashMap<i32, usize>,
+    /// The area of the crop box covered by text, and the whole of it.
+    #[serde(skip)]
+    covered: f32,
+    #[serde(skip)]
+    area: f32,
+}
+
+impl Counts {
+    /// Adds in the counts of more text.
+    fn add(&mut self, other: &Counts) {
+        self.characters += other.characters;
+        self.words += other.words;
+        self.lines += other.lines;
+        self.items += other.items;
+        for (font, count) in &other.fonts {
+            *self.fonts.entry(font.clone()).or_default() += count;
+        }
+        for (&size, &count) in &other.sizes {
+            *self.sizes.entry(size).or_default() += count;
+        }
+        self.covered += other.covered;
+        self.area += other.area;
+        self.finish();
+    }
+
+    /// Works out the font size and coverage from the counts.
+    fn finish(&mut self) {
+        self.font_size = self.sizes.iter().max_by_key(|&(&size, &count)| (count, -size)).map(|(&size, _)| size as f32 / 2.0);
+        self.coverage = if self.area > 0.0 { (self.covered / self.area).clamp(0.0, 1.0) } else { 0.0 };
+    }
+
+    /// A row of the table `--stats` writes as text.
+    pub fn row(&self, label: &str) -> String {
+        let font_size = self.font_size.map(|size| format!("{:.1}", size)).unwrap_or_else(|| "-".to_string());
+        format!("{:>6} {:>10} {:>8} {:>7} {:>7} {:>9} {:>8.1}%", label, self.characters, self.words, self.lines, self.items, font_size, self.coverage * 100.0)
+    }
+}
+
+/// The head of the table of `Counts::row`.
+pub const HEADER: &str = "  page characters    words   lines   items font size coverage";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageStats {
+    pub page: u32,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+impl PageStats {
+    /// The counts of a page laid out, splitting its lines into words as `origin` and
+    /// `word_gap` have them.
+    pub fn new(page: &PageText, origin: Origin, word_gap: f32) -> PageStats {
+        let mut counts = Counts::default();
+        let mut boxes = Vec::new();
+        for line in page.paragraphs.iter().flat_map(|paragraph| &paragraph.lines) {
+            counts.lines += 1;
+            counts.words += line.words(origin, word_gap).len();
+            for item in &line.items {
+                counts.items += 1;
+                let characters = item.text.chars().filter(|c| !c.is_whitespace()).count();
+                counts.characters += characters;
+                let font = item.font.clone().or_else(|| item.font_resource.clone()).unwrap_or_default();
+                *counts.fonts.entry(font).or_default() += characters;
+                *counts.sizes.entry((item.font_size * 2.0).round() as i32).or_default() += characters;
+                boxes.extend(item.bbox.intersection(&page.crop_box));
+            }
+        }
+        counts.fonts.retain(|_, count| *count > 0);
+        counts.sizes.retain(|_, count| *count > 0);
+        counts.covered = covered_area(&boxes);
+        counts.area = page.crop_box.width() * page.crop_box.height();
+        counts.finish();
+        PageStats { page: page.page_num, counts }
+    }
+}
+
+/// The counts of a document, with those of its pages.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DocumentStats {
+    pub page_count: usize,
+    /// Pages without any text that could be extracted, as scanned pages without OCR.
+    pub empty_pages: usize,
+    #[serde(flatten)]
+    pub counts: Counts,
+    /// Those of each page, unless they were written as they were counted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<PageStats>,
+}
+
+impl DocumentStats {
+    /// Adds a page, keeping its counts among `pages` with `keep`.
+    pub fn add(&mut self, page: PageStats, keep: bool) {
+        self.page_count += 1;
+        if page.counts.characters == 0 {
+            self.empty_pages += 1;
+        }
+        self.counts.add(&page.counts);
+        if keep {
+            self.pages.push(page);
+        }
+    }
+
+    /// The end of the table `--stats` writes as text: the totals, then the fonts, most used
+    /// first, and how many pages have no text.
+    pub fn footer(&self) -> String {
+        let mut footer = self.counts.row("total") + "\n";
+        let mut fonts: Vec<(&String, &usize)> = self.counts.fonts.iter().collect();
+        fonts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let fonts: Vec<String> = fonts.iter().map(|(font, count)| format!("{} {}", if font.is_empty() { "(unnamed)" } else { font }, count)).collect();
+        if !fonts.is_empty() {
+            let _ = writeln!(footer, "fonts: {}", fonts.join(", "));
+        }
+        let _ = writeln!(footer, "pages without text: {} of {}", self.empty_pages, self.page_count);
+        footer
+    }
+}
+
+/// The area of the union of the rectangles: across each strip between their left and right
+/// edges, the length of the spans they cover.
+fn covered_area(rects: &[Rect]) -> f32 {
+    let mut edges: Vec<f32> = rects.iter().flat_map(|rect| [rect.x0, rect.x1]).filter(|x| x.is_finite()).collect();
+    edges.sort_by(f32::total_cmp);
+    edges.dedup();
+    let mut area = 0.0;
+    for strip in edges.windows(2) {
+        let (left, right) = (strip[0], strip[1]);
+        let mut spans: Vec<(f32, f32)> = rects.iter().filter(|rect| rect.x0 <= left && rect.x1 >= right).map(|rect| (rect.y0, rect.y1)).collect();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (mut covered, mut end) = (0.0, f32::NEG_INFINITY);
+        for (y0, y1) in spans {
+            let start = y0.max(end);
+            if y1 > start {
+                covered += y1 - start;
+                end = y1;
+            }
+        }
+        area += covered * (right - left);
+    }
+    area
+}
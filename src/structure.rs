@@ -0,0 +1,117 @@
+//! The logical structure tree of tagged PDFs (PDF 32000-1:2008, 14.7), used to put text in
+//! the author's reading order instead of the order it happens to be drawn in.
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+
+/// How deeply structure elements may nest before the rest of a branch is ignored.
+const MAX_DEPTH: usize = 256;
+
+/// Identifies a marked-content sequence: its `/MCID` within a content stream, which is the
+/// page's own content unless `stream` names a form XObject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentId {
+    pub stream: Option<ObjectId>,
+    pub mcid: i64,
+}
+
+/// A marked-content sequence referenced by a structure element.
+#[derive(Debug, Clone)]
+pub struct StructureRef {
+    pub page: ObjectId,
+    pub content: ContentId,
+    /// The element's structure type after `/RoleMap`, such as `P`, `H1` or `TD`.
+    pub role: String,
+}
+
+/// Collects the marked content referenced by the structure tree, in logical order. Untagged
+/// documents give an empty list.
+pub fn reading_order(doc: &Document) -> Vec<StructureRef> {
+    let Ok(root) = doc.catalog().and_then(|catalog| catalog.get_deref(b"StructTreeRoot", doc)).and_then(Object::as_dict)
+    else {
+        return Vec::new();
+    };
+    let mut walker = Walker {
+        doc,
+        role_map: root.get_deref(b"RoleMap", doc).and_then(Object::as_dict).ok(),
+        visited: HashSet::new(),
+        refs: Vec::new(),
+    };
+    if let Ok(kids) = root.get(b"K") {
+        walker.kids(kids, None, "Document", 0);
+    }
+    walker.refs
+}
+
+struct Walker<'a> {
+    doc: &'a Document,
+    role_map: Option<&'a Dictionary>,
+    /// Indirect elements already walked, so cycles and shared kids are followed once.
+    visited: HashSet<ObjectId>,
+    refs: Vec<StructureRef>,
+}
+
+impl<'a> Walker<'a> {
+    /// Walks the `/K` entry of an element with the given page and role.
+    fn kids(&mut self, kids: &'a Object, page: Option<ObjectId>, role: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        match kids {
+            Object::Integer(mcid) => {
+                if let Some(page) = page {
+                    let content = ContentId { stream: None, mcid: *mcid };
+                    self.refs.push(StructureRef { page, content, role: role.to_string() });
+                }
+            }
+            Object::Array(kids) => {
+                for kid in kids {
+                    self.kids(kid, page, role, depth + 1);
+                }
+            }
+            Object::Reference(id) if self.visited.insert(*id) => {
+                if let Ok(kid) = self.doc.get_object(*id) {
+                    self.kids(kid, page, role, depth + 1);
+                }
+            }
+            Object::Dictionary(dict) => self.dict(dict, page, role, depth),
+            _ => {}
+        }
+    }
+
+    fn dict(&mut self, dict: &'a Dictionary, page: Option<ObjectId>, role: &str, depth: usize) {
+        let page = dict.get(b"Pg").and_then(Object::as_reference).ok().or(page);
+        match dict.get(b"Type").and_then(Object::as_name).ok() {
+            Some(b"MCR") => {
+                let (Some(page), Ok(mcid)) = (page, dict.get(b"MCID").and_then(Object::as_i64)) else { return };
+                let stream = dict.get(b"Stm").and_then(Object::as_reference).ok();
+                let content = ContentId { stream, mcid };
+                self.refs.push(StructureRef { page, content, role: role.to_string() });
+            }
+            // Annotations and other objects carry no marked content.
+            Some(b"OBJR") => {}
+            _ => {
+                let role = match dict.get(b"S").and_then(Object::as_name) {
+                    Ok(name) => self.standard_role(name),
+                    Err(_) => role.to_string(),
+                };
+                if let Ok(kids) = dict.get(b"K") {
+                    self.kids(kids, page, &role, depth + 1);
+                }
+            }
+        }
+    }
+
+    /// Maps a custom structure type to a standard one through `/RoleMap`, which may chain.
+    fn standard_role(&self, name: &[u8]) -> String {
+        let mut name = name;
+        let mut seen = HashSet::new();
+        while let Some(mapped) = self.role_map.and_then(|map| map.get(name).and_then(Object::as_name).ok()) {
+            if !seen.insert(name) {
+                break;
+            }
+            name = mapped;
+        }
+        String::from_utf8_lossy(name).into_owned()
+    }
+}
@@ -0,0 +1,160 @@
+//! Documents built in memory for the tests of the modules that read them: pages of content
+//! streams written out by hand, Helvetica as `/F1` on every one.
+
+use crate::{ExtractOptions, Extractor, PageItems, TextItem};
+use lopdf::{dictionary, Dictionary, Document, EncryptionState, EncryptionVersion, Object, ObjectId, Permissions, Stream};
+
+/// A document under construction, its pages sharing one font and XObject resource each.
+pub(crate) struct Fixture {
+    doc: Document,
+    pages_id: ObjectId,
+    fonts: Dictionary,
+    xobjects: Dictionary,
+    pages: Vec<(Vec<u8>, Dictionary)>,
+}
+
+impl Fixture {
+    pub fn new() -> Fixture {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let helvetica = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        Fixture { doc, pages_id, fonts: dictionary! { "F1" => helvetica }, xobjects: Dictionary::new(), pages: Vec::new() }
+    }
+
+    pub fn object(&mut self, object: impl Into<Object>) -> ObjectId {
+        self.doc.add_object(object)
+    }
+
+    /// Adds a font every page has as `/name`.
+    pub fn font(&mut self, name: &str, font: Dictionary) -> ObjectId {
+        let id = self.doc.add_object(font);
+        self.fonts.set(name, id);
+        id
+    }
+
+    /// Adds an XObject every page has as `/name`.
+    pub fn xobject(&mut self, name: &str, xobject: Stream) -> ObjectId {
+        let id = self.doc.add_object(xobject);
+        self.xobjects.set(name, id);
+        id
+    }
+
+    /// Adds a letter-size page showing `content`.
+    pub fn page(&mut self, content: impl AsRef<[u8]>) -> &mut Fixture {
+        self.page_with(content, Dictionary::new())
+    }
+
+    /// Adds a page showing `content`, with `entries` added to or replacing those of its
+    /// dictionary, such as `/Rotate`.
+    pub fn page_with(&mut self, content: impl AsRef<[u8]>, entries: Dictionary) -> &mut Fixture {
+        self.pages.push((content.as_ref().to_vec(), entries));
+        self
+    }
+
+    /// The document, its catalog extended by `catalog`.
+    pub fn document(mut self, catalog: Dictionary) -> Document {
+        let resources = self.doc.add_object(dictionary! { "Font" => self.fonts, "XObject" => self.xobjects });
+        let mut kids = Vec::new();
+        for (content, entries) in self.pages {
+            let contents = self.doc.add_object(Stream::new(Dictionary::new(), content));
+            let mut page = dictionary! {
+                "Type" => "Page",
+                "Parent" => self.pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Resources" => resources,
+                "Contents" => contents,
+            };
+            set_all(&mut page, entries);
+            kids.push(Object::Reference(self.doc.add_object(page)));
+        }
+        let count = kids.len() as i64;
+        self.doc.objects.insert(self.pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+        let mut root = dictionary! { "Type" => "Catalog", "Pages" => self.pages_id };
+        set_all(&mut root, catalog);
+        let root = self.doc.add_object(root);
+        self.doc.trailer.set("Root", root);
+        self.doc
+    }
+
+    /// The document as the bytes of a PDF file.
+    pub fn bytes(self) -> Vec<u8> {
+        save(self.document(Dictionary::new()))
+    }
+}
+
+/// Sets the entries of `dict` to those of `entries`, replacing those it has, which
+/// `Dictionary::extend` would instead keep alongside in an array.
+fn set_all(dict: &mut Dictionary, entries: Dictionary) {
+    for (key, value) in entries {
+        dict.set(key, value);
+    }
+}
+
+/// `doc` encrypted by the standard security handler with a key of `key_length` bits: 40 for
+/// the first version, RC4 with a longer key otherwise. It opens with `user_password`, which
+/// may be empty, or with `owner_password`.
+pub(crate) fn encrypted(mut doc: Document, user_password: &str, owner_password: &str, key_length: usize) -> Vec<u8> {
+    let id = Object::string_literal("0123456789abcdef");
+    doc.trailer.set("ID", vec![id.clone(), id]);
+    let permissions = Permissions::PRINTABLE;
+    let version = match key_length {
+        40 => EncryptionVersion::V1 { document: &doc, owner_password, user_password, permissions },
+        _ => EncryptionVersion::V2 { document: &doc, owner_password, user_password, key_length, permissions },
+    };
+    let state = EncryptionState::try_from(version).expect("the document encrypts");
+    doc.encrypt(&state).expect("the document encrypts");
+    save(doc)
+}
+
+pub(crate) fn save(mut doc: Document) -> Vec<u8> {
+    let mut data = Vec::new();
+    doc.save_to(&mut data).expect("a document built in memory saves");
+    data
+}
+
+/// A document of a page for each content stream.
+pub(crate) fn pdf(pages: &[&str]) -> Vec<u8> {
+    let mut fixture = Fixture::new();
+    for content in pages {
+        fixture.page(content);
+    }
+    fixture.bytes()
+}
+
+/// The pages of `data` as read with `options`.
+pub(crate) fn pages_with(data: &[u8], options: &ExtractOptions) -> Vec<PageItems> {
+    let extractor = Extractor::from_bytes(data, options).expect("the fixture opens");
+    extractor.page_items().collect::<Result<_, _>>().expect("the fixture's pages read")
+}
+
+/// The items of the first page of `data`, read with `options`.
+pub(crate) fn items_with(data: &[u8], options: &ExtractOptions) -> Vec<TextItem> {
+    pages_with(data, options).into_iter().next().map(|page| page.items).unwrap_or_default()
+}
+
+/// The items a page showing `content` has, read with the default options.
+pub(crate) fn items(content: &str) -> Vec<TextItem> {
+    items_with(&pdf(&[content]), &ExtractOptions::default())
+}
+
+/// A form XObject showing `content`, drawn with `entries` in its dictionary, such as its
+/// `/Matrix` or `/Resources`.
+pub(crate) fn form(content: &str, entries: Dictionary) -> Stream {
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+    };
+    set_all(&mut dict, entries);
+    Stream::new(dict, content.as_bytes().to_vec())
+}
+
+/// The text of each item.
+pub(crate) fn texts(items: &[TextItem]) -> Vec<&str> {
+    items.iter().map(|item| item.text.as_str()).collect()
+}
+
+/// Whether two coordinates agree to a hundredth of a point.
+pub(crate) fn near(a: f32, b: f32) -> bool {
+    (a - b).abs() < 0.01
+}
@@ -0,0 +1,51 @@
+//! Reading independent pages on several threads, their results kept in page order.
+
+use crate::{Error, ExtractOptions};
+use rayon::prelude::*;
+use std::io;
+
+/// Where page work runs.
+pub(crate) enum Threads {
+    /// On the calling thread, one page after another.
+    One,
+    /// On rayon's global pool, one thread per core unless the program set it up otherwise.
+    Global,
+    /// On a pool of the number of threads asked for.
+    Pool(rayon::ThreadPool),
+}
+
+impl Threads {
+    pub fn new(options: &ExtractOptions) -> Result<Threads, Error> {
+        // Debug output follows each page from its operators to its paragraphs, so pages are read
+        // one at a time for it not to interleave.
+        if options.debug_ops {
+            return Ok(Threads::One);
+        }
+        match options.threads {
+            None => Ok(Threads::Global),
+            Some(1) => Ok(Threads::One),
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map(Threads::Pool)
+                .map_err(|err| Error::Io(io::Error::other(err))),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            Threads::One => 1,
+            Threads::Global => rayon::current_num_threads(),
+            Threads::Pool(pool) => pool.current_num_threads(),
+        }
+    }
+
+    /// `f` of each of `items`, in their order.
+    pub fn map<T: Send, R: Send>(&self, items: Vec<T>, f: impl Fn(T) -> R + Send + Sync) -> Vec<R> {
+        match self {
+            Threads::One => items.into_iter().map(f).collect(),
+            Threads::Global => items.into_par_iter().map(f).collect(),
+            Threads::Pool(pool) => pool.install(|| items.into_par_iter().map(f).collect()),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! `--tuning-report`: how far apart the fragments, baselines and lines of documents are, set
+//! against the thresholds that tell words, lines and paragraphs apart, for choosing those
+//! thresholds for documents they do not suit.
+
+use rapid_pdf::layout::paragraphs;
+use rapid_pdf::{Origin, PageText, TextItem};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// The widest bar of a histogram written as text, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// How many of some distances fall between each pair of marks, a bin's width apart.
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    /// The option whose threshold the distances are measured against, and its value.
+    pub option: &'static str,
+    pub threshold: f32,
+    /// What the distances are measured in.
+    pub unit: &'static str,
+    pub bin_width: f32,
+    /// How many distances are in each bin, the first starting at 0.
+    pub counts: Vec<usize>,
+    /// How many are less than 0, as fragments that overlap, and how many are past the last bin.
+    pub below: usize,
+    pub above: usize,
+}
+
+impl Histogram {
+    fn new(option: &'static str, threshold: f32, unit: &'static str, bin_width: f32, bins: usize) -> Histogram {
+        Histogram { option, threshold, unit, bin_width, counts: vec![0; bins], below: 0, above: 0 }
+    }
+
+    fn add(&mut self, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+        if value < 0.0 {
+            self.below += 1;
+            return;
+        }
+        match self.counts.get_mut((value / self.bin_width) as usize) {
+            Some(count) => *count += 1,
+            None => self.above += 1,
+        }
+    }
+
+    /// The histogram as text: a row for each bin up to the last that is not empty, with a bar
+    /// as long as its count, the one the threshold is in marked.
+    fn write(&self, out: &mut String, title: &str) {
+        let _ = writeln!(out, "{} (in {}, {} {})", title, self.unit, self.option, self.threshold);
+        let last = self.counts.iter().rposition(|&count| count > 0);
+        let widest = self.counts.iter().chain([&self.below, &self.above]).copied().max().unwrap_or(0).max(1);
+        let bar = |count: usize| "#".repeat((count * BAR_WIDTH).div_ceil(widest));
+        if self.below > 0 {
+            let _ = writeln!(out, "  {:>11} {:>7} {}", "< 0", self.below, bar(self.below));
+        }
+        let threshold = (self.threshold / self.bin_width) as usize;
+        for (index, &count) in self.counts.iter().enumerate().take(last.map_or(0, |last| last + 1).max(threshold + 1).min(self.counts.len())) {
+            let range = format!("{:.2}-{:.2}", index as f32 * self.bin_width, (index + 1) as f32 * self.bin_width);
+            let mark = if index == threshold { format!(" <- {}", self.option) } else { String::new() };
+            let row = format!("  {:>11} {:>7} {}{}", range, count, bar(count), mark);
+            let _ = writeln!(out, "{}", row.trim_end());
+        }
+        if self.above > 0 {
+            let _ = writeln!(out, "  {:>11} {:>7} {}", format!(">= {:.2}", self.counts.len() as f32 * self.bin_width), self.above, bar(self.above));
+        }
+    }
+}
+
+/// The distances of a document's text, gathered a page at a time.
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningReport {
+    pub page_count: usize,
+    /// The gaps between consecutive fragments of lines, in font sizes.
+    pub word_gaps: Histogram,
+    /// How far apart the baselines of fragments drawn one after the other are, in font sizes.
+    pub baseline_offsets: Histogram,
+    /// How far apart the baselines of consecutive lines of a column are, in multiples of the
+    /// leading most lines of their page are set with.
+    pub line_distances: Histogram,
+}
+
+impl TuningReport {
+    pub fn new(word_gap: f32, line_tolerance: f32, paragraph_gap: f32) -> TuningReport {
+        TuningReport {
+            page_count: 0,
+            word_gaps: Histogram::new("--word-gap", word_gap, "font sizes", 0.05, 40),
+            baseline_offsets: Histogram::new("--line-tolerance", line_tolerance, "font sizes", 0.05, 40),
+            line_distances: Histogram::new("--paragraph-gap", paragraph_gap, "leadings", 0.1, 40),
+        }
+    }
+
+    /// Adds the distances of a page: those between its items as read, before they are grouped
+    /// into lines, and those of its lines once laid out.
+    pub fn add(&mut self, items: &[TextItem], page: &PageText, origin: Origin) {
+        self.page_count += 1;
+        for pair in items.windows(2) {
+            let size = pair[0].font_size.max(pair[1].font_size);
+            if pair[0].vertical == pair[1].vertical && size > 0.0 {
+                let offset = if pair[0].vertical { pair[1].x - pair[0].x } else { pair[1].y - pair[0].y };
+                self.baseline_offsets.add(offset.abs() / size);
+            }
+        }
+        let lines: Vec<_> = page.paragraphs.iter().flat_map(|paragraph| paragraph.lines.iter().cloned()).collect();
+        for line in &lines {
+            for gap in line.gaps(origin) {
+                self.word_gaps.add(gap);
+            }
+        }
+        let Some(leading) = paragraphs::dominant_leading(&lines).filter(|&leading| leading > 0.0) else { return };
+        for pair in lines.windows(2) {
+            if pair[0].column == pair[1].column && pair[0].vertical == pair[1].vertical && pair[0].region == pair[1].region {
+                self.line_distances.add((paragraphs::baseline(&pair[0]) - paragraphs::baseline(&pair[1])).abs() / leading);
+            }
+        }
+    }
+
+    /// The report as text, a histogram after another.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.word_gaps.write(&mut out, "Gaps between fragments of a line");
+        out.push('\n');
+        self.baseline_offsets.write(&mut out, "Offsets between baselines of consecutive fragments");
+        out.push('\n');
+        self.line_distances.write(&mut out, "Distances between baselines of consecutive lines");
+        out
+    }
+}
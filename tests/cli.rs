@@ -0,0 +1,341 @@
+//! The command-line tool: reading standard input, its exit statuses, its output formats,
+//! reading pages on several threads, highlighting what it finds, counting pages and logging.
+
+mod common;
+
+use common::{rapid_pdf, Files};
+use std::process::Output;
+
+const LINES: [&str; 3] = ["First line of text,", "second line of text,", "and the last one."];
+
+fn run(args: &[&std::ffi::OsStr]) -> Output {
+    rapid_pdf().args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn reads_standard_input_like_a_file() {
+    let data = common::pdf(&[&common::text_page(&LINES), &common::text_page(&["Page two."])]);
+    let files = Files::new();
+    let path = files.write("input.pdf", &data);
+    for format in ["text", "json", "csv"] {
+        let from_file = rapid_pdf().arg(&path).args(["--format", format]).output().unwrap();
+        let from_stdin = rapid_pdf().arg("-").args(["--format", format]).write_stdin(data.clone()).output().unwrap();
+        assert!(from_file.status.success() && from_stdin.status.success(), "{}", stderr(&from_stdin));
+        // Only the name of the file tells them apart.
+        let path = path.to_str().unwrap();
+        let from_file = stdout(&from_file).replace(path, "-");
+        assert_eq!(stdout(&from_stdin), from_file, "--format {}", format);
+    }
+}
+
+#[test]
+fn rejects_standard_input_among_files() {
+    let files = Files::new();
+    let path = files.write("input.pdf", common::pdf(&[&common::text_page(&LINES)]));
+    let output = rapid_pdf().arg(&path).arg("-").write_stdin(Vec::new()).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains('-'), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn takes_one_mode_at_a_time() {
+    let files = Files::new();
+    let path = files.write("input.pdf", common::pdf(&[&common::text_page(&LINES)]));
+    let pairs: [&[&str]; 4] = [&["--metadata", "--outline"], &["--stats", "--find", "line"], &["--dump-ops", "--tuning-report"], &["--page-count", "--split-pages", "--output", "pages"]];
+    for args in pairs {
+        let output = rapid_pdf().arg(&path).args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(3), "{:?}", args);
+        assert!(stderr(&output).contains("cannot be used with"), "{:?}: {}", args, stderr(&output));
+    }
+    assert_eq!(rapid_pdf().arg(&path).arg("--page-count").output().unwrap().status.code(), Some(0));
+}
+
+#[test]
+fn exits_with_the_status_of_each_outcome() {
+    let files = Files::new();
+    let text = files.write("text.pdf", common::pdf(&[&common::text_page(&LINES)]));
+    let broken = files.write("broken.pdf", common::broken_second_page());
+    let locked = files.write("locked.pdf", common::encrypted(common::document(&[&common::text_page(&LINES)]), "secret", "owner"));
+    let scan = files.write("scan.pdf", common::scan());
+    let not_pdf = files.write("not.pdf", "just some text, not a PDF at all\n");
+    let missing = files.path().join("missing.pdf");
+    let cases: [(&[&std::ffi::OsStr], i32); 12] = [
+        (&[], 3),
+        (&[text.as_os_str()], 0),
+        (&[text.as_os_str(), "--find".as_ref(), "second line".as_ref()], 0),
+        (&[text.as_os_str(), "--find".as_ref(), "nowhere".as_ref()], 1),
+        (&[broken.as_os_str()], 2),
+        (&[text.as_os_str(), "--pages".as_ref(), "2".as_ref()], 3),
+        (&[text.as_os_str(), "--no-such-flag".as_ref()], 3),
+        (&[missing.as_os_str()], 4),
+        (&[locked.as_os_str()], 5),
+        (&[locked.as_os_str(), "--password".as_ref(), "wrong".as_ref()], 5),
+        (&[not_pdf.as_os_str()], 6),
+        (&[scan.as_os_str(), "--fail-if-image-only".as_ref()], 7),
+    ];
+    for (args, status) in cases {
+        let output = run(args);
+        assert_eq!(output.status.code(), Some(status), "{:?}: {}", args, stderr(&output));
+    }
+    // Without a file to read, the usage is what is wrong.
+    let output = run(&[]);
+    assert!(stderr(&output).contains("Usage: rapid_pdf"), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    // The right password opens it, and a scan is no failure unless asked to be.
+    assert_eq!(run(&[locked.as_os_str(), "--password".as_ref(), "secret".as_ref()]).status.code(), Some(0));
+    assert_eq!(run(&[scan.as_os_str()]).status.code(), Some(0));
+}
+
+#[test]
+fn writes_what_it_can_read_of_a_partly_broken_file() {
+    let files = Files::new();
+    let broken = files.write("broken.pdf", common::broken_second_page());
+    let output = run(&[broken.as_os_str()]);
+    assert_eq!(output.status.code(), Some(2));
+    // The pages either side of the broken one are read in full, and only it is told of.
+    let text = stdout(&output);
+    assert!(text.find("The first page reads.").unwrap() < text.find("The third page reads.").unwrap(), "{}", text);
+    let errors = stderr(&output);
+    assert_eq!(errors.matches("Warning: skipping content stream ").count(), 1, "{}", errors);
+    assert!(errors.contains("1 page could not be read in full, the rest was written: page 2"), "{}", errors);
+}
+
+#[test]
+fn writes_text() {
+    let files = Files::new();
+    let path = files.write("text.pdf", common::pdf(&[&common::text_page(&LINES), &common::text_page(&["Page two."])]));
+    let output = run(&[path.as_os_str()]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.contains("First line of text, second line of text, and the last one."), "{}", text);
+    assert!(text.find("the last one.").unwrap() < text.find("Page two.").unwrap());
+}
+
+#[test]
+fn writes_json() {
+    let files = Files::new();
+    let path = files.write("text.pdf", common::pdf(&[&common::text_page(&LINES), &common::text_page(&["Page two."])]));
+    let output = run(&[path.as_os_str(), "--format".as_ref(), "json".as_ref()]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+    let pages = json["pages"].as_array().unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!((&pages[0]["page"], &pages[0]["width"], &pages[0]["height"]), (&1.into(), &612.0.into(), &792.0.into()));
+    let items = pages[0]["items"].as_array().unwrap();
+    let texts: Vec<&str> = items.iter().map(|item| item["text"].as_str().unwrap()).collect();
+    assert_eq!(texts, LINES);
+    assert_eq!((&items[0]["x"], &items[0]["y"], &items[0]["font_size"]), (&72.0.into(), &720.0.into(), &12.0.into()));
+}
+
+#[test]
+fn writes_the_skew_of_each_line() {
+    let files = Files::new();
+    // The second page is the first turned 0.3 degrees counterclockwise.
+    let turned = format!("q 0.99998629 0.00523596 -0.00523596 0.99998629 0 0 cm {} Q", common::text_page(&LINES));
+    let path = files.write("turned.pdf", common::pdf(&[&common::text_page(&LINES), &turned]));
+    let output = run(&[path.as_os_str(), "--format".as_ref(), "json".as_ref()]);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+    let lines = |page: usize| json["pages"][page]["lines"].as_array().unwrap().clone();
+    let (upright, turned) = (lines(0), lines(1));
+    assert_eq!(upright.iter().map(|line| line["text"].as_str().unwrap()).collect::<Vec<_>>(), LINES);
+    assert_eq!(turned.iter().map(|line| &line["text"]).collect::<Vec<_>>(), upright.iter().map(|line| &line["text"]).collect::<Vec<_>>());
+    assert!(upright.iter().all(|line| line["skew"] == 0.0));
+    assert!(turned.iter().all(|line| (line["skew"].as_f64().unwrap() - 0.3).abs() < 0.01), "{:?}", turned);
+    assert_eq!((&upright[0]["bbox"]["x0"], &upright[0]["column"]), (&72.0.into(), &0.into()));
+}
+
+#[test]
+fn writes_json_lines() {
+    let files = Files::new();
+    let path = files.write("text.pdf", common::pdf(&[&common::text_page(&LINES), &common::text_page(&["Page two."])]));
+    let output = run(&[path.as_os_str(), "--format".as_ref(), "jsonl".as_ref()]);
+    assert!(output.status.success());
+    let records: Vec<serde_json::Value> = stdout(&output).lines().map(|line| serde_json::from_str(line).expect("each line is JSON")).collect();
+    let pages: Vec<&serde_json::Value> = records.iter().filter(|record| record["type"] == "page").collect();
+    assert_eq!(pages.iter().map(|page| page["page"].as_u64().unwrap()).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(pages[1]["items"][0]["text"], "Page two.");
+}
+
+#[test]
+fn writes_markdown_and_html() {
+    let heading = "BT /F1 24 Tf 72 740 Td (A Heading) Tj ET";
+    let files = Files::new();
+    let path = files.write("text.pdf", common::pdf(&[&format!("{} {}", heading, common::text_page(&LINES))]));
+    let markdown = run(&[path.as_os_str(), "--format".as_ref(), "markdown".as_ref()]);
+    assert!(markdown.status.success());
+    assert!(stdout(&markdown).contains("# A Heading"), "{}", stdout(&markdown));
+    assert!(stdout(&markdown).contains("First line of text, second line of text, and the last one."));
+    let html = run(&[path.as_os_str(), "--format".as_ref(), "html".as_ref()]);
+    assert!(html.status.success());
+    assert!(stdout(&html).contains("A Heading") && stdout(&html).contains("</html>"), "{}", stdout(&html));
+}
+
+#[test]
+fn reads_pages_on_threads_as_on_one() {
+    let pages: Vec<String> = (1..=40).map(|page| common::text_page(&[&format!("Page {} begins here,", page), "then goes on", &format!("and ends {}.", page)])).collect();
+    let pages: Vec<&str> = pages.iter().map(String::as_str).collect();
+    let files = Files::new();
+    let path = files.write("pages.pdf", common::pdf(&pages));
+    for format in ["text", "json", "jsonl", "csv"] {
+        let sequential = run(&[path.as_os_str(), "--format".as_ref(), format.as_ref(), "--threads".as_ref(), "1".as_ref()]);
+        let parallel = run(&[path.as_os_str(), "--format".as_ref(), format.as_ref(), "--threads".as_ref(), "4".as_ref()]);
+        assert!(sequential.status.success() && parallel.status.success(), "{}", stderr(&parallel));
+        assert_eq!(stdout(&parallel), stdout(&sequential), "--format {}", format);
+    }
+    let text = stdout(&run(&[path.as_os_str(), "--threads".as_ref(), "4".as_ref()])).to_string();
+    let starts: Vec<usize> = (1..=40).map(|page| text.find(&format!("Page {} begins", page)).unwrap()).collect();
+    assert!(starts.windows(2).all(|pair| pair[0] < pair[1]), "pages out of order");
+}
+
+/// The annotations of `/Annots` of page `page_num` of `doc`.
+fn annotations(doc: &lopdf::Document, page_num: u32) -> Vec<&lopdf::Dictionary> {
+    let page = doc.get_dictionary(doc.get_pages()[&page_num]).unwrap();
+    let Ok(annots) = page.get_deref(b"Annots", doc) else { return Vec::new() };
+    annots.as_array().unwrap().iter().map(|annot| doc.dereference(annot).unwrap().1.as_dict().unwrap()).collect()
+}
+
+fn numbers(dict: &lopdf::Dictionary, key: &[u8]) -> Vec<f32> {
+    dict.get(key).unwrap().as_array().unwrap().iter().map(|number| number.as_float().unwrap()).collect()
+}
+
+#[test]
+fn annotates_a_copy_with_the_matches() {
+    use lopdf::{dictionary, Object};
+
+    let mut doc = common::document(&[&common::text_page(&["A needle in a haystack."]), &common::text_page(&["Only hay."]), &common::text_page(&["Another needle here."])]);
+    // The third page has an annotation already, which is kept.
+    let note = doc.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Text", "Rect" => vec![0.into(), 0.into(), 20.into(), 20.into()] });
+    let third = doc.get_pages()[&3];
+    doc.get_dictionary_mut(third).unwrap().set("Annots", vec![Object::Reference(note)]);
+    let data = common::bytes(doc);
+    let files = Files::new();
+    let path = files.write("haystack.pdf", &data);
+    let target = files.path().join("highlighted.pdf");
+    let output = run(&[path.as_os_str(), "--find".as_ref(), "needle".as_ref(), "--annotate-output".as_ref(), target.as_os_str(), "--highlight-color".as_ref(), "ff8000".as_ref()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(std::fs::read(&path).unwrap(), data, "the PDF read is left as it was");
+
+    let highlighted = lopdf::Document::load(&target).expect("the copy is a PDF");
+    assert_eq!(highlighted.get_pages().len(), 3);
+    assert!(annotations(&highlighted, 2).is_empty());
+    let first = annotations(&highlighted, 1);
+    let third = annotations(&highlighted, 3);
+    assert_eq!((first.len(), third.len()), (1, 2));
+    assert_eq!(third[0].get(b"Subtype").and_then(Object::as_name).unwrap(), b"Text");
+    // Past "A " and "Another " in 12 pt Helvetica.
+    for (highlight, left) in [(first[0], 72.0 + 11.34), (third[1], 72.0 + 45.36)] {
+        assert_eq!(highlight.get(b"Subtype").and_then(Object::as_name).unwrap(), b"Highlight");
+        assert_eq!(highlight.get(b"Contents").and_then(Object::as_str).unwrap(), b"needle");
+        assert_eq!(numbers(highlight, b"C"), [1.0, 128.0 / 255.0, 0.0]);
+        let quads = numbers(highlight, b"QuadPoints");
+        assert_eq!(quads.len(), 8);
+        let rect = numbers(highlight, b"Rect");
+        // The box of the quadrilateral, over the word where the line is written.
+        let xs: Vec<f32> = quads.iter().step_by(2).copied().collect();
+        let ys: Vec<f32> = quads.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(rect, [xs.iter().copied().fold(f32::INFINITY, f32::min), ys.iter().copied().fold(f32::INFINITY, f32::min),
+            xs.iter().copied().fold(f32::NEG_INFINITY, f32::max), ys.iter().copied().fold(f32::NEG_INFINITY, f32::max)]);
+        assert!((rect[0] - left).abs() < 0.1, "{:?}", rect);
+        assert!(rect[1] < 720.0 && rect[3] > 720.0, "{:?}", rect);
+    }
+}
+
+#[test]
+fn counts_pages_without_reading_their_content() {
+    // The second page's content does not decompress, which reading its text tells.
+    let files = Files::new();
+    let broken = files.write("broken.pdf", common::broken_second_page());
+    assert_eq!(run(&[broken.as_os_str()]).status.code(), Some(2));
+    let count = run(&[broken.as_os_str(), "--page-count".as_ref()]);
+    assert_eq!(count.status.code(), Some(0), "{}", stderr(&count));
+    assert_eq!(stdout(&count), "3\n");
+    assert!(count.stderr.is_empty(), "{}", stderr(&count));
+    let info = run(&[broken.as_os_str(), "--page-info".as_ref(), "--format".as_ref(), "json".as_ref()]);
+    assert_eq!(info.status.code(), Some(0), "{}", stderr(&info));
+    assert!(info.stderr.is_empty(), "{}", stderr(&info));
+    let json: serde_json::Value = serde_json::from_slice(&info.stdout).expect("the output is JSON");
+    let pages = json[0]["pages"].as_array().unwrap();
+    assert_eq!(pages.iter().map(|page| page["has_content"].as_bool().unwrap()).collect::<Vec<_>>(), [true, true, true]);
+}
+
+#[test]
+fn writes_only_the_text_to_standard_output() {
+    let files = Files::new();
+    let broken = files.write("broken.pdf", common::broken_second_page());
+    // The pages' text parted by form feeds, the second having none.
+    let text = "The first page reads.\n\x0c\x0cThe third page reads.\n";
+    let default = rapid_pdf().arg(&broken).env_remove("RUST_LOG").output().unwrap();
+    assert_eq!(stdout(&default), text);
+    assert!(stderr(&default).starts_with("Warning: skipping content stream "), "{}", stderr(&default));
+    // However much is logged, and however that is asked for, it goes to stderr.
+    let traced = rapid_pdf().arg(&broken).arg("-vvv").output().unwrap();
+    assert_eq!(stdout(&traced), text);
+    assert!(stderr(&traced).contains("(The first page reads.) Tj"), "{}", stderr(&traced));
+    let logged = rapid_pdf().arg(&broken).env("RUST_LOG", "trace").output().unwrap();
+    assert_eq!(stdout(&logged), text);
+    assert!(stderr(&logged).starts_with("Reading "), "{}", stderr(&logged));
+}
+
+/// The codes of `errors`, with their pages for those that have one.
+fn error_codes(errors: &serde_json::Value) -> Vec<(&str, Option<u64>)> {
+    errors.as_array().expect("errors is an array").iter().map(|error| (error["code"].as_str().unwrap(), error["page"].as_u64())).collect()
+}
+
+#[test]
+fn writes_the_errors_as_json() {
+    let files = Files::new();
+    let text = files.write("text.pdf", common::pdf(&[&common::text_page(&LINES)]));
+    let broken = files.write("broken.pdf", common::broken_second_page());
+    let locked = files.write("locked.pdf", common::encrypted(common::document(&[&common::text_page(&LINES)]), "secret", "owner"));
+    let not_pdf = files.write("not.pdf", "just some text, not a PDF at all\n");
+    let missing = files.path().join("missing.pdf");
+    let failures: [(&[&std::ffi::OsStr], i32, &str, Option<u64>); 5] = [
+        (&[text.as_os_str(), "--pages".as_ref(), "2".as_ref()], 3, "page-out-of-range", Some(2)),
+        (&[missing.as_os_str()], 4, "file-not-found", None),
+        (&[locked.as_os_str()], 5, "password-required", None),
+        (&[locked.as_os_str(), "--password".as_ref(), "wrong".as_ref()], 5, "wrong-password", None),
+        (&[not_pdf.as_os_str()], 6, "malformed-pdf", None),
+    ];
+    // Arguments that cannot be parsed are written as JSON too, when that can be told.
+    let output = rapid_pdf().arg(&text).args(["--format", "json", "--no-such-flag"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+    assert_eq!(error_codes(&json["errors"]), [("invalid-arguments", None)]);
+    assert!(stderr(&output).contains("--no-such-flag"));
+    for (args, status, code, page) in failures {
+        let output = rapid_pdf().args(args).args(["--format", "json"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(status), "{:?}: {}", args, stderr(&output));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+        assert_eq!(error_codes(&json["errors"]), [(code, page)], "{:?}", args);
+        assert_eq!(json["errors"][0]["path"], args[0].to_str().unwrap());
+        assert!(json["errors"][0]["message"].is_string());
+    }
+
+    // Pages that cannot be read are told in the document's own.
+    let output = run(&[broken.as_os_str(), "--format".as_ref(), "json".as_ref()]);
+    assert_eq!(output.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+    assert_eq!(error_codes(&json["errors"]), [("content", Some(2))]);
+    assert_eq!(json["pages"][0]["items"][0]["text"], "The first page reads.");
+    assert_eq!(json["pages"][2]["items"][0]["text"], "The third page reads.");
+
+    // Reading several, those that fail are told among those read.
+    let output = run(&[text.as_os_str(), missing.as_os_str(), "--format".as_ref(), "json".as_ref()]);
+    assert_eq!(output.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("the output is JSON");
+    let files = json.as_array().unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(error_codes(&files[0]["errors"]), []);
+    assert_eq!(error_codes(&files[1]["errors"]), [("file-not-found", None)]);
+    assert_eq!(files[1]["path"], missing.to_str().unwrap());
+}
@@ -0,0 +1,114 @@
+//! PDF files built for the command-line tests: pages of content streams written out by hand,
+//! Helvetica as `/F1` on every one.
+
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use lopdf::{dictionary, Document, EncryptionState, EncryptionVersion, Object, Permissions, Stream};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// The command-line tool, as built for the tests.
+pub fn rapid_pdf() -> Command {
+    Command::cargo_bin("rapid_pdf").expect("the binary builds")
+}
+
+/// A document of a letter-size page for each content stream.
+pub fn document(pages: &[&str]) -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let helvetica = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+    let resources = doc.add_object(dictionary! { "Font" => dictionary! { "F1" => helvetica } });
+    let mut kids = Vec::new();
+    for content in pages {
+        let contents = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+        let page = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => resources,
+            "Contents" => contents,
+        });
+        kids.push(Object::Reference(page));
+    }
+    let count = kids.len() as i64;
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+    let root = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", root);
+    doc
+}
+
+pub fn bytes(mut doc: Document) -> Vec<u8> {
+    let mut data = Vec::new();
+    doc.save_to(&mut data).expect("a document built in memory saves");
+    data
+}
+
+/// The bytes of a PDF file of a page for each content stream.
+pub fn pdf(pages: &[&str]) -> Vec<u8> {
+    bytes(document(pages))
+}
+
+/// `doc` encrypted with RC4 and a 128-bit key, opening with `user_password`, which may be
+/// empty, or with `owner_password`.
+pub fn encrypted(mut doc: Document, user_password: &str, owner_password: &str) -> Vec<u8> {
+    let id = Object::string_literal("0123456789abcdef");
+    doc.trailer.set("ID", vec![id.clone(), id]);
+    let version = EncryptionVersion::V2 { document: &doc, owner_password, user_password, key_length: 128, permissions: Permissions::PRINTABLE };
+    let state = EncryptionState::try_from(version).expect("the document encrypts");
+    doc.encrypt(&state).expect("the document encrypts");
+    bytes(doc)
+}
+
+/// A document of one page that is a scan: a gray image painted over the whole of it, and no
+/// text.
+pub fn scan() -> Vec<u8> {
+    let mut doc = document(&["q 612 0 0 792 0 0 cm /Im0 Do Q"]);
+    let image = Stream::new(
+        dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 2, "Height" => 2, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8 },
+        vec![0x20, 0x80, 0x80, 0x20],
+    );
+    let image = doc.add_object(image);
+    let page = doc.page_iter().next().expect("the document has a page");
+    let resources = doc.get_dictionary(page).unwrap().get(b"Resources").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(resources).unwrap().set("XObject", dictionary! { "Im0" => image });
+    bytes(doc)
+}
+
+/// A document of three pages, the second drawn by a content stream that does not decompress.
+pub fn broken_second_page() -> Vec<u8> {
+    let mut doc = document(&[&text_page(&["The first page reads."]), "", &text_page(&["The third page reads."])]);
+    let page = doc.page_iter().nth(1).expect("the document has three pages");
+    let contents = doc.get_dictionary(page).unwrap().get(b"Contents").unwrap().as_reference().unwrap();
+    let broken = Stream::new(dictionary! { "Filter" => "FlateDecode" }, b"this is not deflated".to_vec());
+    doc.objects.insert(contents, Object::Stream(broken));
+    bytes(doc)
+}
+
+/// A page of 12-point Helvetica showing `lines` from the top down, 14 points apart.
+pub fn text_page(lines: &[&str]) -> String {
+    let shown: Vec<String> = lines.iter().map(|line| format!("({}) Tj T*", line)).collect();
+    format!("BT /F1 12 Tf 14 TL 72 720 Td {} ET", shown.join(" "))
+}
+
+/// A directory to write the files of one test into, removed with it.
+pub struct Files {
+    dir: TempDir,
+}
+
+impl Files {
+    pub fn new() -> Files {
+        Files { dir: tempfile::tempdir().expect("a temporary directory") }
+    }
+
+    /// Writes `data` to the file `name`, returning its path.
+    pub fn write(&self, name: &str, data: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.dir.path().join(name);
+        std::fs::write(&path, data).expect("the fixture writes");
+        path
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
@@ -0,0 +1,221 @@
+//! The output formats, read back the way their consumers read them.
+
+mod common;
+
+use common::{rapid_pdf, Files};
+use rapid_pdf::{ExtractOptions, Extractor, TextItem};
+
+/// The items of every page of `data`, read with the default options.
+fn items(data: &[u8]) -> Vec<TextItem> {
+    let options = ExtractOptions::default();
+    let extractor = Extractor::from_bytes(data, &options).expect("the fixture opens");
+    extractor.page_items().flat_map(|page| page.expect("the fixture's pages read").items).collect()
+}
+
+#[test]
+fn csv_reads_back_as_the_items() {
+    // A comma, quotes, a line break in a string and a tab: what joining with commas gets wrong.
+    let content = r#"BT /F1 12 Tf 72 700 Td (Smith, John) Tj 0 -20 Td (say "cheese") Tj 0 -20 Td (two\nlines) Tj 0 -20 Td (tab\there) Tj ET"#;
+    let data = common::pdf(&[content, "BT /F1 9 Tf 100 100 Td (Second page) Tj ET"]);
+    let files = Files::new();
+    let path = files.write("items.pdf", &data);
+    let expected = items(&data);
+    for (format, separator) in [("csv", b','), ("tsv", b'\t')] {
+        let output = rapid_pdf().arg(&path).args(["--format", format]).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let mut reader = csv::ReaderBuilder::new().delimiter(separator).from_reader(output.stdout.as_slice());
+        let header: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        assert_eq!(header[..8], ["page", "text", "x", "y", "width", "height", "font_size", "font"]);
+        let rows: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), expected.len(), "{} rows", format);
+        for (row, item) in rows.iter().zip(&expected) {
+            assert_eq!(row[0].parse::<u32>().unwrap(), item.page_num);
+            assert_eq!(&row[1], item.text);
+            let number = |index: usize| row[index].parse::<f32>().unwrap();
+            assert!((number(2) - item.x).abs() < 0.01 && (number(3) - item.y).abs() < 0.01);
+            assert!((number(4) - item.width).abs() < 0.01 && (number(6) - item.font_size).abs() < 0.01);
+            assert_eq!(&row[7], item.font.as_deref().unwrap_or_default());
+        }
+    }
+    let texts: Vec<&str> = expected.iter().map(|item| item.text.as_str()).collect();
+    assert_eq!(texts, ["Smith, John", "say \"cheese\"", "two\nlines", "tab\there", "Second page"]);
+}
+
+/// The `bbox` of an hOCR element's title, `[x0, y0, x1, y1]`.
+fn hocr_bbox(node: roxmltree::Node) -> [u32; 4] {
+    let title = node.attribute("title").expect("hOCR elements have a title");
+    let bbox = title.split(';').find_map(|property| property.trim().strip_prefix("bbox ")).expect("the title has a bbox");
+    let numbers: Vec<u32> = bbox.split(' ').map(|number| number.parse().expect("bbox numbers are integers")).collect();
+    numbers.try_into().expect("a bbox has four numbers")
+}
+
+fn within(inner: [u32; 4], outer: [u32; 4]) -> bool {
+    inner[0] >= outer[0] && inner[1] >= outer[1] && inner[2] <= outer[2] && inner[3] <= outer[3] && inner[0] <= inner[2] && inner[1] <= inner[3]
+}
+
+fn class(node: &roxmltree::Node, class: &str) -> bool {
+    node.is_element() && node.attribute("class") == Some(class)
+}
+
+/// The child elements of an hOCR element of one class.
+fn children<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &'static str) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(move |child| class(child, name))
+}
+
+#[test]
+fn hocr_nests_words_in_lines_in_paragraphs_in_pages() {
+    let data = common::pdf(&[&common::text_page(&["Hello <world> & more", "Second line"]), &common::text_page(&["Page two"])]);
+    let files = Files::new();
+    let path = files.write("hocr.pdf", &data);
+    let output = rapid_pdf().arg(&path).args(["--format", "hocr"]).output().unwrap();
+    assert!(output.status.success());
+    let xml = String::from_utf8(output.stdout).unwrap();
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(&xml, options).expect("hOCR is well-formed XHTML");
+    let html = document.root_element();
+    assert_eq!((html.tag_name().name(), html.tag_name().namespace()), ("html", Some("http://www.w3.org/1999/xhtml")));
+    let capabilities = html.descendants().find(|node| node.attribute("name") == Some("ocr-capabilities")).unwrap();
+    assert_eq!(capabilities.attribute("content"), Some("ocr_page ocr_carea ocr_par ocr_line ocrx_word"));
+
+    let pages: Vec<_> = html.descendants().filter(|node| class(node, "ocr_page")).collect();
+    assert_eq!(pages.len(), 2);
+    let mut ids = std::collections::HashSet::new();
+    let mut words = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        assert_eq!(page.attribute("id"), Some(format!("page_{}", index + 1).as_str()));
+        assert_eq!(hocr_bbox(*page), [0, 0, 612, 792]);
+        assert!(page.attribute("title").unwrap().contains(&format!("ppageno {}", index)));
+        for area in children(*page, "ocr_carea") {
+            assert!(within(hocr_bbox(area), hocr_bbox(*page)));
+            for paragraph in children(area, "ocr_par") {
+                assert_eq!(paragraph.tag_name().name(), "p");
+                assert!(within(hocr_bbox(paragraph), hocr_bbox(area)));
+                for line in children(paragraph, "ocr_line") {
+                    assert!(within(hocr_bbox(line), hocr_bbox(paragraph)));
+                    assert!(line.attribute("title").unwrap().contains("; baseline "));
+                    for word in children(line, "ocrx_word") {
+                        assert!(within(hocr_bbox(word), hocr_bbox(line)));
+                        assert!(word.attribute("title").unwrap().ends_with("; x_wconf 100"));
+                        words.push((word.attribute("id").unwrap().to_string(), word.text().unwrap().to_string()));
+                    }
+                }
+            }
+        }
+        for node in page.descendants().filter(|node| node.is_element()) {
+            assert!(ids.insert(node.attribute("id").expect("every hOCR element has an id").to_string()));
+        }
+    }
+    let expected = [
+        ("page_1_word_1", "Hello"),
+        ("page_1_word_2", "<world>"),
+        ("page_1_word_3", "&"),
+        ("page_1_word_4", "more"),
+        ("page_1_word_5", "Second"),
+        ("page_1_word_6", "line"),
+        ("page_2_word_1", "Page"),
+        ("page_2_word_2", "two"),
+    ];
+    let words: Vec<(&str, &str)> = words.iter().map(|(id, text)| (id.as_str(), text.as_str())).collect();
+    assert_eq!(words, expected);
+    // Boxes are measured from the top: the first line is above the second.
+    let line_tops: Vec<u32> = pages[0].descendants().filter(|node| class(node, "ocr_line")).map(|line| hocr_bbox(line)[1]).collect();
+    assert!(line_tops[0] < line_tops[1] && line_tops[0] < 100);
+}
+
+/// An ALTO element's `HPOS`, `VPOS`, `WIDTH` and `HEIGHT`, the last absent from `SP`.
+fn alto_box(node: roxmltree::Node) -> [f32; 4] {
+    let number = |name: &str| node.attribute(name).map(|value| value.parse::<f32>().expect("ALTO positions are numbers"));
+    [number("HPOS").unwrap(), number("VPOS").unwrap(), number("WIDTH").unwrap(), number("HEIGHT").unwrap_or(0.0)]
+}
+
+fn alto_within(inner: roxmltree::Node, outer: roxmltree::Node) -> bool {
+    let ([x, y, width, height], [outer_x, outer_y, outer_width, outer_height]) = (alto_box(inner), alto_box(outer));
+    width >= 0.0 && height >= 0.0 && x >= outer_x && y >= outer_y && x + width <= outer_x + outer_width && y + height <= outer_y + outer_height
+}
+
+/// The names of an element's child elements, in order.
+fn element_names<'a>(node: roxmltree::Node<'a, '_>) -> Vec<&'a str> {
+    node.children().filter(|child| child.is_element()).map(|child| child.tag_name().name()).collect()
+}
+
+/// The elements and attributes of ALTO 4 that the output is made of, as its schema orders
+/// and requires them.
+#[test]
+fn alto_follows_the_alto_4_schema() {
+    let data = common::pdf(&[&common::text_page(&["Hello <world> & more", "Second line"]), &common::text_page(&["Page two"])]);
+    let files = Files::new();
+    let path = files.write("alto.pdf", &data);
+    let output = rapid_pdf().arg(&path).args(["--format", "alto"]).output().unwrap();
+    assert!(output.status.success());
+    let xml = String::from_utf8(output.stdout).unwrap();
+    let document = roxmltree::Document::parse(&xml).expect("ALTO is well-formed XML");
+    const NAMESPACE: &str = "http://www.loc.gov/standards/alto/ns-v4#";
+    let alto = document.root_element();
+    assert_eq!((alto.tag_name().name(), alto.tag_name().namespace()), ("alto", Some(NAMESPACE)));
+    let schema = alto.attribute(("http://www.w3.org/2001/XMLSchema-instance", "schemaLocation")).unwrap();
+    assert_eq!(schema, format!("{} http://www.loc.gov/standards/alto/v4/alto-4-4.xsd", NAMESPACE));
+    assert!(document.descendants().filter(|node| node.is_element()).all(|node| node.tag_name().namespace() == Some(NAMESPACE)));
+    assert_eq!(element_names(alto), ["Description", "Layout"]);
+    let description = alto.first_element_child().unwrap();
+    assert_eq!(element_names(description)[0], "MeasurementUnit");
+    assert!(["pixel", "mm10", "inch1200"].contains(&description.first_element_child().unwrap().text().unwrap()));
+
+    let layout = alto.last_element_child().unwrap();
+    assert_eq!(element_names(layout), ["Page", "Page"]);
+    let mut ids = std::collections::HashSet::new();
+    let mut strings = Vec::new();
+    for (index, page) in layout.children().filter(|node| node.is_element()).enumerate() {
+        let (id, number) = (format!("page_{}", index + 1), (index + 1).to_string());
+        assert_eq!((page.attribute("ID"), page.attribute("PHYSICAL_IMG_NR")), (Some(id.as_str()), Some(number.as_str())));
+        assert_eq!((page.attribute("WIDTH"), page.attribute("HEIGHT")), (Some("612"), Some("792")));
+        assert_eq!(element_names(page), ["PrintSpace"]);
+        let print_space = page.first_element_child().unwrap();
+        for block in print_space.children().filter(|node| node.is_element()) {
+            assert_eq!(block.tag_name().name(), "TextBlock");
+            assert!(alto_within(block, print_space));
+            for line in block.children().filter(|node| node.is_element()) {
+                assert_eq!(line.tag_name().name(), "TextLine");
+                assert!(alto_within(line, block));
+                // Strings with a space between each two, and none at the ends.
+                let names = element_names(line);
+                assert!(names.iter().enumerate().all(|(index, &name)| name == if index % 2 == 0 { "String" } else { "SP" }), "{:?}", names);
+                assert_eq!(names.last(), Some(&"String"));
+                for string in line.children().filter(|node| node.is_element()) {
+                    assert!(alto_within(string, line));
+                    if string.tag_name().name() == "String" {
+                        strings.push(string.attribute("CONTENT").expect("a String has its CONTENT").to_string());
+                    }
+                }
+            }
+        }
+        for node in page.descendants().filter(|node| node.is_element()) {
+            if let Some(id) = node.attribute("ID") {
+                assert!(ids.insert(id.to_string()), "ID {} twice", id);
+            }
+        }
+    }
+    assert_eq!(strings, ["Hello", "<world>", "&", "more", "Second", "line", "Page", "two"]);
+}
+
+#[test]
+fn markdown_and_json_place_images_among_the_text() {
+    // A figure between two paragraphs, drawn inline, and a second after all the text.
+    let content = "BT /F1 12 Tf 72 700 Td (The text above the figure.) Tj ET \
+        q 200 0 0 100 72 560 cm BI /W 1 /H 1 /CS /G /BPC 8 ID x EI Q \
+        BT /F1 12 Tf 72 530 Td (Figure 1: the caption under it.) Tj ET \
+        q 100 0 0 100 72 100 cm BI /W 2 /H 1 /CS /G /BPC 8 ID xy EI Q";
+    let files = Files::new();
+    let path = files.write("figure.pdf", common::pdf(&[content]));
+    let markdown = rapid_pdf().arg(&path).args(["--format", "markdown"]).output().unwrap();
+    assert!(markdown.status.success());
+    let markdown = String::from_utf8(markdown.stdout).unwrap();
+    let blocks: Vec<&str> = markdown.split("\n\n").map(str::trim).filter(|block| !block.is_empty()).collect();
+    assert_eq!(blocks, ["The text above the figure.", "![image](#page1-img1)", "Figure 1: the caption under it.", "![image](#page1-img2)"]);
+
+    let json = rapid_pdf().arg(&path).args(["--format", "json"]).output().unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json.stdout).expect("the output is JSON");
+    let items = json["pages"][0]["items"].as_array().unwrap();
+    let kinds: Vec<(&str, Option<&str>)> = items.iter().map(|item| (item["type"].as_str().unwrap(), item["text"].as_str())).collect();
+    assert_eq!(kinds, [("text", Some("The text above the figure.")), ("image", None), ("text", Some("Figure 1: the caption under it.")), ("image", None)]);
+    assert_eq!((&items[1]["width"], &items[1]["rect"]["y0"], &items[1]["rect"]["x1"]), (&1.into(), &560.0.into(), &272.0.into()));
+}
@@ -0,0 +1,93 @@
+//! How much memory reading a document takes: the pages of a document, read one after another,
+//! are each decompressed when read and dropped when done with, however many there are.
+
+mod common;
+
+use common::Files;
+use rapid_pdf::{ExtractOptions, Extractor};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The system allocator, counting the bytes allocated and the most allocated at once.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Starts counting the peak afresh from what is allocated now, which it returns.
+fn reset_peak() -> usize {
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(allocated, Ordering::Relaxed);
+    allocated
+}
+
+const LINES_PER_PAGE: usize = 400;
+
+/// A page of `LINES_PER_PAGE` lines, some 20 KB of content that compresses to little.
+fn page(number: usize) -> String {
+    let mut content = String::from("BT /F1 10 Tf 12 TL 72 780 Td\n");
+    for line in 0..LINES_PER_PAGE {
+        content.push_str(&format!("(Line {} of page {}, the same words again and again.) '\n", line, number));
+    }
+    content.push_str("ET");
+    content
+}
+
+/// A file of `pages` pages, its content streams compressed, with the number of bytes of
+/// content they decompress to.
+fn many_pages(files: &Files, pages: usize) -> (std::path::PathBuf, usize) {
+    let pages: Vec<String> = (1..=pages).map(page).collect();
+    let content = pages.iter().map(String::len).sum();
+    let mut doc = common::document(&pages.iter().map(String::as_str).collect::<Vec<_>>());
+    doc.compress();
+    (files.write(&format!("{}-pages.pdf", pages.len()), common::bytes(doc)), content)
+}
+
+/// The most allocated at once opening the file at `path`, then reading its pages one after
+/// another on one thread, each over what was allocated before.
+fn peaks(path: &std::path::Path) -> (usize, usize) {
+    let options = ExtractOptions::builder().threads(Some(1)).build().unwrap();
+    let before = reset_peak();
+    let extractor = Extractor::open(path, &options).unwrap();
+    let opening = PEAK.load(Ordering::Relaxed) - before;
+    let before = reset_peak();
+    for page in extractor.page_items() {
+        assert_eq!(page.unwrap().items.len(), LINES_PER_PAGE);
+    }
+    (opening, PEAK.load(Ordering::Relaxed) - before)
+}
+
+#[test]
+fn reads_one_page_at_a_time() {
+    let files = Files::new();
+    let (few, _) = many_pages(&files, 10);
+    let (many, content) = many_pages(&files, 100);
+    assert!((std::fs::metadata(&many).unwrap().len() as usize) * 10 < content, "the fixture's content compresses");
+
+    let (_, reading_few) = peaks(&few);
+    let (opening, reading_many) = peaks(&many);
+    // Opening reads the objects, their streams still compressed, and the cross-reference table.
+    assert!(opening < content / 4, "opening took {} bytes for {} of content", opening, content);
+    // Only a page at a time is held decompressed, with its items: ten times the pages take
+    // hardly more memory.
+    assert!(reading_many < reading_few * 5 / 4, "reading 100 pages took {} bytes at most, 10 pages {}", reading_many, reading_few);
+}
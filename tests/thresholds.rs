@@ -0,0 +1,43 @@
+//! How the thresholds of word gaps, lines and paragraphs group the text of a page, each
+//! page written for its text to be grouped one way by default and the other past a threshold.
+
+mod common;
+
+use common::{rapid_pdf, Files};
+
+/// "Tracked" and "type" 0.2 em apart, 12 pt Helvetica "Tracked" being 43.344 points wide.
+const TRACKED: &str = "BT /F1 12 Tf 72 720 Td (Tracked) Tj ET BT /F1 12 Tf 117.744 720 Td (type) Tj ET";
+
+/// "Raised" and "note", its baseline 0.2 em above, half an em to the right of it.
+const RAISED: &str = "BT /F1 12 Tf 72 720 Td (Raised) Tj ET BT /F1 12 Tf 115.344 722.4 Td (note) Tj ET";
+
+/// Two paragraphs of two lines 14 points apart, 21 points between them.
+const PARAGRAPHS: &str = "BT /F1 12 Tf 14 TL 72 720 Td (First line,) Tj T* (second line.) Tj 0 -21 Td (Third line,) Tj T* (fourth line.) Tj ET";
+
+/// The text of a page showing `content`, read with `args`.
+fn text(content: &str, args: &[&str]) -> String {
+    let files = Files::new();
+    let path = files.write("page.pdf", common::pdf(&[content]));
+    let output = rapid_pdf().arg(&path).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn separates_words_past_the_space_threshold() {
+    assert_eq!(text(TRACKED, &[]), "Trackedtype\n");
+    assert_eq!(text(TRACKED, &["--space-threshold", "0.15"]), "Tracked type\n");
+    assert_eq!(text(TRACKED, &["--word-gap", "0.15"]), "Tracked type\n");
+}
+
+#[test]
+fn separates_lines_past_the_line_tolerance() {
+    assert_eq!(text(RAISED, &[]), "Raised note\n");
+    assert_eq!(text(RAISED, &["--line-tolerance", "0.1"]), "Raised\n\nnote\n");
+}
+
+#[test]
+fn separates_paragraphs_past_the_paragraph_gap() {
+    assert_eq!(text(PARAGRAPHS, &[]), "First line, second line.\n\nThird line, fourth line.\n");
+    assert_eq!(text(PARAGRAPHS, &["--paragraph-gap", "1.6"]), "First line, second line. Third line, fourth line.\n");
+}